@@ -0,0 +1,183 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use std::{ffi::CString, fmt, str::FromStr};
+use x11::{xft, xlib, xrender};
+
+/// A color from the config file: `#RGB`, `#RRGGBB`, `#RRGGBBAA` or an X
+/// color name such as `"slate gray"`.
+///
+/// Names can only be looked up on the server, so they stay `Named` until
+/// [`Color::resolve`] is called with a display connection.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "String")]
+pub enum Color {
+    Rgba {
+        red: u8,
+        green: u8,
+        blue: u8,
+        alpha: u8,
+    },
+    Named(String),
+}
+
+impl Color {
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self::Rgba {
+            red,
+            green,
+            blue,
+            alpha: 0xFF,
+        }
+    }
+
+    /// Looks a named color up in the server's color database, against the
+    /// default colormap of the display.
+    ///
+    /// # Safety
+    /// The display pointer must be valid and point to an active X display connection.
+    pub unsafe fn resolve(&self, display: *mut xlib::Display) -> Result<Self> {
+        let Self::Named(name) = self else {
+            return Ok(self.clone());
+        };
+
+        let c_name =
+            CString::new(name.as_str()).map_err(|_| anyhow!("Invalid color '{}'", name))?;
+        let colormap = xlib::XDefaultColormap(display, xlib::XDefaultScreen(display));
+        let mut color: xlib::XColor = std::mem::zeroed();
+        if xlib::XParseColor(display, colormap, c_name.as_ptr(), &mut color) == 0 {
+            bail!("Unknown color '{}'", name);
+        }
+
+        Ok(Self::Rgba {
+            red: (color.red >> 8) as u8,
+            green: (color.green >> 8) as u8,
+            blue: (color.blue >> 8) as u8,
+            alpha: 0xFF,
+        })
+    }
+
+    /// The color as an `0xAARRGGBB` pixel value. Unresolved names come out
+    /// as opaque black.
+    pub fn pixel(&self) -> u64 {
+        match *self {
+            Self::Rgba {
+                red,
+                green,
+                blue,
+                alpha,
+            } => u64::from_be_bytes([0, 0, 0, 0, alpha, red, green, blue]),
+            Self::Named(_) => 0xFF000000,
+        }
+    }
+
+    /// Allocates the color for drawing with Xft.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection.
+    /// - The visual and colormap must belong to that display.
+    pub unsafe fn to_xft(
+        &self,
+        display: *mut xlib::Display,
+        visual: *mut xlib::Visual,
+        colormap: xlib::Colormap,
+    ) -> Result<xft::XftColor> {
+        let mut color: xft::XftColor = std::mem::zeroed();
+        let allocated = match self {
+            Self::Rgba {
+                red,
+                green,
+                blue,
+                alpha,
+            } => {
+                let value = xrender::XRenderColor {
+                    red: *red as u16 * 0x101,
+                    green: *green as u16 * 0x101,
+                    blue: *blue as u16 * 0x101,
+                    alpha: *alpha as u16 * 0x101,
+                };
+                xft::XftColorAllocValue(display, visual, colormap, &value, &mut color)
+            }
+            Self::Named(name) => {
+                let c_name = CString::new(name.as_str()).unwrap_or_default();
+                xft::XftColorAllocName(display, visual, colormap, c_name.as_ptr(), &mut color)
+            }
+        };
+
+        if allocated == 0 {
+            bail!("Failed to allocate color '{}'", self);
+        }
+        Ok(color)
+    }
+}
+
+fn parse_hex(digits: &str) -> Option<Vec<u8>> {
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    match digits.len() {
+        // #RGB is shorthand for #RRGGBB
+        3 => digits
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8 * 0x11))
+            .collect(),
+        6 | 8 => (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+            .collect(),
+        _ => None,
+    }
+}
+
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        if let Some(digits) = s.strip_prefix('#') {
+            let bytes = parse_hex(digits).ok_or_else(|| {
+                anyhow!("Invalid color '{}': expected #RGB, #RRGGBB or #RRGGBBAA", s)
+            })?;
+            return Ok(Self::Rgba {
+                red: bytes[0],
+                green: bytes[1],
+                blue: bytes[2],
+                alpha: bytes.get(3).copied().unwrap_or(0xFF),
+            });
+        }
+
+        if s.is_empty() {
+            bail!("Empty color");
+        }
+        Ok(Self::Named(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Rgba {
+                red,
+                green,
+                blue,
+                alpha: 0xFF,
+            } => write!(f, "#{:02X}{:02X}{:02X}", red, green, blue),
+            Self::Rgba {
+                red,
+                green,
+                blue,
+                alpha,
+            } => write!(f, "#{:02X}{:02X}{:02X}{:02X}", red, green, blue, alpha),
+            Self::Named(name) => f.write_str(name),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Default, Clone)]
+pub struct GameMode {
+    #[serde(default)]
+    pub auto: bool,
+}
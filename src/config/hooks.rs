@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// The window manager events hooks can be attached to.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HookKind {
+    WindowMapped,
+    WindowClosed,
+    WorkspaceChanged,
+    FocusChanged,
+    MonitorChanged,
+}
+
+impl HookKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::WindowMapped => "window_mapped",
+            Self::WindowClosed => "window_closed",
+            Self::WorkspaceChanged => "workspace_changed",
+            Self::FocusChanged => "focus_changed",
+            Self::MonitorChanged => "monitor_changed",
+        }
+    }
+}
+
+/// A shell command run on every event of the given kind.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Hook {
+    pub event: HookKind,
+    pub command: String,
+}
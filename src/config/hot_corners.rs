@@ -0,0 +1,56 @@
+use serde::Deserialize;
+
+use crate::utils::command::Command;
+
+#[derive(Deserialize, Clone)]
+pub struct HotCorners {
+    #[serde(default)]
+    pub top_left: Option<Command>,
+    #[serde(default)]
+    pub top_right: Option<Command>,
+    #[serde(default)]
+    pub bottom_left: Option<Command>,
+    #[serde(default)]
+    pub bottom_right: Option<Command>,
+    #[serde(default = "default_size")]
+    pub size: u32,
+    #[serde(default = "default_dwell_ms")]
+    pub dwell_ms: u64,
+    #[serde(default = "default_cooldown_ms")]
+    pub cooldown_ms: u64,
+}
+
+impl Default for HotCorners {
+    fn default() -> Self {
+        Self {
+            top_left: None,
+            top_right: None,
+            bottom_left: None,
+            bottom_right: None,
+            size: default_size(),
+            dwell_ms: default_dwell_ms(),
+            cooldown_ms: default_cooldown_ms(),
+        }
+    }
+}
+
+fn default_size() -> u32 {
+    2
+}
+
+fn default_dwell_ms() -> u64 {
+    150
+}
+
+fn default_cooldown_ms() -> u64 {
+    1000
+}
+
+impl HotCorners {
+    pub fn is_empty(&self) -> bool {
+        self.top_left.is_none()
+            && self.top_right.is_none()
+            && self.bottom_left.is_none()
+            && self.bottom_right.is_none()
+    }
+}
@@ -0,0 +1,30 @@
+use serde::Deserialize;
+
+use crate::utils::command::Command;
+
+/// Locks the session down for a dedicated display.
+#[derive(Deserialize, Default, Clone)]
+pub struct Kiosk {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Commands that binds, hot corners and IPC may still run. Everything
+    /// else, `exit`, `restart`, `close` and spawning included, is ignored.
+    #[serde(default)]
+    pub allowed_commands: Vec<Command>,
+    /// Started fullscreen, and started again whenever it exits.
+    #[serde(default)]
+    pub application: Option<String>,
+}
+
+impl Kiosk {
+    /// Whether `command` may run. A sequence may if all its commands may.
+    pub fn allows(&self, command: &Command) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        match command {
+            Command::Sequence(commands) => commands.iter().all(|c| self.allows(c)),
+            command => self.allowed_commands.contains(command),
+        }
+    }
+}
@@ -1,27 +1,117 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
-use std::{fs, path::PathBuf};
-
+use std::{collections::BTreeMap, fs, ops::Range, path::PathBuf};
+use toml_edit::{DocumentMut, ImDocument, Item, Table, Value};
+use x11::xlib;
+
+use super::{
+    color::Color,
+    game_mode::GameMode,
+    hooks::Hook,
+    hot_corners::HotCorners,
+    kiosk::Kiosk,
+    logging::LoggingConfig,
+    migrate::{self, Migration},
+    pointer_barriers::PointerBarriers,
+    rules::Rule,
+    screenshot::ScreenshotConfig,
+    session::SessionConfig,
+    setup::Setup,
+    theme::ThemeConfig,
+    workspace::{ReturnWhenEmpty, WorkspaceConfig},
+};
 use crate::{
-    ui::appearance::{Appearance, FloatingWindow, NotificationAppearance},
+    ui::{
+        appearance::{
+            Appearance, BarAppearance, FloatingWindow, NotificationAppearance, SlideAnimation,
+            WorkspaceStyle,
+        },
+        layout::LayoutSettings,
+    },
     utils::{
-        command::Command,
+        command::{CloseTarget, Command, Spawn},
         keybind::{self, Bind},
+        xsettings::Palette,
     },
 };
 
 #[derive(Deserialize, Clone)]
 pub struct Config {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default = "default_modifier")]
     pub modifier: String,
     pub binds: Vec<Bind>,
     #[serde(default)]
     pub appearance: Appearance,
+    #[serde(default)]
+    pub theme: ThemeConfig,
     #[serde(default = "default_logging_enabled")]
     pub logging_enabled: bool,
+    #[serde(default)]
+    pub logging: LoggingConfig,
     #[serde(default = "default_auto_generated")]
     pub auto_generated: bool,
     #[serde(default = "default_notifications_enabled")]
     pub notifications_enabled: bool,
+    #[serde(default = "default_focus_on_activate")]
+    pub focus_on_activate: bool,
+    #[serde(default)]
+    pub close_target: CloseTarget,
+    #[serde(default)]
+    pub clipboard_manager: bool,
+    /// Move and resize dragged windows at most once per frame.
+    #[serde(default)]
+    pub limit_drag_rate: bool,
+    #[serde(default)]
+    pub startup_script: Option<String>,
+    #[serde(default)]
+    pub wallpaper: Option<String>,
+    #[serde(default)]
+    pub tag_mode: bool,
+    #[serde(default)]
+    pub workspaces: Vec<WorkspaceConfig>,
+    #[serde(default)]
+    pub return_when_empty: ReturnWhenEmpty,
+    /// Start with one workspace, add more with `workspace new` and drop
+    /// empty ones that aren't shown.
+    #[serde(default)]
+    pub dynamic_workspaces: bool,
+    /// Workspaces (1-based) pinned to each RandR output, or to `primary`.
+    #[serde(default)]
+    pub monitors: BTreeMap<String, Vec<usize>>,
+    #[serde(default)]
+    pub game_mode: GameMode,
+    #[serde(default)]
+    pub kiosk: Kiosk,
+    #[serde(default)]
+    pub pointer_barriers: PointerBarriers,
+    #[serde(default)]
+    pub hot_corners: HotCorners,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub screenshot: ScreenshotConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    /// Set by [`Config::load`] when the file was in an older format. The
+    /// upgrade isn't written back until [`Migration::apply`] is called.
+    #[serde(skip)]
+    pub migration: Option<Migration>,
+    /// Entries [`Config::parse`] had to drop, as "line N, key: problem".
+    /// Their defaults are used instead.
+    #[serde(skip)]
+    pub errors: Vec<String>,
+}
+
+fn default_version() -> u32 {
+    migrate::CURRENT_VERSION
+}
+
+fn default_modifier() -> String {
+    "alt".to_string()
 }
 
 fn default_logging_enabled() -> bool {
@@ -36,104 +126,146 @@ fn default_notifications_enabled() -> bool {
     true
 }
 
+fn default_focus_on_activate() -> bool {
+    true
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
-            modifier: "alt".to_string(),
+            version: migrate::CURRENT_VERSION,
+            modifier: default_modifier(),
             binds: vec![
-                Bind {
-                    key: "w".to_string(),
-                    command: Command::Exit,
-                },
-                Bind {
-                    key: "q".to_string(),
-                    command: Command::Spawn("alacritty".to_string()),
-                },
-                Bind {
-                    key: "c".to_string(),
-                    command: Command::Close,
-                },
-                Bind {
-                    key: "space".to_string(),
-                    command: Command::ToggleFloat,
-                },
-                Bind {
-                    key: "f".to_string(),
-                    command: Command::ToggleFullscreen,
-                },
-                Bind {
-                    key: "1".to_string(),
-                    command: Command::Workspace(0),
-                },
-                Bind {
-                    key: "2".to_string(),
-                    command: Command::Workspace(1),
-                },
-                Bind {
-                    key: "3".to_string(),
-                    command: Command::Workspace(2),
-                },
-                Bind {
-                    key: "4".to_string(),
-                    command: Command::Workspace(3),
-                },
-                Bind {
-                    key: "5".to_string(),
-                    command: Command::Workspace(4),
-                },
-                Bind {
-                    key: "6".to_string(),
-                    command: Command::Workspace(5),
-                },
-                Bind {
-                    key: "7".to_string(),
-                    command: Command::Workspace(6),
-                },
-                Bind {
-                    key: "8".to_string(),
-                    command: Command::Workspace(7),
-                },
-                Bind {
-                    key: "9".to_string(),
-                    command: Command::Workspace(8),
-                },
-                Bind {
-                    key: "0".to_string(),
-                    command: Command::Workspace(9),
-                },
+                Bind::new("w", Command::Exit),
+                Bind::new("q", Command::Spawn(Spawn::new("alacritty"))),
+                Bind::new("c", Command::Close),
+                Bind::new("space", Command::ToggleFloat),
+                Bind::new("f", Command::ToggleFullscreen),
+                Bind::new("slash", Command::ShowBinds),
+                Bind::new("1", Command::Workspace(0)),
+                Bind::new("2", Command::Workspace(1)),
+                Bind::new("3", Command::Workspace(2)),
+                Bind::new("4", Command::Workspace(3)),
+                Bind::new("5", Command::Workspace(4)),
+                Bind::new("6", Command::Workspace(5)),
+                Bind::new("7", Command::Workspace(6)),
+                Bind::new("8", Command::Workspace(7)),
+                Bind::new("9", Command::Workspace(8)),
+                Bind::new("0", Command::Workspace(9)),
             ],
             appearance: Appearance {
                 border_width: 2,
-                border_color: "#2B0000".to_string(),
-                focused_border_color: "#FF0000".to_string(),
+                border_color: Color::rgb(0x2B, 0x00, 0x00),
+                focused_border_color: Color::rgb(0xFF, 0x00, 0x00),
+                urgent_border_color: Color::rgb(0xFF, 0x88, 0x00),
+                sticky_border_color: Color::rgb(0x00, 0x5F, 0x5F),
                 gaps: 8,
+                nmaster: 1,
                 floating: FloatingWindow {
                     center_on_float: true,
                     width: 800,
                     height: 600,
+                    show_size: false,
+                    resize_step: 50,
                 },
+                slide: SlideAnimation::default(),
                 focus_follows_mouse: true,
                 notification: NotificationAppearance {
-                    background_color: "#0F0F0F".to_string(),
-                    border_color: "#FF0000".to_string(),
+                    background_color: Color::rgb(0x0F, 0x0F, 0x0F),
+                    border_color: Color::rgb(0xFF, 0x00, 0x00),
                 },
+                bar: BarAppearance {
+                    enabled: true,
+                    ..Default::default()
+                },
+                scale: None,
+                system_colors: false,
+                configured_colors: Vec::new(),
             },
+            theme: ThemeConfig::default(),
             logging_enabled: true,
+            logging: LoggingConfig::default(),
             auto_generated: true,
             notifications_enabled: true,
+            focus_on_activate: true,
+            close_target: CloseTarget::Focused,
+            clipboard_manager: false,
+            limit_drag_rate: false,
+            startup_script: None,
+            wallpaper: None,
+            tag_mode: false,
+            workspaces: Vec::new(),
+            return_when_empty: ReturnWhenEmpty::Off,
+            dynamic_workspaces: false,
+            monitors: BTreeMap::new(),
+            game_mode: GameMode::default(),
+            kiosk: Kiosk::default(),
+            pointer_barriers: PointerBarriers::default(),
+            hot_corners: HotCorners::default(),
+            rules: Vec::new(),
+            screenshot: ScreenshotConfig::default(),
+            session: SessionConfig::default(),
+            hooks: Vec::new(),
+            migration: None,
+            errors: Vec::new(),
         }
     }
 }
 
 impl Config {
+    /// The output the workspace at `index` (0-based) is pinned to.
+    pub fn pinned_output(&self, index: usize) -> Option<&str> {
+        self.monitors
+            .iter()
+            .find(|(_, workspaces)| workspaces.contains(&(index + 1)))
+            .map(|(output, _)| output.as_str())
+    }
+
     pub fn get_keysym_for_key(&self, key: &str) -> u64 {
         keybind::get_keysym_for_key(key)
     }
 
+    /// Key combinations grabbed by more than one bind.
+    pub fn keybind_conflicts(&self) -> Vec<String> {
+        keybind::find_conflicts(&self.modifier, &self.binds)
+    }
+
     pub fn get_modifier(&self) -> u32 {
         keybind::get_modifier(&self.modifier)
     }
 
+    /// The modifiers `bind` is grabbed with.
+    pub fn get_bind_modifier(&self, bind: &Bind) -> u32 {
+        keybind::get_modifier(bind.modifier(&self.modifier))
+    }
+
+    /// Works out the colors, taking each from the first of: the config
+    /// file, the selected theme, the system colors when they are on, and
+    /// the defaults. Named colors are then looked up on the server.
+    ///
+    /// # Safety
+    /// The display pointer must be valid and point to an active X display connection.
+    pub unsafe fn resolve_colors(&mut self, display: *mut xlib::Display) -> Result<()> {
+        let mut errors = Vec::new();
+        self.appearance.reset_colors();
+        if self.appearance.system_colors {
+            self.appearance.apply_palette(&Palette::read(display));
+        }
+        match self.theme.selected() {
+            Ok(Some(theme)) => self.appearance.apply_theme(&theme),
+            Ok(None) => {}
+            Err(e) => errors.push(e.to_string()),
+        }
+        if let Err(e) = self.appearance.resolve_colors(display) {
+            errors.push(e.to_string());
+        }
+
+        if !errors.is_empty() {
+            anyhow::bail!("{}", errors.join(", "));
+        }
+        Ok(())
+    }
+
     pub fn get_border_color(&self) -> u64 {
         self.appearance.get_border_color()
     }
@@ -142,16 +274,117 @@ impl Config {
         self.appearance.get_focused_border_color()
     }
 
+    pub fn get_workspace_label(&self, workspace: usize, style: WorkspaceStyle) -> String {
+        let ws = self.workspaces.iter().find(|ws| ws.index == workspace + 1);
+        let label = match style {
+            WorkspaceStyle::Numbers => None,
+            WorkspaceStyle::Names => ws.and_then(|ws| ws.name.clone()),
+            WorkspaceStyle::Icons => ws.and_then(|ws| ws.icon.clone()),
+        };
+        label.unwrap_or_else(|| (workspace + 1).to_string())
+    }
+
+    pub fn get_layout_settings(&self, workspace: usize) -> LayoutSettings {
+        let mut settings = LayoutSettings::from_config(self);
+        if let Some(ws) = self.workspaces.iter().find(|ws| ws.index == workspace + 1) {
+            ws.apply(&mut settings);
+        }
+        settings
+    }
+
     pub fn load() -> Result<Self> {
         let config_path = Self::get_config_path()?;
 
         if !config_path.exists() {
-            Self::create_default_config(&config_path)?;
+            Self::create_default_config(&config_path, None)?;
         }
 
         let content = fs::read_to_string(&config_path).context("Failed to read config file")?;
+        let migration = migrate::migrate(&content)?;
+        let content = migration.as_ref().map_or(&content, |m| &m.content);
+
+        let mut config = Self::parse(content)?;
+        config.migration = migration;
+        Ok(config)
+    }
 
-        toml::from_str(&content).context("Failed to parse config file")
+    /// Parses a config file, dropping entries that don't deserialize so
+    /// one typo doesn't throw away the rest of the file. What was dropped
+    /// ends up in [`Config::errors`]. Only broken TOML syntax, or a file
+    /// that is still unusable after dropping entries, is an error.
+    pub fn parse(content: &str) -> Result<Self> {
+        let document = ImDocument::parse(content).context("Failed to parse config file")?;
+        let mut root = document.as_table().clone();
+        let mut errors = Vec::new();
+
+        loop {
+            let e = match toml_edit::de::from_document::<Config>(DocumentMut::from(root.clone())) {
+                Ok(mut config) => {
+                    config.errors = errors;
+                    let root = Item::Table(root);
+                    let keys = config.appearance.colors_mut().map(|(key, ..)| key);
+                    config.appearance.configured_colors = keys
+                        .into_iter()
+                        .filter(|key| {
+                            key.split('.')
+                                .try_fold(&root, |item, k| item.get(k))
+                                .is_some()
+                        })
+                        .map(String::from)
+                        .collect();
+                    if let Err(e) = config.theme.selected() {
+                        let line = root
+                            .get("theme")
+                            .and_then(|theme| theme.get("name"))
+                            .and_then(Item::span)
+                            .map_or(0, |span| content[..span.start].matches('\n').count() + 1);
+                        config
+                            .errors
+                            .push(format!("line {}, theme.name: {}", line, e));
+                        config.theme.name = None;
+                    }
+                    return Ok(config);
+                }
+                Err(e) => e,
+            };
+
+            let location = e.span().and_then(|span| {
+                let path =
+                    find_path(&Item::Table(root.clone()), &span).filter(|path| !path.is_empty())?;
+                let line = content[..span.start].matches('\n').count() + 1;
+                Some((path, line))
+            });
+            let Some((path, line)) = location else {
+                anyhow::bail!("Failed to parse config file: {}", e.message());
+            };
+
+            let error = format!("line {}, {}: {}", line, format_path(&path), e.message());
+            log::warn!("Ignoring invalid config entry at {}", error);
+            errors.push(error);
+            // A list entry with a bad field goes as a whole, a bind without
+            // its command, or with only part of it, is no use.
+            let entry = path
+                .iter()
+                .position(|segment| matches!(segment, Segment::Index(_)))
+                .map_or(path.len(), |i| i + 1);
+            remove_path(&mut root, &path[..entry]);
+        }
+    }
+
+    pub fn get_ready_file_path() -> Result<PathBuf> {
+        let home = std::env::var("HOME").context("Failed to get HOME directory")?;
+
+        Ok(PathBuf::from(home).join(".cache/velowm/ready"))
+    }
+
+    /// Whether there is no config file yet, on the very first start.
+    pub fn is_first_run() -> bool {
+        Self::get_config_path().is_ok_and(|path| !path.exists())
+    }
+
+    /// Writes a new config file with the choices made on the first start.
+    pub fn create(setup: &Setup) -> Result<()> {
+        Self::create_default_config(&Self::get_config_path()?, Some(setup))
     }
 
     pub fn get_config_path() -> Result<PathBuf> {
@@ -160,7 +393,7 @@ impl Config {
         Ok(PathBuf::from(home).join(".config/velowm/config.toml"))
     }
 
-    fn create_default_config(path: &PathBuf) -> Result<()> {
+    fn create_default_config(path: &PathBuf, setup: Option<&Setup>) -> Result<()> {
         let default_config = r###"# Global modifier key for all shortcuts
 # You can combine multiple modifiers with + like:
 # modifier = "alt+shift"
@@ -169,6 +402,7 @@ impl Config {
 modifier = "alt"
 
 # Enable or disable logging
+# Logs go to ~/.cache/velowm/velowm.log, see [logging] below
 logging_enabled = true
 
 # Set to false to disable the popup notification
@@ -177,25 +411,142 @@ auto_generated = true
 # Enable or disable notifications
 notifications_enabled = true
 
+# When an application asks to be activated, switch to its workspace
+# and focus it. When false, the window is marked urgent instead.
+focus_on_activate = true
+
+# The window close and kill act on: the "focused" one or the one under
+# the "pointer".
+close_target = "focused"
+
+# Keep what was copied to the clipboard after the application it came
+# from exits. Works with applications that hand the clipboard over on
+# exit (GTK, Qt, ...). Leave off when running another clipboard manager.
+clipboard_manager = false
+
+# Move and resize dragged windows at most once per frame of the screen's
+# refresh rate. Smooths out drags with high polling rate mice on slow
+# drivers.
+limit_drag_rate = false
+
+# Script run once the window manager is fully initialized (not on restart)
+# velowm also creates ~/.cache/velowm/ready while it is running, its path is
+# passed to the script as $VELOWM_READY_FILE
+# startup_script = "~/.config/velowm/autostart.sh"
+
+# Image (PNG, JPEG, ...) drawn on the root window, scaled to cover each
+# monitor. Decoding needs gdk-pixbuf. Change it at runtime over IPC with
+# "set_wallpaper <path>".
+# wallpaper = "~/pictures/wallpaper.png"
+
+# Use dwm-style tags instead of workspaces. A window can carry several
+# tags and any combination of tags can be viewed at once.
+# The workspace commands act on single tags in this mode.
+tag_mode = false
+
+# When the last window of the current workspace closes, switch to the
+# workspace shown before it ("previous") or to a fixed one (a number from
+# 1 to 10). "off" stays on the empty workspace.
+return_when_empty = "off"
+
+# Start with a single workspace and add more with "workspace new". Empty
+# workspaces are removed once another one is shown, and the ones after
+# them move up. workspace<N> counts the workspaces there are. Not used in
+# tag mode.
+dynamic_workspaces = false
+
 # Window appearance
 [appearance]
 # Border width in pixels
 border_width = 2
-# Border color in hex format (supports transparency)
-border_color = "#2B0000"
+# Colors are "#RGB", "#RRGGBB", "#RRGGBBAA" (with alpha) or X color
+# names such as "dark red". Invalid colors are reported and replaced
+# by their defaults. Colors left commented out come from [theme] or
+# the system theme, and are the defaults shown here otherwise.
+# Border color of unfocused windows
+# border_color = "#2B0000"
 # Border color for focused windows
-focused_border_color = "#FF0000"
-# Gap between windows in pixels
+# focused_border_color = "#FF0000"
+# Border color of unfocused windows asking for attention
+# urgent_border_color = "#FF8800"
+# Border color of unfocused windows shown on every workspace
+# (toggle_global)
+# sticky_border_color = "#005F5F"
+# Gap between windows in pixels. Dragging the gap between the master
+# and the stack area with the mouse moves the split.
 gaps = 8
+# Number of windows in the master area
+nmaster = 1
 # Whether focus follows mouse movement
 focus_follows_mouse = true
+# Size factor for the bar and notifications, e.g. 2.0 on a 4K screen.
+# When unset it follows the desktop DPI (Xft/DPI from an XSETTINGS
+# daemon, or the Xft.dpi X resource) relative to 96.
+# scale = 1.0
+# Take the colors not set in this file from the system theme: the
+# X resources loaded with xrdb (*.background, *.foreground, *.color1,
+# ...) or the Gtk/ColorScheme of an XSETTINGS daemon. A resource such
+# as velowm.bar.focused_color sets one color directly. Colors set in
+# this file or by [theme] take precedence.
+system_colors = false
 
 # Notification appearance
 [appearance.notification]
-# Background color for notification windows
-background_color = "#0F0F0F"
+# Background color for notification windows (alpha needs a compositor)
+# background_color = "#0F0F0F"
 # Border color for notification windows
-border_color = "#FF0000"
+# border_color = "#FF0000"
+
+# Built-in status bar
+[appearance.bar]
+# Show the bar at the top of the screen
+enabled = true
+# Monitors with a bar: "primary" (the RandR primary output, or the first
+# monitor when none is set) or "all"
+monitors = "primary"
+# Bar height in pixels
+height = 20
+# Xft font name
+font = "monospace:size=10"
+# Background color of the bar. With a compositing manager running the
+# alpha of "#RRGGBBAA" makes the bar translucent, e.g. "#0F0F0FCC"
+# background_color = "#0F0F0F"
+# How workspaces are labeled: numbers, names or icons
+# Names and icons are taken from the [[workspaces]] entries
+workspace_style = "numbers"
+# Workspace indicator colors per state
+# focused_color = "#FF0000"
+# occupied_color = "#FFFFFF"
+# urgent_color = "#FFA500"
+# empty_color = "#555555"
+# Commands run when scrolling over the bar. Over the layout indicator
+# scrolling cycles layouts instead. Use "none" to do nothing.
+scroll_up = "previous_workspace"
+scroll_down = "next_workspace"
+# Show the mark of the focused window before its title, e.g. "[a] vim"
+show_marks = false
+# Show the windows of a workspace while hovering its button
+preview = true
+
+# Built-in modules on the right end of the bar, in the order listed:
+# battery (read from /sys/class/power_supply, hidden without a battery)
+# network (first interface that is up, from /sys/class/net)
+# window_usage (CPU and memory of the focused window's process, from /proc)
+[appearance.bar.modules]
+right = []
+# right = ["network", "battery"]
+# Seconds between refreshes
+interval = 5
+
+# Long text in the bar is shortened with an ellipsis
+[appearance.bar.truncate]
+# Maximum width of the focused window's title in pixels
+# 0 only limits it to the space between the other segments
+title = 400
+# Maximum width of each module in pixels, 0 for no limit
+module = 0
+# Where text is cut: "end" or "middle"
+ellipsis = "end"
 
 # Floating window settings
 [appearance.floating]
@@ -205,16 +556,231 @@ center_on_float = true
 width = 800
 # Default height for floating windows
 height = 600
+# Show the size while resizing with the mouse, in characters for
+# terminals ("80x24") and in pixels for other windows
+show_size = false
+# Pixels grow_width, shrink_width, grow_height and shrink_height resize by
+resize_step = 50
+
+# Slide the windows of the old workspace out and those of the new one in
+# when switching workspaces. Never while a fullscreen window is shown on
+# either of them or in game mode.
+[appearance.slide]
+enabled = false
+# Length of the slide in milliseconds, at most 500
+duration = 150
+
+# Color themes for borders, the bar and notifications. Colors set under
+# [appearance] override the theme's.
+[theme]
+# A preset (gruvbox, nord, dracula, solarized) or a custom theme below.
+# Switch at runtime with the set_theme command.
+# name = "nord"
+
+# Custom themes take the color keys of [appearance], any left out keep
+# their defaults:
+# [theme.custom.mine]
+# border_color = "#333333"
+# focused_border_color = "#88C0D0"
+# notification = { background_color = "#222222", border_color = "#88C0D0" }
+# bar = { background_color = "#222222", focused_color = "#88C0D0" }
+
+# Game mode locks all binds except toggle_game_mode, disables
+# focus follows mouse and keeps the bar and notifications below
+# the focused fullscreen window
+[game_mode]
+# Enter game mode automatically when a fullscreen window is focused
+auto = false
+
+# Kiosk mode for dedicated displays: binds, hot corners and IPC only run
+# the allowed commands, so exit, restart, close and spawn are off unless
+# listed (velowm msg answers others with an error). Messages from clients other than the application, such as
+# requests to switch workspaces or activate windows, are ignored.
+[kiosk]
+enabled = false
+# allowed_commands = ["toggle_bar"]
+# Started fullscreen, and started again whenever it exits
+# application = "firefox --kiosk https://example.com"
+
+# Pointer barriers between monitors, so the cursor stops at the edge
+# instead of drifting onto the next monitor
+[pointer_barriers]
+enabled = false
+# How far (in pixels) the pointer has to be pushed against the edge
+# before it is let through
+resistance = 100
+# Hold this modifier to cross the edge without resistance
+bypass_modifier = "shift"
+
+# Commands run when the pointer rests in a corner of a monitor
+# Accepts the same commands as keybindings
+[hot_corners]
+# top_left = "workspace1"
+# bottom_right = "spawn slock"
+# Corner size in pixels
+size = 2
+# How long the pointer has to stay in the corner, in milliseconds
+dwell_ms = 150
+# Minimum time between two triggers, in milliseconds
+cooldown_ms = 1000
+
+# Where the screenshot command saves its PNGs
+[screenshot]
+# "~" is your home directory, it is created when missing
+directory = "~/Pictures/screenshots"
+
+# Log levels and rotation
+# RUST_LOG overrides the levels when set. They can be changed at runtime
+# with: velowm msg log_level "info,velowm::ui=debug"
+[logging]
+# Default level: error, warn, info, debug or trace
+level = "debug"
+# Rotate velowm.log once it is larger than this, in kilobytes
+max_size_kb = 1024
+# Number of rotated logs kept (velowm.log.1, velowm.log.2, ...)
+max_files = 3
+
+# Per-module levels
+[logging.modules]
+# "velowm::ui::bar" = "warn"
+
+# Remember where windows were when velowm exits, so the usual
+# arrangement comes back on the next start (even after a reboot)
+# Windows are recognized by their WM_CLASS
+[session]
+# Save the workspace and floating geometry of every window on exit
+save = false
+# Launch the saved applications again on the next start
+relaunch = false
+
+# Window rules (optional)
+# A rule matches on class, instance and/or title (exact match) and
+# applies to windows when they are first mapped. The first match wins.
+# [[rules]]
+# class = "Pavucontrol"
+# workspace = 3
+# floating = true
+# Where a floating window opens and its size: pixels (x and y on the
+# whole screen) or a percentage of the monitor, such as "25%"
+# x = 100
+# y = 100
+# width = 600
+# height = "50%"
+# Keep the screen on while the window is focused (fullscreen windows
+# always do unless this is false)
+# inhibit_idle = true
+# Float the window on every workspace, as with toggle_global
+# global = true
+# Open the window on a monitor: an output name, "primary" or a 1-based
+# index. With workspace set too, that workspace tiles on this monitor.
+# monitor = "HDMI-1"
+# Never focus the window, e.g. an on-screen keyboard
+# focus = false
+# Ignore the window asking to go fullscreen by itself
+# allow_fullscreen = false
+# Tile the window even when it calls itself a dialog
+# force_tiling = true
+# Leave the window alone entirely, e.g. a screen magnifier or an input
+# method popup: it is mapped but gets no border, focus or workspace
+# unmanaged = true
+
+# Hooks run a shell command whenever something happens (optional)
+# Events: window_mapped, window_closed, workspace_changed, focus_changed,
+# monitor_changed
+# The command gets the details in its environment:
+#   VELOWM_EVENT      the event name
+#   VELOWM_WINDOW     window id in hex (empty when focus was lost)
+#   VELOWM_WORKSPACE  1-based workspace (the first tag in tag mode)
+#   VELOWM_MONITOR    0-based monitor index
+# [[hooks]]
+# event = "workspace_changed"
+# command = "notify-send \"Workspace $VELOWM_WORKSPACE\""
+#
+# Bars can follow the same events without a hook: `velowm msg subscribe`
+# prints one JSON object per line, plus title_changed whenever the focused
+# window or its title changes.
+
+# Per-workspace layout settings (optional)
+# Values not set here fall back to the global defaults.
+# Available layouts: master_stack, monocle
+# [[workspaces]]
+# index = 1
+# name = "web"
+# icon = "\uf269"
+# layout = "master_stack"
+# master_ratio = 0.6
+# gaps = 4
+# nmaster = 1
+
+# Pin workspaces to monitors by output name (see `xrandr --listmonitors`).
+# Their windows are tiled on that monitor wherever the pointer is. While
+# the monitor is unplugged they fall back to the primary one.
+[monitors]
+# "DP-1" = [1, 2, 3, 4, 5]
+# "HDMI-1" = [6, 7, 8, 9, 10]
+# "primary" stands for whichever output is the RandR primary one
+# primary = [1, 2]
 
 # Keybindings
 # Format: bind = key,command
 # Commands:
 #   - exit: Exit the window manager
-#   - close: Close focused window
-#   - workspace<N>: Switch to workspace N (1-10)
+#   - restart: Restart the window manager in place, keeping windows and layouts
+#   - reload_config: Reload this file (also done automatically when it is saved)
+#   - show_binds: List every bind by category, any key closes the list
+#   - set_theme <name>: Switch to another theme until velowm exits (kept across restarts)
+#   - close: Close the focused window, or the one under the pointer (see close_target)
+#   - kill: Disconnect the client of that window, for windows that hang and ignore close
+#   - workspace<N>: Switch to workspace N (1-32), if there are that many
+#   - workspace new: Add a workspace at the end and switch to it (dynamic_workspaces only)
+#   - next_workspace / previous_workspace: Switch to the neighbouring workspace, wrapping around
+#   - move_to_workspace<N>: Move focused window to workspace N (1-32), if there are that many
 #   - toggle_float: Toggle floating mode for focused window
+#   - toggle_global: Float the focused window on every workspace, it comes along on each switch
+#   - float_all / tile_all: Float every window of the current workspace where it is, or tile them all
+#   - center_window: Center the focused floating window on its monitor
+#   - move_to_corner <tl|tr|bl|br>: Move the focused floating window into a corner of its monitor
+#   - move_to_monitor <N>: Move the focused floating window to monitor N (1 is the first)
+#   - move_window_to_monitor <next|prev|primary|output>: Send the focused window to the workspace shown on another monitor
+#   - move_workspace_to_monitor <next|prev|primary|output>: Move the current workspace with all its windows to another monitor, e.g. "move_workspace_to_monitor HDMI-1"
+#   - grow_width / shrink_width / grow_height / shrink_height: Resize the focused floating window around its center
+#   - focus_primary_monitor: Switch to the workspace shown on the primary monitor and move the pointer there
 #   - toggle_fullscreen: Toggle fullscreen mode for focused window
-#   - Any other string will be executed as a command
+#   - fullscreen_all_monitors: Toggle fullscreen spanning all monitors
+#   - increase_masters: Add one window to the master area
+#   - decrease_masters: Remove one window from the master area
+#   - toggle_game_mode: Leave or re-enter game mode (stays active in game mode)
+#   - increase_master_ratio / decrease_master_ratio: Resize the master area
+#   - increase_gaps / decrease_gaps: Change the gaps of the current workspace
+#   - reset_layout: Reset the current workspace layout to the configured values
+#   - rotate_stack_cw / rotate_stack_ccw: Move every tiled window one place on, the last one (or the master) wrapping around
+#   - reverse_stack: Turn the order of the tiled windows around
+#   - cycle_layout: Switch the current workspace to the next layout
+#   - toggle_bar: Show or hide the bar until velowm exits (kept across restarts)
+#   - view_tag<N>: Show only windows tagged N (1-32), same as workspace<N> without tag mode
+#   - toggle_view_tag<N>: Add or remove tag N from the current view (tag mode only)
+#   - tag_window<N>: Give the focused window only tag N, same as move_to_workspace<N>
+#   - toggle_window_tag<N>: Add or remove tag N on the focused window (tag mode only)
+#   - mark <char>: Mark the focused window with a character, taking it from any other window
+#   - goto_mark <char>: Switch to the workspace of the marked window and focus it
+#   - screenshot <full|window|region>: Save a screenshot of all monitors, the focused window or a region dragged out with the mouse (Escape cancels)
+#   - spawn <command>: Run a program, e.g. "spawn alacritty"
+# A list of commands runs them in order, e.g.
+#   command = ["workspace3", "spawn firefox"]
+# A table sets the working directory ("~" is your home) and environment
+# of a program, e.g.
+#   command = { spawn = "alacritty", cwd = "~/projects", env = { FOO = "1" } }
+# Keys are X keysym names, e.g. "q", "space", "Return", "Escape", "F1" or
+# "XF86AudioRaiseVolume" (see `xev` for the name of a key)
+# A bind can use other modifiers than the global one, e.g.
+#   [[binds]]
+#   key = "F4"
+#   modifier = "alt"
+#   command = "close"
+# on_release = true runs a bind when the key is let go instead of when it
+# is pressed, repeat = false keeps a held key from running it again.
+# confirm = true asks before running the bind, in a dialog answered with
+# y/n, or with Left/Right and Return
 [[binds]]
 key = "w"
 command = "exit"
@@ -235,6 +801,10 @@ command = "toggle_float"
 key = "f"
 command = "toggle_fullscreen"
 
+[[binds]]
+key = "slash"
+command = "show_binds"
+
 # Workspace bindings
 [[binds]]
 key = "1"
@@ -280,6 +850,97 @@ command = "workspace10""###;
             fs::create_dir_all(parent).context("Failed to create config directory")?;
         }
 
-        fs::write(path, default_config).context("Failed to write default config")
+        let content = migrate::version_line() + default_config;
+        let content = match setup {
+            Some(setup) => setup.apply(&content)?,
+            None => content,
+        };
+        fs::write(path, content).context("Failed to write default config")
+    }
+}
+
+/// A step from a TOML item to one of its children.
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// The path to the innermost item whose source covers `span`.
+fn find_path(item: &Item, span: &Range<usize>) -> Option<Vec<Segment>> {
+    let children: Vec<(Segment, Item)> = match item {
+        Item::Table(table) => table
+            .iter()
+            .map(|(key, child)| (Segment::Key(key.to_string()), child.clone()))
+            .collect(),
+        Item::ArrayOfTables(tables) => tables
+            .iter()
+            .enumerate()
+            .map(|(i, table)| (Segment::Index(i), Item::Table(table.clone())))
+            .collect(),
+        Item::Value(Value::InlineTable(table)) => table
+            .iter()
+            .map(|(key, child)| (Segment::Key(key.to_string()), Item::Value(child.clone())))
+            .collect(),
+        Item::Value(Value::Array(array)) => array
+            .iter()
+            .enumerate()
+            .map(|(i, child)| (Segment::Index(i), Item::Value(child.clone())))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    for (segment, child) in children {
+        if let Some(mut path) = find_path(&child, span) {
+            path.insert(0, segment);
+            return Some(path);
+        }
     }
+
+    item.span()
+        .filter(|own| own.start <= span.start && span.end <= own.end)
+        .map(|_| Vec::new())
+}
+
+fn remove_path(root: &mut Table, path: &[Segment]) {
+    let Some((Segment::Key(first), rest)) = path.split_first() else {
+        return;
+    };
+    let Some((last, parents)) = rest.split_last() else {
+        root.remove(first);
+        return;
+    };
+    let mut parent = root.get_mut(first);
+    for segment in parents {
+        parent = parent.and_then(|item| match segment {
+            Segment::Key(key) => item.get_mut(key),
+            Segment::Index(i) => item.get_mut(i),
+        });
+    }
+
+    match (parent, last) {
+        (Some(Item::Table(table)), Segment::Key(key)) => {
+            table.remove(key);
+        }
+        (Some(Item::Value(Value::InlineTable(table))), Segment::Key(key)) => {
+            table.remove(key);
+        }
+        (Some(Item::ArrayOfTables(tables)), Segment::Index(i)) => tables.remove(*i),
+        (Some(Item::Value(Value::Array(array))), Segment::Index(i)) => {
+            array.remove(*i);
+        }
+        _ => {}
+    }
+}
+
+/// Keys only, e.g. `binds.command`. The line number already says which
+/// entry of an array it was.
+fn format_path(path: &[Segment]) -> String {
+    let keys: Vec<&str> = path
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Key(key) => Some(key.as_str()),
+            Segment::Index(_) => None,
+        })
+        .collect();
+    keys.join(".")
 }
@@ -0,0 +1,51 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Deserialize, Clone)]
+pub struct LoggingConfig {
+    #[serde(default = "default_level")]
+    pub level: String,
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+    #[serde(default = "default_max_size_kb")]
+    pub max_size_kb: u64,
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_level(),
+            modules: BTreeMap::new(),
+            max_size_kb: default_max_size_kb(),
+            max_files: default_max_files(),
+        }
+    }
+}
+
+fn default_level() -> String {
+    String::from("debug")
+}
+
+fn default_max_size_kb() -> u64 {
+    1024
+}
+
+fn default_max_files() -> usize {
+    3
+}
+
+impl LoggingConfig {
+    /// The configured levels as a `RUST_LOG` style filter.
+    pub fn filter_spec(&self) -> String {
+        std::iter::once(self.level.clone())
+            .chain(
+                self.modules
+                    .iter()
+                    .map(|(module, level)| format!("{}={}", module, level)),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use toml_edit::{DocumentMut, Item, Value};
+
+use crate::utils::command::Command;
+
+/// Version written to new config files. Files without a `version` key are
+/// version 1.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// One upgrade step, from the version at its index + 1 to the next one.
+/// Each step records a line per change it makes.
+type Step = fn(&mut DocumentMut, &mut Vec<String>);
+
+const STEPS: [Step; CURRENT_VERSION as usize - 1] = [v1_spawn_commands];
+
+/// An upgraded config file, not yet written to disk.
+#[derive(Clone, Debug)]
+pub struct Migration {
+    pub from: u32,
+    /// The upgraded file. Comments and formatting are kept.
+    pub content: String,
+    /// What changed, for telling the user.
+    pub changes: Vec<String>,
+}
+
+/// Upgrades a config file to [`CURRENT_VERSION`]. Returns `None` when it
+/// is already up to date.
+pub fn migrate(content: &str) -> Result<Option<Migration>> {
+    let mut document = content
+        .parse::<DocumentMut>()
+        .context("Failed to parse config file")?;
+
+    let from = match document.get("version") {
+        None => 1,
+        Some(version) => match version.as_integer() {
+            Some(version) if version >= 1 => version as u32,
+            _ => bail!("Invalid config version, expected a number from 1 up"),
+        },
+    };
+    if from > CURRENT_VERSION {
+        log::warn!(
+            "Config version {} is newer than this velowm understands ({})",
+            from,
+            CURRENT_VERSION
+        );
+        return Ok(None);
+    }
+    if from == CURRENT_VERSION {
+        return Ok(None);
+    }
+
+    let mut changes = Vec::new();
+    for step in &STEPS[from as usize - 1..] {
+        step(&mut document, &mut changes);
+    }
+
+    changes.push(format!("Set version to {}", CURRENT_VERSION));
+    let content = if document.contains_key("version") {
+        document["version"] = Item::Value(Value::from(CURRENT_VERSION as i64));
+        document.to_string()
+    } else {
+        // At the top of the file, where the template has it.
+        format!("{}{}", version_line(), document)
+    };
+
+    Ok(Some(Migration {
+        from,
+        content,
+        changes,
+    }))
+}
+
+/// The `version` key as written to new and upgraded files.
+pub fn version_line() -> String {
+    format!(
+        "# Config format version, older files are upgraded automatically\nversion = {}\n\n",
+        CURRENT_VERSION
+    )
+}
+
+impl Migration {
+    /// Copies the original file next to it as `<name>.v<from>.bak` and
+    /// writes the upgraded one in its place. Returns the backup path.
+    pub fn apply(&self, path: &Path) -> Result<PathBuf> {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(format!(".v{}.bak", self.from));
+        let backup = PathBuf::from(backup);
+
+        fs::copy(path, &backup)
+            .with_context(|| format!("Failed to back up config to {}", backup.display()))?;
+        fs::write(path, &self.content).context("Failed to write upgraded config")?;
+        Ok(backup)
+    }
+
+    /// A summary for the notification shown after upgrading.
+    pub fn summary(&self, backup: &Path) -> String {
+        let mut summary = format!(
+            "Config upgraded from version {} to {}\nThe old file is at {}\n",
+            self.from,
+            CURRENT_VERSION,
+            backup.display()
+        );
+        for change in &self.changes {
+            summary.push_str("\n- ");
+            summary.push_str(change);
+        }
+        summary
+    }
+}
+
+/// Version 1 ran bind commands it didn't know as programs
+/// (`command = "alacritty"`). They are spelled `spawn alacritty` now.
+fn v1_spawn_commands(document: &mut DocumentMut, changes: &mut Vec<String>) {
+    let Some(binds) = document
+        .get_mut("binds")
+        .and_then(Item::as_array_of_tables_mut)
+    else {
+        return;
+    };
+
+    for bind in binds.iter_mut() {
+        let Some(command) = bind.get("command").and_then(Item::as_str) else {
+            continue;
+        };
+        if command.parse::<Command>().is_ok() {
+            continue;
+        }
+
+        let spawn = format!("spawn {}", command);
+        changes.push(format!(
+            "Changed bind command \"{}\" to \"{}\"",
+            command, spawn
+        ));
+        let decor = bind["command"].as_value().map(|v| v.decor().clone());
+        let mut value = Value::from(spawn);
+        if let Some(decor) = decor {
+            *value.decor_mut() = decor;
+        }
+        bind["command"] = Item::Value(value);
+    }
+}
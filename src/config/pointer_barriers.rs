@@ -0,0 +1,29 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+pub struct PointerBarriers {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_resistance")]
+    pub resistance: u32,
+    #[serde(default = "default_bypass_modifier")]
+    pub bypass_modifier: String,
+}
+
+impl Default for PointerBarriers {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            resistance: default_resistance(),
+            bypass_modifier: default_bypass_modifier(),
+        }
+    }
+}
+
+fn default_resistance() -> u32 {
+    100
+}
+
+fn default_bypass_modifier() -> String {
+    String::from("shift")
+}
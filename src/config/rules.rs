@@ -0,0 +1,160 @@
+use serde::{de, Deserialize, Serialize, Serializer};
+
+/// Placement applied to newly mapped windows that match `class`, `instance`
+/// and `title`. Unset matchers match anything.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Rule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub floating: Option<bool>,
+    /// Where a floating window opens. Pixels are on the root window,
+    /// percentages of the monitor it opens on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<Length>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<Length>,
+    /// The size of a floating window, in pixels or as a percentage of the
+    /// monitor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<Length>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<Length>,
+    /// Keep the screen from blanking while the window is focused, or with
+    /// `false` not even when it is fullscreen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inhibit_idle: Option<bool>,
+    /// Float the window on every workspace, like `toggle_global`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global: Option<bool>,
+    /// The monitor the window opens on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<RuleMonitor>,
+    /// With `false` the window is never focused, not even when clicked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus: Option<bool>,
+    /// With `false` the client's own requests to go fullscreen are ignored,
+    /// `toggle_fullscreen` still works.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_fullscreen: Option<bool>,
+    /// Tile the window even if it says it is a dialog, over `floating` and
+    /// `global`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub force_tiling: Option<bool>,
+    /// With `true` the window is mapped as it asks and otherwise left
+    /// alone: no border, no focus, no place in a workspace or the layout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unmanaged: Option<bool>,
+}
+
+/// A monitor named in a rule: a RandR output such as "HDMI-1", "primary",
+/// or its 1-based position among the monitors.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum RuleMonitor {
+    Index(usize),
+    Output(String),
+}
+
+/// A position or size in a rule: pixels, or a percentage of the monitor
+/// written as a string like "50%".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Length {
+    Pixels(i32),
+    Percent(f32),
+}
+
+impl Length {
+    /// The position on the root window, for a monitor spanning `size`
+    /// pixels from `start` along the same axis.
+    pub fn position(self, start: i32, size: u32) -> i32 {
+        match self {
+            Self::Pixels(pixels) => pixels,
+            Self::Percent(_) => start + self.size(size) as i32,
+        }
+    }
+
+    /// The size in pixels, for a monitor `size` pixels long along the same
+    /// axis. Never less than one pixel.
+    pub fn size(self, size: u32) -> u32 {
+        let pixels = match self {
+            Self::Pixels(pixels) => pixels,
+            Self::Percent(percent) => (size as f32 * percent / 100.0).round() as i32,
+        };
+        pixels.max(1) as u32
+    }
+}
+
+impl<'de> Deserialize<'de> for Length {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Pixels(i32),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Pixels(pixels) => Ok(Self::Pixels(pixels)),
+            Raw::Text(text) => text
+                .trim()
+                .strip_suffix('%')
+                .and_then(|percent| percent.trim().parse::<f32>().ok())
+                .filter(|percent| (0.0..=100.0).contains(percent))
+                .map(Self::Percent)
+                .ok_or_else(|| {
+                    de::Error::custom(format!(
+                        "Invalid length '{}', expected pixels or a percentage like \"50%\"",
+                        text
+                    ))
+                }),
+        }
+    }
+}
+
+impl Serialize for Length {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Pixels(pixels) => serializer.serialize_i32(*pixels),
+            Self::Percent(percent) => serializer.serialize_str(&format!("{}%", percent)),
+        }
+    }
+}
+
+/// The properties of a window that rules are matched against.
+pub struct WindowProperties {
+    pub class: String,
+    pub instance: String,
+    pub title: String,
+}
+
+impl Rule {
+    pub fn matches(&self, props: &WindowProperties) -> bool {
+        let matches = |pattern: &Option<String>, value: &str| {
+            pattern.as_ref().is_none_or(|pattern| pattern == value)
+        };
+
+        (self.class.is_some() || self.instance.is_some() || self.title.is_some())
+            && matches(&self.class, &props.class)
+            && matches(&self.instance, &props.instance)
+            && matches(&self.title, &props.title)
+    }
+
+    /// The 0-based workspace index, `workspace` itself is 1-based like in `[[workspaces]]`.
+    pub fn workspace_index(&self) -> Option<usize> {
+        self.workspace
+            .filter(|&ws| (1..=10).contains(&ws))
+            .map(|ws| ws - 1)
+    }
+}
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+pub struct ScreenshotConfig {
+    /// Where screenshots are saved, "~" is the home directory.
+    #[serde(default = "default_directory")]
+    pub directory: String,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            directory: default_directory(),
+        }
+    }
+}
+
+fn default_directory() -> String {
+    "~/Pictures/screenshots".to_string()
+}
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Default, Clone)]
+pub struct SessionConfig {
+    #[serde(default)]
+    pub save: bool,
+    #[serde(default)]
+    pub relaunch: bool,
+}
@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use std::{env, path::Path};
+use toml_edit::{value, ArrayOfTables, DocumentMut, Table};
+
+/// Modifiers offered on the first start.
+pub const MODIFIERS: [&str; 2] = ["alt", "super"];
+
+/// Terminals looked for on the first start, in the order they are offered.
+const TERMINALS: [&str; 10] = [
+    "alacritty",
+    "kitty",
+    "wezterm",
+    "st",
+    "urxvt",
+    "xfce4-terminal",
+    "gnome-terminal",
+    "konsole",
+    "terminator",
+    "xterm",
+];
+
+/// Launchers looked for on the first start, with the command they are
+/// bound to.
+const LAUNCHERS: [(&str, &str); 3] = [
+    ("rofi", "rofi -show drun"),
+    ("dmenu_run", "dmenu_run"),
+    ("j4-dmenu-desktop", "j4-dmenu-desktop"),
+];
+
+/// Key the launcher is bound to, free in the default config.
+const LAUNCHER_KEY: &str = "d";
+
+/// What was picked on the first start, written into the new config file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Setup {
+    pub modifier: String,
+    pub terminal: String,
+    /// The command that opens the launcher, if one was picked.
+    pub launcher: Option<String>,
+}
+
+impl Setup {
+    /// Sets the modifier, binds the terminal where the default config binds
+    /// alacritty and adds a bind for the launcher. Comments are kept.
+    pub fn apply(&self, config: &str) -> Result<String> {
+        let mut document = config
+            .parse::<DocumentMut>()
+            .context("Failed to parse default config")?;
+        document["modifier"] = value(&self.modifier);
+
+        let binds = document
+            .get_mut("binds")
+            .and_then(|binds| binds.as_array_of_tables_mut());
+        let binds = match binds {
+            Some(binds) => binds,
+            None => {
+                document["binds"] = ArrayOfTables::new().into();
+                document["binds"].as_array_of_tables_mut().unwrap()
+            }
+        };
+        for bind in binds.iter_mut() {
+            if bind.get("command").and_then(|c| c.as_str()) == Some("spawn alacritty") {
+                bind["command"] = value(format!("spawn {}", self.terminal));
+            }
+        }
+        if let Some(launcher) = &self.launcher {
+            let mut bind = Table::new();
+            bind["key"] = value(LAUNCHER_KEY);
+            bind["command"] = value(format!("spawn {}", launcher));
+            binds.push(bind);
+        }
+        Ok(document.to_string())
+    }
+}
+
+/// Whether `program` is an executable in `$PATH`.
+fn installed(program: &str) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    env::var_os("PATH").is_some_and(|path| {
+        env::split_paths(&path).any(|dir| {
+            Path::new(&dir)
+                .join(program)
+                .metadata()
+                .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        })
+    })
+}
+
+/// The known terminals that are installed.
+pub fn detect_terminals() -> Vec<&'static str> {
+    TERMINALS.into_iter().filter(|t| installed(t)).collect()
+}
+
+/// The known launchers that are installed, as their name and command.
+pub fn detect_launchers() -> Vec<(&'static str, &'static str)> {
+    LAUNCHERS
+        .into_iter()
+        .filter(|(name, _)| installed(name))
+        .collect()
+}
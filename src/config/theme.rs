@@ -0,0 +1,133 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use super::color::Color;
+
+/// Themes that ship with the window manager, in the same format as
+/// `[theme.custom.<name>]`.
+const PRESETS: &str = r##"
+[gruvbox]
+border_color = "#3C3836"
+focused_border_color = "#D79921"
+urgent_border_color = "#FB4934"
+sticky_border_color = "#689D6A"
+notification = { background_color = "#282828", border_color = "#D79921" }
+bar = { background_color = "#1D2021", focused_color = "#FABD2F", occupied_color = "#EBDBB2", urgent_color = "#FB4934", empty_color = "#665C54" }
+
+[nord]
+border_color = "#3B4252"
+focused_border_color = "#88C0D0"
+urgent_border_color = "#BF616A"
+sticky_border_color = "#A3BE8C"
+notification = { background_color = "#2E3440", border_color = "#88C0D0" }
+bar = { background_color = "#2E3440", focused_color = "#88C0D0", occupied_color = "#D8DEE9", urgent_color = "#BF616A", empty_color = "#4C566A" }
+
+[dracula]
+border_color = "#44475A"
+focused_border_color = "#BD93F9"
+urgent_border_color = "#FF5555"
+sticky_border_color = "#8BE9FD"
+notification = { background_color = "#282A36", border_color = "#BD93F9" }
+bar = { background_color = "#21222C", focused_color = "#BD93F9", occupied_color = "#F8F8F2", urgent_color = "#FF5555", empty_color = "#6272A4" }
+
+[solarized]
+border_color = "#073642"
+focused_border_color = "#268BD2"
+urgent_border_color = "#DC322F"
+sticky_border_color = "#2AA198"
+notification = { background_color = "#002B36", border_color = "#268BD2" }
+bar = { background_color = "#002B36", focused_color = "#268BD2", occupied_color = "#93A1A1", urgent_color = "#DC322F", empty_color = "#586E75" }
+"##;
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationTheme {
+    pub background_color: Option<Color>,
+    pub border_color: Option<Color>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct BarTheme {
+    pub background_color: Option<Color>,
+    pub focused_color: Option<Color>,
+    pub occupied_color: Option<Color>,
+    pub urgent_color: Option<Color>,
+    pub empty_color: Option<Color>,
+}
+
+/// A set of colors laid out like `[appearance]`. Colors it leaves out are
+/// the defaults, or the system colors when those are on.
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    pub border_color: Option<Color>,
+    pub focused_border_color: Option<Color>,
+    pub urgent_border_color: Option<Color>,
+    pub sticky_border_color: Option<Color>,
+    #[serde(default)]
+    pub notification: NotificationTheme,
+    #[serde(default)]
+    pub bar: BarTheme,
+}
+
+impl Theme {
+    /// Returns the color for a key below `appearance`, such as
+    /// `bar.focused_color`.
+    pub fn color(&self, key: &str) -> Option<&Color> {
+        match key {
+            "border_color" => self.border_color.as_ref(),
+            "focused_border_color" => self.focused_border_color.as_ref(),
+            "urgent_border_color" => self.urgent_border_color.as_ref(),
+            "sticky_border_color" => self.sticky_border_color.as_ref(),
+            "notification.background_color" => self.notification.background_color.as_ref(),
+            "notification.border_color" => self.notification.border_color.as_ref(),
+            "bar.background_color" => self.bar.background_color.as_ref(),
+            "bar.focused_color" => self.bar.focused_color.as_ref(),
+            "bar.occupied_color" => self.bar.occupied_color.as_ref(),
+            "bar.urgent_color" => self.bar.urgent_color.as_ref(),
+            "bar.empty_color" => self.bar.empty_color.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct ThemeConfig {
+    /// A preset or one of the custom themes. Unset keeps the colors of
+    /// `[appearance]`.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub custom: BTreeMap<String, Theme>,
+}
+
+impl ThemeConfig {
+    /// Looks up a theme, preferring custom themes over presets of the same
+    /// name.
+    pub fn get(&self, name: &str) -> Result<Theme> {
+        if let Some(theme) = self.custom.get(name) {
+            return Ok(theme.clone());
+        }
+        let mut presets = presets();
+        presets.remove(name).ok_or_else(|| {
+            let names: Vec<&str> = presets
+                .keys()
+                .chain(self.custom.keys())
+                .map(String::as_str)
+                .collect();
+            anyhow!("Unknown theme '{}', available: {}", name, names.join(", "))
+        })
+    }
+
+    /// The selected theme, if any.
+    pub fn selected(&self) -> Result<Option<Theme>> {
+        self.name.as_deref().map(|name| self.get(name)).transpose()
+    }
+}
+
+/// The preset themes by name.
+pub fn presets() -> BTreeMap<String, Theme> {
+    toml::from_str(PRESETS).unwrap_or_default()
+}
@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Context, Result};
+use std::{
+    ffi::{CString, OsStr},
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::Path,
+};
+
+/// Watches the directory of the config file with inotify. Watching the
+/// directory rather than the file keeps working for editors that save by
+/// renaming a new file over the old one.
+pub struct ConfigWatcher {
+    fd: libc::c_int,
+    file_name: Vec<u8>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> Result<Self> {
+        let dir = path
+            .parent()
+            .ok_or_else(|| anyhow!("Config path has no parent directory"))?;
+        let file_name = path
+            .file_name()
+            .map(OsStr::as_bytes)
+            .ok_or_else(|| anyhow!("Config path has no file name"))?
+            .to_vec();
+        let c_dir = CString::new(dir.as_os_str().to_os_string().into_vec())
+            .context("Invalid config directory")?;
+
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error())
+                .context("Failed to create inotify instance");
+        }
+
+        let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE;
+        if unsafe { libc::inotify_add_watch(fd, c_dir.as_ptr(), mask) } < 0 {
+            let err = std::io::Error::last_os_error();
+            unsafe {
+                libc::close(fd);
+            }
+            return Err(err).with_context(|| format!("Failed to watch {}", dir.display()));
+        }
+
+        Ok(Self { fd, file_name })
+    }
+
+    pub fn fd(&self) -> libc::c_int {
+        self.fd
+    }
+
+    /// Drains pending events and returns whether the config file was written.
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        let mut buf = [0u8; 4096];
+        let header = std::mem::size_of::<libc::inotify_event>();
+
+        loop {
+            let len = unsafe { libc::read(self.fd, buf.as_mut_ptr() as *mut _, buf.len()) };
+            if len <= 0 {
+                break;
+            }
+
+            let mut offset = 0;
+            while offset + header <= len as usize {
+                let event = unsafe {
+                    std::ptr::read_unaligned(buf[offset..].as_ptr() as *const libc::inotify_event)
+                };
+                let name = &buf[offset + header..offset + header + event.len as usize];
+                let name = name.split(|&b| b == 0).next().unwrap_or_default();
+                if name == self.file_name.as_slice() {
+                    changed = true;
+                }
+                offset += header + event.len as usize;
+            }
+        }
+
+        changed
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
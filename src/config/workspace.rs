@@ -0,0 +1,77 @@
+use serde::{de, Deserialize};
+
+use crate::ui::layout::{LayoutKind, LayoutSettings};
+
+#[derive(Deserialize, Clone)]
+pub struct WorkspaceConfig {
+    pub index: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub layout: Option<LayoutKind>,
+    #[serde(default)]
+    pub master_ratio: Option<f32>,
+    #[serde(default)]
+    pub gaps: Option<u32>,
+    #[serde(default)]
+    pub nmaster: Option<usize>,
+}
+
+impl WorkspaceConfig {
+    pub fn apply(&self, settings: &mut LayoutSettings) {
+        if let Some(kind) = self.layout {
+            settings.kind = kind;
+        }
+        if let Some(ratio) = self.master_ratio {
+            settings.master_ratio = ratio.clamp(0.1, 0.9);
+        }
+        if let Some(gaps) = self.gaps {
+            settings.gaps = gaps;
+        }
+        if let Some(nmaster) = self.nmaster {
+            settings.nmaster = nmaster;
+        }
+    }
+}
+
+/// Where to go once the last window of the current workspace closes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReturnWhenEmpty {
+    /// Stay on the empty workspace.
+    #[default]
+    Off,
+    /// The workspace shown before this one.
+    Previous,
+    /// A fixed workspace, 0-based.
+    Home(usize),
+}
+
+impl<'de> Deserialize<'de> for ReturnWhenEmpty {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(usize),
+            Name(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(n) if (1..=10).contains(&n) => Ok(Self::Home(n - 1)),
+            Raw::Name(name) if name == "off" => Ok(Self::Off),
+            Raw::Name(name) if name == "previous" => Ok(Self::Previous),
+            Raw::Number(n) => Err(de::Error::custom(format!(
+                "Invalid workspace {}, expected 1-10",
+                n
+            ))),
+            Raw::Name(name) => Err(de::Error::custom(format!(
+                "Invalid value '{}', expected off, previous or a workspace number",
+                name
+            ))),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use log::{debug, warn};
+use std::os::raw::c_int;
+use x11::{xfixes, xinput2, xlib};
+
+use crate::{
+    config::pointer_barriers::PointerBarriers,
+    utils::{keybind, x11::Monitor},
+};
+
+struct Barrier {
+    id: xfixes::PointerBarrier,
+    vertical: bool,
+}
+
+/// Pointer barriers on the edges shared by two monitors.
+///
+/// The pointer is held at the edge until it has been pushed against it by
+/// `resistance` pixels, or released right away while the bypass modifier is held.
+pub struct BarrierManager {
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    xi_opcode: c_int,
+    barriers: Vec<Barrier>,
+    resistance: f64,
+    bypass_mask: u32,
+    hit: Option<(xfixes::PointerBarrier, xinput2::BarrierEventID)>,
+    pressure: f64,
+}
+
+impl BarrierManager {
+    /// Creates barriers between the given monitors. Returns `None` when the
+    /// server lacks XFixes 5 or XInput 2.3.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection.
+    /// - The root window must be a valid window ID for the given display.
+    /// - The caller must ensure the display connection remains valid for the lifetime of the manager.
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        monitors: &[Monitor],
+        config: &PointerBarriers,
+    ) -> Option<Self> {
+        let mut xi_opcode = 0;
+        let mut event_base = 0;
+        let mut error_base = 0;
+        if xlib::XQueryExtension(
+            display,
+            c"XInputExtension".as_ptr(),
+            &mut xi_opcode,
+            &mut event_base,
+            &mut error_base,
+        ) == 0
+        {
+            warn!("XInput extension not available, pointer barriers disabled");
+            return None;
+        }
+
+        let (mut major, mut minor) = (2, 3);
+        if xinput2::XIQueryVersion(display, &mut major, &mut minor) != xlib::Success as c_int
+            || (major, minor) < (2, 3)
+        {
+            warn!("XInput 2.3 not available, pointer barriers disabled");
+            return None;
+        }
+
+        let (mut fixes_major, fixes_minor) = (5, 0);
+        if xfixes::XFixesQueryVersion(display, &mut fixes_major, &fixes_minor) == 0
+            || fixes_major < 5
+        {
+            warn!("XFixes 5 not available, pointer barriers disabled");
+            return None;
+        }
+
+        let mut mask_bits = [0u8; 4];
+        for event in [xinput2::XI_BarrierHit, xinput2::XI_BarrierLeave] {
+            mask_bits[(event / 8) as usize] |= 1 << (event % 8);
+        }
+        let mut mask = xinput2::XIEventMask {
+            deviceid: xinput2::XIAllMasterDevices,
+            mask_len: mask_bits.len() as c_int,
+            mask: mask_bits.as_mut_ptr(),
+        };
+        xinput2::XISelectEvents(display, root, &mut mask, 1);
+
+        let mut manager = Self {
+            display,
+            root,
+            xi_opcode,
+            barriers: Vec::new(),
+            resistance: config.resistance as f64,
+            bypass_mask: keybind::get_modifier(&config.bypass_modifier),
+            hit: None,
+            pressure: 0.0,
+        };
+        manager.create_barriers(monitors);
+
+        Some(manager)
+    }
+
+    unsafe fn create_barriers(&mut self, monitors: &[Monitor]) {
+        for a in monitors {
+            for b in monitors {
+                let a_right = a.x + a.width as i32;
+                let a_bottom = a.y + a.height as i32;
+                let b_right = b.x + b.width as i32;
+                let b_bottom = b.y + b.height as i32;
+
+                if a_right == b.x {
+                    let top = a.y.max(b.y);
+                    let bottom = a_bottom.min(b_bottom);
+                    if top < bottom {
+                        self.create_barrier(b.x, top, b.x, bottom, true);
+                    }
+                }
+
+                if a_bottom == b.y {
+                    let left = a.x.max(b.x);
+                    let right = a_right.min(b_right);
+                    if left < right {
+                        self.create_barrier(left, b.y, right, b.y, false);
+                    }
+                }
+            }
+        }
+    }
+
+    unsafe fn create_barrier(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, vertical: bool) {
+        debug!("Creating pointer barrier {},{} -> {},{}", x1, y1, x2, y2);
+        let id = xfixes::XFixesCreatePointerBarrier(
+            self.display,
+            self.root,
+            x1,
+            y1,
+            x2,
+            y2,
+            0,
+            0,
+            std::ptr::null_mut(),
+        );
+        if id != 0 {
+            self.barriers.push(Barrier { id, vertical });
+        }
+    }
+
+    /// Handles a generic event, returning whether it was a barrier event.
+    ///
+    /// # Safety
+    /// - The display connection must still be valid
+    /// - The event must have been read from this manager's display
+    pub unsafe fn handle_event(&mut self, cookie: &mut xlib::XGenericEventCookie) -> bool {
+        if cookie.extension != self.xi_opcode
+            || (cookie.evtype != xinput2::XI_BarrierHit
+                && cookie.evtype != xinput2::XI_BarrierLeave)
+        {
+            return false;
+        }
+
+        if xlib::XGetEventData(self.display, cookie) == 0 {
+            return true;
+        }
+
+        let barrier_event = *(cookie.data as *const xinput2::XIBarrierEvent);
+        if cookie.evtype == xinput2::XI_BarrierLeave {
+            self.hit = None;
+            self.pressure = 0.0;
+        } else {
+            self.handle_hit(&barrier_event);
+        }
+
+        xlib::XFreeEventData(self.display, cookie);
+        true
+    }
+
+    unsafe fn handle_hit(&mut self, event: &xinput2::XIBarrierEvent) {
+        let Some(barrier) = self.barriers.iter().find(|b| b.id == event.barrier) else {
+            return;
+        };
+
+        if self.hit != Some((event.barrier, event.eventid)) {
+            self.hit = Some((event.barrier, event.eventid));
+            self.pressure = 0.0;
+        }
+
+        self.pressure += if barrier.vertical {
+            event.dx.abs()
+        } else {
+            event.dy.abs()
+        };
+
+        if self.pressure >= self.resistance || self.bypass_held() {
+            xinput2::XIBarrierReleasePointer(
+                self.display,
+                event.deviceid,
+                event.barrier,
+                event.eventid,
+            );
+            xlib::XFlush(self.display);
+            self.hit = None;
+            self.pressure = 0.0;
+        }
+    }
+
+    unsafe fn bypass_held(&self) -> bool {
+        if self.bypass_mask == 0 {
+            return false;
+        }
+
+        let mut root_return = 0;
+        let mut child_return = 0;
+        let mut root_x = 0;
+        let mut root_y = 0;
+        let mut win_x = 0;
+        let mut win_y = 0;
+        let mut mask = 0;
+        xlib::XQueryPointer(
+            self.display,
+            self.root,
+            &mut root_return,
+            &mut child_return,
+            &mut root_x,
+            &mut root_y,
+            &mut win_x,
+            &mut win_y,
+            &mut mask,
+        );
+
+        mask & self.bypass_mask == self.bypass_mask
+    }
+}
+
+impl Drop for BarrierManager {
+    fn drop(&mut self) {
+        unsafe {
+            for barrier in &self.barriers {
+                xfixes::XFixesDestroyPointerBarrier(self.display, barrier.id);
+            }
+        }
+    }
+}
@@ -1,9 +1,282 @@
 use x11::xlib;
 
+#[derive(Clone, Copy, Debug)]
+pub struct KeyEvent {
+    pub window: xlib::Window,
+    pub keycode: u32,
+    pub state: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ButtonEvent {
+    pub window: xlib::Window,
+    pub button: u32,
+    pub state: u32,
+    pub x: i32,
+    pub y: i32,
+    pub x_root: i32,
+    pub y_root: i32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MotionEvent {
+    pub window: xlib::Window,
+    pub state: u32,
+    pub x_root: i32,
+    pub y_root: i32,
+}
+
+/// A client asking to be moved, resized or restacked. Only the fields
+/// selected by `value_mask` (`CWX`, `CWWidth`, ...) are meaningful.
+#[derive(Clone, Copy, Debug)]
+pub struct ConfigureRequest {
+    pub window: xlib::Window,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub border_width: u32,
+    pub sibling: xlib::Window,
+    pub stack_mode: i32,
+    pub value_mask: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ClientMessage {
+    pub window: xlib::Window,
+    pub message_type: xlib::Atom,
+    pub format: i32,
+    /// The payload as 32-bit values.
+    pub data: [i64; 5],
+    /// The payload as bytes, for format 8 messages.
+    pub bytes: [u8; 20],
+}
+
+impl ClientMessage {
+    /// The `index`th 32-bit value as the client sent it. Xlib hands format
+    /// 32 data over as sign-extended longs, so 0xFFFFFFFF arrives as -1.
+    pub fn card32(&self, index: usize) -> u32 {
+        self.data[index] as u32
+    }
+}
+
+/// A client asking the owner of a selection to convert it.
+#[derive(Clone, Copy, Debug)]
+pub struct SelectionRequest {
+    pub owner: xlib::Window,
+    pub requestor: xlib::Window,
+    pub selection: xlib::Atom,
+    pub target: xlib::Atom,
+    /// Where to store the result, `0` for obsolete clients that expect it
+    /// in `target`.
+    pub property: xlib::Atom,
+    pub time: xlib::Time,
+}
+
+/// The answer to a conversion the window manager asked for. `property` is
+/// `0` when the owner refused.
+#[derive(Clone, Copy, Debug)]
+pub struct SelectionNotify {
+    pub requestor: xlib::Window,
+    pub selection: xlib::Atom,
+    pub target: xlib::Atom,
+    pub property: xlib::Atom,
+}
+
+/// The events the window manager reacts to, decoupled from the Xlib union.
+#[derive(Clone, Copy, Debug)]
 pub enum Event {
-    KeyPress(xlib::XKeyEvent),
-    KeyRelease(xlib::XKeyEvent),
-    ButtonPress(xlib::XButtonEvent),
-    ButtonRelease(xlib::XButtonEvent),
-    MotionNotify(xlib::XMotionEvent),
+    KeyPress(KeyEvent),
+    KeyRelease(KeyEvent),
+    ButtonPress(ButtonEvent),
+    ButtonRelease(ButtonEvent),
+    MotionNotify(MotionEvent),
+    EnterNotify {
+        window: xlib::Window,
+    },
+    LeaveNotify {
+        window: xlib::Window,
+    },
+    Expose {
+        window: xlib::Window,
+        count: i32,
+    },
+    MapRequest {
+        window: xlib::Window,
+    },
+    UnmapNotify {
+        window: xlib::Window,
+        /// Sent by the client itself, as ICCCM asks when withdrawing a
+        /// window that isn't mapped.
+        synthetic: bool,
+    },
+    DestroyNotify {
+        window: xlib::Window,
+    },
+    ConfigureRequest(ConfigureRequest),
+    ConfigureNotify {
+        window: xlib::Window,
+        border_width: u32,
+    },
+    ClientMessage(ClientMessage),
+    PropertyNotify {
+        window: xlib::Window,
+        atom: xlib::Atom,
+    },
+    SelectionRequest(SelectionRequest),
+    SelectionNotify(SelectionNotify),
+    SelectionClear {
+        window: xlib::Window,
+        selection: xlib::Atom,
+    },
+    /// Extension events (XInput) that still need `XGetEventData`.
+    Generic(xlib::XGenericEventCookie),
+}
+
+impl Event {
+    /// Translates an Xlib event. Returns `None` for event types the window
+    /// manager doesn't handle.
+    pub fn from_xevent(event: &xlib::XEvent) -> Option<Self> {
+        let event = *event;
+        Some(match event.get_type() {
+            xlib::KeyPress => Event::KeyPress(KeyEvent::from(xlib::XKeyEvent::from(event))),
+            xlib::KeyRelease => Event::KeyRelease(KeyEvent::from(xlib::XKeyEvent::from(event))),
+            xlib::ButtonPress => {
+                Event::ButtonPress(ButtonEvent::from(xlib::XButtonEvent::from(event)))
+            }
+            xlib::ButtonRelease => {
+                Event::ButtonRelease(ButtonEvent::from(xlib::XButtonEvent::from(event)))
+            }
+            xlib::MotionNotify => {
+                let motion = xlib::XMotionEvent::from(event);
+                Event::MotionNotify(MotionEvent {
+                    window: motion.window,
+                    state: motion.state,
+                    x_root: motion.x_root,
+                    y_root: motion.y_root,
+                })
+            }
+            xlib::EnterNotify => Event::EnterNotify {
+                window: xlib::XCrossingEvent::from(event).window,
+            },
+            xlib::LeaveNotify => Event::LeaveNotify {
+                window: xlib::XCrossingEvent::from(event).window,
+            },
+            xlib::Expose => {
+                let expose = xlib::XExposeEvent::from(event);
+                Event::Expose {
+                    window: expose.window,
+                    count: expose.count,
+                }
+            }
+            xlib::MapRequest => Event::MapRequest {
+                window: xlib::XMapRequestEvent::from(event).window,
+            },
+            xlib::UnmapNotify => {
+                let unmap = xlib::XUnmapEvent::from(event);
+                Event::UnmapNotify {
+                    window: unmap.window,
+                    synthetic: unmap.send_event != 0,
+                }
+            }
+            xlib::DestroyNotify => Event::DestroyNotify {
+                window: xlib::XDestroyWindowEvent::from(event).window,
+            },
+            xlib::ConfigureRequest => {
+                let request = xlib::XConfigureRequestEvent::from(event);
+                Event::ConfigureRequest(ConfigureRequest {
+                    window: request.window,
+                    x: request.x,
+                    y: request.y,
+                    width: request.width.max(0) as u32,
+                    height: request.height.max(0) as u32,
+                    border_width: request.border_width.max(0) as u32,
+                    sibling: request.above,
+                    stack_mode: request.detail,
+                    value_mask: request.value_mask,
+                })
+            }
+            xlib::ConfigureNotify => {
+                let notify = xlib::XConfigureEvent::from(event);
+                Event::ConfigureNotify {
+                    window: notify.window,
+                    border_width: notify.border_width.max(0) as u32,
+                }
+            }
+            xlib::ClientMessage => {
+                let message = xlib::XClientMessageEvent::from(event);
+                let mut bytes = [0u8; 20];
+                for (byte, &b) in bytes.iter_mut().zip(message.data.as_bytes()) {
+                    *byte = b as u8;
+                }
+                Event::ClientMessage(ClientMessage {
+                    window: message.window,
+                    message_type: message.message_type,
+                    format: message.format,
+                    data: [0, 1, 2, 3, 4].map(|i| message.data.get_long(i)),
+                    bytes,
+                })
+            }
+            xlib::PropertyNotify => {
+                let property = xlib::XPropertyEvent::from(event);
+                Event::PropertyNotify {
+                    window: property.window,
+                    atom: property.atom,
+                }
+            }
+            xlib::SelectionRequest => {
+                let request = xlib::XSelectionRequestEvent::from(event);
+                Event::SelectionRequest(SelectionRequest {
+                    owner: request.owner,
+                    requestor: request.requestor,
+                    selection: request.selection,
+                    target: request.target,
+                    property: request.property,
+                    time: request.time,
+                })
+            }
+            xlib::SelectionNotify => {
+                let notify = xlib::XSelectionEvent::from(event);
+                Event::SelectionNotify(SelectionNotify {
+                    requestor: notify.requestor,
+                    selection: notify.selection,
+                    target: notify.target,
+                    property: notify.property,
+                })
+            }
+            xlib::SelectionClear => {
+                let clear = xlib::XSelectionClearEvent::from(event);
+                Event::SelectionClear {
+                    window: clear.window,
+                    selection: clear.selection,
+                }
+            }
+            xlib::GenericEvent => Event::Generic(xlib::XGenericEventCookie::from(event)),
+            _ => return None,
+        })
+    }
+}
+
+impl From<xlib::XKeyEvent> for KeyEvent {
+    fn from(event: xlib::XKeyEvent) -> Self {
+        Self {
+            window: event.window,
+            keycode: event.keycode,
+            state: event.state,
+        }
+    }
+}
+
+impl From<xlib::XButtonEvent> for ButtonEvent {
+    fn from(event: xlib::XButtonEvent) -> Self {
+        Self {
+            window: event.window,
+            button: event.button,
+            state: event.state,
+            x: event.x,
+            y: event.y,
+            x_root: event.x_root,
+            y_root: event.y_root,
+        }
+    }
 }
@@ -0,0 +1,124 @@
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::hot_corners::HotCorners,
+    utils::{command::Command, x11::Monitor},
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl FromStr for Corner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tl" | "top_left" => Ok(Corner::TopLeft),
+            "tr" | "top_right" => Ok(Corner::TopRight),
+            "bl" | "bottom_left" => Ok(Corner::BottomLeft),
+            "br" | "bottom_right" => Ok(Corner::BottomRight),
+            _ => Err(format!("Unknown corner '{}', expected tl, tr, bl or br", s)),
+        }
+    }
+}
+
+/// Tracks the pointer against the corners of every monitor and reports the
+/// configured command once the pointer has rested in a corner long enough.
+pub struct HotCornerTracker {
+    config: HotCorners,
+    monitors: Vec<Monitor>,
+    entered: Option<(Corner, Instant)>,
+    fired: bool,
+    cooldown_until: Option<Instant>,
+}
+
+impl HotCornerTracker {
+    pub fn new(config: HotCorners, monitors: Vec<Monitor>) -> Self {
+        Self {
+            config,
+            monitors,
+            entered: None,
+            fired: false,
+            cooldown_until: None,
+        }
+    }
+
+    fn corner_at(&self, x: i32, y: i32) -> Option<Corner> {
+        let size = self.config.size as i32;
+        let monitor = self.monitors.iter().find(|m| m.contains(x, y))?;
+
+        let left = x < monitor.x + size;
+        let right = x >= monitor.x + monitor.width as i32 - size;
+        let top = y < monitor.y + size;
+        let bottom = y >= monitor.y + monitor.height as i32 - size;
+
+        match (left, right, top, bottom) {
+            (true, _, true, _) => Some(Corner::TopLeft),
+            (_, true, true, _) => Some(Corner::TopRight),
+            (true, _, _, true) => Some(Corner::BottomLeft),
+            (_, true, _, true) => Some(Corner::BottomRight),
+            _ => None,
+        }
+    }
+
+    fn command_for(&self, corner: Corner) -> Option<&Command> {
+        match corner {
+            Corner::TopLeft => self.config.top_left.as_ref(),
+            Corner::TopRight => self.config.top_right.as_ref(),
+            Corner::BottomLeft => self.config.bottom_left.as_ref(),
+            Corner::BottomRight => self.config.bottom_right.as_ref(),
+        }
+    }
+
+    /// Feeds a new pointer position from the motion handler.
+    pub fn update(&mut self, x: i32, y: i32) {
+        let corner = self
+            .corner_at(x, y)
+            .filter(|&c| self.command_for(c).is_some());
+
+        match (corner, self.entered) {
+            (Some(corner), Some((entered, _))) if corner == entered => (),
+            (Some(corner), _) => {
+                self.entered = Some((corner, Instant::now()));
+                self.fired = false;
+            }
+            (None, _) => {
+                self.entered = None;
+                self.fired = false;
+            }
+        }
+    }
+
+    /// Time left until the pending corner fires, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        let (_, since) = self.entered.filter(|_| !self.fired)?;
+        let mut deadline = since + Duration::from_millis(self.config.dwell_ms);
+        if let Some(cooldown) = self.cooldown_until {
+            deadline = deadline.max(cooldown);
+        }
+
+        Some(deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns the command of the corner the pointer has dwelt in, at most
+    /// once per visit and never during the cooldown.
+    pub fn poll(&mut self) -> Option<Command> {
+        if !self.timeout().is_some_and(|t| t.is_zero()) {
+            return None;
+        }
+
+        let (corner, _) = self.entered?;
+        self.fired = true;
+        self.cooldown_until = Some(Instant::now() + Duration::from_millis(self.config.cooldown_ms));
+
+        self.command_for(corner).cloned()
+    }
+}
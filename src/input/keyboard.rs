@@ -1,6 +1,24 @@
 use x11::xlib;
 
+use crate::utils::x11::QUEUED_AFTER_READING;
+
 pub struct KeyboardState {
     pub modifiers: u32,
     pub last_key: Option<xlib::KeyCode>,
 }
+
+/// Whether a key release is half of an auto-repeat, which X sends as a
+/// release followed by a press of the same key at the same time.
+///
+/// # Safety
+/// The display pointer must be valid and point to an active X display connection.
+pub unsafe fn is_auto_repeat(display: *mut xlib::Display, release: &xlib::XKeyEvent) -> bool {
+    if xlib::XEventsQueued(display, QUEUED_AFTER_READING) == 0 {
+        return false;
+    }
+    let mut next: xlib::XEvent = std::mem::zeroed();
+    xlib::XPeekEvent(display, &mut next);
+    next.get_type() == xlib::KeyPress
+        && next.key.keycode == release.keycode
+        && next.key.time == release.time
+}
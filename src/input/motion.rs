@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+use x11::xlib;
+
+use crate::{input::event::MotionEvent, utils::x11::QUEUED_AFTER_READING};
+
+/// Replaces `event`, a `MotionNotify`, with the last of the motion events
+/// queued right behind it for the same window. A mouse polling at 1000Hz
+/// then moves a dragged window once per burst instead of once per report.
+///
+/// # Safety
+/// The display pointer must be valid and point to an active X display connection.
+pub unsafe fn skip_to_latest_motion(display: *mut xlib::Display, event: &mut xlib::XEvent) {
+    let window = event.motion.window;
+    let mut next: xlib::XEvent = std::mem::zeroed();
+    // Any other event in between stops it to keep the order intact.
+    while xlib::XEventsQueued(display, QUEUED_AFTER_READING) > 0 {
+        xlib::XPeekEvent(display, &mut next);
+        if next.get_type() != xlib::MotionNotify || next.motion.window != window {
+            break;
+        }
+        xlib::XNextEvent(display, event);
+    }
+}
+
+/// Holds back motion during a drag so windows are moved at most once per
+/// `interval`. The last motion held back is handed out once the interval
+/// is over, so the window always ends up under the pointer.
+pub struct MotionThrottle {
+    interval: Duration,
+    last: Option<Instant>,
+    pending: Option<MotionEvent>,
+}
+
+impl MotionThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+            pending: None,
+        }
+    }
+
+    /// One frame at `rate` Hz.
+    pub fn per_frame(rate: u32) -> Self {
+        Self::new(Duration::from_secs(1) / rate.max(1))
+    }
+
+    /// Returns `event` when it may be handled now, otherwise keeps it for
+    /// [`poll`](Self::poll).
+    pub fn offer(&mut self, event: MotionEvent) -> Option<MotionEvent> {
+        let now = Instant::now();
+        if self.last.is_some_and(|last| now < last + self.interval) {
+            self.pending = Some(event);
+            return None;
+        }
+        self.last = Some(now);
+        self.pending = None;
+        Some(event)
+    }
+
+    /// Time left until the motion held back is due, if there is one.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.pending.as_ref()?;
+        let last = self.last?;
+        Some((last + self.interval).saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns the motion held back once it is due.
+    pub fn poll(&mut self) -> Option<MotionEvent> {
+        if !self.timeout().is_some_and(|t| t.is_zero()) {
+            return None;
+        }
+        self.last = Some(Instant::now());
+        self.pending.take()
+    }
+
+    /// Returns the motion held back right away, for when the drag ends.
+    pub fn flush(&mut self) -> Option<MotionEvent> {
+        self.last = None;
+        self.pending.take()
+    }
+}
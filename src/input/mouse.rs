@@ -1,5 +1,110 @@
+use crate::ui::cursor::Shape;
+
 pub struct MouseState {
     pub x: i32,
     pub y: i32,
     pub button: Option<u32>,
 }
+
+/// Which edge of a window follows the pointer along one axis.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResizeSide {
+    /// The left or top edge.
+    Start,
+    /// The right or bottom edge.
+    End,
+    /// Neither, the size stays as it is.
+    Fixed,
+}
+
+/// The edges an interactive resize moves.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ResizeEdges {
+    pub horizontal: ResizeSide,
+    pub vertical: ResizeSide,
+}
+
+impl ResizeEdges {
+    /// Picks the edges from where the pointer is in a `width`x`height`
+    /// window: the outer thirds grab the corner or edge they are on, the
+    /// centre falls back to the corner of its quadrant.
+    pub fn at(x: i32, y: i32, width: u32, height: u32) -> Self {
+        let side = |position: i32, size: u32| {
+            let size = size as i32;
+            if position < size / 3 {
+                ResizeSide::Start
+            } else if position >= size - size / 3 {
+                ResizeSide::End
+            } else {
+                ResizeSide::Fixed
+            }
+        };
+        let nearest = |position: i32, size: u32| {
+            if position < size as i32 / 2 {
+                ResizeSide::Start
+            } else {
+                ResizeSide::End
+            }
+        };
+
+        match (side(x, width), side(y, height)) {
+            (ResizeSide::Fixed, ResizeSide::Fixed) => Self {
+                horizontal: nearest(x, width),
+                vertical: nearest(y, height),
+            },
+            (horizontal, vertical) => Self {
+                horizontal,
+                vertical,
+            },
+        }
+    }
+
+    /// The cursor that shows which edges move.
+    pub fn shape(&self) -> Shape {
+        match (self.horizontal, self.vertical) {
+            (ResizeSide::Start, ResizeSide::Start) => Shape::TopLeft,
+            (ResizeSide::End, ResizeSide::Start) => Shape::TopRight,
+            (ResizeSide::Start, ResizeSide::End) => Shape::BottomLeft,
+            (ResizeSide::End, ResizeSide::End) => Shape::BottomRight,
+            (ResizeSide::Fixed, _) => Shape::SizeVertical,
+            (_, ResizeSide::Fixed) => Shape::SizeHorizontal,
+        }
+    }
+}
+
+/// What a client asks for with `_NET_WM_MOVERESIZE`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MoveResize {
+    Move,
+    /// Resizing by the given edges, or the ones nearest to the pointer
+    /// for a resize started from the keyboard.
+    Resize(Option<ResizeEdges>),
+    Cancel,
+}
+
+impl MoveResize {
+    /// Reads the direction of the message, `None` for unknown ones.
+    pub fn from_direction(direction: i64) -> Option<Self> {
+        use ResizeSide::{End, Fixed, Start};
+        let edges = |horizontal, vertical| {
+            Some(Self::Resize(Some(ResizeEdges {
+                horizontal,
+                vertical,
+            })))
+        };
+        match direction {
+            0 => edges(Start, Start),
+            1 => edges(Fixed, Start),
+            2 => edges(End, Start),
+            3 => edges(End, Fixed),
+            4 => edges(End, End),
+            5 => edges(Fixed, End),
+            6 => edges(Start, End),
+            7 => edges(Start, Fixed),
+            8 | 10 => Some(Self::Move),
+            9 => Some(Self::Resize(None)),
+            11 => Some(Self::Cancel),
+            _ => None,
+        }
+    }
+}
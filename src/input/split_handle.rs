@@ -0,0 +1,90 @@
+use x11::xlib;
+
+use crate::ui::layout::Geometry;
+
+/// An input-only window over the gap between the master and the stack
+/// column. It shows a resize cursor and takes the clicks that start
+/// dragging the split.
+///
+/// It sits at the bottom of the stack, so only the part of the gap no
+/// window covers reacts to the pointer.
+pub struct SplitHandle {
+    display: *mut xlib::Display,
+    pub window: xlib::Window,
+    area: Option<Geometry>,
+}
+
+impl SplitHandle {
+    /// Creates the handle, unmapped until it is placed.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection
+    /// - The root window must be a valid window ID for the given display
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        cursor: xlib::Cursor,
+    ) -> Self {
+        let mut attrs: xlib::XSetWindowAttributes = std::mem::zeroed();
+        attrs.override_redirect = 1;
+        attrs.cursor = cursor;
+        attrs.event_mask = xlib::ButtonPressMask | xlib::ButtonReleaseMask | xlib::ButtonMotionMask;
+        let window = xlib::XCreateWindow(
+            display,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            0,
+            xlib::InputOnly as u32,
+            std::ptr::null_mut(),
+            xlib::CWOverrideRedirect | xlib::CWCursor | xlib::CWEventMask,
+            &mut attrs,
+        );
+
+        Self {
+            display,
+            window,
+            area: None,
+        }
+    }
+
+    /// Moves the handle over `area`, or hides it for `None`.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn place(&mut self, area: Option<Geometry>) {
+        if area == self.area {
+            return;
+        }
+        self.area = area;
+
+        match area {
+            Some(area) => {
+                xlib::XMoveResizeWindow(
+                    self.display,
+                    self.window,
+                    area.x,
+                    area.y,
+                    area.width.max(1),
+                    area.height.max(1),
+                );
+                xlib::XMapWindow(self.display, self.window);
+                xlib::XLowerWindow(self.display, self.window);
+            }
+            None => {
+                xlib::XUnmapWindow(self.display, self.window);
+            }
+        }
+    }
+}
+
+impl Drop for SplitHandle {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XDestroyWindow(self.display, self.window);
+        }
+    }
+}
@@ -1,4 +1,14 @@
 pub mod velowm_core {
+    pub mod builder;
+    pub mod clipboard;
+    pub mod focus;
+    pub mod hooks;
+    pub mod idle;
+    pub mod ipc;
+    pub mod process;
+    pub mod registry;
+    pub mod session;
+    pub mod startup;
     pub mod window;
     pub mod wm;
     pub mod workspace;
@@ -7,25 +17,60 @@ pub mod velowm_core {
 pub mod utils {
     pub mod command;
     pub mod keybind;
+    pub mod logging;
+    pub mod pixbuf;
     pub mod x11;
+    pub mod xephyr;
+    pub mod xsettings;
 }
 
 pub mod input {
+    pub mod barrier;
     pub mod event;
+    pub mod hot_corner;
     pub mod keyboard;
+    pub mod motion;
     pub mod mouse;
+    pub mod split_handle;
 }
 
 pub mod ui {
     pub mod appearance;
+    pub mod bar;
+    pub mod bar_module;
+    pub mod bind_help;
+    pub mod confirm;
     pub mod cursor;
+    pub mod drop_indicator;
     pub mod layout;
+    pub mod modal;
     pub mod notification;
+    pub mod screenshot;
+    pub mod setup_wizard;
+    pub mod size_popup;
+    pub mod slide;
+    pub mod wallpaper;
+    pub mod workspace_preview;
 }
 
 pub mod config {
+    pub mod color;
+    pub mod game_mode;
+    pub mod hooks;
+    pub mod hot_corners;
+    pub mod kiosk;
     pub mod loader;
+    pub mod logging;
+    pub mod migrate;
+    pub mod pointer_barriers;
+    pub mod rules;
+    pub mod screenshot;
+    pub mod session;
+    pub mod setup;
+    pub mod theme;
+    pub mod watcher;
+    pub mod workspace;
 }
 
 pub use config::loader::Config;
-pub use velowm_core::{window::Window, wm::WindowManager};
+pub use velowm_core::{builder::WindowManagerBuilder, window::Window, wm::WindowManager};
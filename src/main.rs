@@ -1,82 +1,246 @@
 use anyhow::Result;
-use log::error;
-use rand::random;
-use std::{
-    env, fs,
-    io::{self, Write},
-    path::PathBuf,
-    process,
+use log::{error, info, warn};
+use std::{env, io::Write, os::unix::process::CommandExt, process};
+use velowm::{
+    ui::setup_wizard,
+    utils::{logging, x11::Display, xephyr::Xephyr},
+    velowm_core::{ipc, wm::WindowManager},
+    Config,
 };
-use velowm::{velowm_core::wm::WindowManager, Config};
 
-fn get_log_file_path() -> Result<PathBuf> {
-    let cache_dir = PathBuf::from(env::var("HOME")?).join(".cache/velowm");
-    fs::create_dir_all(&cache_dir)?;
-    Ok(cache_dir.join(format!("log{}.log", random::<u32>())))
+/// `velowm msg <command>`: sends a command to the running instance.
+/// `velowm msg subscribe` prints the event stream instead.
+fn send_message(args: &[String]) -> ! {
+    if args.is_empty() {
+        eprintln!("usage: velowm msg <command>");
+        process::exit(2);
+    }
+
+    if args == ["subscribe"] {
+        let result = ipc::subscribe(|event| {
+            let mut stdout = std::io::stdout().lock();
+            // Consumers read line by line, flush so they see each event at once.
+            if writeln!(stdout, "{}", event)
+                .and_then(|_| stdout.flush())
+                .is_err()
+            {
+                process::exit(0);
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("velowm: {:#}", e);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    match ipc::send(&args.join(" ")) {
+        Ok(response) => {
+            println!("{}", response);
+            process::exit(if response.starts_with("ok") { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprintln!("velowm: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// `velowm --check-config`: reports problems in the config file without
+/// starting. Exits with 1 when there are any.
+fn check_config() -> ! {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("velowm: {:#}", e);
+            process::exit(1);
+        }
+    };
+
+    let mut problems = config.errors.clone();
+    problems.extend(config.keybind_conflicts());
+    if let Some(migration) = &config.migration {
+        println!(
+            "Config is in format version {} and will be upgraded to {} on startup",
+            migration.from,
+            velowm::config::migrate::CURRENT_VERSION
+        );
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    if problems.is_empty() {
+        println!("Config OK");
+        process::exit(0);
+    }
+    process::exit(1);
+}
+
+fn usage() -> ! {
+    eprintln!("usage: velowm [-d|--display <display>]");
+    eprintln!("       velowm --embedded <width>x<height>");
+    eprintln!("       velowm --check-config");
+    eprintln!("       velowm msg <command>");
+    eprintln!("       velowm msg subscribe");
+    process::exit(2);
 }
 
-struct DualWriter {
-    file: fs::File,
+#[derive(Default)]
+struct Options {
+    display: Option<String>,
+    /// Screen size of the nested Xephyr server to run in.
+    embedded: Option<(u32, u32)>,
 }
 
-impl Write for DualWriter {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        io::stdout().write_all(buf)?;
-        self.file.write_all(buf)?;
-        Ok(buf.len())
+impl Options {
+    fn parse(args: &[String]) -> Self {
+        let mut options = Options::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || inline.clone().or_else(|| args.next().cloned());
+
+            match flag {
+                "-d" | "--display" => options.display = Some(value().unwrap_or_else(|| usage())),
+                "--embedded" => {
+                    let size = value().unwrap_or_else(|| usage());
+                    let Some(size) = size
+                        .split_once('x')
+                        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    else {
+                        eprintln!("velowm: invalid size '{}', expected WIDTHxHEIGHT", size);
+                        usage();
+                    };
+                    options.embedded = Some(size);
+                }
+                _ => {
+                    eprintln!("velowm: unknown argument '{}'", arg);
+                    usage();
+                }
+            }
+        }
+
+        if options.display.is_some() && options.embedded.is_some() {
+            eprintln!("velowm: --display and --embedded can't be combined");
+            usage();
+        }
+        options
     }
+}
 
-    fn flush(&mut self) -> io::Result<()> {
-        io::stdout().flush()?;
-        self.file.flush()
+/// Asks for the modifier, the terminal and the launcher on the first start
+/// and writes the config file with them. Skipping it leaves the defaults,
+/// written when the window manager loads the config.
+fn first_start_setup(display: Option<&str>) {
+    let display = match Display::new(display) {
+        Ok(display) => display,
+        Err(e) => {
+            warn!("Skipping first start setup: {:#}", e);
+            return;
+        }
+    };
+    if let Some(setup) = unsafe { setup_wizard::run(&display) } {
+        info!("Writing config from first start setup: {:?}", setup);
+        if let Err(e) = Config::create(&setup) {
+            error!("Failed to write config: {:#}", e);
+        }
     }
 }
 
 fn main() -> Result<()> {
-    let config = Config::load().unwrap_or_default();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().is_some_and(|arg| arg == "msg") {
+        send_message(&args[1..]);
+    }
+    if args == ["--check-config"] {
+        check_config();
+    }
+    let mut options = Options::parse(&args);
+
+    // The config file is written by the first start setup once the
+    // display is open.
+    let first_run = Config::is_first_run();
+    let config = match first_run {
+        true => Config::default(),
+        false => Config::load().unwrap_or_default(),
+    };
 
     if config.logging_enabled {
-        if env::var("RUST_LOG").is_err() {
-            env::set_var("RUST_LOG", "debug");
+        if let Err(e) = logging::init(&config.logging) {
+            eprintln!("velowm: failed to set up logging: {:#}", e);
         }
-
-        let log_file = fs::File::create(get_log_file_path()?)?;
-        let dual_writer = DualWriter { file: log_file };
-
-        env_logger::Builder::from_default_env()
-            .format(|buf, record| {
-                writeln!(
-                    buf,
-                    "{} [{}] {}",
-                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                    record.level(),
-                    record.args()
-                )
-            })
-            .target(env_logger::Target::Pipe(Box::new(dual_writer)))
-            .init();
     }
 
-    if env::var("WAYLAND_DISPLAY").is_ok()
-        || env::var("XDG_SESSION_TYPE").is_ok_and(|v| v == "wayland")
+    // A nested server runs fine on XWayland.
+    if options.embedded.is_none()
+        && (env::var("WAYLAND_DISPLAY").is_ok()
+            || env::var("XDG_SESSION_TYPE").is_ok_and(|v| v == "wayland"))
     {
         error!("Wayland session detected. velowm is an X11 window manager and cannot run under Wayland.");
         process::exit(1);
     }
 
-    if env::var("DISPLAY").is_err() {
-        error!("DISPLAY environment variable not set. Are you running inside X11?");
-        process::exit(1);
+    // Kept alive until the window manager exits. On restart the server is
+    // handed over to the new process instead.
+    let xephyr = match options.embedded {
+        Some((width, height)) => match Xephyr::start(width, height) {
+            Ok(xephyr) => {
+                info!(
+                    "Running nested on {} ({}x{})",
+                    xephyr.display, width, height
+                );
+                options.display = Some(xephyr.display.clone());
+                Some(xephyr)
+            }
+            Err(e) => {
+                error!("Failed to start nested X server: {:#}", e);
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    match &options.display {
+        // Clients spawned by the WM have to end up on the same display.
+        Some(name) => env::set_var("DISPLAY", name),
+        None if env::var("DISPLAY").is_err() => {
+            error!("DISPLAY environment variable not set and no --display given. Are you running inside X11?");
+            process::exit(1);
+        }
+        None => (),
+    }
+
+    if first_run {
+        first_start_setup(options.display.as_deref());
     }
 
-    match WindowManager::new() {
-        Ok(mut wm) => wm.run()?,
+    match WindowManager::new(options.display.as_deref()) {
+        Ok(mut wm) => {
+            wm.run()?;
+
+            if wm.restart_requested() {
+                drop(wm);
+                let err = process::Command::new(env::current_exe()?)
+                    .args(env::args_os().skip(1))
+                    .exec();
+                error!("Failed to restart window manager: {}", err);
+                drop(xephyr);
+                process::exit(1);
+            }
+        }
         Err(e) => {
-            error!("Failed to initialize window manager: {}", e);
+            error!("Failed to initialize window manager: {:#}", e);
             error!("Make sure X11 is running and you have the correct permissions");
+            drop(xephyr);
             process::exit(1);
         }
     }
 
+    drop(xephyr);
     Ok(())
 }
@@ -1,22 +1,234 @@
+use anyhow::{bail, Result};
 use serde::Deserialize;
+use x11::xlib;
 
-#[derive(Deserialize, Default, Clone)]
+use crate::{
+    config::{color::Color, theme::Theme},
+    utils::{
+        command::{deserialize_optional_command, Command},
+        xsettings::{self, Palette},
+    },
+};
+
+#[derive(Deserialize, Clone)]
 pub struct NotificationAppearance {
     #[serde(default = "default_notification_background_color")]
-    pub background_color: String,
+    pub background_color: Color,
     #[serde(default = "default_notification_border_color")]
-    pub border_color: String,
+    pub border_color: Color,
+}
+
+impl Default for NotificationAppearance {
+    fn default() -> Self {
+        Self {
+            background_color: default_notification_background_color(),
+            border_color: default_notification_border_color(),
+        }
+    }
+}
+
+fn default_notification_background_color() -> Color {
+    Color::rgb(0x0F, 0x0F, 0x0F)
+}
+
+fn default_notification_border_color() -> Color {
+    Color::rgb(0xFF, 0x00, 0x00)
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceStyle {
+    Numbers,
+    Names,
+    Icons,
+}
+
+/// Which monitors get a bar.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BarMonitors {
+    /// Only the RandR primary output, or the first monitor without one.
+    Primary,
+    All,
+}
+
+/// A built-in bar segment showing system state.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BarModule {
+    Battery,
+    Network,
+    /// CPU and memory use of the focused window's process.
+    WindowUsage,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BarModules {
+    /// Modules on the right end of the bar, left to right.
+    #[serde(default)]
+    pub right: Vec<BarModule>,
+    /// Seconds between refreshes.
+    #[serde(default = "default_module_interval")]
+    pub interval: u64,
+}
+
+impl Default for BarModules {
+    fn default() -> Self {
+        Self {
+            right: Vec::new(),
+            interval: default_module_interval(),
+        }
+    }
+}
+
+fn default_module_interval() -> u64 {
+    5
+}
+
+/// Which part of a long text is replaced by the ellipsis.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Ellipsis {
+    End,
+    Middle,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BarTruncate {
+    /// Widest the window title may get, in pixels. 0 only limits it to the
+    /// space left between the other segments.
+    #[serde(default = "default_title_max_width")]
+    pub title: u32,
+    /// Widest each module may get, in pixels, 0 for no limit.
+    #[serde(default)]
+    pub module: u32,
+    #[serde(default = "default_ellipsis")]
+    pub ellipsis: Ellipsis,
+}
+
+impl Default for BarTruncate {
+    fn default() -> Self {
+        Self {
+            title: default_title_max_width(),
+            module: 0,
+            ellipsis: default_ellipsis(),
+        }
+    }
+}
+
+fn default_title_max_width() -> u32 {
+    400
 }
 
-fn default_notification_background_color() -> String {
-    String::from("#0F0F0F")
+fn default_ellipsis() -> Ellipsis {
+    Ellipsis::End
 }
 
-fn default_notification_border_color() -> String {
-    String::from("#FF0000")
+#[derive(Deserialize, Clone)]
+pub struct BarAppearance {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_bar_height")]
+    pub height: u32,
+    #[serde(default = "default_bar_font")]
+    pub font: String,
+    #[serde(default = "default_bar_background_color")]
+    pub background_color: Color,
+    #[serde(default = "default_workspace_style")]
+    pub workspace_style: WorkspaceStyle,
+    #[serde(default = "default_bar_focused_color")]
+    pub focused_color: Color,
+    #[serde(default = "default_bar_occupied_color")]
+    pub occupied_color: Color,
+    #[serde(default = "default_bar_urgent_color")]
+    pub urgent_color: Color,
+    #[serde(default = "default_bar_empty_color")]
+    pub empty_color: Color,
+    #[serde(
+        default = "default_bar_scroll_up",
+        deserialize_with = "deserialize_optional_command"
+    )]
+    pub scroll_up: Option<Command>,
+    #[serde(
+        default = "default_bar_scroll_down",
+        deserialize_with = "deserialize_optional_command"
+    )]
+    pub scroll_down: Option<Command>,
+    #[serde(default)]
+    pub modules: BarModules,
+    #[serde(default)]
+    pub truncate: BarTruncate,
+    /// Put the mark of the focused window in front of its title.
+    #[serde(default)]
+    pub show_marks: bool,
+    #[serde(default = "default_bar_monitors")]
+    pub monitors: BarMonitors,
+    /// Show the windows of a workspace while its button is hovered.
+    #[serde(default = "default_bar_preview")]
+    pub preview: bool,
+}
+
+impl Default for BarAppearance {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            height: default_bar_height(),
+            font: default_bar_font(),
+            background_color: default_bar_background_color(),
+            workspace_style: default_workspace_style(),
+            focused_color: default_bar_focused_color(),
+            occupied_color: default_bar_occupied_color(),
+            urgent_color: default_bar_urgent_color(),
+            empty_color: default_bar_empty_color(),
+            scroll_up: default_bar_scroll_up(),
+            scroll_down: default_bar_scroll_down(),
+            modules: BarModules::default(),
+            truncate: BarTruncate::default(),
+            show_marks: false,
+            monitors: default_bar_monitors(),
+            preview: default_bar_preview(),
+        }
+    }
+}
+
+fn default_bar_preview() -> bool {
+    true
+}
+fn default_bar_monitors() -> BarMonitors {
+    BarMonitors::Primary
+}
+fn default_bar_height() -> u32 {
+    20
+}
+fn default_bar_font() -> String {
+    String::from("monospace:size=10")
+}
+fn default_bar_background_color() -> Color {
+    Color::rgb(0x0F, 0x0F, 0x0F)
+}
+fn default_workspace_style() -> WorkspaceStyle {
+    WorkspaceStyle::Numbers
+}
+fn default_bar_focused_color() -> Color {
+    Color::rgb(0xFF, 0x00, 0x00)
+}
+fn default_bar_occupied_color() -> Color {
+    Color::rgb(0xFF, 0xFF, 0xFF)
+}
+fn default_bar_urgent_color() -> Color {
+    Color::rgb(0xFF, 0xA5, 0x00)
+}
+fn default_bar_empty_color() -> Color {
+    Color::rgb(0x55, 0x55, 0x55)
+}
+fn default_bar_scroll_up() -> Option<Command> {
+    Some(Command::PreviousWorkspace)
+}
+fn default_bar_scroll_down() -> Option<Command> {
+    Some(Command::NextWorkspace)
 }
 
-#[derive(Deserialize, Default, Clone)]
+#[derive(Deserialize, Clone)]
 pub struct FloatingWindow {
     #[serde(default)]
     pub center_on_float: bool,
@@ -24,66 +236,326 @@ pub struct FloatingWindow {
     pub width: u32,
     #[serde(default = "default_float_height")]
     pub height: u32,
+    /// Show the size while resizing with the mouse.
+    #[serde(default)]
+    pub show_size: bool,
+    /// Pixels the grow and shrink commands add or take away.
+    #[serde(default = "default_resize_step")]
+    pub resize_step: u32,
 }
 
-#[derive(Deserialize, Default, Clone)]
+impl Default for FloatingWindow {
+    fn default() -> Self {
+        Self {
+            center_on_float: false,
+            width: default_float_width(),
+            height: default_float_height(),
+            show_size: false,
+            resize_step: default_resize_step(),
+        }
+    }
+}
+
+/// Windows sliding out and in when switching workspaces.
+#[derive(Deserialize, Clone)]
+pub struct SlideAnimation {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Length of the slide in milliseconds, at most 500.
+    #[serde(default = "default_slide_duration")]
+    pub duration: u64,
+}
+
+impl Default for SlideAnimation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration: default_slide_duration(),
+        }
+    }
+}
+
+fn default_slide_duration() -> u64 {
+    150
+}
+
+#[derive(Deserialize, Clone)]
 pub struct Appearance {
     #[serde(default = "default_border_width")]
     pub border_width: u32,
     #[serde(default = "default_border_color")]
-    pub border_color: String,
+    pub border_color: Color,
     #[serde(default = "default_focused_border_color")]
-    pub focused_border_color: String,
+    pub focused_border_color: Color,
+    /// Border of unfocused windows that ask for attention.
+    #[serde(default = "default_urgent_border_color")]
+    pub urgent_border_color: Color,
+    /// Border of unfocused windows shown on every workspace.
+    #[serde(default = "default_sticky_border_color")]
+    pub sticky_border_color: Color,
     #[serde(default = "default_gaps")]
     pub gaps: u32,
+    #[serde(default = "default_nmaster")]
+    pub nmaster: usize,
     #[serde(default)]
     pub floating: FloatingWindow,
+    #[serde(default)]
+    pub slide: SlideAnimation,
     #[serde(default = "default_focus_follows_mouse")]
     pub focus_follows_mouse: bool,
     #[serde(default)]
     pub notification: NotificationAppearance,
+    #[serde(default)]
+    pub bar: BarAppearance,
+    /// Factor for the sizes of the bar and notifications. Follows the
+    /// desktop DPI when unset.
+    #[serde(default)]
+    pub scale: Option<f64>,
+    /// Takes the colors not set in the config file from the X resources or
+    /// the XSETTINGS color scheme.
+    #[serde(default)]
+    pub system_colors: bool,
+    /// Keys of the colors set in the config file, such as
+    /// `appearance.bar.focused_color`.
+    #[serde(skip)]
+    pub configured_colors: Vec<String>,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            border_width: default_border_width(),
+            border_color: default_border_color(),
+            focused_border_color: default_focused_border_color(),
+            urgent_border_color: default_urgent_border_color(),
+            sticky_border_color: default_sticky_border_color(),
+            gaps: default_gaps(),
+            nmaster: default_nmaster(),
+            floating: FloatingWindow::default(),
+            slide: SlideAnimation::default(),
+            focus_follows_mouse: default_focus_follows_mouse(),
+            notification: NotificationAppearance::default(),
+            bar: BarAppearance::default(),
+            scale: None,
+            system_colors: false,
+            configured_colors: Vec::new(),
+        }
+    }
+}
+
+/// Multiplies a size in pixels by the UI scale.
+pub fn scaled(pixels: u32, scale: f64) -> u32 {
+    (pixels as f64 * scale).round() as u32
+}
+
+/// The X resource a color follows, by its key below `appearance`.
+fn system_color_name(key: &str) -> &'static str {
+    match key {
+        "focused_border_color" | "notification.border_color" => "color4",
+        "notification.background_color" | "bar.background_color" => "background",
+        "bar.focused_color" => "foreground",
+        "bar.occupied_color" => "color7",
+        "urgent_border_color" | "bar.urgent_color" => "color1",
+        "sticky_border_color" => "color6",
+        _ => "color8",
+    }
 }
 
 fn default_border_width() -> u32 {
     2
 }
-fn default_border_color() -> String {
-    String::from("#2B0000")
+fn default_border_color() -> Color {
+    Color::rgb(0x2B, 0x00, 0x00)
+}
+fn default_focused_border_color() -> Color {
+    Color::rgb(0xFF, 0x00, 0x00)
 }
-fn default_focused_border_color() -> String {
-    String::from("#FF0000")
+fn default_urgent_border_color() -> Color {
+    Color::rgb(0xFF, 0x88, 0x00)
+}
+fn default_sticky_border_color() -> Color {
+    Color::rgb(0x00, 0x5F, 0x5F)
 }
 fn default_gaps() -> u32 {
     8
 }
+fn default_nmaster() -> usize {
+    1
+}
 fn default_float_width() -> u32 {
     800
 }
 fn default_float_height() -> u32 {
     600
 }
+fn default_resize_step() -> u32 {
+    50
+}
 fn default_focus_follows_mouse() -> bool {
     true
 }
 
 impl Appearance {
     pub fn get_border_color(&self) -> u64 {
-        let color = self.border_color.trim_start_matches('#');
-        u64::from_str_radix(color, 16).unwrap_or(0x7A8478)
+        self.border_color.pixel()
     }
 
     pub fn get_focused_border_color(&self) -> u64 {
-        let color = self.focused_border_color.trim_start_matches('#');
-        u64::from_str_radix(color, 16).unwrap_or(0xA7C080)
+        self.focused_border_color.pixel()
+    }
+
+    /// The border color of a window in the given state. Focus wins over
+    /// urgency, and urgency over being sticky.
+    pub fn get_window_border_color(&self, focused: bool, urgent: bool, sticky: bool) -> u64 {
+        match (focused, urgent, sticky) {
+            (true, _, _) => self.get_focused_border_color(),
+            (_, true, _) => self.urgent_border_color.pixel(),
+            (_, _, true) => self.sticky_border_color.pixel(),
+            _ => self.get_border_color(),
+        }
     }
 
     pub fn get_notification_background_color(&self) -> u64 {
-        let color = self.notification.background_color.trim_start_matches('#');
-        u64::from_str_radix(color, 16).unwrap_or(0x0F0F0F)
+        self.notification.background_color.pixel()
     }
 
     pub fn get_notification_border_color(&self) -> u64 {
-        let color = self.notification.border_color.trim_start_matches('#');
-        u64::from_str_radix(color, 16).unwrap_or(0xFF0000)
+        self.notification.border_color.pixel()
+    }
+
+    /// The configured scale, or the desktop DPI relative to 96 when there
+    /// is none.
+    ///
+    /// # Safety
+    /// The display pointer must be valid and point to an active X display connection.
+    pub unsafe fn scale(&self, display: *mut xlib::Display) -> f64 {
+        self.scale
+            .or_else(|| xsettings::dpi(display).map(|dpi| dpi / xsettings::BASE_DPI))
+            .filter(|&scale| scale > 0.0)
+            .unwrap_or(1.0)
+    }
+
+    /// Every color with its config key and default.
+    pub fn colors_mut(&mut self) -> [(&'static str, &mut Color, Color); 11] {
+        [
+            (
+                "appearance.border_color",
+                &mut self.border_color,
+                default_border_color(),
+            ),
+            (
+                "appearance.focused_border_color",
+                &mut self.focused_border_color,
+                default_focused_border_color(),
+            ),
+            (
+                "appearance.urgent_border_color",
+                &mut self.urgent_border_color,
+                default_urgent_border_color(),
+            ),
+            (
+                "appearance.sticky_border_color",
+                &mut self.sticky_border_color,
+                default_sticky_border_color(),
+            ),
+            (
+                "appearance.notification.background_color",
+                &mut self.notification.background_color,
+                default_notification_background_color(),
+            ),
+            (
+                "appearance.notification.border_color",
+                &mut self.notification.border_color,
+                default_notification_border_color(),
+            ),
+            (
+                "appearance.bar.background_color",
+                &mut self.bar.background_color,
+                default_bar_background_color(),
+            ),
+            (
+                "appearance.bar.focused_color",
+                &mut self.bar.focused_color,
+                default_bar_focused_color(),
+            ),
+            (
+                "appearance.bar.occupied_color",
+                &mut self.bar.occupied_color,
+                default_bar_occupied_color(),
+            ),
+            (
+                "appearance.bar.urgent_color",
+                &mut self.bar.urgent_color,
+                default_bar_urgent_color(),
+            ),
+            (
+                "appearance.bar.empty_color",
+                &mut self.bar.empty_color,
+                default_bar_empty_color(),
+            ),
+        ]
+    }
+
+    /// Sets every color the config file doesn't set with `color`, which
+    /// gets the key below `appearance`, such as `bar.focused_color`.
+    fn fill_colors(&mut self, mut color: impl FnMut(&str, Color) -> Option<Color>) {
+        let configured = std::mem::take(&mut self.configured_colors);
+        for (key, current, default) in self.colors_mut() {
+            if configured.iter().any(|k| k == key) {
+                continue;
+            }
+            if let Some(value) = color(key.trim_start_matches("appearance."), default) {
+                *current = value;
+            }
+        }
+        self.configured_colors = configured;
+    }
+
+    /// Puts the colors not set in the config file back to their defaults.
+    pub fn reset_colors(&mut self) {
+        self.fill_colors(|_, default| Some(default));
+    }
+
+    /// Replaces the colors not set in the config file with the ones of the
+    /// system theme.
+    pub fn apply_palette(&mut self, palette: &Palette) {
+        self.fill_colors(|key, _| {
+            let value = palette.get(key, system_color_name(key))?;
+            value
+                .parse()
+                .map_err(|e| log::warn!("Ignoring system color for {}: {}", key, e))
+                .ok()
+        });
+    }
+
+    /// Replaces the colors not set in the config file with the ones `theme`
+    /// sets.
+    pub fn apply_theme(&mut self, theme: &Theme) {
+        self.fill_colors(|key, _| theme.color(key).cloned());
+    }
+
+    /// Looks up every named color on the server. Colors that don't exist
+    /// are replaced by their defaults and reported together.
+    ///
+    /// # Safety
+    /// The display pointer must be valid and point to an active X display connection.
+    pub unsafe fn resolve_colors(&mut self, display: *mut xlib::Display) -> Result<()> {
+        let colors = self.colors_mut();
+
+        let mut errors = Vec::new();
+        for (key, color, default) in colors {
+            match color.resolve(display) {
+                Ok(resolved) => *color = resolved,
+                Err(e) => {
+                    errors.push(format!("{}: {}", key, e));
+                    *color = default;
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            bail!("{}", errors.join(", "));
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,436 @@
+use std::{
+    ffi::CString,
+    time::{Duration, Instant},
+};
+use x11::{xft, xlib, xrender};
+
+use crate::{
+    config::{color::Color, loader::Config},
+    ui::{
+        appearance::{scaled, BarModule, Ellipsis},
+        bar_module::WindowUsage,
+        layout::{Geometry, LayoutKind},
+    },
+    utils::{x11::WindowVisual, xsettings},
+};
+
+pub struct WorkspaceState {
+    pub focused: bool,
+    pub occupied: bool,
+    pub urgent: bool,
+}
+
+/// Everything the bar shows about the window manager.
+pub struct BarState {
+    pub workspaces: Vec<WorkspaceState>,
+    /// Windows on the current workspace, or in the current view in tag mode.
+    pub window_count: usize,
+    pub layout: LayoutKind,
+    /// Title of the focused window.
+    pub title: Option<String>,
+    /// `_NET_WM_PID` of the focused window.
+    pub focused_pid: Option<u32>,
+}
+
+pub struct StatusBar {
+    display: *mut xlib::Display,
+    pub window: xlib::Window,
+    draw: *mut xft::XftDraw,
+    font: *mut xft::XftFont,
+    visual: WindowVisual,
+    background: xft::XftColor,
+    focused: xft::XftColor,
+    occupied: xft::XftColor,
+    urgent: xft::XftColor,
+    empty: xft::XftColor,
+    config: Config,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    padding: i32,
+    scale: f64,
+    buttons: Vec<(i32, i32)>,
+    layout_button: (i32, i32),
+    /// Current text of the configured modules, hidden ones as `None`.
+    modules: Vec<Option<String>>,
+    window_usage: WindowUsage,
+    next_refresh: Instant,
+}
+
+impl StatusBar {
+    /// Creates the status bar window at the top of the given area.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection.
+    /// - The root window must be a valid window ID for the given display.
+    /// - The caller must ensure the display connection remains valid for the lifetime of the bar.
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        x: i32,
+        y: i32,
+        width: u32,
+        config: Config,
+    ) -> Self {
+        let screen = xlib::XDefaultScreen(display);
+        let window_visual = WindowVisual::new(display);
+        let WindowVisual {
+            visual, colormap, ..
+        } = window_visual;
+        let scale = config.appearance.scale(display);
+        let height = scaled(config.appearance.bar.height, scale);
+
+        let background_pixel = config.appearance.bar.background_color.pixel();
+        let window =
+            window_visual.create_window(display, root, x, y, width, height, 0, 0, background_pixel);
+
+        let mut attrs: xlib::XSetWindowAttributes = std::mem::zeroed();
+        attrs.override_redirect = 1;
+        xlib::XChangeWindowAttributes(display, window, xlib::CWOverrideRedirect, &mut attrs);
+        xlib::XSelectInput(
+            display,
+            window,
+            xlib::ExposureMask
+                | xlib::ButtonPressMask
+                | xlib::PointerMotionMask
+                | xlib::LeaveWindowMask,
+        );
+
+        let mut font = Self::open_font(display, screen, &config.appearance.bar.font, scale);
+        if font.is_null() {
+            log::warn!(
+                "Failed to load bar font '{}', falling back to monospace",
+                config.appearance.bar.font
+            );
+            font = Self::open_font(display, screen, "monospace", scale);
+        }
+
+        let draw = xft::XftDrawCreate(display, window, visual, colormap);
+
+        let bar = &config.appearance.bar;
+        let background = Self::alloc_color(display, visual, colormap, &bar.background_color);
+        let focused = Self::alloc_color(display, visual, colormap, &bar.focused_color);
+        let occupied = Self::alloc_color(display, visual, colormap, &bar.occupied_color);
+        let urgent = Self::alloc_color(display, visual, colormap, &bar.urgent_color);
+        let empty = Self::alloc_color(display, visual, colormap, &bar.empty_color);
+
+        xlib::XMapRaised(display, window);
+
+        Self {
+            display,
+            window,
+            draw,
+            font,
+            visual: window_visual,
+            background,
+            focused,
+            occupied,
+            urgent,
+            empty,
+            config,
+            x,
+            y,
+            width,
+            height,
+            padding: scaled(8, scale) as i32,
+            scale,
+            buttons: Vec::new(),
+            layout_button: (0, 0),
+            modules: Vec::new(),
+            window_usage: WindowUsage::default(),
+            next_refresh: Instant::now(),
+        }
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.config.appearance.bar.modules.interval.max(1))
+    }
+
+    /// Time left until the modules are due for a refresh, if there are any.
+    pub fn timeout(&self) -> Option<Duration> {
+        if self.config.appearance.bar.modules.right.is_empty() {
+            return None;
+        }
+        Some(self.next_refresh.saturating_duration_since(Instant::now()))
+    }
+
+    /// Rereads the modules when they are due. Returns whether any of them
+    /// changed, in which case the bar needs a redraw.
+    pub fn poll(&mut self) -> bool {
+        if !self.timeout().is_some_and(|t| t.is_zero()) {
+            return false;
+        }
+
+        self.next_refresh = Instant::now() + self.refresh_interval();
+        let modules = self
+            .config
+            .appearance
+            .bar
+            .modules
+            .right
+            .iter()
+            .map(|module| module.read(&mut self.window_usage))
+            .collect::<Vec<_>>();
+
+        let changed = modules != self.modules;
+        self.modules = modules;
+        changed
+    }
+
+    /// Opens a fontconfig pattern at the DPI matching `scale`, unless the
+    /// pattern sets its own.
+    unsafe fn open_font(
+        display: *mut xlib::Display,
+        screen: i32,
+        pattern: &str,
+        scale: f64,
+    ) -> *mut xft::XftFont {
+        let pattern = if pattern.contains("dpi=") {
+            pattern.to_string()
+        } else {
+            format!("{}:dpi={}", pattern, xsettings::BASE_DPI * scale)
+        };
+        let name = CString::new(pattern).unwrap_or_default();
+        xft::XftFontOpenName(display, screen, name.as_ptr())
+    }
+
+    unsafe fn alloc_color(
+        display: *mut xlib::Display,
+        visual: *mut xlib::Visual,
+        colormap: xlib::Colormap,
+        color: &Color,
+    ) -> xft::XftColor {
+        color
+            .to_xft(display, visual, colormap)
+            .or_else(|e| {
+                log::warn!("{}, using white for the bar", e);
+                Color::rgb(0xFF, 0xFF, 0xFF).to_xft(display, visual, colormap)
+            })
+            .unwrap_or_else(|_| std::mem::zeroed())
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the index of the workspace button at the given x coordinate.
+    pub fn workspace_at(&self, x: i32) -> Option<usize> {
+        self.buttons
+            .iter()
+            .position(|&(start, width)| x >= start && x < start + width)
+    }
+
+    /// Like [`workspace_at`](Self::workspace_at), for an x coordinate on the root window.
+    pub fn workspace_at_root(&self, x_root: i32) -> Option<usize> {
+        self.workspace_at(x_root - self.x)
+    }
+
+    /// Where the button of a workspace is on the root window.
+    pub fn workspace_button(&self, index: usize) -> Option<Geometry> {
+        let &(start, width) = self.buttons.get(index)?;
+        Some(Geometry {
+            x: self.x + start,
+            y: self.y,
+            width: width as u32,
+            height: self.height,
+        })
+    }
+
+    /// Shortens `text` with an ellipsis until it is at most `max_width`
+    /// pixels wide. A `max_width` of 0 or less leaves nothing.
+    unsafe fn fit_text(&self, text: &str, max_width: i32, ellipsis: Ellipsis) -> String {
+        if self.text_width(text) <= max_width {
+            return text.to_string();
+        }
+
+        let chars = text.chars().collect::<Vec<_>>();
+        let shorten = |keep: usize| -> String {
+            let (head, tail) = match ellipsis {
+                Ellipsis::End => (keep, 0),
+                Ellipsis::Middle => (keep - keep / 2, keep / 2),
+            };
+            chars[..head]
+                .iter()
+                .chain(std::iter::once(&'…'))
+                .chain(&chars[chars.len() - tail..])
+                .collect()
+        };
+
+        if self.text_width("…") > max_width {
+            return String::new();
+        }
+
+        // Largest number of kept characters that still fits.
+        let (mut low, mut high) = (0, chars.len() - 1);
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if self.text_width(&shorten(mid)) <= max_width {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        shorten(low)
+    }
+
+    unsafe fn text_width(&self, text: &str) -> i32 {
+        let mut extents: xrender::XGlyphInfo = std::mem::zeroed();
+        xft::XftTextExtentsUtf8(
+            self.display,
+            self.font,
+            text.as_ptr(),
+            text.len() as i32,
+            &mut extents,
+        );
+        extents.xOff as i32
+    }
+
+    /// Whether the given x coordinate is on the layout indicator.
+    pub fn is_layout_at(&self, x: i32) -> bool {
+        let (start, width) = self.layout_button;
+        x >= start && x < start + width
+    }
+
+    /// Redraws the bar with the given state.
+    ///
+    /// # Safety
+    /// - The display connection must still be valid
+    /// - The bar window must not have been destroyed
+    pub unsafe fn draw(&mut self, state: &BarState) {
+        // Another window got focus, its usage is read right away.
+        let modules = &self.config.appearance.bar.modules.right;
+        if self.window_usage.set_pid(state.focused_pid) && modules.contains(&BarModule::WindowUsage)
+        {
+            self.next_refresh = Instant::now();
+        }
+        xft::XftDrawRect(self.draw, &self.background, 0, 0, self.width, self.height);
+        if self.font.is_null() {
+            return;
+        }
+
+        let style = self.config.appearance.bar.workspace_style;
+        let baseline =
+            ((self.height as i32 + (*self.font).ascent - (*self.font).descent) / 2).max(0);
+
+        self.buttons.clear();
+        let mut x = 0;
+        for (index, state) in state.workspaces.iter().enumerate() {
+            let label = self.config.get_workspace_label(index, style);
+            let button_width = self.text_width(&label) + self.padding * 2;
+
+            let color = if state.focused {
+                xft::XftDrawRect(
+                    self.draw,
+                    &self.focused,
+                    x,
+                    0,
+                    button_width as u32,
+                    self.height,
+                );
+                &self.background
+            } else if state.urgent {
+                &self.urgent
+            } else if state.occupied {
+                &self.occupied
+            } else {
+                &self.empty
+            };
+
+            xft::XftDrawStringUtf8(
+                self.draw,
+                color,
+                self.font,
+                x + self.padding,
+                baseline,
+                label.as_ptr(),
+                label.len() as i32,
+            );
+
+            self.buttons.push((x, button_width));
+            x += button_width;
+        }
+
+        let layout = format!("{} {}", state.layout.symbol(), state.window_count);
+        let layout_width = self.text_width(&layout) + self.padding * 2;
+        xft::XftDrawStringUtf8(
+            self.draw,
+            &self.occupied,
+            self.font,
+            x + self.padding,
+            baseline,
+            layout.as_ptr(),
+            layout.len() as i32,
+        );
+        self.layout_button = (x, layout_width);
+
+        x += layout_width;
+
+        let truncate = &self.config.appearance.bar.truncate;
+        let module_max = match truncate.module {
+            0 => i32::MAX,
+            width => width as i32,
+        };
+
+        let mut right = self.width as i32;
+        for text in self.modules.iter().rev().flatten() {
+            let text = self.fit_text(text, module_max, truncate.ellipsis);
+            right -= self.text_width(&text) + self.padding * 2;
+            xft::XftDrawStringUtf8(
+                self.draw,
+                &self.occupied,
+                self.font,
+                right + self.padding,
+                baseline,
+                text.as_ptr(),
+                text.len() as i32,
+            );
+        }
+
+        if let Some(title) = &state.title {
+            let mut max_width = right - x - self.padding * 2;
+            if truncate.title > 0 {
+                max_width = max_width.min(scaled(truncate.title, self.scale) as i32);
+            }
+            let title = self.fit_text(title, max_width, truncate.ellipsis);
+            xft::XftDrawStringUtf8(
+                self.draw,
+                &self.occupied,
+                self.font,
+                x + self.padding,
+                baseline,
+                title.as_ptr(),
+                title.len() as i32,
+            );
+        }
+
+        xlib::XFlush(self.display);
+    }
+}
+
+impl Drop for StatusBar {
+    fn drop(&mut self) {
+        unsafe {
+            for color in [
+                &mut self.background,
+                &mut self.focused,
+                &mut self.occupied,
+                &mut self.urgent,
+                &mut self.empty,
+            ] {
+                xft::XftColorFree(
+                    self.display,
+                    self.visual.visual,
+                    self.visual.colormap,
+                    color,
+                );
+            }
+            xft::XftDrawDestroy(self.draw);
+            if !self.font.is_null() {
+                xft::XftFontClose(self.display, self.font);
+            }
+            xlib::XDestroyWindow(self.display, self.window);
+            self.visual.free(self.display);
+        }
+    }
+}
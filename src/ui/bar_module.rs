@@ -0,0 +1,126 @@
+use std::{fs, path::Path, time::Instant};
+
+use super::appearance::BarModule;
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+const NET: &str = "/sys/class/net";
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Entries of a sysfs class directory, sorted so the output is stable.
+fn class_entries(class: &str) -> Vec<std::path::PathBuf> {
+    let mut entries = fs::read_dir(class)
+        .map(|dir| dir.flatten().map(|entry| entry.path()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+/// `BAT 80%`, with `+` appended while charging. `None` without a battery.
+fn battery() -> Option<String> {
+    let battery = class_entries(POWER_SUPPLY)
+        .into_iter()
+        .find(|path| read_trimmed(&path.join("type")).as_deref() == Some("Battery"))?;
+
+    let capacity = read_trimmed(&battery.join("capacity"))?;
+    let charging = read_trimmed(&battery.join("status")).as_deref() == Some("Charging");
+
+    Some(format!(
+        "BAT {}%{}",
+        capacity,
+        if charging { "+" } else { "" }
+    ))
+}
+
+/// The first interface that is up, or `offline`.
+fn network() -> Option<String> {
+    let interface = class_entries(NET).into_iter().find(|path| {
+        path.file_name().is_some_and(|name| name != "lo")
+            && read_trimmed(&path.join("operstate")).as_deref() == Some("up")
+    });
+
+    Some(match interface {
+        Some(path) => {
+            let name = path.file_name()?.to_string_lossy().into_owned();
+            if path.join("wireless").exists() {
+                format!("{} (wifi)", name)
+            } else {
+                name
+            }
+        }
+        None => String::from("offline"),
+    })
+}
+
+/// The process of the focused window, followed through `/proc`.
+#[derive(Default)]
+pub struct WindowUsage {
+    pid: Option<u32>,
+    /// CPU time of the process in clock ticks at the previous read.
+    last: Option<(u64, Instant)>,
+}
+
+impl WindowUsage {
+    /// Follows `pid` from now on. Returns whether it is another process.
+    pub fn set_pid(&mut self, pid: Option<u32>) -> bool {
+        if pid == self.pid {
+            return false;
+        }
+        self.pid = pid;
+        self.last = None;
+        true
+    }
+
+    /// `firefox 12% 340M`: the share of one core used since the previous
+    /// read and the resident memory. The CPU share is left out on the first
+    /// read. `None` when the process isn't visible in `/proc`.
+    fn read(&mut self) -> Option<String> {
+        let proc = Path::new("/proc").join(self.pid?.to_string());
+        let name = read_trimmed(&proc.join("comm"))?;
+        let rss = fs::read_to_string(proc.join("status"))
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))
+            .and_then(|kb| kb.trim().trim_end_matches("kB").trim().parse::<u64>().ok())?;
+
+        // The command name in parentheses may contain spaces, utime and
+        // stime are the 12th and 13th field after it.
+        let stat = fs::read_to_string(proc.join("stat")).ok()?;
+        let fields = stat
+            .rsplit_once(')')?
+            .1
+            .split_whitespace()
+            .collect::<Vec<_>>();
+        let ticks = fields.get(11)?.parse::<u64>().ok()? + fields.get(12)?.parse::<u64>().ok()?;
+        let now = Instant::now();
+        let cpu = self.last.replace((ticks, now)).map(|(last, at)| {
+            let per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+            let seconds = now.duration_since(at).as_secs_f64().max(f64::EPSILON);
+            ticks.saturating_sub(last) as f64 / per_second / seconds * 100.0
+        });
+
+        let memory = match rss {
+            kb if kb >= 1024 * 1024 => format!("{:.1}G", kb as f64 / (1024.0 * 1024.0)),
+            kb => format!("{}M", kb / 1024),
+        };
+        Some(match cpu {
+            Some(cpu) => format!("{} {:.0}% {}", name, cpu, memory),
+            None => format!("{} {}", name, memory),
+        })
+    }
+}
+
+impl BarModule {
+    /// Reads the current text of the module. `None` hides it.
+    pub fn read(&self, window: &mut WindowUsage) -> Option<String> {
+        match self {
+            Self::Battery => battery(),
+            Self::Network => network(),
+            Self::WindowUsage => window.read(),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::ffi::CString;
+use x11::xlib;
+
+use crate::{
+    ui::modal::{text, ModalDialog},
+    utils::x11::Monitor,
+};
+
+const PADDING: i32 = 12;
+const LINE_HEIGHT: i32 = 16;
+/// Space between the keys and the command, and between columns.
+const GAP: i32 = 24;
+
+/// One line of the overlay: a heading, or a bind with its keys.
+enum Line {
+    Heading(CString),
+    Bind(CString, CString),
+}
+
+/// Lists `groups` of binds, each a heading and its keys and commands, in
+/// an overlay centered on `area` until a key is pressed. Lines that don't
+/// fit the height of `area` go on in further columns.
+///
+/// # Safety
+/// The display pointer must be valid and point to an active X display connection,
+/// and root must be its root window.
+pub unsafe fn show_binds(
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    area: Monitor,
+    groups: &[(&str, Vec<(String, String)>)],
+    background: u64,
+    border: u64,
+) {
+    let Some(dialog) = ModalDialog::new(display, root, background, border) else {
+        return;
+    };
+
+    let mut lines = Vec::new();
+    for (heading, binds) in groups {
+        if !lines.is_empty() {
+            lines.push(Line::Heading(CString::default()));
+        }
+        lines.push(Line::Heading(text(heading)));
+        for (keys, command) in binds {
+            lines.push(Line::Bind(text(keys), text(command)));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(Line::Heading(text("No binds configured")));
+    }
+
+    // Columns as tall as the monitor allows, each as wide as its keys
+    // and commands need.
+    let per_column = ((area.height as i32 - PADDING * 2) / LINE_HEIGHT).max(1) as usize;
+    let columns = lines
+        .chunks(per_column)
+        .map(|column| {
+            let (mut keys, mut commands) = (0, 0);
+            for line in column {
+                match line {
+                    Line::Heading(heading) => keys = keys.max(dialog.text_width(heading)),
+                    Line::Bind(key, command) => {
+                        keys = keys.max(dialog.text_width(key));
+                        commands = commands.max(dialog.text_width(command));
+                    }
+                }
+            }
+            (column, keys, commands)
+        })
+        .collect::<Vec<_>>();
+
+    let content_width = columns
+        .iter()
+        .map(|(_, keys, commands)| keys + GAP + commands)
+        .sum::<i32>()
+        + GAP * (columns.len() as i32 - 1);
+    let width = (content_width + PADDING * 2).min(area.width as i32).max(1);
+    let rows = lines.len().min(per_column) as i32;
+    let height = (rows * LINE_HEIGHT + PADDING * 2)
+        .min(area.height as i32)
+        .max(1);
+    dialog.show(area, width, height);
+
+    dialog.next_key(|dialog| {
+        let mut column_x = PADDING;
+        for (column, keys, commands) in &columns {
+            let mut baseline = PADDING + LINE_HEIGHT - 4;
+            for line in column.iter() {
+                match line {
+                    Line::Heading(heading) => {
+                        dialog.draw_string(column_x, baseline, heading, dialog.border)
+                    }
+                    Line::Bind(key, command) => {
+                        dialog.draw_string(column_x, baseline, key, dialog.white);
+                        dialog.draw_string(column_x + keys + GAP, baseline, command, dialog.white);
+                    }
+                }
+                baseline += LINE_HEIGHT;
+            }
+            column_x += keys + GAP + commands + GAP;
+        }
+    });
+}
@@ -0,0 +1,87 @@
+use x11::{keysym, xlib};
+
+use crate::{
+    ui::modal::{text, ModalDialog},
+    utils::x11::Monitor,
+};
+
+const PADDING: i32 = 12;
+const LINE_HEIGHT: i32 = 16;
+const BUTTON_WIDTH: i32 = 64;
+const BUTTON_HEIGHT: i32 = 24;
+
+/// Asks `question` in a dialog centered on `area` and waits for the
+/// answer while the keyboard is grabbed. Left, Right and Tab move between
+/// Yes and No, Return or space picks the selected one, `y` and `n` answer
+/// right away and Escape says no.
+///
+/// No is selected at first, and the answer is no when the keyboard can't
+/// be grabbed.
+///
+/// # Safety
+/// The display pointer must be valid and point to an active X display connection,
+/// and root must be its root window.
+pub unsafe fn confirm(
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    area: Monitor,
+    question: &str,
+    background: u64,
+    border: u64,
+) -> bool {
+    let Some(dialog) = ModalDialog::new(display, root, background, border) else {
+        return false;
+    };
+    let question = text(question);
+
+    let width = (dialog.text_width(&question) + PADDING * 2).max(BUTTON_WIDTH * 2 + PADDING * 3);
+    let height = PADDING * 3 + LINE_HEIGHT + BUTTON_HEIGHT;
+    dialog.show(area, width, height);
+
+    let labels = [text("Yes"), text("No")];
+    let buttons_x = (width - BUTTON_WIDTH * 2 - PADDING) / 2;
+    let buttons_y = PADDING * 2 + LINE_HEIGHT;
+    let (question, labels) = (&question, &labels);
+    let paint = |yes: bool| {
+        move |dialog: &ModalDialog| {
+            dialog.draw_string(PADDING, PADDING + LINE_HEIGHT - 4, question, dialog.white);
+            for (index, label) in labels.iter().enumerate() {
+                let selected = (index == 0) == yes;
+                let button_x = buttons_x + index as i32 * (BUTTON_WIDTH + PADDING);
+                let color = if selected {
+                    dialog.border
+                } else {
+                    dialog.white
+                };
+                dialog.draw_rectangle(
+                    button_x,
+                    buttons_y,
+                    BUTTON_WIDTH,
+                    BUTTON_HEIGHT,
+                    color,
+                    selected,
+                );
+                dialog.draw_string(
+                    button_x + (BUTTON_WIDTH - dialog.text_width(label)) / 2,
+                    buttons_y + BUTTON_HEIGHT / 2 + 5,
+                    label,
+                    dialog.white,
+                );
+            }
+        }
+    };
+
+    let mut yes = false;
+    loop {
+        match dialog.next_key(paint(yes)) {
+            keysym::XK_y => return true,
+            keysym::XK_n | keysym::XK_Escape => return false,
+            keysym::XK_Return | keysym::XK_KP_Enter | keysym::XK_space => return yes,
+            keysym::XK_Left | keysym::XK_Right | keysym::XK_Tab => {
+                yes = !yes;
+                dialog.draw(paint(yes));
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,9 +1,46 @@
 use anyhow::Result;
+use std::{cell::RefCell, collections::HashMap};
 use x11::xlib;
 
+/// Shapes from the X cursor font.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Shape {
+    Normal,
+    Grabbing,
+    Move,
+    Busy,
+    SizeHorizontal,
+    SizeVertical,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Crosshair,
+}
+
+impl Shape {
+    /// The glyph in the cursor font, see `X11/cursorfont.h`.
+    fn glyph(self) -> u32 {
+        match self {
+            Self::Normal => 68,          // XC_left_ptr
+            Self::Grabbing => 90,        // XC_hand2
+            Self::Move => 52,            // XC_fleur
+            Self::Busy => 150,           // XC_watch
+            Self::SizeHorizontal => 108, // XC_sb_h_double_arrow
+            Self::SizeVertical => 116,   // XC_sb_v_double_arrow
+            Self::TopLeft => 134,        // XC_top_left_corner
+            Self::TopRight => 136,       // XC_top_right_corner
+            Self::BottomLeft => 12,      // XC_bottom_left_corner
+            Self::BottomRight => 14,     // XC_bottom_right_corner
+            Self::Crosshair => 34,       // XC_crosshair
+        }
+    }
+}
+
+/// The cursors used by the window manager. Each one is created the first
+/// time it is needed and freed on drop.
 pub struct Cursor {
-    normal: xlib::Cursor,
-    grabbing: xlib::Cursor,
+    cursors: RefCell<HashMap<Shape, xlib::Cursor>>,
     display: *mut xlib::Display,
 }
 
@@ -14,30 +51,52 @@ impl Cursor {
     /// The display pointer must be valid and point to an active X display connection.
     /// The caller must ensure the display connection remains valid for the lifetime of the cursor.
     pub unsafe fn new(display: *mut xlib::Display) -> Result<Self> {
-        let normal = xlib::XCreateFontCursor(display, 68);
-        let grabbing = xlib::XCreateFontCursor(display, 90); // XC_hand2
-
         Ok(Self {
-            normal,
-            grabbing,
+            cursors: RefCell::new(HashMap::new()),
             display,
         })
     }
 
+    pub fn get(&self, shape: Shape) -> xlib::Cursor {
+        *self
+            .cursors
+            .borrow_mut()
+            .entry(shape)
+            .or_insert_with(|| unsafe { xlib::XCreateFontCursor(self.display, shape.glyph()) })
+    }
+
     pub fn normal(&self) -> xlib::Cursor {
-        self.normal
+        self.get(Shape::Normal)
     }
 
     pub fn grabbing(&self) -> xlib::Cursor {
-        self.grabbing
+        self.get(Shape::Grabbing)
+    }
+
+    /// Four arrows, for moving floating windows.
+    pub fn moving(&self) -> xlib::Cursor {
+        self.get(Shape::Move)
+    }
+
+    pub fn busy(&self) -> xlib::Cursor {
+        self.get(Shape::Busy)
+    }
+
+    pub fn size_horizontal(&self) -> xlib::Cursor {
+        self.get(Shape::SizeHorizontal)
+    }
+
+    pub fn size_vertical(&self) -> xlib::Cursor {
+        self.get(Shape::SizeVertical)
     }
 }
 
 impl Drop for Cursor {
     fn drop(&mut self) {
         unsafe {
-            xlib::XFreeCursor(self.display, self.normal);
-            xlib::XFreeCursor(self.display, self.grabbing);
+            for cursor in self.cursors.get_mut().values() {
+                xlib::XFreeCursor(self.display, *cursor);
+            }
         }
     }
 }
@@ -0,0 +1,87 @@
+use x11::xlib;
+
+use crate::ui::layout::Geometry;
+
+/// Width of the outline in pixels.
+const THICKNESS: u32 = 4;
+
+/// An outline around the tiled window a dragged window takes the place of
+/// once it is dropped. Drawn with four thin windows along the edges, so
+/// the window inside stays visible and clickable.
+pub struct DropIndicator {
+    display: *mut xlib::Display,
+    edges: [xlib::Window; 4],
+    area: Option<Geometry>,
+}
+
+impl DropIndicator {
+    /// Creates the outline, unmapped until it is shown.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection
+    /// - The root window must be a valid window ID for the given display
+    pub unsafe fn new(display: *mut xlib::Display, root: xlib::Window, color: u64) -> Self {
+        let edges = [0; 4].map(|_| {
+            let edge = xlib::XCreateSimpleWindow(display, root, 0, 0, 1, 1, 0, 0, color);
+            let mut attrs: xlib::XSetWindowAttributes = std::mem::zeroed();
+            attrs.override_redirect = 1;
+            xlib::XChangeWindowAttributes(display, edge, xlib::CWOverrideRedirect, &mut attrs);
+            edge
+        });
+
+        Self {
+            display,
+            edges,
+            area: None,
+        }
+    }
+
+    /// Whether `window` is part of the outline.
+    pub fn contains(&self, window: xlib::Window) -> bool {
+        self.edges.contains(&window)
+    }
+
+    /// Outlines `area`, or hides the outline for `None`.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn show(&mut self, area: Option<Geometry>) {
+        if area == self.area {
+            return;
+        }
+        self.area = area;
+
+        let Some(area) = area else {
+            for edge in self.edges {
+                xlib::XUnmapWindow(self.display, edge);
+            }
+            xlib::XFlush(self.display);
+            return;
+        };
+
+        let thickness = THICKNESS.min(area.width / 2).min(area.height / 2).max(1);
+        let right = area.x + (area.width - thickness) as i32;
+        let bottom = area.y + (area.height - thickness) as i32;
+        let sides = [
+            (area.x, area.y, area.width, thickness),
+            (area.x, bottom, area.width, thickness),
+            (area.x, area.y, thickness, area.height),
+            (right, area.y, thickness, area.height),
+        ];
+        for (edge, (x, y, width, height)) in self.edges.into_iter().zip(sides) {
+            xlib::XMoveResizeWindow(self.display, edge, x, y, width.max(1), height.max(1));
+            xlib::XMapRaised(self.display, edge);
+        }
+        xlib::XFlush(self.display);
+    }
+}
+
+impl Drop for DropIndicator {
+    fn drop(&mut self) {
+        unsafe {
+            for edge in self.edges {
+                xlib::XDestroyWindow(self.display, edge);
+            }
+        }
+    }
+}
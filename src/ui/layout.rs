@@ -1,6 +1,11 @@
+use serde::{Deserialize, Serialize};
 use x11::{xinerama, xlib};
 
-use crate::config::loader::Config;
+use crate::{
+    config::loader::Config,
+    utils::x11::Monitor,
+    velowm_core::registry::{WindowId, WindowRegistry},
+};
 
 pub struct Window {
     id: xlib::Window,
@@ -10,30 +15,173 @@ pub struct Window {
     height: u32,
 }
 
-pub struct Monitor {
-    x: i32,
-    y: i32,
-    width: u32,
-    height: u32,
+impl WindowId for Window {
+    fn window_id(&self) -> xlib::Window {
+        self.id
+    }
+}
+
+impl Window {
+    /// Where the window was last put.
+    fn geometry(&self) -> Geometry {
+        Geometry {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl From<Monitor> for Geometry {
+    fn from(monitor: Monitor) -> Self {
+        Self {
+            x: monitor.x,
+            y: monitor.y,
+            width: monitor.width,
+            height: monitor.height,
+        }
+    }
+}
+
+impl From<Geometry> for Monitor {
+    fn from(geometry: Geometry) -> Self {
+        Self {
+            x: geometry.x,
+            y: geometry.y,
+            width: geometry.width,
+            height: geometry.height,
+        }
+    }
+}
+
+/// Space reserved for a dock (such as the bar) at one edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dock {
+    None,
+    Top(u32),
+    Bottom(u32),
+    Left(u32),
+    Right(u32),
+}
+
+/// Space reserved at each edge of the monitor, in pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Struts {
+    pub top: u32,
+    pub bottom: u32,
+    pub left: u32,
+    pub right: u32,
+}
+
+impl Struts {
+    /// The space needed to keep clear of both: docks on the same edge
+    /// overlap, so the larger one counts.
+    pub fn max(self, other: Struts) -> Struts {
+        Struts {
+            top: self.top.max(other.top),
+            bottom: self.bottom.max(other.bottom),
+            left: self.left.max(other.left),
+            right: self.right.max(other.right),
+        }
+    }
+}
+
+impl From<Dock> for Struts {
+    fn from(dock: Dock) -> Self {
+        let mut struts = Struts::default();
+        match dock {
+            Dock::None => {}
+            Dock::Top(height) => struts.top = height,
+            Dock::Bottom(height) => struts.bottom = height,
+            Dock::Left(width) => struts.left = width,
+            Dock::Right(width) => struts.right = width,
+        }
+        struts
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutKind {
+    MasterStack,
+    Monocle,
+}
+
+impl LayoutKind {
+    pub fn next(self) -> Self {
+        match self {
+            Self::MasterStack => Self::Monocle,
+            Self::Monocle => Self::MasterStack,
+        }
+    }
+
+    /// Short indicator shown in the bar.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::MasterStack => "[]=",
+            Self::Monocle => "[M]",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LayoutSettings {
+    pub kind: LayoutKind,
+    pub master_ratio: f32,
+    pub gaps: u32,
+    pub nmaster: usize,
+}
+
+impl LayoutSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            kind: LayoutKind::MasterStack,
+            master_ratio: 0.5,
+            gaps: config.appearance.gaps,
+            nmaster: config.appearance.nmaster,
+        }
+    }
+
+    /// Takes the values that differ between `old` and `new`, a workspace's
+    /// configured settings before and after a config reload. The others
+    /// keep what was changed at runtime.
+    pub fn rebase(&mut self, old: &LayoutSettings, new: &LayoutSettings) {
+        if old.kind != new.kind {
+            self.kind = new.kind;
+        }
+        if old.master_ratio != new.master_ratio {
+            self.master_ratio = new.master_ratio;
+        }
+        if old.gaps != new.gaps {
+            self.gaps = new.gaps;
+        }
+        if old.nmaster != new.nmaster {
+            self.nmaster = new.nmaster;
+        }
+    }
 }
 
 pub struct MasterStackLayout {
-    windows: Vec<Window>,
+    windows: WindowRegistry<Window>,
     display: *mut xlib::Display,
     root: xlib::Window,
-    master_width_ratio: f32,
-    current_monitor: Monitor,
+    settings: LayoutSettings,
+    current_monitor: Geometry,
     config: Config,
-    focused_window: Option<xlib::Window>,
-    dock_height: u32,
-    dock_position: DockPosition,
-}
-
-#[derive(PartialEq)]
-enum DockPosition {
-    Top,
-    Bottom,
-    None,
+    /// Space reserved by each dock window, the internal bar included, and
+    /// the area the dock covers.
+    docks: Vec<(xlib::Window, Struts, Geometry)>,
+    /// Set by `relayout`, cleared once `flush` has laid the windows out.
+    pending: bool,
 }
 
 impl MasterStackLayout {
@@ -51,7 +199,7 @@ impl MasterStackLayout {
 
             if !monitors.is_null() && num_monitors > 0 {
                 let monitor = *monitors;
-                let mon = Monitor {
+                let mon = Geometry {
                     x: monitor.x_org as i32,
                     y: monitor.y_org as i32,
                     width: monitor.width as u32,
@@ -60,7 +208,7 @@ impl MasterStackLayout {
                 xlib::XFree(monitors as *mut _);
                 mon
             } else {
-                Monitor {
+                Geometry {
                     x: 0,
                     y: 0,
                     width: xlib::XDisplayWidth(display, screen) as u32,
@@ -70,15 +218,14 @@ impl MasterStackLayout {
         };
 
         Self {
-            windows: Vec::new(),
+            windows: WindowRegistry::new(),
             display,
             root,
-            master_width_ratio: 0.5,
+            settings: LayoutSettings::from_config(&config),
             current_monitor,
             config,
-            focused_window: None,
-            dock_height: 0,
-            dock_position: DockPosition::None,
+            docks: Vec::new(),
+            pending: false,
         }
     }
 
@@ -86,42 +233,17 @@ impl MasterStackLayout {
         self.root
     }
 
-    pub fn get_focused_window(&self) -> Option<xlib::Window> {
-        self.focused_window
-    }
-
-    pub fn focus_window(&mut self, window: xlib::Window) {
-        if window == self.root {
-            return;
-        }
-
-        unsafe {
-            if let Some(old_focused) = self.focused_window {
-                xlib::XSetWindowBorder(self.display, old_focused, self.config.get_border_color());
-            }
-
-            xlib::XSetWindowBorder(self.display, window, self.config.get_focused_border_color());
-            xlib::XSetInputFocus(
-                self.display,
-                window,
-                xlib::RevertToPointerRoot,
-                xlib::CurrentTime,
-            );
-            xlib::XSync(self.display, 0);
-        }
-
-        self.focused_window = Some(window);
-    }
-
     pub fn add_window(&mut self, window: xlib::Window) {
         unsafe {
             xlib::XSetWindowBorderWidth(self.display, window, self.config.appearance.border_width);
-            xlib::XSetWindowBorder(self.display, window, self.config.get_border_color());
 
             xlib::XSelectInput(
                 self.display,
                 window,
-                xlib::EnterWindowMask | xlib::LeaveWindowMask | xlib::FocusChangeMask,
+                xlib::EnterWindowMask
+                    | xlib::LeaveWindowMask
+                    | xlib::FocusChangeMask
+                    | xlib::PropertyChangeMask,
             );
 
             let mut attrs: xlib::XWindowAttributes = std::mem::zeroed();
@@ -137,38 +259,46 @@ impl MasterStackLayout {
 
             self.windows.push(new_window);
             self.relayout();
+        }
+    }
 
-            self.focus_window(window);
-            xlib::XSync(self.display, 0);
+    /// Lays out the tiled windows of another workspace, handing back the
+    /// ones laid out so far. Of `tiled`, the windows `windows` has keep
+    /// their order and last geometry, so the ones that stay put aren't
+    /// reconfigured. The others are added at the end.
+    pub fn replace_windows(
+        &mut self,
+        mut windows: WindowRegistry<Window>,
+        tiled: &[xlib::Window],
+    ) -> WindowRegistry<Window> {
+        let gone = windows
+            .iter()
+            .map(|w| w.id)
+            .filter(|id| !tiled.contains(id))
+            .collect::<Vec<_>>();
+        for id in gone {
+            windows.remove(id);
         }
+
+        let previous = std::mem::replace(&mut self.windows, windows);
+        for &id in tiled {
+            if !self.windows.contains_id(id) {
+                self.add_window(id);
+            }
+        }
+        self.relayout();
+        previous
     }
 
     pub fn clear_windows(&mut self) {
         self.windows.clear();
-        self.focused_window = None;
     }
 
     pub fn remove_window(&mut self, window: xlib::Window) {
-        if self.focused_window == Some(window) {
-            self.focused_window = None;
-            if let Some(idx) = self.windows.iter().position(|w| w.id == window) {
-                if idx > 0 {
-                    if let Some(prev_window) = self.windows.get(idx - 1) {
-                        self.focus_window(prev_window.id);
-                    }
-                } else if let Some(next_window) = self.windows.get(1) {
-                    self.focus_window(next_window.id);
-                }
-            }
-        }
-        self.windows.retain(|w| w.id != window);
+        self.windows.remove(window);
         self.relayout();
     }
 
-    fn get_screen_dimensions(&self) -> (u32, u32) {
-        (self.current_monitor.width, self.current_monitor.height)
-    }
-
     pub fn update_config(&mut self, config: Config) {
         self.config = config;
 
@@ -181,7 +311,6 @@ impl MasterStackLayout {
                     window.id,
                     self.config.appearance.border_width,
                 );
-                xlib::XSetWindowBorder(self.display, window.id, self.config.get_border_color());
 
                 xlib::XClearWindow(self.display, window.id);
             }
@@ -191,89 +320,139 @@ impl MasterStackLayout {
         self.relayout();
     }
 
-    pub fn update_dock_space(&mut self, y: i32, height: u32) {
-        if y < self.current_monitor.height as i32 / 2 {
-            self.dock_position = DockPosition::Top;
-        } else {
-            self.dock_position = DockPosition::Bottom;
+    pub fn settings(&self) -> &LayoutSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: LayoutSettings) {
+        self.settings = settings;
+        self.relayout();
+    }
+
+    pub fn adjust_master_ratio(&mut self, delta: f32) {
+        self.settings.master_ratio = (self.settings.master_ratio + delta).clamp(0.1, 0.9);
+        self.relayout();
+    }
+
+    /// Moves the split between master and stack to `x` on the root window.
+    pub fn set_split(&mut self, x: i32) {
+        let ratio = ratio_at(x, self.current_monitor, self.struts(), &self.settings);
+        if ratio != self.settings.master_ratio {
+            self.settings.master_ratio = ratio;
+            self.relayout();
         }
-        self.dock_height = height;
+    }
+
+    /// Where the gap between master and stack is, see [`split_gap`].
+    pub fn split_gap(&self) -> Option<Geometry> {
+        split_gap(
+            self.windows.len(),
+            self.current_monitor,
+            self.struts(),
+            &self.settings,
+        )
+    }
+
+    pub fn cycle_kind(&mut self) {
+        self.settings.kind = self.settings.kind.next();
         self.relayout();
     }
 
-    pub fn relayout(&mut self) {
-        let n = self.windows.len();
-        if n == 0 {
-            return;
+    pub fn adjust_gaps(&mut self, delta: i32) {
+        self.settings.gaps = self.settings.gaps.saturating_add_signed(delta);
+        self.relayout();
+    }
+
+    pub fn adjust_nmaster(&mut self, delta: isize) {
+        self.settings.nmaster = self.settings.nmaster.saturating_add_signed(delta);
+        self.relayout();
+    }
+
+    /// The monitor tiled windows are placed on.
+    pub fn monitor(&self) -> Geometry {
+        self.current_monitor
+    }
+
+    /// Moves the tiled windows to another monitor.
+    pub fn set_monitor(&mut self, monitor: Geometry) {
+        if monitor != self.current_monitor {
+            self.current_monitor = monitor;
+            self.relayout();
         }
+    }
 
-        let (screen_width, mut screen_height) = self.get_screen_dimensions();
-        let gaps = self.config.appearance.gaps;
+    /// Reserves space for a dock window covering `area`, replacing what it
+    /// reserved before. Only docks on the layout's monitor take space.
+    pub fn set_dock(&mut self, window: xlib::Window, struts: Struts, area: Geometry) {
+        match self.docks.iter_mut().find(|(id, _, _)| *id == window) {
+            Some(dock) => *dock = (window, struts, area),
+            None => self.docks.push((window, struts, area)),
+        }
+        self.relayout();
+    }
 
-        let y_offset = if self.dock_position == DockPosition::Top {
-            self.dock_height
-        } else {
-            0
-        };
-        screen_height = screen_height.saturating_sub(self.dock_height);
-
-        let usable_width = screen_width.saturating_sub(gaps * 2);
-        let usable_height = screen_height.saturating_sub(gaps * 2);
-
-        let master_width = ((usable_width as f32 * self.master_width_ratio) as u32)
-            .max(usable_width / 3)
-            .min(2 * usable_width / 3);
-        let stack_width = usable_width
-            .saturating_sub(master_width)
-            .saturating_sub(gaps);
-
-        match n {
-            1 => {
-                self.apply_window_geometry(
-                    0,
-                    self.current_monitor.x as u32 + gaps,
-                    self.current_monitor.y as u32 + y_offset + gaps,
-                    usable_width,
-                    usable_height,
-                );
-            }
-            n => {
-                self.apply_window_geometry(
-                    0,
-                    self.current_monitor.x as u32 + gaps,
-                    self.current_monitor.y as u32 + y_offset + gaps,
-                    master_width,
-                    usable_height,
-                );
+    /// Gives the space of a dock back. Returns false for windows that
+    /// aren't docks.
+    pub fn remove_dock(&mut self, window: xlib::Window) -> bool {
+        let count = self.docks.len();
+        self.docks.retain(|(id, _, _)| *id != window);
+        if self.docks.len() == count {
+            return false;
+        }
+        self.relayout();
+        true
+    }
 
-                let stack_count = n - 1;
-                let total_stack_gaps = gaps * (stack_count.saturating_sub(1)) as u32;
-                let height_per_window =
-                    (usable_height.saturating_sub(total_stack_gaps)) / stack_count as u32;
-
-                for i in 1..n {
-                    let stack_index = i - 1;
-                    self.apply_window_geometry(
-                        i,
-                        self.current_monitor.x as u32 + gaps + master_width + gaps,
-                        self.current_monitor.y as u32
-                            + y_offset
-                            + gaps
-                            + (stack_index as u32 * (height_per_window + gaps)),
-                        stack_width,
-                        height_per_window,
-                    );
-                }
-            }
+    /// The space reserved by all docks on the layout's monitor together.
+    pub fn struts(&self) -> Struts {
+        self.struts_on(self.current_monitor)
+    }
+
+    /// The space reserved by the docks on `monitor` together.
+    pub fn struts_on(&self, monitor: Geometry) -> Struts {
+        self.docks
+            .iter()
+            .filter(|(_, _, area)| {
+                area.x < monitor.x + monitor.width as i32
+                    && monitor.x < area.x + area.width as i32
+                    && area.y < monitor.y + monitor.height as i32
+                    && monitor.y < area.y + area.height as i32
+            })
+            .fold(Struts::default(), |total, (_, struts, _)| {
+                total.max(*struts)
+            })
+    }
+
+    /// Asks for the windows to be laid out again. That happens on the next
+    /// [`flush`](Self::flush), so a burst of changes costs one layout.
+    pub fn relayout(&mut self) {
+        self.pending = true;
+    }
+
+    /// Lays the windows out if anything asked for it since the last flush.
+    /// Only windows whose geometry changed are reconfigured.
+    pub fn flush(&mut self) {
+        if !std::mem::take(&mut self.pending) {
+            return;
+        }
+        let ids = self.windows.iter().map(|w| w.id).collect::<Vec<_>>();
+        for (id, geometry) in
+            compute_layout(&ids, self.current_monitor, self.struts(), &self.settings)
+        {
+            self.apply_window_geometry(id, geometry);
         }
     }
 
-    fn apply_window_geometry(&mut self, index: usize, x: u32, y: u32, width: u32, height: u32) {
-        if let Some(window) = self.windows.get_mut(index) {
-            window.x = x as i32;
-            window.y = y as i32;
-            window.width = width;
-            window.height = height;
+    fn apply_window_geometry(&mut self, id: xlib::Window, geometry: Geometry) {
+        if let Some(window) = self
+            .windows
+            .get_mut(id)
+            .filter(|w| w.geometry() != geometry)
+        {
+            window.x = geometry.x;
+            window.y = geometry.y;
+            window.width = geometry.width;
+            window.height = geometry.height;
 
             unsafe {
                 xlib::XMoveResizeWindow(
@@ -288,13 +467,159 @@ impl MasterStackLayout {
         }
     }
 
+    /// Moves every tiled window one place on, the last one becoming the
+    /// master. With `clockwise` unset they move the other way.
+    pub fn rotate_windows(&mut self, clockwise: bool) {
+        self.windows.reorder(|windows| match clockwise {
+            true => windows.rotate_right(1.min(windows.len())),
+            false => windows.rotate_left(1.min(windows.len())),
+        });
+        self.relayout();
+    }
+
+    /// Turns the order of the tiled windows around.
+    pub fn reverse_windows(&mut self) {
+        self.windows.reorder(<[Window]>::reverse);
+        self.relayout();
+    }
+
+    /// Where a tiled window was last placed, borders not included.
+    pub fn window_geometry(&self, window: xlib::Window) -> Option<Geometry> {
+        self.windows.get(window).map(Window::geometry)
+    }
+
     pub fn swap_windows(&mut self, window1: xlib::Window, window2: xlib::Window) {
-        if let (Some(idx1), Some(idx2)) = (
-            self.windows.iter().position(|w| w.id == window1),
-            self.windows.iter().position(|w| w.id == window2),
-        ) {
-            self.windows.swap(idx1, idx2);
+        if self.windows.swap(window1, window2) {
             self.relayout();
         }
     }
 }
+
+/// Tiles the windows on the monitor, leaving room for the docks. The first
+/// `nmaster` windows form the master column, the rest are stacked next to it.
+pub fn compute_layout(
+    windows: &[xlib::Window],
+    monitor: Geometry,
+    docks: impl Into<Struts>,
+    settings: &LayoutSettings,
+) -> Vec<(xlib::Window, Geometry)> {
+    let n = windows.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let gaps = settings.gaps;
+    let area = tiled_area(monitor, docks.into(), gaps);
+
+    if settings.kind == LayoutKind::Monocle {
+        return windows.iter().map(|&id| (id, area)).collect();
+    }
+
+    let master_count = settings.nmaster.min(n);
+    if master_count == 0 || master_count == n {
+        return tile_column(windows, area, gaps);
+    }
+
+    let master_width = (area.width as f32 * settings.master_ratio) as u32;
+    let stack = Geometry {
+        x: area.x + (master_width + gaps) as i32,
+        width: area.width.saturating_sub(master_width).saturating_sub(gaps),
+        ..area
+    };
+
+    let mut geometries = tile_column(
+        &windows[..master_count],
+        Geometry {
+            width: master_width,
+            ..area
+        },
+        gaps,
+    );
+    geometries.extend(tile_column(&windows[master_count..], stack, gaps));
+    geometries
+}
+
+/// The gap between the master and the stack column of `count` windows,
+/// where dragging moves the split. `None` unless there are both columns.
+pub fn split_gap(
+    count: usize,
+    monitor: Geometry,
+    docks: impl Into<Struts>,
+    settings: &LayoutSettings,
+) -> Option<Geometry> {
+    let master_count = settings.nmaster.min(count);
+    if settings.kind == LayoutKind::Monocle
+        || master_count == 0
+        || master_count == count
+        || settings.gaps == 0
+    {
+        return None;
+    }
+
+    let area = tiled_area(monitor, docks.into(), settings.gaps);
+    let master_width = (area.width as f32 * settings.master_ratio) as u32;
+    Some(Geometry {
+        x: area.x + master_width as i32,
+        width: settings.gaps,
+        ..area
+    })
+}
+
+/// The master ratio that puts the middle of the split gap at `x`, kept
+/// within the bounds `adjust_master_ratio` uses.
+pub fn ratio_at(
+    x: i32,
+    monitor: Geometry,
+    docks: impl Into<Struts>,
+    settings: &LayoutSettings,
+) -> f32 {
+    let area = tiled_area(monitor, docks.into(), settings.gaps);
+    let master_width = x - area.x - (settings.gaps / 2) as i32;
+    (master_width as f32 / area.width.max(1) as f32).clamp(0.1, 0.9)
+}
+
+/// The part of the monitor left for tiling once docks and outer gaps are
+/// taken off.
+fn tiled_area(monitor: Geometry, struts: Struts, gaps: u32) -> Geometry {
+    Geometry {
+        x: monitor.x + (struts.left + gaps) as i32,
+        y: monitor.y + (struts.top + gaps) as i32,
+        width: monitor
+            .width
+            .saturating_sub(struts.left + struts.right)
+            .saturating_sub(gaps * 2),
+        height: monitor
+            .height
+            .saturating_sub(struts.top + struts.bottom)
+            .saturating_sub(gaps * 2),
+    }
+}
+
+fn tile_column(
+    windows: &[xlib::Window],
+    column: Geometry,
+    gaps: u32,
+) -> Vec<(xlib::Window, Geometry)> {
+    let count = windows.len() as u32;
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let total_gaps = gaps * count.saturating_sub(1);
+    let height_per_window = column.height.saturating_sub(total_gaps) / count;
+
+    windows
+        .iter()
+        .enumerate()
+        .map(|(slot, &id)| {
+            (
+                id,
+                Geometry {
+                    y: column.y + (slot as u32 * (height_per_window + gaps)) as i32,
+                    height: height_per_window,
+                    ..column
+                },
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,220 @@
+use std::ffi::CString;
+use x11::xlib;
+
+use crate::utils::x11::{Monitor, WindowVisual};
+
+/// Turns `text` into a C string for drawing, dropping any NUL bytes.
+pub fn text(text: &str) -> CString {
+    CString::new(text.replace('\0', "")).unwrap_or_default()
+}
+
+/// A dialog drawn by velowm that holds the keyboard while it is open:
+/// confirmations, the bind overlay and the first start setup. The grab is
+/// released and the window destroyed when it is dropped.
+pub struct ModalDialog {
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    visual: WindowVisual,
+    gc: xlib::GC,
+    font: *mut xlib::XFontStruct,
+    /// Text color, opaque on 32-bit visuals.
+    pub white: u64,
+    /// Border color, also used to highlight the selection.
+    pub border: u64,
+}
+
+impl ModalDialog {
+    /// Grabs the keyboard and creates the dialog, unmapped until
+    /// [`ModalDialog::show`] is called. `None` when the keyboard can't be
+    /// grabbed.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection
+    /// - The root window must be a valid window ID for the given display
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        background: u64,
+        border: u64,
+    ) -> Option<Self> {
+        if xlib::XGrabKeyboard(
+            display,
+            root,
+            0,
+            xlib::GrabModeAsync,
+            xlib::GrabModeAsync,
+            xlib::CurrentTime,
+        ) != xlib::GrabSuccess
+        {
+            return None;
+        }
+
+        let screen = xlib::XDefaultScreen(display);
+        let visual = WindowVisual::new(display);
+        let white = xlib::XWhitePixel(display, screen) | if visual.argb { 0xFF000000 } else { 0 };
+
+        let window = visual.create_window(display, root, 0, 0, 1, 1, 2, border, background);
+        let mut attrs: xlib::XSetWindowAttributes = std::mem::zeroed();
+        attrs.override_redirect = 1;
+        attrs.event_mask = xlib::ExposureMask;
+        xlib::XChangeWindowAttributes(
+            display,
+            window,
+            xlib::CWOverrideRedirect | xlib::CWEventMask,
+            &mut attrs,
+        );
+
+        let gc = xlib::XCreateGC(display, window, 0, std::ptr::null_mut());
+        let font_name = CString::new("-*-*-medium-r-*-*-14-*-*-*-*-*-*-*").unwrap();
+        let font = xlib::XLoadQueryFont(display, font_name.as_ptr());
+        if !font.is_null() {
+            xlib::XSetFont(display, gc, (*font).fid);
+        }
+
+        Some(Self {
+            display,
+            window,
+            visual,
+            gc,
+            font,
+            white,
+            border,
+        })
+    }
+
+    /// Width of `text` in the dialog font.
+    pub fn text_width(&self, text: &CString) -> i32 {
+        let length = text.as_bytes().len() as i32;
+        if self.font.is_null() {
+            length * 8
+        } else {
+            unsafe { xlib::XTextWidth(self.font, text.as_ptr(), length) }
+        }
+    }
+
+    /// Maps the dialog at the given size, centered on `area`.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn show(&self, area: Monitor, width: i32, height: i32) {
+        xlib::XMoveResizeWindow(
+            self.display,
+            self.window,
+            area.x + (area.width as i32 - width) / 2,
+            area.y + (area.height as i32 - height) / 2,
+            width.max(1) as u32,
+            height.max(1) as u32,
+        );
+        xlib::XMapRaised(self.display, self.window);
+    }
+
+    /// Clears the dialog and lets `paint` draw its content.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn draw(&self, paint: impl Fn(&Self)) {
+        xlib::XClearWindow(self.display, self.window);
+        paint(self);
+        xlib::XFlush(self.display);
+    }
+
+    /// Draws `text` with its baseline at `y`.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn draw_string(&self, x: i32, y: i32, text: &CString, color: u64) {
+        xlib::XSetForeground(self.display, self.gc, color);
+        xlib::XDrawString(
+            self.display,
+            self.window,
+            self.gc,
+            x,
+            y,
+            text.as_ptr(),
+            text.as_bytes().len() as i32,
+        );
+    }
+
+    /// Draws a rectangle, filled or as an outline.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn draw_rectangle(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        color: u64,
+        filled: bool,
+    ) {
+        let draw = if filled {
+            xlib::XFillRectangle
+        } else {
+            xlib::XDrawRectangle
+        };
+        xlib::XSetForeground(self.display, self.gc, color);
+        draw(
+            self.display,
+            self.window,
+            self.gc,
+            x,
+            y,
+            width.max(0) as u32,
+            height.max(0) as u32,
+        );
+    }
+
+    /// Waits for the next key press and returns its keysym, redrawing the
+    /// dialog with `paint` whenever it is exposed meanwhile. Every other
+    /// event, exposures of the bar and notifications among them, stays
+    /// queued for the window manager.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn next_key(&self, paint: impl Fn(&Self)) -> u32 {
+        unsafe extern "C" fn is_dialog_event(
+            _: *mut xlib::Display,
+            event: *mut xlib::XEvent,
+            window: xlib::XPointer,
+        ) -> i32 {
+            let window = *(window as *const xlib::Window);
+            match (*event).get_type() {
+                xlib::KeyPress => 1,
+                xlib::Expose => ((*event).expose.window == window) as i32,
+                _ => 0,
+            }
+        }
+
+        let mut window = self.window;
+        let mut event: xlib::XEvent = std::mem::zeroed();
+        loop {
+            xlib::XIfEvent(
+                self.display,
+                &mut event,
+                Some(is_dialog_event),
+                &mut window as *mut xlib::Window as xlib::XPointer,
+            );
+            match event.get_type() {
+                xlib::KeyPress => return xlib::XLookupKeysym(&mut event.key, 0) as u32,
+                _ if event.expose.count == 0 => self.draw(&paint),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for ModalDialog {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.font.is_null() {
+                xlib::XFreeFont(self.display, self.font);
+            }
+            xlib::XFreeGC(self.display, self.gc);
+            xlib::XDestroyWindow(self.display, self.window);
+            self.visual.free(self.display);
+            xlib::XUngrabKeyboard(self.display, xlib::CurrentTime);
+            xlib::XSync(self.display, 0);
+        }
+    }
+}
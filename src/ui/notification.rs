@@ -1,14 +1,45 @@
 use std::ffi::CString;
 use x11::xlib;
 
+use crate::{
+    ui::appearance::{scaled, Appearance, NotificationAppearance},
+    utils::x11::{set_property, Atoms, WindowVisual},
+};
+
+/// Space between a button label and its outline, before scaling.
+const BUTTON_PADDING: u32 = 6;
+
+/// A button under the message of a notification.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NotificationAction {
+    /// Opens the config file in `$VISUAL` or `$EDITOR`.
+    OpenConfig,
+    /// Closes every notification.
+    DismissAll,
+}
+
+impl NotificationAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::OpenConfig => "Open config",
+            Self::DismissAll => "Dismiss all",
+        }
+    }
+}
+
 pub struct NotificationWindow {
     display: *mut xlib::Display,
     pub window: xlib::Window,
+    visual: WindowVisual,
     gc: xlib::GC,
     font: *mut xlib::XFontStruct,
     current_message: Option<String>,
+    actions: Vec<NotificationAction>,
+    /// Horizontal extent of each action button, in the order of `actions`.
+    buttons: Vec<(i32, i32)>,
     line_height: i32,
     padding: i32,
+    button_padding: i32,
     width: i32,
     height: i32,
     x: i32,
@@ -18,10 +49,13 @@ pub struct NotificationWindow {
 pub struct NotificationManager {
     display: *mut xlib::Display,
     root: xlib::Window,
+    atoms: Atoms,
     notifications: Vec<NotificationWindow>,
     width: i32,
     padding: i32,
     initial_y: i32,
+    scale: f64,
+    appearance: NotificationAppearance,
 }
 
 impl NotificationManager {
@@ -31,15 +65,40 @@ impl NotificationManager {
     ///
     /// The display pointer must be valid and point to an active X display connection.
     /// The root window must be a valid window ID for the given display.
-    pub unsafe fn new(display: *mut xlib::Display, root: xlib::Window) -> Self {
-        Self {
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        atoms: Atoms,
+        appearance: &Appearance,
+    ) -> Self {
+        let mut manager = Self {
             display,
             root,
+            atoms,
             notifications: Vec::new(),
-            width: 600,
-            padding: 10,
-            initial_y: 50,
-        }
+            width: 0,
+            padding: 0,
+            initial_y: 0,
+            scale: 1.0,
+            appearance: appearance.notification.clone(),
+        };
+        manager.set_appearance(appearance);
+        manager
+    }
+
+    /// Styles notifications shown from now on after `appearance`, whose
+    /// colors have to be resolved already.
+    ///
+    /// # Safety
+    ///
+    /// The display pointer stored in self must still be valid and point to an active X display connection.
+    pub unsafe fn set_appearance(&mut self, appearance: &Appearance) {
+        let scale = appearance.scale(self.display);
+        self.scale = scale;
+        self.width = scaled(600, scale) as i32;
+        self.padding = scaled(10, scale) as i32;
+        self.initial_y = scaled(50, scale) as i32;
+        self.appearance = appearance.notification.clone();
     }
 
     /// Shows an error notification with the given message.
@@ -48,23 +107,67 @@ impl NotificationManager {
     ///
     /// The display pointer stored in self must still be valid and point to an active X display connection.
     pub unsafe fn show_error(&mut self, message: &str) {
-        let mut notification = NotificationWindow::new(self.display, self.root, self.width);
+        self.show_error_with_actions(message, &[]);
+    }
+
+    /// Shows a notification that isn't an error, such as where a
+    /// screenshot was saved.
+    ///
+    /// # Safety
+    ///
+    /// The display pointer stored in self must still be valid and point to an active X display connection.
+    pub unsafe fn show_message(&mut self, message: &str) {
+        self.show_error_with_actions(message, &[]);
+    }
+
+    /// Shows an error notification with a row of buttons under the message.
+    ///
+    /// # Safety
+    ///
+    /// The display pointer stored in self must still be valid and point to an active X display connection.
+    pub unsafe fn show_error_with_actions(
+        &mut self,
+        message: &str,
+        actions: &[NotificationAction],
+    ) {
+        let mut notification = NotificationWindow::new(
+            self.display,
+            self.root,
+            &self.atoms,
+            self.width,
+            self.scale,
+            &self.appearance,
+        );
+        notification.actions = actions.to_vec();
         notification.show_error(message);
         self.notifications.push(notification);
         self.relayout();
     }
 
-    /// Handles button press events for notification windows.
+    /// Handles button press events for notification windows. A click
+    /// anywhere dismisses the notification; a click on one of its buttons
+    /// also returns that action. `DismissAll` is handled here.
     ///
     /// # Safety
     ///
     /// The display pointer stored in self must still be valid and point to an active X display connection.
     /// The window ID must be valid for the given display.
-    pub unsafe fn handle_button_press(&mut self, window: xlib::Window) {
-        if let Some(index) = self.notifications.iter().position(|n| n.window == window) {
+    pub unsafe fn handle_button_press(
+        &mut self,
+        window: xlib::Window,
+        x: i32,
+        y: i32,
+    ) -> Option<NotificationAction> {
+        let index = self.notifications.iter().position(|n| n.window == window)?;
+        let action = self.notifications[index].action_at(x, y);
+
+        if action == Some(NotificationAction::DismissAll) {
+            self.notifications.clear();
+        } else {
             self.notifications.remove(index);
-            self.relayout();
         }
+        self.relayout();
+        action
     }
 
     /// Handles expose events for notification windows.
@@ -110,28 +213,36 @@ impl NotificationWindow {
     /// - The display pointer must be valid and point to an active X display connection
     /// - The root window must be a valid window ID for the given display
     /// - The caller must ensure the display connection remains valid for the lifetime of this window
-    pub unsafe fn new(display: *mut xlib::Display, root: xlib::Window, width: i32) -> Self {
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        atoms: &Atoms,
+        width: i32,
+        scale: f64,
+        appearance: &NotificationAppearance,
+    ) -> Self {
         let screen = xlib::XDefaultScreen(display);
-        let white = xlib::XWhitePixel(display, screen);
+        let visual = WindowVisual::new(display);
+        // The alpha byte has to be set explicitly on a 32-bit visual.
+        let white = xlib::XWhitePixel(display, screen) | if visual.argb { 0xFF000000 } else { 0 };
 
-        let line_height = 20i32;
-        let padding = 10i32;
+        let line_height = scaled(20, scale) as i32;
+        let padding = scaled(10, scale) as i32;
         let initial_height = line_height + padding * 2;
         let x = (xlib::XDisplayWidth(display, screen) - width) / 2;
         let y = 50;
 
-        let config = crate::config::loader::Config::load().unwrap_or_default();
-        let background_color = config.appearance.get_notification_background_color();
-        let border_color = config.appearance.get_notification_border_color();
+        let background_color = appearance.background_color.pixel();
+        let border_color = appearance.border_color.pixel();
 
-        let window = xlib::XCreateSimpleWindow(
+        let window = visual.create_window(
             display,
             root,
             x,
             y,
             width as u32,
             initial_height as u32,
-            2,
+            scaled(2, scale),
             border_color,
             background_color,
         );
@@ -148,39 +259,30 @@ impl NotificationWindow {
             &mut attrs,
         );
 
-        let net_wm_window_type = xlib::XInternAtom(display, c"_NET_WM_WINDOW_TYPE".as_ptr(), 0);
-        let net_wm_window_type_dock =
-            xlib::XInternAtom(display, c"_NET_WM_WINDOW_TYPE_DOCK".as_ptr(), 0);
-
-        xlib::XChangeProperty(
+        set_property(
             display,
             window,
-            net_wm_window_type,
+            atoms.net_wm_window_type,
             xlib::XA_ATOM,
-            32,
-            xlib::PropModeReplace,
-            &net_wm_window_type_dock as *const u64 as *const u8,
-            1,
+            &[atoms.net_wm_window_type_dock],
         );
 
-        let net_wm_state = xlib::XInternAtom(display, c"_NET_WM_STATE".as_ptr(), 0);
-        let net_wm_state_above = xlib::XInternAtom(display, c"_NET_WM_STATE_ABOVE".as_ptr(), 0);
-
-        xlib::XChangeProperty(
+        set_property(
             display,
             window,
-            net_wm_state,
+            atoms.net_wm_state,
             xlib::XA_ATOM,
-            32,
-            xlib::PropModeReplace,
-            &net_wm_state_above as *const u64 as *const u8,
-            1,
+            &[atoms.net_wm_state_above],
         );
 
         let gc = xlib::XCreateGC(display, window, 0, std::ptr::null_mut());
         xlib::XSetForeground(display, gc, white);
 
-        let font_name = CString::new("-*-*-medium-r-*-*-14-*-*-*-*-*-*-*").unwrap();
+        let font_name = CString::new(format!(
+            "-*-*-medium-r-*-*-{}-*-*-*-*-*-*-*",
+            scaled(14, scale)
+        ))
+        .unwrap();
         let font = xlib::XLoadQueryFont(display, font_name.as_ptr());
 
         if !font.is_null() {
@@ -192,11 +294,15 @@ impl NotificationWindow {
         Self {
             display,
             window,
+            visual,
             gc,
             font,
             current_message: None,
+            actions: Vec::new(),
+            buttons: Vec::new(),
             line_height,
             padding,
+            button_padding: scaled(BUTTON_PADDING, scale) as i32,
             width,
             height: initial_height,
             x,
@@ -214,6 +320,10 @@ impl NotificationWindow {
 
         let lines: Vec<&str> = message.split('\n').collect();
         self.height = self.line_height * lines.len() as i32 + self.padding * 2;
+        if !self.actions.is_empty() {
+            self.height += self.button_height() + self.padding;
+        }
+        self.buttons = self.layout_buttons();
 
         xlib::XResizeWindow(
             self.display,
@@ -237,6 +347,47 @@ impl NotificationWindow {
         self.redraw();
     }
 
+    fn button_height(&self) -> i32 {
+        self.line_height + self.button_padding
+    }
+
+    /// Top of the button row.
+    fn button_y(&self) -> i32 {
+        self.height - self.padding - self.button_height()
+    }
+
+    unsafe fn text_width(&self, text: &str) -> i32 {
+        if self.font.is_null() {
+            return text.len() as i32 * 8;
+        }
+        let text = CString::new(text).unwrap_or_default();
+        xlib::XTextWidth(self.font, text.as_ptr(), text.as_bytes().len() as i32)
+    }
+
+    unsafe fn layout_buttons(&self) -> Vec<(i32, i32)> {
+        let mut x = self.padding;
+        self.actions
+            .iter()
+            .map(|action| {
+                let width = self.text_width(action.label()) + self.button_padding * 2;
+                let button = (x, width);
+                x += width + self.padding;
+                button
+            })
+            .collect()
+    }
+
+    fn action_at(&self, x: i32, y: i32) -> Option<NotificationAction> {
+        let button_y = self.button_y();
+        if y < button_y || y >= button_y + self.button_height() {
+            return None;
+        }
+        self.buttons
+            .iter()
+            .position(|&(start, width)| x >= start && x < start + width)
+            .map(|index| self.actions[index])
+    }
+
     unsafe fn move_to(&mut self, y: i32) {
         self.y = y;
         let screen = xlib::XDefaultScreen(self.display);
@@ -254,7 +405,7 @@ impl NotificationWindow {
             xlib::XClearWindow(self.display, self.window);
 
             let lines: Vec<&str> = message.split('\n').collect();
-            let mut y = self.padding + self.line_height - 5;
+            let mut y = self.padding + self.line_height * 3 / 4;
 
             for line in lines {
                 let line = CString::new(line.trim()).unwrap();
@@ -270,6 +421,29 @@ impl NotificationWindow {
                 y += self.line_height;
             }
 
+            let button_y = self.button_y();
+            for (action, &(x, width)) in self.actions.iter().zip(&self.buttons) {
+                xlib::XDrawRectangle(
+                    self.display,
+                    self.window,
+                    self.gc,
+                    x,
+                    button_y,
+                    width as u32,
+                    self.button_height() as u32,
+                );
+                let label = CString::new(action.label()).unwrap();
+                xlib::XDrawString(
+                    self.display,
+                    self.window,
+                    self.gc,
+                    x + self.button_padding,
+                    button_y + self.line_height - self.line_height / 10,
+                    label.as_ptr(),
+                    label.as_bytes().len() as i32,
+                );
+            }
+
             xlib::XFlush(self.display);
         }
     }
@@ -283,6 +457,7 @@ impl Drop for NotificationWindow {
             }
             xlib::XFreeGC(self.display, self.gc);
             xlib::XDestroyWindow(self.display, self.window);
+            self.visual.free(self.display);
         }
     }
 }
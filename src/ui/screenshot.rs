@@ -0,0 +1,222 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use x11::xlib;
+
+use crate::utils::{pixbuf::Image, x11::Monitor};
+
+/// What `screenshot` captures.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScreenshotTarget {
+    /// Every monitor.
+    Full,
+    /// The focused window with its border.
+    Window,
+    /// A rectangle dragged out with the mouse.
+    Region,
+}
+
+impl FromStr for ScreenshotTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Self::Full),
+            "window" => Ok(Self::Window),
+            "region" => Ok(Self::Region),
+            _ => Err(format!(
+                "Invalid screenshot target '{}', expected full, window or region",
+                s
+            )),
+        }
+    }
+}
+
+/// The rectangle between two corners, given in any order.
+pub fn rectangle((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> Monitor {
+    Monitor {
+        x: x1.min(x2),
+        y: y1.min(y2),
+        width: x1.abs_diff(x2),
+        height: y1.abs_diff(y2),
+    }
+}
+
+/// Name of a screenshot taken at `time`.
+pub fn file_name(time: DateTime<Local>) -> String {
+    time.format("screenshot-%Y-%m-%d-%H%M%S.png").to_string()
+}
+
+/// Copies `area` of the root window, as it is shown on screen, into an
+/// image.
+///
+/// # Safety
+/// The display pointer must be valid and point to an active X display connection,
+/// and root must be its root window.
+pub unsafe fn capture(
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    area: Monitor,
+) -> Result<Image> {
+    // XGetImage fails on anything outside the root window.
+    let screen = xlib::XDefaultScreen(display);
+    let (left, top) = (area.x.max(0), area.y.max(0));
+    let right = (area.x + area.width as i32).min(xlib::XDisplayWidth(display, screen));
+    let bottom = (area.y + area.height as i32).min(xlib::XDisplayHeight(display, screen));
+    if right <= left || bottom <= top {
+        bail!("Nothing to capture");
+    }
+    let area = rectangle((left, top), (right, bottom));
+
+    let ximage = xlib::XGetImage(
+        display,
+        root,
+        area.x,
+        area.y,
+        area.width,
+        area.height,
+        xlib::XAllPlanes(),
+        xlib::ZPixmap,
+    );
+    if ximage.is_null() {
+        bail!("Failed to read the screen");
+    }
+
+    let masks = [
+        (*ximage).red_mask,
+        (*ximage).green_mask,
+        (*ximage).blue_mask,
+    ];
+    let mut rgb = Vec::with_capacity(area.width as usize * area.height as usize * 3);
+    for y in 0..area.height as i32 {
+        for x in 0..area.width as i32 {
+            let pixel = xlib::XGetPixel(ximage, x, y);
+            rgb.extend(masks.map(|mask| sample(pixel, mask)));
+        }
+    }
+    xlib::XDestroyImage(ximage);
+
+    Image::from_rgb(area.width, area.height, &rgb)
+}
+
+/// The 8-bit value of the channel under `mask` in `pixel`.
+fn sample(pixel: u64, mask: u64) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let shift = mask.trailing_zeros();
+    (((pixel & mask) >> shift) * 0xFF / (mask >> shift)) as u8
+}
+
+/// Saves `image` in `directory`, creating it if needed, and returns the
+/// path of the file.
+pub fn save(image: &Image, directory: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(directory)
+        .with_context(|| format!("Failed to create {}", directory.display()))?;
+    let path = directory.join(file_name(Local::now()));
+    image.save_png(&path)?;
+    Ok(path)
+}
+
+/// Lets the user drag out a rectangle with the first button while the
+/// pointer and keyboard are grabbed. Returns `None` when it is cancelled
+/// with Escape or another button, or the grab fails.
+///
+/// # Safety
+/// The display pointer must be valid and point to an active X display connection,
+/// and root must be its root window.
+pub unsafe fn select_region(
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    cursor: xlib::Cursor,
+) -> Option<Monitor> {
+    let mask = xlib::ButtonPressMask | xlib::ButtonReleaseMask | xlib::PointerMotionMask;
+    if xlib::XGrabPointer(
+        display,
+        root,
+        0,
+        mask as u32,
+        xlib::GrabModeAsync,
+        xlib::GrabModeAsync,
+        0,
+        cursor,
+        xlib::CurrentTime,
+    ) != xlib::GrabSuccess
+    {
+        return None;
+    }
+    if xlib::XGrabKeyboard(
+        display,
+        root,
+        0,
+        xlib::GrabModeAsync,
+        xlib::GrabModeAsync,
+        xlib::CurrentTime,
+    ) != xlib::GrabSuccess
+    {
+        xlib::XUngrabPointer(display, xlib::CurrentTime);
+        return None;
+    }
+
+    // Inverting twice restores what was under the outline.
+    let mut values: xlib::XGCValues = std::mem::zeroed();
+    values.function = xlib::GXinvert;
+    values.subwindow_mode = xlib::IncludeInferiors;
+    let gc = xlib::XCreateGC(
+        display,
+        root,
+        (xlib::GCFunction | xlib::GCSubwindowMode) as u64,
+        &mut values,
+    );
+    let outline = |area: Monitor| {
+        xlib::XDrawRectangle(display, root, gc, area.x, area.y, area.width, area.height);
+    };
+
+    let mut start = None;
+    let mut shown: Option<Monitor> = None;
+    let mut event: xlib::XEvent = std::mem::zeroed();
+    let selected = loop {
+        xlib::XMaskEvent(display, mask | xlib::KeyPressMask, &mut event);
+        match event.get_type() {
+            xlib::KeyPress
+                if xlib::XLookupKeysym(&mut event.key, 0) == x11::keysym::XK_Escape as u64 =>
+            {
+                break None;
+            }
+            xlib::ButtonPress if event.button.button == xlib::Button1 => {
+                start = Some((event.button.x_root, event.button.y_root));
+            }
+            xlib::ButtonPress => break None,
+            xlib::MotionNotify => {
+                let Some(start) = start else {
+                    continue;
+                };
+                if let Some(area) = shown {
+                    outline(area);
+                }
+                let area = rectangle(start, (event.motion.x_root, event.motion.y_root));
+                outline(area);
+                shown = Some(area);
+            }
+            xlib::ButtonRelease if event.button.button == xlib::Button1 => {
+                if let Some(start) = start {
+                    break Some(rectangle(start, (event.button.x_root, event.button.y_root)));
+                }
+            }
+            _ => {}
+        }
+    };
+
+    if let Some(area) = shown {
+        outline(area);
+    }
+    xlib::XFreeGC(display, gc);
+    xlib::XUngrabKeyboard(display, xlib::CurrentTime);
+    xlib::XUngrabPointer(display, xlib::CurrentTime);
+    xlib::XSync(display, 0);
+    selected.filter(|area| area.width > 0 && area.height > 0)
+}
@@ -0,0 +1,153 @@
+use x11::{keysym, xlib};
+
+use crate::{
+    config::{
+        loader::Config,
+        setup::{self, Setup, MODIFIERS},
+    },
+    ui::modal::{text, ModalDialog},
+    utils::x11::{Display, Monitor},
+};
+
+const PADDING: i32 = 12;
+const LINE_HEIGHT: i32 = 18;
+const MIN_WIDTH: i32 = 360;
+const HINT: &str = "Up/Down to choose, Return to pick, Escape to skip";
+
+/// Asks for the modifier, the terminal and the launcher on the first
+/// start, offering only the terminals and launchers that are installed.
+/// Steps with nothing to pick from are left out. `None` when the user
+/// skips the setup with Escape or the keyboard can't be grabbed.
+///
+/// # Safety
+/// The display connection must be valid and no other client may hold a
+/// keyboard grab.
+pub unsafe fn run(display: &Display) -> Option<Setup> {
+    let mut config = Config::default();
+    let _ = config.resolve_colors(display.raw());
+    let background = config.appearance.get_notification_background_color();
+    let border = config.appearance.get_notification_border_color();
+    let area = display.pointer_monitor();
+
+    // Steps are numbered among those that are shown.
+    let terminals = setup::detect_terminals();
+    let launchers = setup::detect_launchers();
+    let total = 1 + usize::from(terminals.len() > 1) + usize::from(!launchers.is_empty());
+    let mut step = 0;
+    let mut ask = |question: &str, options: &[&str]| {
+        step += 1;
+        let title = format!("Welcome to velowm ({}/{})", step, total);
+        choose(
+            display.raw(),
+            display.root(),
+            area,
+            &[&title, question],
+            options,
+            background,
+            border,
+        )
+    };
+
+    let modifier = MODIFIERS[ask("Modifier key for all shortcuts", &MODIFIERS)?];
+
+    let terminal = match terminals.as_slice() {
+        [] => "alacritty",
+        [terminal] => terminal,
+        _ => terminals[ask("Terminal opened with Q", &terminals)?],
+    };
+
+    let launcher = if launchers.is_empty() {
+        None
+    } else {
+        let mut options = launchers.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        options.push("none");
+        let picked = ask("Launcher opened with D", &options)?;
+        launchers
+            .get(picked)
+            .map(|(_, command)| command.to_string())
+    };
+
+    Some(Setup {
+        modifier: modifier.to_string(),
+        terminal: terminal.to_string(),
+        launcher,
+    })
+}
+
+/// Shows `lines` of text above `options` in a dialog centered on `area`
+/// and returns the index of the option picked, `None` for Escape.
+unsafe fn choose(
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    area: Monitor,
+    lines: &[&str],
+    options: &[&str],
+    background: u64,
+    border: u64,
+) -> Option<usize> {
+    let dialog = ModalDialog::new(display, root, background, border)?;
+    let lines = lines.iter().map(|line| text(line)).collect::<Vec<_>>();
+    let options = options
+        .iter()
+        .map(|option| text(option))
+        .collect::<Vec<_>>();
+    let hint = text(HINT);
+
+    let width = lines
+        .iter()
+        .chain(&options)
+        .chain([&hint])
+        .map(|line| dialog.text_width(line) + PADDING * 4)
+        .max()
+        .unwrap_or(0)
+        .max(MIN_WIDTH);
+    let rows = (lines.len() + options.len() + 1) as i32;
+    let height = rows * LINE_HEIGHT + PADDING * 4;
+    dialog.show(area, width, height);
+
+    let (lines, options, hint) = (&lines, &options, &hint);
+    let paint = |selected: usize| {
+        move |dialog: &ModalDialog| {
+            let baseline = |y: i32| y + LINE_HEIGHT - 5;
+            let mut y = PADDING;
+            for line in lines {
+                dialog.draw_string(PADDING, baseline(y), line, dialog.white);
+                y += LINE_HEIGHT;
+            }
+            y += PADDING;
+            for (index, option) in options.iter().enumerate() {
+                if index == selected {
+                    dialog.draw_rectangle(
+                        PADDING,
+                        y,
+                        width - PADDING * 2,
+                        LINE_HEIGHT,
+                        dialog.border,
+                        true,
+                    );
+                }
+                dialog.draw_string(PADDING * 2, baseline(y), option, dialog.white);
+                y += LINE_HEIGHT;
+            }
+            y += PADDING;
+            dialog.draw_string(PADDING, baseline(y), hint, dialog.white);
+        }
+    };
+
+    let mut selected = 0;
+    loop {
+        match dialog.next_key(paint(selected)) {
+            keysym::XK_Escape => return None,
+            keysym::XK_Return | keysym::XK_KP_Enter | keysym::XK_space => return Some(selected),
+            keysym::XK_Up | keysym::XK_Left => {
+                selected = selected.checked_sub(1).unwrap_or(options.len() - 1);
+                dialog.draw(paint(selected));
+            }
+            keysym::XK_Down | keysym::XK_Right | keysym::XK_Tab => {
+                selected = (selected + 1) % options.len();
+                dialog.draw(paint(selected));
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::ffi::CString;
+use x11::xlib;
+
+use crate::utils::x11::WindowVisual;
+
+const PADDING: i32 = 6;
+const LINE_HEIGHT: i32 = 16;
+
+/// A small label showing the size of a window while it is resized with the
+/// mouse, e.g. `80x24` for a terminal or `640x480` in pixels.
+pub struct SizePopup {
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    visual: WindowVisual,
+    gc: xlib::GC,
+    font: *mut xlib::XFontStruct,
+}
+
+impl SizePopup {
+    /// Creates the popup, unmapped until [`SizePopup::show`] is called.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection
+    /// - The root window must be a valid window ID for the given display
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        background: u64,
+        border: u64,
+    ) -> Self {
+        let screen = xlib::XDefaultScreen(display);
+        let visual = WindowVisual::new(display);
+        let white = xlib::XWhitePixel(display, screen) | if visual.argb { 0xFF000000 } else { 0 };
+
+        let window = visual.create_window(display, root, 0, 0, 1, 1, 1, border, background);
+
+        let mut attrs: xlib::XSetWindowAttributes = std::mem::zeroed();
+        attrs.override_redirect = 1;
+        attrs.save_under = 1;
+        xlib::XChangeWindowAttributes(
+            display,
+            window,
+            xlib::CWOverrideRedirect | xlib::CWSaveUnder,
+            &mut attrs,
+        );
+
+        let gc = xlib::XCreateGC(display, window, 0, std::ptr::null_mut());
+        xlib::XSetForeground(display, gc, white);
+
+        let font_name = CString::new("-*-*-medium-r-*-*-14-*-*-*-*-*-*-*").unwrap();
+        let font = xlib::XLoadQueryFont(display, font_name.as_ptr());
+        if !font.is_null() {
+            xlib::XSetFont(display, gc, (*font).fid);
+        }
+
+        Self {
+            display,
+            window,
+            visual,
+            gc,
+            font,
+        }
+    }
+
+    /// Shows `text` centered on the given area.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn show(&self, text: &str, x: i32, y: i32, width: u32, height: u32) {
+        let Ok(text) = CString::new(text) else {
+            return;
+        };
+        let length = text.as_bytes().len() as i32;
+        let text_width = if self.font.is_null() {
+            length * 8
+        } else {
+            xlib::XTextWidth(self.font, text.as_ptr(), length)
+        };
+
+        let popup_width = text_width + PADDING * 2;
+        let popup_height = LINE_HEIGHT + PADDING * 2;
+        xlib::XMoveResizeWindow(
+            self.display,
+            self.window,
+            x + (width as i32 - popup_width) / 2,
+            y + (height as i32 - popup_height) / 2,
+            popup_width as u32,
+            popup_height as u32,
+        );
+        xlib::XMapRaised(self.display, self.window);
+
+        xlib::XClearWindow(self.display, self.window);
+        xlib::XDrawString(
+            self.display,
+            self.window,
+            self.gc,
+            PADDING,
+            PADDING + LINE_HEIGHT - 4,
+            text.as_ptr(),
+            length,
+        );
+        xlib::XFlush(self.display);
+    }
+}
+
+impl Drop for SizePopup {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.font.is_null() {
+                xlib::XFreeFont(self.display, self.font);
+            }
+            xlib::XFreeGC(self.display, self.gc);
+            xlib::XDestroyWindow(self.display, self.window);
+            self.visual.free(self.display);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::{Duration, Instant};
+use x11::xlib;
+
+/// Longest a slide may take, whatever the config says.
+pub const MAX_DURATION: Duration = Duration::from_millis(500);
+
+/// A window taking part in a slide, at the position it rests at.
+#[derive(Clone, Copy)]
+pub struct SlideWindow {
+    pub id: xlib::Window,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Slides the windows of the workspace left behind out of the monitor
+/// while those of the new one slide in from the other side. Frames are
+/// drawn from the timer of the event loop, one per refresh of the screen.
+pub struct WorkspaceSlide {
+    display: *mut xlib::Display,
+    start: Instant,
+    duration: Duration,
+    frame: Duration,
+    last_frame: Instant,
+    /// How far windows travel, negative to slide to the right.
+    distance: i32,
+    outgoing: Vec<SlideWindow>,
+    incoming: Vec<SlideWindow>,
+}
+
+impl WorkspaceSlide {
+    /// Starts a slide over `distance` pixels, moving the incoming windows
+    /// to where they slide in from. Map them once this returns.
+    ///
+    /// # Safety
+    /// The display pointer must be valid and point to an active X display connection.
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        duration: Duration,
+        rate: u32,
+        distance: i32,
+        outgoing: Vec<SlideWindow>,
+        incoming: Vec<SlideWindow>,
+    ) -> Self {
+        let now = Instant::now();
+        let slide = Self {
+            display,
+            start: now,
+            duration: duration.min(MAX_DURATION),
+            frame: Duration::from_secs(1) / rate.max(1),
+            last_frame: now,
+            distance,
+            outgoing,
+            incoming,
+        };
+        slide.draw(0.0);
+        slide
+    }
+
+    /// Time left until the next frame is due.
+    pub fn timeout(&self) -> Duration {
+        (self.last_frame + self.frame).saturating_duration_since(Instant::now())
+    }
+
+    /// Draws the next frame once it is due. Returns false when the slide
+    /// is over and every window is back in its place.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn poll(&mut self) -> bool {
+        if !self.timeout().is_zero() {
+            return true;
+        }
+        self.last_frame = Instant::now();
+        let elapsed = self.start.elapsed();
+        if elapsed >= self.duration {
+            self.finish();
+            return false;
+        }
+        self.draw(elapsed.as_secs_f64() / self.duration.as_secs_f64());
+        true
+    }
+
+    /// Ends the slide right away: the incoming windows stand where they
+    /// belong and the outgoing ones are unmapped and put back in place.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn finish(&mut self) {
+        for window in self.outgoing.drain(..) {
+            xlib::XUnmapWindow(self.display, window.id);
+            xlib::XMoveWindow(self.display, window.id, window.x, window.y);
+        }
+        for window in self.incoming.drain(..) {
+            xlib::XMoveWindow(self.display, window.id, window.x, window.y);
+        }
+        xlib::XFlush(self.display);
+    }
+
+    /// Moves the windows to where they are `progress` (0 to 1) into the
+    /// slide, slowing down towards the end.
+    unsafe fn draw(&self, progress: f64) {
+        let eased = 1.0 - (1.0 - progress.clamp(0.0, 1.0)).powi(3);
+        let out = (self.distance as f64 * eased).round() as i32;
+        let incoming = self.distance - out;
+        for window in &self.outgoing {
+            xlib::XMoveWindow(self.display, window.id, window.x - out, window.y);
+        }
+        for window in &self.incoming {
+            xlib::XMoveWindow(self.display, window.id, window.x + incoming, window.y);
+        }
+        xlib::XFlush(self.display);
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::{bail, Result};
+use std::{ffi::c_char, path::Path};
+use x11::xlib;
+
+use crate::utils::{
+    pixbuf::Image,
+    x11::{set_property, Atoms, Monitor},
+};
+
+/// Size an image of `width` by `height` is scaled to so it covers `area`
+/// without distortion, and the offset of the part shown.
+pub fn cover(width: u32, height: u32, area: Monitor) -> (u32, u32, u32, u32) {
+    let scale = f64::max(
+        area.width as f64 / width.max(1) as f64,
+        area.height as f64 / height.max(1) as f64,
+    );
+    let scaled_width = ((width as f64 * scale).round() as u32).max(area.width);
+    let scaled_height = ((height as f64 * scale).round() as u32).max(area.height);
+    (
+        scaled_width,
+        scaled_height,
+        (scaled_width - area.width) / 2,
+        (scaled_height - area.height) / 2,
+    )
+}
+
+/// Where a channel of 8 bits goes in a pixel of the given visual mask.
+fn channel(value: u8, mask: u64) -> u64 {
+    let shift = mask.trailing_zeros();
+    let bits = (mask >> shift).count_ones().min(8);
+    ((value as u64) >> (8 - bits)) << shift
+}
+
+/// The root window background, kept alive as long as it is shown.
+pub struct Wallpaper {
+    display: *mut xlib::Display,
+    pixmap: xlib::Pixmap,
+}
+
+impl Wallpaper {
+    /// Draws the image at `path` over each monitor, scaled to cover it, and
+    /// makes that the root window background. `_XROOTPMAP_ID` and
+    /// `ESETROOT_PMAP_ID` point to it for pseudo-transparent clients.
+    ///
+    /// # Safety
+    /// The display pointer must be valid and point to an active X display connection,
+    /// and root must be its root window.
+    pub unsafe fn set(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        atoms: &Atoms,
+        path: &Path,
+        monitors: &[Monitor],
+    ) -> Result<Self> {
+        let image = Image::open(path)?;
+        let screen = xlib::XDefaultScreen(display);
+        let depth = xlib::XDefaultDepth(display, screen);
+        let width = xlib::XDisplayWidth(display, screen) as u32;
+        let height = xlib::XDisplayHeight(display, screen) as u32;
+
+        let pixmap = xlib::XCreatePixmap(display, root, width, height, depth as u32);
+        let gc = xlib::XCreateGC(display, pixmap, 0, std::ptr::null_mut());
+        xlib::XSetForeground(display, gc, xlib::XBlackPixel(display, screen));
+        xlib::XFillRectangle(display, pixmap, gc, 0, 0, width, height);
+
+        let (image_width, image_height) = image.size();
+        let result = monitors.iter().try_for_each(|&monitor| {
+            let (scaled_width, scaled_height, offset_x, offset_y) =
+                cover(image_width, image_height, monitor);
+            let scaled = image.scale(scaled_width, scaled_height)?;
+            Self::draw(display, pixmap, gc, &scaled, monitor, (offset_x, offset_y))
+        });
+        xlib::XFreeGC(display, gc);
+        if let Err(e) = result {
+            xlib::XFreePixmap(display, pixmap);
+            return Err(e);
+        }
+
+        xlib::XSetWindowBackgroundPixmap(display, root, pixmap);
+        xlib::XClearWindow(display, root);
+        for property in [atoms.xrootpmap_id, atoms.esetroot_pmap_id] {
+            set_property(display, root, property, xlib::XA_PIXMAP, &[pixmap]);
+        }
+        xlib::XFlush(display);
+
+        Ok(Self { display, pixmap })
+    }
+
+    /// Copies the part of `image` at `offset` into `monitor` on `pixmap`.
+    unsafe fn draw(
+        display: *mut xlib::Display,
+        pixmap: xlib::Pixmap,
+        gc: xlib::GC,
+        image: &Image,
+        monitor: Monitor,
+        (offset_x, offset_y): (u32, u32),
+    ) -> Result<()> {
+        let screen = xlib::XDefaultScreen(display);
+        let visual = xlib::XDefaultVisual(display, screen);
+        let depth = xlib::XDefaultDepth(display, screen);
+        let pixels = image.pixels();
+        let mut data = Vec::with_capacity(monitor.width as usize * monitor.height as usize);
+        for y in 0..monitor.height {
+            for x in 0..monitor.width {
+                let [red, green, blue] = pixels.rgb(x + offset_x, y + offset_y);
+                data.push(
+                    (channel(red, (*visual).red_mask)
+                        | channel(green, (*visual).green_mask)
+                        | channel(blue, (*visual).blue_mask)) as u32,
+                );
+            }
+        }
+
+        let ximage = xlib::XCreateImage(
+            display,
+            visual,
+            depth as u32,
+            xlib::ZPixmap,
+            0,
+            data.as_mut_ptr() as *mut c_char,
+            monitor.width,
+            monitor.height,
+            32,
+            0,
+        );
+        if ximage.is_null() {
+            bail!("Failed to create the wallpaper image");
+        }
+        if (*ximage).bits_per_pixel != 32 {
+            (*ximage).data = std::ptr::null_mut();
+            xlib::XDestroyImage(ximage);
+            bail!("Wallpapers need a display with 32 bits per pixel");
+        }
+        // The pixels are written in the client's byte order.
+        (*ximage).byte_order = if cfg!(target_endian = "little") {
+            xlib::LSBFirst
+        } else {
+            xlib::MSBFirst
+        };
+
+        xlib::XPutImage(
+            display,
+            pixmap,
+            gc,
+            ximage,
+            0,
+            0,
+            monitor.x,
+            monitor.y,
+            monitor.width,
+            monitor.height,
+        );
+        // `data` owns the pixels, XDestroyImage would free them.
+        (*ximage).data = std::ptr::null_mut();
+        xlib::XDestroyImage(ximage);
+        Ok(())
+    }
+}
+
+impl Drop for Wallpaper {
+    fn drop(&mut self) {
+        unsafe { xlib::XFreePixmap(self.display, self.pixmap) };
+    }
+}
@@ -0,0 +1,204 @@
+use std::ffi::CString;
+use x11::xlib;
+
+use crate::{ui::layout::Geometry, utils::x11::WindowVisual};
+
+const PADDING: i32 = 6;
+const LINE_HEIGHT: i32 = 16;
+/// Width of the miniature of the monitor, the height follows its aspect.
+const THUMBNAIL_WIDTH: u32 = 160;
+/// Titles listed at most, the rest are summed up in one line.
+const MAX_TITLES: usize = 8;
+const MAX_TITLE_CHARS: usize = 40;
+
+/// A window as the preview shows it.
+pub struct PreviewWindow {
+    pub title: String,
+    /// Where the window is on the root window.
+    pub area: Geometry,
+}
+
+/// A popup under a workspace button of the bar, with a miniature of the
+/// windows on that workspace and a list of their titles.
+pub struct WorkspacePreview {
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    visual: WindowVisual,
+    gc: xlib::GC,
+    font: *mut xlib::XFontStruct,
+    /// The bar and the workspace shown, `None` while hidden.
+    pub shown: Option<(xlib::Window, usize)>,
+}
+
+impl WorkspacePreview {
+    /// Creates the popup, unmapped until [`WorkspacePreview::show`] is called.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection
+    /// - The root window must be a valid window ID for the given display
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        background: u64,
+        border: u64,
+    ) -> Self {
+        let screen = xlib::XDefaultScreen(display);
+        let visual = WindowVisual::new(display);
+        let white = xlib::XWhitePixel(display, screen) | if visual.argb { 0xFF000000 } else { 0 };
+
+        let window = visual.create_window(display, root, 0, 0, 1, 1, 1, border, background);
+
+        let mut attrs: xlib::XSetWindowAttributes = std::mem::zeroed();
+        attrs.override_redirect = 1;
+        attrs.save_under = 1;
+        xlib::XChangeWindowAttributes(
+            display,
+            window,
+            xlib::CWOverrideRedirect | xlib::CWSaveUnder,
+            &mut attrs,
+        );
+
+        let gc = xlib::XCreateGC(display, window, 0, std::ptr::null_mut());
+        xlib::XSetForeground(display, gc, white);
+
+        let font_name = CString::new("-*-*-medium-r-*-*-14-*-*-*-*-*-*-*").unwrap();
+        let font = xlib::XLoadQueryFont(display, font_name.as_ptr());
+        if !font.is_null() {
+            xlib::XSetFont(display, gc, (*font).fid);
+        }
+
+        Self {
+            display,
+            window,
+            visual,
+            gc,
+            font,
+            shown: None,
+        }
+    }
+
+    /// Shows `windows`, laid out as they are on `monitor`, right below or
+    /// above `anchor`, whichever side of it has room within `bounds`.
+    ///
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn show(
+        &mut self,
+        anchor: Geometry,
+        bounds: Geometry,
+        monitor: Geometry,
+        windows: &[PreviewWindow],
+    ) {
+        let thumbnail_height =
+            (THUMBNAIL_WIDTH as u64 * monitor.height as u64 / monitor.width.max(1) as u64) as u32;
+        let titles = Self::titles(windows);
+
+        let width = THUMBNAIL_WIDTH as i32 + PADDING * 2;
+        let height =
+            thumbnail_height as i32 + PADDING * 3 + LINE_HEIGHT * titles.len().max(1) as i32;
+        let below = anchor.y + anchor.height as i32;
+        let y = if below + height <= bounds.y + bounds.height as i32 {
+            below
+        } else {
+            anchor.y - height
+        };
+        let x = anchor
+            .x
+            .min(bounds.x + bounds.width as i32 - width)
+            .max(bounds.x);
+        xlib::XMoveResizeWindow(self.display, self.window, x, y, width as u32, height as u32);
+        xlib::XMapRaised(self.display, self.window);
+        xlib::XClearWindow(self.display, self.window);
+
+        xlib::XDrawRectangle(
+            self.display,
+            self.window,
+            self.gc,
+            PADDING,
+            PADDING,
+            THUMBNAIL_WIDTH,
+            thumbnail_height,
+        );
+        let scale = THUMBNAIL_WIDTH as f64 / monitor.width.max(1) as f64;
+        for window in windows {
+            let area = window.area;
+            xlib::XDrawRectangle(
+                self.display,
+                self.window,
+                self.gc,
+                PADDING + ((area.x - monitor.x) as f64 * scale) as i32,
+                PADDING + ((area.y - monitor.y) as f64 * scale) as i32,
+                ((area.width as f64 * scale) as u32).max(2),
+                ((area.height as f64 * scale) as u32).max(2),
+            );
+        }
+
+        let mut baseline = PADDING * 2 + thumbnail_height as i32 + LINE_HEIGHT - 4;
+        for title in titles {
+            xlib::XDrawString(
+                self.display,
+                self.window,
+                self.gc,
+                PADDING,
+                baseline,
+                title.as_ptr(),
+                title.as_bytes().len() as i32,
+            );
+            baseline += LINE_HEIGHT;
+        }
+        xlib::XFlush(self.display);
+    }
+
+    /// One line per window, cut short to fit the popup.
+    fn titles(windows: &[PreviewWindow]) -> Vec<CString> {
+        if windows.is_empty() {
+            return vec![CString::new("No windows").unwrap()];
+        }
+
+        let mut lines = windows
+            .iter()
+            .take(MAX_TITLES)
+            .map(|window| {
+                let mut title = window
+                    .title
+                    .chars()
+                    .filter(|&c| c != '\0')
+                    .take(MAX_TITLE_CHARS)
+                    .collect::<String>();
+                if window.title.chars().count() > MAX_TITLE_CHARS {
+                    title.push_str("...");
+                }
+                title
+            })
+            .collect::<Vec<_>>();
+        if windows.len() > MAX_TITLES {
+            lines.push(format!("and {} more", windows.len() - MAX_TITLES));
+        }
+        lines
+            .into_iter()
+            .filter_map(|line| CString::new(line).ok())
+            .collect()
+    }
+
+    /// # Safety
+    /// The display connection must still be valid.
+    pub unsafe fn hide(&mut self) {
+        if self.shown.take().is_some() {
+            xlib::XUnmapWindow(self.display, self.window);
+            xlib::XFlush(self.display);
+        }
+    }
+}
+
+impl Drop for WorkspacePreview {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.font.is_null() {
+                xlib::XFreeFont(self.display, self.font);
+            }
+            xlib::XFreeGC(self.display, self.gc);
+            xlib::XDestroyWindow(self.display, self.window);
+            self.visual.free(self.display);
+        }
+    }
+}
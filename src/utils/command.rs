@@ -1,15 +1,179 @@
 use serde::{de, Deserialize};
-use std::str::FromStr;
+use std::{collections::BTreeMap, fmt, path::PathBuf, process, str::FromStr};
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(try_from = "String")]
+use crate::{
+    input::hot_corner::Corner, ui::screenshot::ScreenshotTarget, velowm_core::wm::MAX_WORKSPACES,
+};
+
+/// Deserializes from a command string, a [`Spawn`] table, or an array of
+/// those that runs in order.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Command {
     Exit,
     Close,
-    Spawn(String),
+    Kill,
+    Spawn(Spawn),
     Workspace(usize),
+    NewWorkspace,
+    NextWorkspace,
+    PreviousWorkspace,
+    MoveToWorkspace(usize),
     ToggleFloat,
+    ToggleGlobal,
+    FloatAll,
+    TileAll,
+    CenterWindow,
+    MoveToCorner(Corner),
+    MoveToMonitor(usize),
+    MoveWorkspaceToMonitor(MonitorTarget),
+    MoveWindowToMonitor(MonitorTarget),
+    FocusPrimaryMonitor,
+    GrowWidth,
+    ShrinkWidth,
+    GrowHeight,
+    ShrinkHeight,
     ToggleFullscreen,
+    FullscreenAllMonitors,
+    IncreaseMasters,
+    DecreaseMasters,
+    ToggleGameMode,
+    IncreaseMasterRatio,
+    DecreaseMasterRatio,
+    IncreaseGaps,
+    DecreaseGaps,
+    ResetLayout,
+    RotateStackClockwise,
+    RotateStackCounterClockwise,
+    ReverseStack,
+    CycleLayout,
+    ToggleBar,
+    Restart,
+    ReloadConfig,
+    SetTheme(String),
+    Mark(char),
+    GotoMark(char),
+    Screenshot(ScreenshotTarget),
+    ViewTag(usize),
+    ToggleViewTag(usize),
+    TagWindow(usize),
+    ToggleWindowTag(usize),
+    ShowBinds,
+    Sequence(Vec<Command>),
+}
+
+/// The window `close` and `kill` act on.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseTarget {
+    #[default]
+    Focused,
+    /// The window under the pointer, focused or not.
+    Pointer,
+}
+
+/// Where `move_workspace_to_monitor` and `move_window_to_monitor` send
+/// the workspace or window.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MonitorTarget {
+    Next,
+    Previous,
+    /// The RandR primary output.
+    Primary,
+    /// A RandR output, such as "HDMI-1".
+    Output(String),
+}
+
+impl FromStr for MonitorTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err("Missing monitor: next, prev, primary or an output name".to_string()),
+            "next" => Ok(Self::Next),
+            "prev" => Ok(Self::Previous),
+            "primary" => Ok(Self::Primary),
+            output => Ok(Self::Output(output.to_string())),
+        }
+    }
+}
+
+/// A program to launch. `spawn <command>` sets only `command`, the table
+/// form `{ spawn = "alacritty", cwd = "~/projects", env = { FOO = "1" } }`
+/// the rest as well.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Spawn {
+    #[serde(rename = "spawn")]
+    pub command: String,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Spawn {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            cwd: None,
+            env: BTreeMap::new(),
+        }
+    }
+
+    /// The program name, without arguments.
+    pub fn program(&self) -> &str {
+        self.command
+            .split_whitespace()
+            .next()
+            .unwrap_or(&self.command)
+    }
+
+    /// Runs `command` through `sh`, so it can have arguments and quoting.
+    /// `exec` keeps the pid the program's own, startup notification
+    /// matches windows by it.
+    pub fn to_process(&self) -> process::Command {
+        let mut command = process::Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("exec {}", self.command))
+            .envs(&self.env);
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(expand_home(cwd));
+        }
+        command
+    }
+}
+
+/// Replaces a leading `~` with `$HOME`.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(format!("{}{}", home.to_string_lossy(), rest))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+fn parse_index(arg: &str) -> Result<usize, String> {
+    let idx = arg
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| format!("Invalid workspace index: {}", arg))?;
+    if idx == 0 || idx > MAX_WORKSPACES {
+        return Err(format!(
+            "Workspace index must be between 1 and {}",
+            MAX_WORKSPACES
+        ));
+    }
+    Ok(idx - 1)
+}
+
+fn parse_mark(arg: &str) -> Result<char, String> {
+    let mut chars = arg.trim().chars();
+    match (chars.next(), chars.next()) {
+        (Some(mark), None) => Ok(mark),
+        _ => Err(format!("Mark must be a single character: {}", arg)),
+    }
 }
 
 impl FromStr for Command {
@@ -19,20 +183,229 @@ impl FromStr for Command {
         match s {
             "exit" => Ok(Command::Exit),
             "close" => Ok(Command::Close),
+            "kill" => Ok(Command::Kill),
             "toggle_float" => Ok(Command::ToggleFloat),
+            "toggle_global" => Ok(Command::ToggleGlobal),
+            "float_all" => Ok(Command::FloatAll),
+            "tile_all" => Ok(Command::TileAll),
+            "center_window" => Ok(Command::CenterWindow),
+            "grow_width" => Ok(Command::GrowWidth),
+            "shrink_width" => Ok(Command::ShrinkWidth),
+            "grow_height" => Ok(Command::GrowHeight),
+            "shrink_height" => Ok(Command::ShrinkHeight),
+            s if s.starts_with("move_to_corner ") => {
+                Ok(Command::MoveToCorner(s[15..].trim().parse()?))
+            }
+            s if s.starts_with("move_to_monitor") => {
+                let arg = s[15..].trim();
+                match arg.parse::<usize>() {
+                    Ok(n) if n >= 1 => Ok(Command::MoveToMonitor(n - 1)),
+                    _ => Err(format!("Invalid monitor number: {}", arg)),
+                }
+            }
             "toggle_fullscreen" => Ok(Command::ToggleFullscreen),
-            s if s.starts_with("spawn ") => Ok(Command::Spawn(s[6..].to_string())),
-            s if s.starts_with("workspace") => {
-                let idx = s[9..]
-                    .trim()
-                    .parse::<usize>()
-                    .map_err(|_| format!("Invalid workspace index: {}", &s[9..]))?;
-                if idx == 0 || idx > 10 {
-                    return Err("Workspace index must be between 1 and 10".to_string());
+            "fullscreen_all_monitors" => Ok(Command::FullscreenAllMonitors),
+            "increase_masters" => Ok(Command::IncreaseMasters),
+            "decrease_masters" => Ok(Command::DecreaseMasters),
+            "toggle_game_mode" => Ok(Command::ToggleGameMode),
+            "increase_master_ratio" => Ok(Command::IncreaseMasterRatio),
+            "decrease_master_ratio" => Ok(Command::DecreaseMasterRatio),
+            "increase_gaps" => Ok(Command::IncreaseGaps),
+            "decrease_gaps" => Ok(Command::DecreaseGaps),
+            "reset_layout" => Ok(Command::ResetLayout),
+            "rotate_stack_cw" => Ok(Command::RotateStackClockwise),
+            "rotate_stack_ccw" => Ok(Command::RotateStackCounterClockwise),
+            "reverse_stack" => Ok(Command::ReverseStack),
+            "cycle_layout" => Ok(Command::CycleLayout),
+            "toggle_bar" => Ok(Command::ToggleBar),
+            "focus_primary_monitor" => Ok(Command::FocusPrimaryMonitor),
+            "next_workspace" => Ok(Command::NextWorkspace),
+            "previous_workspace" => Ok(Command::PreviousWorkspace),
+            "restart" => Ok(Command::Restart),
+            "reload_config" => Ok(Command::ReloadConfig),
+            "show_binds" => Ok(Command::ShowBinds),
+            s if s.starts_with("set_theme ") => Ok(Command::SetTheme(s[10..].trim().to_string())),
+            s if s.starts_with("move_workspace_to_monitor ") => Ok(
+                Command::MoveWorkspaceToMonitor(s["move_workspace_to_monitor ".len()..].parse()?),
+            ),
+            s if s.starts_with("move_window_to_monitor ") => Ok(Command::MoveWindowToMonitor(
+                s["move_window_to_monitor ".len()..].parse()?,
+            )),
+            s if s.starts_with("mark ") => Ok(Command::Mark(parse_mark(&s[5..])?)),
+            s if s.starts_with("goto_mark ") => Ok(Command::GotoMark(parse_mark(&s[10..])?)),
+            s if s.starts_with("screenshot ") => Ok(Command::Screenshot(s[11..].trim().parse()?)),
+            s if s.starts_with("spawn ") => Ok(Command::Spawn(Spawn::new(&s[6..]))),
+            "workspace new" => Ok(Command::NewWorkspace),
+            s if s.starts_with("move_to_workspace") => {
+                Ok(Command::MoveToWorkspace(parse_index(&s[17..])?))
+            }
+            s if s.starts_with("workspace") => Ok(Command::Workspace(parse_index(&s[9..])?)),
+            s if s.starts_with("toggle_view_tag") => {
+                Ok(Command::ToggleViewTag(parse_index(&s[15..])?))
+            }
+            s if s.starts_with("view_tag") => Ok(Command::ViewTag(parse_index(&s[8..])?)),
+            s if s.starts_with("toggle_window_tag") => {
+                Ok(Command::ToggleWindowTag(parse_index(&s[17..])?))
+            }
+            s if s.starts_with("tag_window") => Ok(Command::TagWindow(parse_index(&s[10..])?)),
+            _ => Err(format!("Unknown command '{}'", s)),
+        }
+    }
+}
+
+impl Command {
+    /// The heading a bind is listed under by `show_binds`. Sequences go
+    /// with their first command.
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::Spawn(_) => "Launch",
+            Self::Close
+            | Self::Kill
+            | Self::ToggleFloat
+            | Self::ToggleGlobal
+            | Self::FloatAll
+            | Self::TileAll
+            | Self::CenterWindow
+            | Self::MoveToCorner(_)
+            | Self::GrowWidth
+            | Self::ShrinkWidth
+            | Self::GrowHeight
+            | Self::ShrinkHeight
+            | Self::ToggleFullscreen
+            | Self::FullscreenAllMonitors
+            | Self::Mark(_)
+            | Self::GotoMark(_) => "Windows",
+            Self::Workspace(_)
+            | Self::NewWorkspace
+            | Self::NextWorkspace
+            | Self::PreviousWorkspace
+            | Self::MoveToWorkspace(_)
+            | Self::ViewTag(_)
+            | Self::ToggleViewTag(_)
+            | Self::TagWindow(_)
+            | Self::ToggleWindowTag(_) => "Workspaces",
+            Self::MoveToMonitor(_)
+            | Self::MoveWorkspaceToMonitor(_)
+            | Self::MoveWindowToMonitor(_)
+            | Self::FocusPrimaryMonitor => "Monitors",
+            Self::IncreaseMasters
+            | Self::DecreaseMasters
+            | Self::IncreaseMasterRatio
+            | Self::DecreaseMasterRatio
+            | Self::IncreaseGaps
+            | Self::DecreaseGaps
+            | Self::ResetLayout
+            | Self::RotateStackClockwise
+            | Self::RotateStackCounterClockwise
+            | Self::ReverseStack
+            | Self::CycleLayout => "Layout",
+            Self::Exit
+            | Self::Restart
+            | Self::ReloadConfig
+            | Self::SetTheme(_)
+            | Self::ToggleBar
+            | Self::ToggleGameMode
+            | Self::Screenshot(_)
+            | Self::ShowBinds => "Session",
+            Self::Sequence(commands) => commands.first().map_or("Session", Self::category),
+        }
+    }
+}
+
+/// Writes the command the way it is written in the config file, with the
+/// commands of a sequence separated by `; `.
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Exit => "exit",
+            Self::Close => "close",
+            Self::Kill => "kill",
+            Self::NewWorkspace => "workspace new",
+            Self::NextWorkspace => "next_workspace",
+            Self::PreviousWorkspace => "previous_workspace",
+            Self::ToggleFloat => "toggle_float",
+            Self::ToggleGlobal => "toggle_global",
+            Self::FloatAll => "float_all",
+            Self::TileAll => "tile_all",
+            Self::CenterWindow => "center_window",
+            Self::FocusPrimaryMonitor => "focus_primary_monitor",
+            Self::GrowWidth => "grow_width",
+            Self::ShrinkWidth => "shrink_width",
+            Self::GrowHeight => "grow_height",
+            Self::ShrinkHeight => "shrink_height",
+            Self::ToggleFullscreen => "toggle_fullscreen",
+            Self::FullscreenAllMonitors => "fullscreen_all_monitors",
+            Self::IncreaseMasters => "increase_masters",
+            Self::DecreaseMasters => "decrease_masters",
+            Self::ToggleGameMode => "toggle_game_mode",
+            Self::IncreaseMasterRatio => "increase_master_ratio",
+            Self::DecreaseMasterRatio => "decrease_master_ratio",
+            Self::IncreaseGaps => "increase_gaps",
+            Self::DecreaseGaps => "decrease_gaps",
+            Self::ResetLayout => "reset_layout",
+            Self::RotateStackClockwise => "rotate_stack_cw",
+            Self::RotateStackCounterClockwise => "rotate_stack_ccw",
+            Self::ReverseStack => "reverse_stack",
+            Self::CycleLayout => "cycle_layout",
+            Self::ToggleBar => "toggle_bar",
+            Self::Restart => "restart",
+            Self::ReloadConfig => "reload_config",
+            Self::ShowBinds => "show_binds",
+            Self::Spawn(spawn) => return write!(f, "spawn {}", spawn.command),
+            Self::Workspace(index) => return write!(f, "workspace{}", index + 1),
+            Self::MoveToWorkspace(index) => return write!(f, "move_to_workspace{}", index + 1),
+            Self::MoveToCorner(corner) => {
+                let corner = match corner {
+                    Corner::TopLeft => "tl",
+                    Corner::TopRight => "tr",
+                    Corner::BottomLeft => "bl",
+                    Corner::BottomRight => "br",
+                };
+                return write!(f, "move_to_corner {}", corner);
+            }
+            Self::MoveToMonitor(index) => return write!(f, "move_to_monitor {}", index + 1),
+            Self::MoveWorkspaceToMonitor(target) => {
+                return write!(f, "move_workspace_to_monitor {}", target)
+            }
+            Self::MoveWindowToMonitor(target) => {
+                return write!(f, "move_window_to_monitor {}", target)
+            }
+            Self::SetTheme(name) => return write!(f, "set_theme {}", name),
+            Self::Mark(mark) => return write!(f, "mark {}", mark),
+            Self::GotoMark(mark) => return write!(f, "goto_mark {}", mark),
+            Self::Screenshot(target) => {
+                let target = match target {
+                    ScreenshotTarget::Full => "full",
+                    ScreenshotTarget::Window => "window",
+                    ScreenshotTarget::Region => "region",
+                };
+                return write!(f, "screenshot {}", target);
+            }
+            Self::ViewTag(index) => return write!(f, "view_tag{}", index + 1),
+            Self::ToggleViewTag(index) => return write!(f, "toggle_view_tag{}", index + 1),
+            Self::TagWindow(index) => return write!(f, "tag_window{}", index + 1),
+            Self::ToggleWindowTag(index) => return write!(f, "toggle_window_tag{}", index + 1),
+            Self::Sequence(commands) => {
+                for (i, command) in commands.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+                    write!(f, "{}", command)?;
                 }
-                Ok(Command::Workspace(idx - 1))
+                return Ok(());
             }
-            _ => Err(format!("Unknown command: {}", s)),
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for MonitorTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Next => f.write_str("next"),
+            Self::Previous => f.write_str("prev"),
+            Self::Primary => f.write_str("primary"),
+            Self::Output(name) => f.write_str(name),
         }
     }
 }
@@ -45,10 +418,57 @@ impl TryFrom<String> for Command {
     }
 }
 
+struct CommandVisitor;
+
+impl<'de> de::Visitor<'de> for CommandVisitor {
+    type Value = Command;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a command, a spawn table or an array of commands")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Command, E> {
+        Command::from_str(s).map_err(E::custom)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Command, A::Error> {
+        let mut commands = Vec::new();
+        while let Some(command) = seq.next_element::<Command>()? {
+            commands.push(command);
+        }
+        match commands.len() {
+            0 => Err(de::Error::custom("Empty command list")),
+            1 => Ok(commands.remove(0)),
+            _ => Ok(Command::Sequence(commands)),
+        }
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Command, A::Error> {
+        Spawn::deserialize(de::value::MapAccessDeserializer::new(map)).map(Command::Spawn)
+    }
+}
+
+impl<'de> Deserialize<'de> for Command {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(CommandVisitor)
+    }
+}
+
 pub fn deserialize_command<'de, D>(deserializer: D) -> Result<Command, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    Command::deserialize(deserializer)
+}
+
+/// Like [`deserialize_command`], with `"none"` for no command.
+pub fn deserialize_optional_command<'de, D>(deserializer: D) -> Result<Option<Command>, D::Error>
 where
     D: de::Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    Command::from_str(&s).map_err(de::Error::custom)
+    match s.as_str() {
+        "none" => Ok(None),
+        s => Command::from_str(s).map(Some).map_err(de::Error::custom),
+    }
 }
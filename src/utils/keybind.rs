@@ -1,78 +1,253 @@
-use serde::Deserialize;
-use x11::keysym;
+use serde::{de, Deserialize};
+use std::ffi::{CStr, CString};
+use x11::xlib;
 
 use super::command::{deserialize_command, Command};
 
 #[derive(Clone, Deserialize)]
 pub struct Bind {
+    #[serde(deserialize_with = "deserialize_key")]
     pub key: String,
     #[serde(deserialize_with = "deserialize_command")]
     pub command: Command,
+    /// Overrides the global `modifier` for this bind, e.g. "super+shift".
+    #[serde(default)]
+    pub modifier: Option<String>,
+    /// Run when the key is let go instead of when it is pressed.
+    #[serde(default)]
+    pub on_release: bool,
+    /// Run again while the key is held and auto-repeats.
+    #[serde(default = "default_repeat")]
+    pub repeat: bool,
+    /// Ask for confirmation in a dialog before running the command.
+    #[serde(default)]
+    pub confirm: bool,
 }
 
+fn default_repeat() -> bool {
+    true
+}
+
+impl Bind {
+    /// A bind pressed with the global modifier.
+    pub fn new(key: &str, command: Command) -> Self {
+        Self {
+            key: key.to_string(),
+            command,
+            modifier: None,
+            on_release: false,
+            repeat: default_repeat(),
+            confirm: false,
+        }
+    }
+
+    /// The modifiers held with the key, this bind's own or `default`.
+    pub fn modifier<'a>(&'a self, default: &'a str) -> &'a str {
+        self.modifier.as_deref().unwrap_or(default)
+    }
+
+    /// The keys as they are printed on the keyboard, e.g. `Alt+/` for
+    /// `slash` pressed with `alt`, for naming the bind to the user.
+    pub fn label(&self, default: &str) -> String {
+        let mut keys = self
+            .modifier(default)
+            .split('+')
+            .filter(|modifier| !modifier.is_empty())
+            .map(capitalize)
+            .collect::<Vec<_>>();
+        keys.push(key_label(&self.key));
+        keys.join("+")
+    }
+}
+
+/// `alt` as `Alt`.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// The character on the key for printable keys, whose keysyms are their
+/// ASCII codes, and the capitalized keysym name for the others.
+fn key_label(key: &str) -> String {
+    match lookup_keysym(key).and_then(|keysym| u8::try_from(keysym).ok()) {
+        Some(code @ 0x21..=0x7e) => char::from(code).to_ascii_uppercase().to_string(),
+        _ => capitalize(key),
+    }
+}
+
+/// Modifiers that tell binds apart. Lock keys such as Caps Lock are left
+/// out.
+pub const MODIFIER_MASK: u32 =
+    xlib::ShiftMask | xlib::ControlMask | xlib::Mod1Mask | xlib::Mod4Mask;
+
+/// The keysym a bind's key grabs, `NoSymbol` (0) if there is none. Keys
+/// from the config file were checked by [`deserialize_key`] already.
 pub fn get_keysym_for_key(key: &str) -> u64 {
-    match key.to_lowercase().as_str() {
-        "a" => keysym::XK_a,
-        "b" => keysym::XK_b,
-        "c" => keysym::XK_c,
-        "d" => keysym::XK_d,
-        "e" => keysym::XK_e,
-        "f" => keysym::XK_f,
-        "g" => keysym::XK_g,
-        "h" => keysym::XK_h,
-        "i" => keysym::XK_i,
-        "j" => keysym::XK_j,
-        "k" => keysym::XK_k,
-        "l" => keysym::XK_l,
-        "m" => keysym::XK_m,
-        "n" => keysym::XK_n,
-        "o" => keysym::XK_o,
-        "p" => keysym::XK_p,
-        "q" => keysym::XK_q,
-        "r" => keysym::XK_r,
-        "s" => keysym::XK_s,
-        "t" => keysym::XK_t,
-        "u" => keysym::XK_u,
-        "v" => keysym::XK_v,
-        "w" => keysym::XK_w,
-        "x" => keysym::XK_x,
-        "y" => keysym::XK_y,
-        "z" => keysym::XK_z,
-        "0" => keysym::XK_0,
-        "1" => keysym::XK_1,
-        "2" => keysym::XK_2,
-        "3" => keysym::XK_3,
-        "4" => keysym::XK_4,
-        "5" => keysym::XK_5,
-        "6" => keysym::XK_6,
-        "7" => keysym::XK_7,
-        "8" => keysym::XK_8,
-        "9" => keysym::XK_9,
-        "space" => keysym::XK_space,
-        _ => keysym::XK_w,
+    lookup_keysym(key).unwrap_or(0)
+}
+
+/// Looks up an X keysym name such as `q`, `space`, `Return` or `F1`. Case
+/// is forgiving, `escape` and `ESCAPE` find `Escape`. Letters always mean
+/// the key, `Q` is the same as `q`.
+pub fn lookup_keysym(key: &str) -> Option<u64> {
+    let lower = key.to_lowercase();
+    let mut chars = lower.chars();
+    let capitalized = chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect::<String>());
+
+    [Some(lower.clone()), Some(key.to_string()), capitalized]
+        .into_iter()
+        .flatten()
+        .find_map(|name| string_to_keysym(&name))
+}
+
+fn string_to_keysym(name: &str) -> Option<u64> {
+    let name = CString::new(name).ok()?;
+    let keysym = unsafe { xlib::XStringToKeysym(name.as_ptr()) };
+    (keysym != 0).then_some(keysym)
+}
+
+pub fn deserialize_key<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let key = String::deserialize(deserializer)?;
+    match lookup_keysym(&key) {
+        Some(_) => Ok(key),
+        None => Err(de::Error::custom(unknown_key_message(&key))),
+    }
+}
+
+fn unknown_key_message(key: &str) -> String {
+    let suggestions = suggest_keys(key);
+    match suggestions.as_slice() {
+        [] => format!(
+            "Unknown key '{}', expected an X keysym name like 'q', 'Return' or 'F1'",
+            key
+        ),
+        [only] => format!("Unknown key '{}', did you mean '{}'?", key, only),
+        [rest @ .., last] => format!(
+            "Unknown key '{}', did you mean '{}' or '{}'?",
+            key,
+            rest.join("', '"),
+            last
+        ),
+    }
+}
+
+/// Up to three keysym names close to `key`.
+fn suggest_keys(key: &str) -> Vec<String> {
+    let key = key.to_lowercase();
+    let limit = (key.chars().count() / 3).max(1);
+
+    let mut names: Vec<(usize, String)> = keysym_names()
+        .into_iter()
+        .map(|name| (edit_distance(&key, &name.to_lowercase()), name))
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    names.sort();
+    names.dedup_by(|a, b| a.1.eq_ignore_ascii_case(&b.1));
+    names.into_iter().take(3).map(|(_, name)| name).collect()
+}
+
+/// Names of the keysyms a keyboard is likely to have: Latin-1, function
+/// and modifier keys, and the XF86 media keys.
+fn keysym_names() -> Vec<String> {
+    (0x20..=0xff)
+        .chain(0xff00..=0xffff)
+        .chain(0x1008ff00..=0x1008ffff)
+        .filter_map(|keysym| {
+            let name = unsafe { xlib::XKeysymToString(keysym) };
+            (!name.is_null()).then(|| {
+                unsafe { CStr::from_ptr(name) }
+                    .to_string_lossy()
+                    .into_owned()
+            })
+        })
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// A keysym, the modifier mask held with it and whether it runs on
+/// release.
+type Combination = (u64, u32, bool);
+
+/// Describes every key combination that more than one bind grabs. All of
+/// them run when it's pressed. `modifier` is the global one.
+pub fn find_conflicts(modifier: &str, binds: &[Bind]) -> Vec<String> {
+    let mut groups: Vec<(Combination, Vec<&Bind>)> = Vec::new();
+    for bind in binds {
+        let combination = (
+            get_keysym_for_key(&bind.key),
+            get_modifier(bind.modifier(modifier)),
+            bind.on_release,
+        );
+        match groups.iter_mut().find(|(c, _)| *c == combination) {
+            Some((_, group)) => group.push(bind),
+            None => groups.push((combination, vec![bind])),
+        }
     }
-    .into()
+
+    groups
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|(_, group)| {
+            let keys: Vec<String> = group
+                .iter()
+                .map(|bind| format!("\"{}\"", bind.key))
+                .collect();
+            format!(
+                "{}+{}{} is bound {} times, by keys {}",
+                group[0].modifier(modifier),
+                group[0].key.to_lowercase(),
+                if group[0].on_release {
+                    " (on release)"
+                } else {
+                    ""
+                },
+                group.len(),
+                keys.join(", ")
+            )
+        })
+        .collect()
 }
 
 pub fn get_modifier(modifier: &str) -> u32 {
     modifier
         .split('+')
         .map(|m| match m.trim().to_lowercase().as_str() {
-            "alt" => x11::xlib::Mod1Mask,
-            "ctrl" => x11::xlib::ControlMask,
-            "shift" => x11::xlib::ShiftMask,
-            "super" | "win" => x11::xlib::Mod4Mask,
-            _ => x11::xlib::Mod1Mask,
+            "alt" => xlib::Mod1Mask,
+            "ctrl" => xlib::ControlMask,
+            "shift" => xlib::ShiftMask,
+            "super" | "win" => xlib::Mod4Mask,
+            _ => xlib::Mod1Mask,
         })
         .fold(0, |acc, mask| acc | mask)
 }
 
 pub fn get_modifier_for_key(key: &str) -> u32 {
     match key.to_lowercase().as_str() {
-        "alt" => x11::xlib::Mod1Mask,
-        "ctrl" => x11::xlib::ControlMask,
-        "shift" => x11::xlib::ShiftMask,
-        "super" | "win" => x11::xlib::Mod4Mask,
-        _ => x11::xlib::Mod1Mask,
+        "alt" => xlib::Mod1Mask,
+        "ctrl" => xlib::ControlMask,
+        "shift" => xlib::ShiftMask,
+        "super" | "win" => xlib::Mod4Mask,
+        _ => xlib::Mod1Mask,
     }
 }
@@ -0,0 +1,208 @@
+use anyhow::{anyhow, Context, Result};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::{Mutex, OnceLock, RwLock},
+};
+
+use crate::config::logging::LoggingConfig;
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// A default level plus per-module overrides, in `RUST_LOG` syntax:
+/// `info,velowm::ui=debug`.
+struct Filter {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    fn parse(spec: &str) -> Result<Self> {
+        let mut filter = Filter {
+            default: LevelFilter::Info,
+            modules: Vec::new(),
+        };
+
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    filter
+                        .modules
+                        .push((module.trim().to_string(), parse_level(level)?));
+                }
+                None => filter.default = parse_level(directive)?,
+            }
+        }
+
+        // Longest prefix first, so the most specific module wins.
+        filter
+            .modules
+            .sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        Ok(filter)
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .find(|(module, _)| {
+                target == module
+                    || target
+                        .strip_prefix(module.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter> {
+    LevelFilter::from_str(level.trim()).map_err(|_| anyhow!("Invalid log level: {}", level))
+}
+
+/// `velowm.log` that is rotated to `velowm.log.1`, `.2`, ... once it grows
+/// past `max_size` bytes.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, max_files: usize) -> Result<Self> {
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .context("Failed to open log file")?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == 0 {
+            self.file.set_len(0)?;
+        } else {
+            let _ = fs::remove_file(self.rotated_path(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let _ = fs::rename(self.rotated_path(index), self.rotated_path(index + 1));
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = File::options().create(true).append(true).open(&self.path)?;
+        }
+
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.max_size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+struct Logger {
+    filter: RwLock<Filter>,
+    file: Mutex<RotatingFile>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter
+            .read()
+            .is_ok_and(|filter| metadata.level() <= filter.level_for(metadata.target()))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            record.level(),
+            record.args()
+        );
+
+        let _ = io::stdout().write_all(line.as_bytes());
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_line(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+pub fn get_log_file_path() -> Result<PathBuf> {
+    let home = env::var("HOME").context("Failed to get HOME directory")?;
+
+    Ok(PathBuf::from(home).join(".cache/velowm/velowm.log"))
+}
+
+/// Installs the global logger. `RUST_LOG` takes precedence over the
+/// configured levels when it is set.
+pub fn init(config: &LoggingConfig) -> Result<()> {
+    let spec = env::var("RUST_LOG").unwrap_or_else(|_| config.filter_spec());
+    let filter = Filter::parse(&spec)?;
+
+    let path = get_log_file_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create log directory")?;
+    }
+    let file = RotatingFile::open(path, config.max_size_kb * 1024, config.max_files)?;
+
+    log::set_max_level(filter.max_level());
+    let logger = LOGGER.get_or_init(|| Logger {
+        filter: RwLock::new(filter),
+        file: Mutex::new(file),
+    });
+    log::set_logger(logger).map_err(|e| anyhow!("Failed to install logger: {}", e))
+}
+
+/// Replaces the log filter at runtime, e.g. `debug` or `info,velowm::ui=trace`.
+pub fn set_filter(spec: &str) -> Result<()> {
+    let logger = LOGGER.get().ok_or_else(|| anyhow!("Logging is disabled"))?;
+    let filter = Filter::parse(spec)?;
+
+    log::set_max_level(filter.max_level());
+    *logger
+        .filter
+        .write()
+        .map_err(|_| anyhow!("Log filter lock poisoned"))? = filter;
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::{
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    path::Path,
+    sync::OnceLock,
+};
+
+/// Decodes and encodes images. It is opened the first time it is needed,
+/// so velowm still runs where it isn't installed.
+const PIXBUF_LIBRARY: &CStr = c"libgdk_pixbuf-2.0.so.0";
+
+/// `GDK_INTERP_BILINEAR`
+const INTERP_BILINEAR: c_int = 2;
+
+/// `GDK_COLORSPACE_RGB`
+const COLORSPACE_RGB: c_int = 0;
+
+#[repr(C)]
+struct GError {
+    domain: u32,
+    code: c_int,
+    message: *const c_char,
+}
+
+type Pixbuf = c_void;
+
+/// The few gdk-pixbuf functions used here.
+struct PixbufApi {
+    new: unsafe extern "C" fn(c_int, c_int, c_int, c_int, c_int) -> *mut Pixbuf,
+    new_from_file: unsafe extern "C" fn(*const c_char, *mut *mut GError) -> *mut Pixbuf,
+    savev: unsafe extern "C" fn(
+        *mut Pixbuf,
+        *const c_char,
+        *const c_char,
+        *mut *mut c_char,
+        *mut *mut c_char,
+        *mut *mut GError,
+    ) -> c_int,
+    scale_simple: unsafe extern "C" fn(*const Pixbuf, c_int, c_int, c_int) -> *mut Pixbuf,
+    get_width: unsafe extern "C" fn(*const Pixbuf) -> c_int,
+    get_height: unsafe extern "C" fn(*const Pixbuf) -> c_int,
+    get_rowstride: unsafe extern "C" fn(*const Pixbuf) -> c_int,
+    get_n_channels: unsafe extern "C" fn(*const Pixbuf) -> c_int,
+    get_has_alpha: unsafe extern "C" fn(*const Pixbuf) -> c_int,
+    get_pixels: unsafe extern "C" fn(*const Pixbuf) -> *mut u8,
+    object_unref: unsafe extern "C" fn(*mut c_void),
+    error_free: unsafe extern "C" fn(*mut GError),
+}
+
+impl PixbufApi {
+    fn get() -> Result<&'static Self> {
+        static API: OnceLock<Result<PixbufApi, String>> = OnceLock::new();
+        API.get_or_init(|| unsafe { Self::load() })
+            .as_ref()
+            .map_err(|e| anyhow!("{}", e))
+    }
+
+    unsafe fn load() -> Result<Self, String> {
+        let library = libc::dlopen(PIXBUF_LIBRARY.as_ptr(), libc::RTLD_NOW);
+        if library.is_null() {
+            return Err(format!(
+                "Images need gdk-pixbuf, failed to load {}",
+                PIXBUF_LIBRARY.to_string_lossy()
+            ));
+        }
+
+        unsafe fn symbol<T>(library: *mut c_void, name: &CStr) -> Result<T, String> {
+            let symbol = libc::dlsym(library, name.as_ptr());
+            if symbol.is_null() {
+                return Err(format!("gdk-pixbuf has no {}", name.to_string_lossy()));
+            }
+            Ok(std::mem::transmute_copy(&symbol))
+        }
+
+        Ok(Self {
+            new: symbol(library, c"gdk_pixbuf_new")?,
+            new_from_file: symbol(library, c"gdk_pixbuf_new_from_file")?,
+            savev: symbol(library, c"gdk_pixbuf_savev")?,
+            scale_simple: symbol(library, c"gdk_pixbuf_scale_simple")?,
+            get_width: symbol(library, c"gdk_pixbuf_get_width")?,
+            get_height: symbol(library, c"gdk_pixbuf_get_height")?,
+            get_rowstride: symbol(library, c"gdk_pixbuf_get_rowstride")?,
+            get_n_channels: symbol(library, c"gdk_pixbuf_get_n_channels")?,
+            get_has_alpha: symbol(library, c"gdk_pixbuf_get_has_alpha")?,
+            get_pixels: symbol(library, c"gdk_pixbuf_get_pixels")?,
+            object_unref: symbol(library, c"g_object_unref")?,
+            error_free: symbol(library, c"g_error_free")?,
+        })
+    }
+}
+
+/// Takes the message out of a `GError`, which names the file already.
+unsafe fn error_message(api: &PixbufApi, error: *mut GError) -> Option<String> {
+    if error.is_null() {
+        return None;
+    }
+    let message = CStr::from_ptr((*error).message)
+        .to_string_lossy()
+        .into_owned();
+    (api.error_free)(error);
+    Some(message)
+}
+
+/// An image in memory, freed on drop.
+pub struct Image {
+    api: &'static PixbufApi,
+    raw: *mut Pixbuf,
+}
+
+impl Image {
+    pub fn open(path: &Path) -> Result<Self> {
+        let api = PixbufApi::get()?;
+        let name = CString::new(path.as_os_str().as_encoded_bytes())
+            .with_context(|| format!("Invalid path {}", path.display()))?;
+
+        unsafe {
+            let mut error = std::ptr::null_mut();
+            let raw = (api.new_from_file)(name.as_ptr(), &mut error);
+            if raw.is_null() {
+                let message = error_message(api, error);
+                bail!(message.unwrap_or_else(|| format!("Failed to load {}", path.display())));
+            }
+            Ok(Self { api, raw })
+        }
+    }
+
+    /// Creates an image from `rgb`, three bytes per pixel and row after row.
+    pub fn from_rgb(width: u32, height: u32, rgb: &[u8]) -> Result<Self> {
+        let api = PixbufApi::get()?;
+        let row = width as usize * 3;
+        if width == 0 || height == 0 || rgb.len() != row * height as usize {
+            bail!("Invalid image data for {}x{}", width, height);
+        }
+
+        unsafe {
+            let raw = (api.new)(COLORSPACE_RGB, 0, 8, width as c_int, height as c_int);
+            if raw.is_null() {
+                bail!("Failed to create a {}x{} image", width, height);
+            }
+            let stride = (api.get_rowstride)(raw) as usize;
+            let pixels = (api.get_pixels)(raw);
+            for (y, line) in rgb.chunks_exact(row).enumerate() {
+                std::ptr::copy_nonoverlapping(line.as_ptr(), pixels.add(y * stride), row);
+            }
+            Ok(Self { api, raw })
+        }
+    }
+
+    /// Writes the image to `path` as PNG.
+    pub fn save_png(&self, path: &Path) -> Result<()> {
+        let name = CString::new(path.as_os_str().as_encoded_bytes())
+            .with_context(|| format!("Invalid path {}", path.display()))?;
+
+        unsafe {
+            let mut error = std::ptr::null_mut();
+            let saved = (self.api.savev)(
+                self.raw,
+                name.as_ptr(),
+                c"png".as_ptr(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut error,
+            );
+            if saved == 0 {
+                let message = error_message(self.api, error);
+                bail!(message.unwrap_or_else(|| format!("Failed to save {}", path.display())));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        unsafe {
+            (
+                (self.api.get_width)(self.raw) as u32,
+                (self.api.get_height)(self.raw) as u32,
+            )
+        }
+    }
+
+    pub fn scale(&self, width: u32, height: u32) -> Result<Self> {
+        let raw = unsafe {
+            (self.api.scale_simple)(self.raw, width as c_int, height as c_int, INTERP_BILINEAR)
+        };
+        if raw.is_null() {
+            bail!("Failed to scale the image to {}x{}", width, height);
+        }
+        Ok(Self { api: self.api, raw })
+    }
+
+    pub fn pixels(&self) -> Pixels<'_> {
+        unsafe {
+            let (width, height) = self.size();
+            let stride = (self.api.get_rowstride)(self.raw) as usize;
+            let channels = (self.api.get_n_channels)(self.raw) as usize;
+            // The last row is only as long as its pixels.
+            let len = stride * (height as usize - 1) + width as usize * channels;
+            Pixels {
+                data: std::slice::from_raw_parts((self.api.get_pixels)(self.raw), len),
+                stride,
+                channels,
+                alpha: (self.api.get_has_alpha)(self.raw) != 0,
+            }
+        }
+    }
+}
+
+/// The 8-bit RGB or RGBA samples of an [`Image`].
+pub struct Pixels<'a> {
+    data: &'a [u8],
+    stride: usize,
+    channels: usize,
+    alpha: bool,
+}
+
+impl Pixels<'_> {
+    /// Red, green and blue of the pixel at `x`, `y`, over black where the
+    /// image is transparent.
+    pub fn rgb(&self, x: u32, y: u32) -> [u8; 3] {
+        let start = y as usize * self.stride + x as usize * self.channels;
+        let pixel = &self.data[start..start + self.channels];
+        let rgb = [pixel[0], pixel[1], pixel[2]];
+        if !self.alpha {
+            return rgb;
+        }
+        rgb.map(|c| (c as u32 * pixel[3] as u32 / 0xFF) as u8)
+    }
+}
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        unsafe { (self.api.object_unref)(self.raw) };
+    }
+}
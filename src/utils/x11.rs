@@ -1,40 +1,512 @@
 use anyhow::{anyhow, Result};
-use std::{env, ffi::CString};
-use x11::xlib;
+use std::{
+    env,
+    ffi::{c_char, CStr, CString},
+};
+use x11::{xinerama, xlib, xrandr};
+
+use crate::input::hot_corner::Corner;
 
 pub struct Display {
     raw: *mut xlib::Display,
+    atoms: Atoms,
+}
+
+macro_rules! atoms {
+    ($($field:ident => $name:literal,)*) => {
+        /// Every atom the window manager uses, interned once per connection.
+        #[derive(Clone, Copy, Debug)]
+        pub struct Atoms {
+            $(pub $field: xlib::Atom,)*
+        }
+
+        impl Atoms {
+            const NAMES: &'static [&'static CStr] = &[$($name,)*];
+
+            /// Interns all atoms in a single round trip.
+            ///
+            /// # Safety
+            /// The display pointer must be valid.
+            pub unsafe fn intern(display: *mut xlib::Display) -> Self {
+                let mut names = Self::NAMES
+                    .iter()
+                    .map(|name| name.as_ptr() as *mut c_char)
+                    .collect::<Vec<_>>();
+                let mut atoms = vec![0; names.len()];
+                xlib::XInternAtoms(
+                    display,
+                    names.as_mut_ptr(),
+                    names.len() as i32,
+                    0,
+                    atoms.as_mut_ptr(),
+                );
+
+                let mut atoms = atoms.into_iter();
+                Self {
+                    $($field: atoms.next().unwrap_or(0),)*
+                }
+            }
+        }
+    };
+}
+
+atoms! {
+    utf8_string => c"UTF8_STRING",
+    wm_protocols => c"WM_PROTOCOLS",
+    wm_delete_window => c"WM_DELETE_WINDOW",
+    wm_take_focus => c"WM_TAKE_FOCUS",
+    net_supported => c"_NET_SUPPORTED",
+    net_supporting_wm_check => c"_NET_SUPPORTING_WM_CHECK",
+    net_active_window => c"_NET_ACTIVE_WINDOW",
+    net_close_window => c"_NET_CLOSE_WINDOW",
+    net_current_desktop => c"_NET_CURRENT_DESKTOP",
+    net_number_of_desktops => c"_NET_NUMBER_OF_DESKTOPS",
+    net_desktop_names => c"_NET_DESKTOP_NAMES",
+    net_wm_desktop => c"_NET_WM_DESKTOP",
+    net_wm_fullscreen_monitors => c"_NET_WM_FULLSCREEN_MONITORS",
+    net_wm_name => c"_NET_WM_NAME",
+    net_wm_moveresize => c"_NET_WM_MOVERESIZE",
+    net_wm_pid => c"_NET_WM_PID",
+    net_wm_user_time => c"_NET_WM_USER_TIME",
+    net_wm_user_time_window => c"_NET_WM_USER_TIME_WINDOW",
+    net_wm_state => c"_NET_WM_STATE",
+    net_wm_strut => c"_NET_WM_STRUT",
+    net_wm_strut_partial => c"_NET_WM_STRUT_PARTIAL",
+    net_wm_state_above => c"_NET_WM_STATE_ABOVE",
+    net_wm_state_fullscreen => c"_NET_WM_STATE_FULLSCREEN",
+    net_wm_window_type => c"_NET_WM_WINDOW_TYPE",
+    net_wm_window_type_dock => c"_NET_WM_WINDOW_TYPE_DOCK",
+    net_wm_window_type_dialog => c"_NET_WM_WINDOW_TYPE_DIALOG",
+    net_wm_window_type_utility => c"_NET_WM_WINDOW_TYPE_UTILITY",
+    net_wm_window_type_splash => c"_NET_WM_WINDOW_TYPE_SPLASH",
+    net_wm_window_type_tooltip => c"_NET_WM_WINDOW_TYPE_TOOLTIP",
+    net_wm_window_type_popup_menu => c"_NET_WM_WINDOW_TYPE_POPUP_MENU",
+    net_wm_window_type_dropdown_menu => c"_NET_WM_WINDOW_TYPE_DROPDOWN_MENU",
+    net_wm_window_type_notification => c"_NET_WM_WINDOW_TYPE_NOTIFICATION",
+    net_startup_id => c"_NET_STARTUP_ID",
+    net_startup_info_begin => c"_NET_STARTUP_INFO_BEGIN",
+    net_startup_info => c"_NET_STARTUP_INFO",
+    xrootpmap_id => c"_XROOTPMAP_ID",
+    esetroot_pmap_id => c"ESETROOT_PMAP_ID",
+    clipboard => c"CLIPBOARD",
+    clipboard_manager => c"CLIPBOARD_MANAGER",
+    save_targets => c"SAVE_TARGETS",
+    targets => c"TARGETS",
+    multiple => c"MULTIPLE",
+    timestamp => c"TIMESTAMP",
+    incr => c"INCR",
+    delete => c"DELETE",
+    insert_selection => c"INSERT_SELECTION",
+    insert_property => c"INSERT_PROPERTY",
+    velowm_selection => c"_VELOWM_SELECTION",
+}
+
+/// Element type of a window property: `u8` for format 8 and `u64` for
+/// format 32, which Xlib hands out as C longs whatever the data type.
+pub trait PropertyData: Copy {
+    const FORMAT: i32;
+}
+
+impl PropertyData for u8 {
+    const FORMAT: i32 = 8;
+}
+
+impl PropertyData for u64 {
+    const FORMAT: i32 = 32;
+}
+
+/// Longest property read, in 32-bit units.
+const MAX_PROPERTY_LENGTH: i64 = 1024;
+
+/// `XEventsQueued` mode that reads what the server has sent without
+/// blocking, missing from the x11 crate.
+pub const QUEUED_AFTER_READING: i32 = 1;
+
+/// Pointer position relative to the root window.
+#[derive(Clone, Copy, Debug)]
+pub struct Pointer {
+    pub x: i32,
+    pub y: i32,
+    /// The top-level window under the pointer, 0 if none.
+    pub child: xlib::Window,
+    pub mask: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Monitor {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Monitor {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && x < self.x + self.width as i32
+            && y >= self.y
+            && y < self.y + self.height as i32
+    }
+
+    pub fn intersects(&self, other: &Monitor) -> bool {
+        self.x < other.x + other.width as i32
+            && other.x < self.x + self.width as i32
+            && self.y < other.y + other.height as i32
+            && other.y < self.y + self.height as i32
+    }
+
+    /// Where a `width`x`height` box goes to sit in the middle.
+    pub fn center(&self, width: u32, height: u32) -> (i32, i32) {
+        (
+            self.x + (self.width as i32 - width as i32) / 2,
+            self.y + (self.height as i32 - height as i32) / 2,
+        )
+    }
+
+    /// Where a `width`x`height` box goes to sit in `corner`, `margin`
+    /// pixels from the edges.
+    pub fn corner(&self, corner: Corner, width: u32, height: u32, margin: u32) -> (i32, i32) {
+        let left = self.x + margin as i32;
+        let top = self.y + margin as i32;
+        let right = self.x + self.width as i32 - (width + margin) as i32;
+        let bottom = self.y + self.height as i32 - (height + margin) as i32;
+        match corner {
+            Corner::TopLeft => (left, top),
+            Corner::TopRight => (right, top),
+            Corner::BottomLeft => (left, bottom),
+            Corner::BottomRight => (right, bottom),
+        }
+    }
+
+    pub fn union(&self, other: &Monitor) -> Monitor {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width as i32).max(other.x + other.width as i32);
+        let bottom = (self.y + self.height as i32).max(other.y + other.height as i32);
+
+        Monitor {
+            x,
+            y,
+            width: (right - x) as u32,
+            height: (bottom - y) as u32,
+        }
+    }
+}
+
+/// Visual, depth and colormap for the windows velowm draws itself.
+#[derive(Clone, Copy)]
+pub struct WindowVisual {
+    pub visual: *mut xlib::Visual,
+    pub depth: i32,
+    pub colormap: xlib::Colormap,
+    /// A 32-bit visual with a colormap created for it.
+    pub argb: bool,
+}
+
+impl WindowVisual {
+    /// Picks a 32-bit TrueColor visual while a compositing manager is
+    /// running, so alpha in background colors shows through, and the
+    /// default visual otherwise.
+    ///
+    /// # Safety
+    /// The display pointer must be valid and point to an active X display connection.
+    pub unsafe fn new(display: *mut xlib::Display) -> Self {
+        let screen = xlib::XDefaultScreen(display);
+        let root = xlib::XRootWindow(display, screen);
+        let default = Self {
+            visual: xlib::XDefaultVisual(display, screen),
+            depth: xlib::XDefaultDepth(display, screen),
+            colormap: xlib::XDefaultColormap(display, screen),
+            argb: false,
+        };
+
+        let selection = CString::new(format!("_NET_WM_CM_S{}", screen)).unwrap_or_default();
+        let selection = xlib::XInternAtom(display, selection.as_ptr(), 0);
+        if xlib::XGetSelectionOwner(display, selection) == 0 {
+            return default;
+        }
+
+        let mut info: xlib::XVisualInfo = std::mem::zeroed();
+        if xlib::XMatchVisualInfo(display, screen, 32, xlib::TrueColor, &mut info) == 0 {
+            return default;
+        }
+
+        Self {
+            visual: info.visual,
+            depth: info.depth,
+            colormap: xlib::XCreateColormap(display, root, info.visual, xlib::AllocNone),
+            argb: true,
+        }
+    }
+
+    /// Creates an unmapped window with this visual.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection.
+    /// - The parent must be a valid window ID for the given display.
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn create_window(
+        &self,
+        display: *mut xlib::Display,
+        parent: xlib::Window,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        border_width: u32,
+        border: u64,
+        background: u64,
+    ) -> xlib::Window {
+        let mut attrs: xlib::XSetWindowAttributes = std::mem::zeroed();
+        attrs.colormap = self.colormap;
+        attrs.border_pixel = border;
+        attrs.background_pixel = background;
+
+        xlib::XCreateWindow(
+            display,
+            parent,
+            x,
+            y,
+            width,
+            height,
+            border_width,
+            self.depth,
+            xlib::InputOutput as u32,
+            self.visual,
+            xlib::CWColormap | xlib::CWBorderPixel | xlib::CWBackPixel,
+            &mut attrs,
+        )
+    }
+
+    /// Frees the colormap created for a 32-bit visual.
+    ///
+    /// # Safety
+    /// The display connection must still be valid and no window may use the colormap anymore.
+    pub unsafe fn free(&self, display: *mut xlib::Display) {
+        if self.argb {
+            xlib::XFreeColormap(display, self.colormap);
+        }
+    }
 }
 
 impl Display {
-    pub fn new() -> Result<Self> {
+    /// Opens the given display, or the one named by `DISPLAY`.
+    pub fn new(display_name: Option<&str>) -> Result<Self> {
+        let display_name = match display_name {
+            Some(name) => name.to_string(),
+            None => env::var("DISPLAY")
+                .map_err(|_| anyhow!("DISPLAY is not set and no display was given"))?,
+        };
+        Self::open(&display_name)
+    }
+
+    pub fn open(display_name: &str) -> Result<Self> {
         unsafe {
             xlib::XSetErrorHandler(Some(Self::error_handler));
         }
 
-        let display_name = env::var("DISPLAY").unwrap_or_else(|_| String::from(":0"));
         let c_display_name =
-            CString::new(display_name).map_err(|_| anyhow!("Invalid DISPLAY variable"))?;
+            CString::new(display_name).map_err(|_| anyhow!("Invalid display name"))?;
         let raw = unsafe { xlib::XOpenDisplay(c_display_name.as_ptr()) };
 
         if raw.is_null() {
-            return Err(anyhow!("Failed to open X display"));
+            return Err(anyhow!(
+                "Cannot open X display '{}': no X server is reachable there",
+                display_name
+            ));
         }
 
-        unsafe {
+        let atoms = unsafe {
             xlib::XSynchronize(raw, 1);
             xlib::XGrabServer(raw);
             xlib::XSync(raw, false as i32);
             xlib::XUngrabServer(raw);
-        }
+            Atoms::intern(raw)
+        };
 
-        Ok(Self { raw })
+        Ok(Self { raw, atoms })
     }
 
     pub fn raw(&self) -> *mut xlib::Display {
         self.raw
     }
 
+    pub fn root(&self) -> xlib::Window {
+        unsafe { xlib::XDefaultRootWindow(self.raw) }
+    }
+
+    pub fn atoms(&self) -> &Atoms {
+        &self.atoms
+    }
+
+    pub fn get_property<T: PropertyData>(
+        &self,
+        window: xlib::Window,
+        property: xlib::Atom,
+        property_type: xlib::Atom,
+    ) -> Option<Vec<T>> {
+        unsafe { get_property(self.raw, window, property, property_type) }
+    }
+
+    pub fn set_property<T: PropertyData>(
+        &self,
+        window: xlib::Window,
+        property: xlib::Atom,
+        property_type: xlib::Atom,
+        data: &[T],
+    ) {
+        unsafe { set_property(self.raw, window, property, property_type, data) }
+    }
+
+    pub fn send_client_message(
+        &self,
+        window: xlib::Window,
+        message_type: xlib::Atom,
+        data: [i64; 5],
+    ) {
+        unsafe { send_client_message(self.raw, window, message_type, data) }
+    }
+
+    pub fn query_pointer(&self) -> Pointer {
+        unsafe { query_pointer(self.raw, self.root()) }
+    }
+
+    pub fn window_attributes(&self, window: xlib::Window) -> Option<xlib::XWindowAttributes> {
+        unsafe {
+            let mut attrs: xlib::XWindowAttributes = std::mem::zeroed();
+            (xlib::XGetWindowAttributes(self.raw, window, &mut attrs) != 0).then_some(attrs)
+        }
+    }
+
+    /// `_NET_WM_NAME`, falling back to `WM_NAME`.
+    pub fn window_title(&self, window: xlib::Window) -> Option<String> {
+        unsafe {
+            get_string_property(self.raw, window, self.atoms.net_wm_name)
+                .or_else(|| get_window_title(self.raw, window))
+        }
+    }
+
+    /// The monitor under the pointer, or the first one.
+    pub fn pointer_monitor(&self) -> Monitor {
+        let pointer = self.query_pointer();
+        let monitors = self.monitors();
+        monitors
+            .iter()
+            .find(|m| m.contains(pointer.x, pointer.y))
+            .copied()
+            .unwrap_or(monitors[0])
+    }
+
+    pub fn sync(&self) {
+        unsafe {
+            xlib::XSync(self.raw, 0);
+        }
+    }
+
+    pub fn monitors(&self) -> Vec<Monitor> {
+        unsafe {
+            let mut num_monitors = 0;
+            let screens = xinerama::XineramaQueryScreens(self.raw, &mut num_monitors);
+
+            if screens.is_null() || num_monitors <= 0 {
+                let screen = xlib::XDefaultScreen(self.raw);
+                return vec![Monitor {
+                    x: 0,
+                    y: 0,
+                    width: xlib::XDisplayWidth(self.raw, screen) as u32,
+                    height: xlib::XDisplayHeight(self.raw, screen) as u32,
+                }];
+            }
+
+            let monitors = std::slice::from_raw_parts(screens, num_monitors as usize)
+                .iter()
+                .map(|screen| Monitor {
+                    x: screen.x_org as i32,
+                    y: screen.y_org as i32,
+                    width: screen.width as u32,
+                    height: screen.height as u32,
+                })
+                .collect();
+            xlib::XFree(screens as *mut _);
+            monitors
+        }
+    }
+
+    /// The RandR monitors by output name, such as "DP-1". Empty without
+    /// RandR 1.5.
+    pub fn outputs(&self) -> Vec<(String, Monitor)> {
+        self.randr_monitors()
+            .into_iter()
+            .map(|(name, monitor, _)| (name, monitor))
+            .collect()
+    }
+
+    /// The monitor of the RandR primary output, or the first monitor when
+    /// no output is primary.
+    pub fn primary_monitor(&self) -> Monitor {
+        self.randr_monitors()
+            .into_iter()
+            .find(|(_, _, primary)| *primary)
+            .map_or_else(|| self.monitors()[0], |(_, monitor, _)| monitor)
+    }
+
+    /// Refresh rate of the screen in Hz, `None` without RandR.
+    pub fn refresh_rate(&self) -> Option<u32> {
+        unsafe {
+            let config = xrandr::XRRGetScreenInfo(self.raw, self.root());
+            if config.is_null() {
+                return None;
+            }
+            let rate = xrandr::XRRConfigCurrentRate(config);
+            xrandr::XRRFreeScreenConfigInfo(config);
+            (rate > 0).then_some(rate as u32)
+        }
+    }
+
+    /// The RandR monitors with their output name and whether they are the
+    /// primary one.
+    fn randr_monitors(&self) -> Vec<(String, Monitor, bool)> {
+        unsafe {
+            let (mut event_base, mut error_base) = (0, 0);
+            let (mut major, mut minor) = (0, 0);
+            if xrandr::XRRQueryExtension(self.raw, &mut event_base, &mut error_base) == 0
+                || xrandr::XRRQueryVersion(self.raw, &mut major, &mut minor) == 0
+                || (major, minor) < (1, 5)
+            {
+                return Vec::new();
+            }
+
+            let mut count = 0;
+            let monitors = xrandr::XRRGetMonitors(self.raw, self.root(), 1, &mut count);
+            if monitors.is_null() {
+                return Vec::new();
+            }
+            let outputs = std::slice::from_raw_parts(monitors, count.max(0) as usize)
+                .iter()
+                .filter_map(|monitor| {
+                    let name = xlib::XGetAtomName(self.raw, monitor.name);
+                    if name.is_null() {
+                        return None;
+                    }
+                    let output = CStr::from_ptr(name).to_string_lossy().into_owned();
+                    xlib::XFree(name as *mut _);
+                    Some((
+                        output,
+                        Monitor {
+                            x: monitor.x,
+                            y: monitor.y,
+                            width: monitor.width as u32,
+                            height: monitor.height as u32,
+                        },
+                        monitor.primary != 0,
+                    ))
+                })
+                .collect();
+            xrandr::XRRFreeMonitors(monitors);
+            outputs
+        }
+    }
+
     unsafe extern "C" fn error_handler(
         display: *mut xlib::Display,
         e: *mut xlib::XErrorEvent,
@@ -63,6 +535,431 @@ impl Display {
     }
 }
 
+/// Gives keyboard focus to a client following the ICCCM input models: the
+/// input hint decides whether `XSetInputFocus` is used, and clients that
+/// advertise `WM_TAKE_FOCUS` are asked to take focus themselves.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn set_focus(display: *mut xlib::Display, atoms: &Atoms, window: xlib::Window) {
+    let accepts_input = input_hint(display, window);
+    let takes_focus = supports_protocol(display, window, atoms.wm_take_focus);
+
+    if accepts_input {
+        xlib::XSetInputFocus(
+            display,
+            window,
+            xlib::RevertToPointerRoot,
+            xlib::CurrentTime,
+        );
+    }
+
+    if takes_focus {
+        send_protocol(display, atoms, window, atoms.wm_take_focus);
+    }
+}
+
+/// The input hint of `WM_HINTS`, true when the client doesn't set one.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+unsafe fn input_hint(display: *mut xlib::Display, window: xlib::Window) -> bool {
+    let hints = xlib::XGetWMHints(display, window);
+    if hints.is_null() {
+        return true;
+    }
+    let input = (*hints).flags & xlib::InputHint == 0 || (*hints).input != 0;
+    xlib::XFree(hints as *mut _);
+    input
+}
+
+/// Whether the client takes keyboard input in any of the ICCCM input
+/// models. Ones that don't, like on-screen keyboards, are never focused.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn takes_input(
+    display: *mut xlib::Display,
+    atoms: &Atoms,
+    window: xlib::Window,
+) -> bool {
+    input_hint(display, window) || supports_protocol(display, window, atoms.wm_take_focus)
+}
+
+/// Checks whether the window lists the given atom in `WM_PROTOCOLS`.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn supports_protocol(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    protocol: xlib::Atom,
+) -> bool {
+    let mut protocols: *mut xlib::Atom = std::ptr::null_mut();
+    let mut num_protocols: i32 = 0;
+
+    if xlib::XGetWMProtocols(display, window, &mut protocols, &mut num_protocols) == 0 {
+        return false;
+    }
+
+    let supported = !protocols.is_null()
+        && std::slice::from_raw_parts(protocols, num_protocols as usize).contains(&protocol);
+    if !protocols.is_null() {
+        xlib::XFree(protocols as *mut _);
+    }
+    supported
+}
+
+/// Sends a `WM_PROTOCOLS` client message with the given protocol atom.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn send_protocol(
+    display: *mut xlib::Display,
+    atoms: &Atoms,
+    window: xlib::Window,
+    protocol: xlib::Atom,
+) {
+    send_client_message(
+        display,
+        window,
+        atoms.wm_protocols,
+        [protocol as i64, xlib::CurrentTime as i64, 0, 0, 0],
+    );
+}
+
+/// Sends a 32-bit format client message to the window.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn send_client_message(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    message_type: xlib::Atom,
+    data: [i64; 5],
+) {
+    let mut message_data: xlib::ClientMessageData = std::mem::zeroed();
+    for (i, value) in data.into_iter().enumerate() {
+        message_data.set_long(i, value);
+    }
+
+    let mut event = xlib::XEvent {
+        client_message: xlib::XClientMessageEvent {
+            type_: xlib::ClientMessage,
+            serial: 0,
+            send_event: 1,
+            display,
+            window,
+            message_type,
+            format: 32,
+            data: message_data,
+        },
+    };
+    xlib::XSendEvent(display, window, 0, xlib::NoEventMask, &mut event);
+}
+
+/// Tells a client its current geometry with a synthetic `ConfigureNotify`,
+/// as ICCCM requires when a configure request is not honored.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn send_configure_notify(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    border_width: u32,
+) {
+    let mut event = xlib::XEvent {
+        configure: xlib::XConfigureEvent {
+            type_: xlib::ConfigureNotify,
+            serial: 0,
+            send_event: 1,
+            display,
+            event: window,
+            window,
+            x,
+            y,
+            width: width as i32,
+            height: height as i32,
+            border_width: border_width as i32,
+            above: 0,
+            override_redirect: 0,
+        },
+    };
+    xlib::XSendEvent(display, window, 0, xlib::StructureNotifyMask, &mut event);
+}
+
+/// Reads a property of the given type and format. Returns `None` when it is
+/// missing or has another format.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn get_property<T: PropertyData>(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    property: xlib::Atom,
+    property_type: xlib::Atom,
+) -> Option<Vec<T>> {
+    let mut actual_type: xlib::Atom = 0;
+    let mut actual_format: i32 = 0;
+    let mut nitems: u64 = 0;
+    let mut bytes_after: u64 = 0;
+    let mut prop: *mut u8 = std::ptr::null_mut();
+
+    if xlib::XGetWindowProperty(
+        display,
+        window,
+        property,
+        0,
+        MAX_PROPERTY_LENGTH,
+        0,
+        property_type,
+        &mut actual_type,
+        &mut actual_format,
+        &mut nitems,
+        &mut bytes_after,
+        &mut prop,
+    ) != 0
+        || prop.is_null()
+    {
+        return None;
+    }
+
+    let values = (actual_format == T::FORMAT)
+        .then(|| std::slice::from_raw_parts(prop as *const T, nitems as usize).to_vec());
+    xlib::XFree(prop as *mut _);
+    values
+}
+
+/// Replaces a property with the given values.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn set_property<T: PropertyData>(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    property: xlib::Atom,
+    property_type: xlib::Atom,
+    data: &[T],
+) {
+    xlib::XChangeProperty(
+        display,
+        window,
+        property,
+        property_type,
+        T::FORMAT,
+        xlib::PropModeReplace,
+        data.as_ptr() as *const u8,
+        data.len() as i32,
+    );
+}
+
+/// Returns where the pointer is on the screen of the given root window.
+///
+/// # Safety
+/// The display pointer must be valid and the window must be its root window.
+pub unsafe fn query_pointer(display: *mut xlib::Display, root: xlib::Window) -> Pointer {
+    let mut root_return: xlib::Window = 0;
+    let mut child: xlib::Window = 0;
+    let mut x = 0;
+    let mut y = 0;
+    let mut win_x = 0;
+    let mut win_y = 0;
+    let mut mask = 0;
+    xlib::XQueryPointer(
+        display,
+        root,
+        &mut root_return,
+        &mut child,
+        &mut x,
+        &mut y,
+        &mut win_x,
+        &mut win_y,
+        &mut mask,
+    );
+
+    Pointer { x, y, child, mask }
+}
+
+/// Returns the `(instance, class)` pair from `WM_CLASS`.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn get_class_hint(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+) -> Option<(String, String)> {
+    let mut hint: xlib::XClassHint = std::mem::zeroed();
+    if xlib::XGetClassHint(display, window, &mut hint) == 0 {
+        return None;
+    }
+
+    let take = |ptr: *mut i8| {
+        if ptr.is_null() {
+            String::new()
+        } else {
+            let value = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+            xlib::XFree(ptr as *mut _);
+            value
+        }
+    };
+
+    Some((take(hint.res_name), take(hint.res_class)))
+}
+
+/// The window a dialog or other transient window belongs to.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn get_transient_for(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+) -> Option<xlib::Window> {
+    let mut owner: xlib::Window = 0;
+    (xlib::XGetTransientForHint(display, window, &mut owner) != 0 && owner != 0).then_some(owner)
+}
+
+/// The parts of `WM_NORMAL_HINTS` that limit interactive resizing.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SizeHints {
+    pub min: Option<(u32, u32)>,
+    pub max: Option<(u32, u32)>,
+    /// Size the increments count from, the minimum size when not set.
+    pub base: (u32, u32),
+    /// Step between sizes, e.g. one character cell in a terminal.
+    pub increment: (u32, u32),
+}
+
+impl SizeHints {
+    /// The closest size to `width`x`height` the client accepts: within the
+    /// minimum and maximum, and a whole number of increments past the base.
+    pub fn constrain(&self, width: u32, height: u32) -> (u32, u32) {
+        let (min_width, min_height) = self.min.unwrap_or((1, 1));
+        let (max_width, max_height) = self.max.unwrap_or((u32::MAX, u32::MAX));
+
+        let step = |size: u32, min: u32, max: u32, base: u32, increment: u32| {
+            let size = size.clamp(min, max.max(min));
+            if increment <= 1 || size < base {
+                return size;
+            }
+            let stepped = base + (size - base) / increment * increment;
+            if stepped < min {
+                stepped + increment
+            } else {
+                stepped
+            }
+        };
+
+        (
+            step(width, min_width, max_width, self.base.0, self.increment.0),
+            step(
+                height,
+                min_height,
+                max_height,
+                self.base.1,
+                self.increment.1,
+            ),
+        )
+    }
+
+    /// The size in increments, such as `80x24` cells for a terminal. `None`
+    /// when the client resizes in pixels.
+    pub fn cells(&self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (width_inc, height_inc) = self.increment;
+        if width_inc <= 1 && height_inc <= 1 {
+            return None;
+        }
+        Some((
+            width.saturating_sub(self.base.0) / width_inc.max(1),
+            height.saturating_sub(self.base.1) / height_inc.max(1),
+        ))
+    }
+}
+
+/// Reads `WM_NORMAL_HINTS`. Clients without them get unrestricted hints.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn get_size_hints(display: *mut xlib::Display, window: xlib::Window) -> SizeHints {
+    let mut hints: xlib::XSizeHints = std::mem::zeroed();
+    let mut supplied = 0;
+    if xlib::XGetWMNormalHints(display, window, &mut hints, &mut supplied) == 0 {
+        return SizeHints::default();
+    }
+
+    let size = |width: i32, height: i32| (width.max(0) as u32, height.max(0) as u32);
+    let min = (hints.flags & xlib::PMinSize != 0).then(|| size(hints.min_width, hints.min_height));
+    let max = (hints.flags & xlib::PMaxSize != 0)
+        .then(|| size(hints.max_width, hints.max_height))
+        .filter(|&(width, height)| width > 0 && height > 0);
+    let base = if hints.flags & xlib::PBaseSize != 0 {
+        size(hints.base_width, hints.base_height)
+    } else {
+        min.unwrap_or((0, 0))
+    };
+    let increment = if hints.flags & xlib::PResizeInc != 0 {
+        size(hints.width_inc, hints.height_inc)
+    } else {
+        (1, 1)
+    };
+
+    SizeHints {
+        min,
+        max,
+        base,
+        increment,
+    }
+}
+
+/// Returns the window title from `WM_NAME`.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn get_window_title(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+) -> Option<String> {
+    let mut name: *mut i8 = std::ptr::null_mut();
+    if xlib::XFetchName(display, window, &mut name) == 0 || name.is_null() {
+        return None;
+    }
+
+    let title = CStr::from_ptr(name).to_string_lossy().into_owned();
+    xlib::XFree(name as *mut _);
+    Some(title)
+}
+
+/// Reads a text property such as `_NET_STARTUP_ID`, whatever its string type.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn get_string_property(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    property: xlib::Atom,
+) -> Option<String> {
+    get_property::<u8>(display, window, property, xlib::AnyPropertyType as u64)
+        .filter(|bytes| !bytes.is_empty())
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Returns the process id a client advertises through `_NET_WM_PID`.
+///
+/// # Safety
+/// The display pointer must be valid and the window must belong to it.
+pub unsafe fn get_window_pid(
+    display: *mut xlib::Display,
+    atoms: &Atoms,
+    window: xlib::Window,
+) -> Option<u32> {
+    get_property::<u64>(display, window, atoms.net_wm_pid, xlib::XA_CARDINAL)?
+        .first()
+        .map(|&pid| pid as u32)
+}
+
 impl Drop for Display {
     fn drop(&mut self) {
         unsafe {
@@ -0,0 +1,92 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::{
+    env,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Carries the running server across a restart: `<pid> <display>`.
+const SERVER_ENV: &str = "VELOWM_EMBEDDED_SERVER";
+
+/// How long Xephyr gets to create its socket.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A nested Xephyr server for `velowm --embedded`, killed when dropped.
+///
+/// The server outlives a restart: the exec'd process finds it through
+/// `VELOWM_EMBEDDED_SERVER` and takes it over instead of starting another.
+pub struct Xephyr {
+    pid: libc::pid_t,
+    pub display: String,
+}
+
+impl Xephyr {
+    /// Starts Xephyr with a `width`x`height` screen on the first free
+    /// display, or takes over the one left behind by a restart.
+    pub fn start(width: u32, height: u32) -> Result<Self> {
+        if let Some(server) = Self::adopt() {
+            return Ok(server);
+        }
+
+        let number = (1..100)
+            .find(|n| {
+                !Path::new(&format!("/tmp/.X11-unix/X{}", n)).exists()
+                    && !Path::new(&format!("/tmp/.X{}-lock", n)).exists()
+            })
+            .ok_or_else(|| anyhow!("No free X display number"))?;
+        let display = format!(":{}", number);
+
+        let child = Command::new("Xephyr")
+            .arg(&display)
+            .args(["-screen", &format!("{}x{}", width, height)])
+            .args(["-resizeable", "-nolisten", "tcp"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to start Xephyr, is it installed?")?;
+        let server = Self {
+            pid: child.id() as libc::pid_t,
+            display,
+        };
+
+        let socket = format!("/tmp/.X11-unix/X{}", number);
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while !Path::new(&socket).exists() {
+            if Instant::now() > deadline || !server.is_running() {
+                bail!("Xephyr did not come up on {}", server.display);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        env::set_var(SERVER_ENV, format!("{} {}", server.pid, server.display));
+        Ok(server)
+    }
+
+    fn adopt() -> Option<Self> {
+        let value = env::var(SERVER_ENV).ok()?;
+        let (pid, display) = value.split_once(' ')?;
+        let server = Self {
+            pid: pid.parse().ok()?,
+            display: display.to_string(),
+        };
+        server.is_running().then_some(server)
+    }
+
+    fn is_running(&self) -> bool {
+        let mut status = 0;
+        unsafe { libc::waitpid(self.pid, &mut status, libc::WNOHANG) == 0 }
+    }
+}
+
+impl Drop for Xephyr {
+    fn drop(&mut self) {
+        unsafe {
+            if libc::kill(self.pid, libc::SIGTERM) == 0 {
+                libc::waitpid(self.pid, std::ptr::null_mut(), 0);
+            }
+        }
+        env::remove_var(SERVER_ENV);
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString},
+};
+use x11::xlib;
+
+use crate::utils::x11::get_property;
+
+/// DPI at which the UI is drawn at its configured pixel sizes.
+pub const BASE_DPI: f64 = 96.0;
+
+/// A value published by an XSETTINGS daemon.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Setting {
+    Integer(i32),
+    String(String),
+    /// Red, green, blue and alpha, 16 bits each.
+    Color([u16; 4]),
+}
+
+/// Parses the contents of the `_XSETTINGS_SETTINGS` property. Returns `None`
+/// when the data is truncated or otherwise malformed.
+pub fn parse_settings(data: &[u8]) -> Option<HashMap<String, Setting>> {
+    let mut reader = Reader {
+        data,
+        pos: 0,
+        big_endian: match *data.first()? {
+            0 => false,
+            1 => true,
+            _ => return None,
+        },
+    };
+
+    reader.skip(8)?;
+    let count = reader.u32()?;
+    let mut settings = HashMap::new();
+    for _ in 0..count {
+        let kind = reader.bytes(2)?[0];
+        let name_len = reader.u16()? as usize;
+        let name = String::from_utf8_lossy(reader.bytes(name_len)?).into_owned();
+        reader.align()?;
+        reader.skip(4)?;
+
+        let value = match kind {
+            0 => Setting::Integer(reader.u32()? as i32),
+            1 => {
+                let len = reader.u32()? as usize;
+                let value = String::from_utf8_lossy(reader.bytes(len)?).into_owned();
+                reader.align()?;
+                Setting::String(value)
+            }
+            2 => {
+                // The wire order is red, blue, green, alpha.
+                let [red, blue, green, alpha] =
+                    [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+                Setting::Color([red, green, blue, alpha])
+            }
+            _ => return None,
+        };
+        settings.insert(name, value);
+    }
+    Some(settings)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn align(&mut self) -> Option<()> {
+        self.skip((4 - self.pos % 4) % 4)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.bytes(2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.bytes(4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+}
+
+/// Parses the `RESOURCE_MANAGER` string that `xrdb` loads, keeping the last
+/// value of each resource name.
+pub fn parse_resources(resources: &str) -> HashMap<String, String> {
+    resources
+        .lines()
+        .filter(|line| !line.starts_with('!'))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Reads the settings of the XSETTINGS daemon running on the default screen,
+/// if there is one.
+///
+/// # Safety
+/// The display pointer must be valid and point to an active X display connection.
+pub unsafe fn read_settings(display: *mut xlib::Display) -> HashMap<String, Setting> {
+    let screen = xlib::XDefaultScreen(display);
+    let Ok(selection) = CString::new(format!("_XSETTINGS_S{}", screen)) else {
+        return HashMap::new();
+    };
+    let selection = xlib::XInternAtom(display, selection.as_ptr(), 0);
+    let owner = xlib::XGetSelectionOwner(display, selection);
+    if owner == 0 {
+        return HashMap::new();
+    }
+
+    let property = xlib::XInternAtom(display, c"_XSETTINGS_SETTINGS".as_ptr(), 0);
+    get_property::<u8>(display, owner, property, property)
+        .and_then(|data| parse_settings(&data))
+        .unwrap_or_default()
+}
+
+/// Reads the resources loaded on the server with `xrdb`.
+///
+/// # Safety
+/// The display pointer must be valid and point to an active X display connection.
+pub unsafe fn read_resources(display: *mut xlib::Display) -> HashMap<String, String> {
+    let resources = xlib::XResourceManagerString(display);
+    if resources.is_null() {
+        return HashMap::new();
+    }
+    parse_resources(&CStr::from_ptr(resources).to_string_lossy())
+}
+
+/// Returns the DPI the desktop asks for, from `Xft/DPI` in XSETTINGS or
+/// the `Xft.dpi` resource.
+///
+/// # Safety
+/// The display pointer must be valid and point to an active X display connection.
+pub unsafe fn dpi(display: *mut xlib::Display) -> Option<f64> {
+    let from_settings = match read_settings(display).get("Xft/DPI") {
+        // XSETTINGS stores the DPI multiplied by 1024.
+        Some(&Setting::Integer(dpi)) if dpi > 0 => Some(dpi as f64 / 1024.0),
+        _ => None,
+    };
+    from_settings.or_else(|| {
+        read_resources(display)
+            .get("Xft.dpi")
+            .and_then(|dpi| dpi.parse::<f64>().ok())
+            .filter(|&dpi| dpi > 0.0)
+    })
+}
+
+/// Colors of the desktop theme, looked up the way X resources are: a
+/// `velowm.` resource first, then the generic `*.name` one.
+pub struct Palette {
+    resources: HashMap<String, String>,
+    /// Colors of the GTK color scheme, by the resource name they stand in for.
+    scheme: HashMap<&'static str, String>,
+}
+
+impl Palette {
+    /// Combines the X resources with the `Gtk/ColorScheme` of an XSETTINGS
+    /// daemon. Resources win where both set a color.
+    pub fn new(settings: &HashMap<String, Setting>, resources: HashMap<String, String>) -> Self {
+        let mut scheme = HashMap::new();
+        if let Some(Setting::String(colors)) = settings.get("Gtk/ColorScheme") {
+            for (name, value) in parse_color_scheme(colors) {
+                let name = match name {
+                    "bg_color" => "background",
+                    "fg_color" => "foreground",
+                    "selected_bg_color" => "color4",
+                    _ => continue,
+                };
+                scheme.insert(name, value.to_string());
+            }
+        }
+        Self { resources, scheme }
+    }
+
+    /// Reads the palette from the server.
+    ///
+    /// # Safety
+    /// The display pointer must be valid and point to an active X display connection.
+    pub unsafe fn read(display: *mut xlib::Display) -> Self {
+        Self::new(&read_settings(display), read_resources(display))
+    }
+
+    /// Looks up `velowm.<key>`, then `*.<name>` and `*<name>`, then the
+    /// color scheme.
+    pub fn get(&self, key: &str, name: &str) -> Option<&str> {
+        [
+            format!("velowm.{}", key),
+            format!("*.{}", name),
+            format!("*{}", name),
+        ]
+        .iter()
+        .find_map(|resource| self.resources.get(resource))
+        .or_else(|| self.scheme.get(name))
+        .map(String::as_str)
+    }
+}
+
+/// Splits a GTK color scheme such as `bg_color:#ffffff\nfg_color:#000000`.
+fn parse_color_scheme(scheme: &str) -> impl Iterator<Item = (&str, &str)> {
+    scheme
+        .split(['\n', ';'])
+        .filter_map(|entry| entry.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+}
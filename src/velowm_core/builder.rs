@@ -0,0 +1,77 @@
+use anyhow::Result;
+use log::warn;
+
+use crate::{
+    config::{loader::Config, watcher::ConfigWatcher},
+    utils::x11::Display,
+};
+
+use super::wm::WindowManager;
+
+/// Sets up a [`WindowManager`] for programs embedding velowm.
+///
+/// Without a display the one in `DISPLAY` is used. Without a config the
+/// user's config file is loaded and watched for changes, like the
+/// `velowm` binary does.
+#[derive(Default)]
+pub struct WindowManagerBuilder {
+    display: Option<String>,
+    config: Option<Config>,
+    bar: Option<bool>,
+    notifications: Option<bool>,
+}
+
+impl WindowManagerBuilder {
+    pub fn display(mut self, name: impl Into<String>) -> Self {
+        self.display = Some(name.into());
+        self
+    }
+
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Shows or hides the bar, whatever the config says.
+    pub fn bar(mut self, enabled: bool) -> Self {
+        self.bar = Some(enabled);
+        self
+    }
+
+    /// Enables or disables notifications, whatever the config says.
+    pub fn notifications(mut self, enabled: bool) -> Self {
+        self.notifications = Some(enabled);
+        self
+    }
+
+    /// Connects to the display and starts managing it.
+    pub fn build(self) -> Result<WindowManager> {
+        let display = Display::new(self.display.as_deref())?;
+
+        let (mut config, load_error, watcher) = match self.config {
+            Some(config) => (config, None, None),
+            None => {
+                let watcher = Config::get_config_path()
+                    .and_then(|path| ConfigWatcher::new(&path))
+                    .map_err(|e| warn!("Config changes won't be picked up: {:#}", e))
+                    .ok();
+                match Config::load() {
+                    Ok(config) => (config, None, watcher),
+                    Err(e) => {
+                        warn!("Failed to load config, using default configuration");
+                        (Config::default(), Some(e), watcher)
+                    }
+                }
+            }
+        };
+
+        if let Some(enabled) = self.bar {
+            config.appearance.bar.enabled = enabled;
+        }
+        if let Some(enabled) = self.notifications {
+            config.notifications_enabled = enabled;
+        }
+
+        WindowManager::init(display, config, load_error, watcher)
+    }
+}
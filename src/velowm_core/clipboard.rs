@@ -0,0 +1,388 @@
+use log::{debug, warn};
+use std::time::{Duration, Instant};
+use x11::xlib;
+
+use crate::{
+    input::event::{SelectionNotify, SelectionRequest},
+    utils::x11::{get_property, set_property, Atoms},
+};
+
+/// How long the clipboard owner may take to answer a conversion before
+/// the save ends with what was stored so far.
+const CONVERT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One conversion of the clipboard, stored the way Xlib returned it.
+struct Target {
+    target: xlib::Atom,
+    kind: xlib::Atom,
+    format: i32,
+    items: usize,
+    /// Format 32 data is an array of C longs, like Xlib expects it back.
+    data: Vec<u8>,
+}
+
+/// A `SAVE_TARGETS` request that is being worked through.
+struct Save {
+    requestor: xlib::Window,
+    property: xlib::Atom,
+    time: xlib::Time,
+    /// Targets still to convert, `None` until the owner listed them.
+    remaining: Option<Vec<xlib::Atom>>,
+    saved: Vec<Target>,
+    /// When the conversion waited for was asked for.
+    waiting_since: Instant,
+}
+
+/// Keeps the clipboard alive after the application that owns it exits.
+///
+/// Owns `CLIPBOARD_MANAGER`, so toolkits that follow the freedesktop
+/// clipboard manager spec convert it to `SAVE_TARGETS` before they quit.
+/// The clipboard is then copied target by target and the manager takes
+/// over `CLIPBOARD`, answering for it until something else is copied.
+pub struct ClipboardManager {
+    display: *mut xlib::Display,
+    atoms: Atoms,
+    window: xlib::Window,
+    targets: Vec<Target>,
+    save: Option<Save>,
+}
+
+impl ClipboardManager {
+    /// Takes `CLIPBOARD_MANAGER`. Returns `None` when another clipboard
+    /// manager holds it.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection.
+    /// - The root window must be a valid window ID for the given display.
+    /// - The caller must ensure the display connection remains valid for the lifetime of the manager.
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        atoms: Atoms,
+    ) -> Option<Self> {
+        if xlib::XGetSelectionOwner(display, atoms.clipboard_manager) != 0 {
+            warn!("Another clipboard manager is running, not taking over");
+            return None;
+        }
+
+        let window = xlib::XCreateSimpleWindow(display, root, -1, -1, 1, 1, 0, 0, 0);
+        xlib::XSetSelectionOwner(display, atoms.clipboard_manager, window, xlib::CurrentTime);
+        if xlib::XGetSelectionOwner(display, atoms.clipboard_manager) != window {
+            warn!("Failed to become the clipboard manager");
+            xlib::XDestroyWindow(display, window);
+            return None;
+        }
+
+        Some(Self {
+            display,
+            atoms,
+            window,
+            targets: Vec::new(),
+            save: None,
+        })
+    }
+
+    /// Answers requests for `CLIPBOARD_MANAGER` and for a clipboard taken
+    /// over. Requests to other owners are ignored.
+    ///
+    /// # Safety
+    /// The display pointer stored in self must still be valid and point to an active X display connection.
+    pub unsafe fn handle_selection_request(&mut self, request: SelectionRequest) {
+        if request.owner != self.window {
+            return;
+        }
+        // Obsolete clients expect the answer in the target itself.
+        let property = match request.property {
+            0 => request.target,
+            property => property,
+        };
+
+        let answered = if request.selection == self.atoms.clipboard_manager {
+            if request.target == self.atoms.save_targets && self.save.is_none() {
+                self.begin_save(request);
+                return;
+            }
+            if request.target == self.atoms.targets {
+                self.reply_targets(request.requestor, property, &[self.atoms.save_targets]);
+            }
+            request.target == self.atoms.targets
+        } else if request.selection == self.atoms.clipboard {
+            let saved: Vec<xlib::Atom> = self.targets.iter().map(|t| t.target).collect();
+            if request.target == self.atoms.targets {
+                self.reply_targets(request.requestor, property, &saved);
+                true
+            } else if let Some(target) = self.targets.iter().find(|t| t.target == request.target) {
+                xlib::XChangeProperty(
+                    self.display,
+                    request.requestor,
+                    property,
+                    target.kind,
+                    target.format,
+                    xlib::PropModeReplace,
+                    target.data.as_ptr(),
+                    target.items as i32,
+                );
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        };
+
+        self.notify(
+            request.requestor,
+            request.selection,
+            request.target,
+            if answered { property } else { 0 },
+            request.time,
+        );
+    }
+
+    /// Collects the clipboard conversions asked for while saving.
+    ///
+    /// # Safety
+    /// The display pointer stored in self must still be valid and point to an active X display connection.
+    pub unsafe fn handle_selection_notify(&mut self, notify: SelectionNotify) {
+        if notify.requestor != self.window {
+            return;
+        }
+        let Some(listed) = self.save.as_ref().map(|save| save.remaining.is_some()) else {
+            return;
+        };
+
+        if !listed {
+            if notify.property == 0 {
+                debug!("Clipboard owner refused to list its targets");
+                self.finish_save();
+                return;
+            }
+            let skipped = [
+                self.atoms.targets,
+                self.atoms.multiple,
+                self.atoms.timestamp,
+                self.atoms.save_targets,
+                self.atoms.delete,
+                self.atoms.insert_selection,
+                self.atoms.insert_property,
+            ];
+            let targets =
+                get_property::<u64>(self.display, self.window, notify.property, xlib::XA_ATOM)
+                    .unwrap_or_default();
+            if let Some(save) = &mut self.save {
+                save.remaining = Some(
+                    targets
+                        .into_iter()
+                        .filter(|target| !skipped.contains(target))
+                        .collect(),
+                );
+            }
+        } else if notify.property != 0 {
+            let target = self.read_target(notify.target, notify.property);
+            if let (Some(save), Some(target)) = (&mut self.save, target) {
+                save.saved.push(target);
+            }
+        }
+        xlib::XDeleteProperty(self.display, self.window, self.atoms.velowm_selection);
+
+        self.convert_next();
+    }
+
+    /// Time left until the owner has to answer the conversion the save
+    /// waits for, if one is running.
+    pub fn timeout(&self) -> Option<Duration> {
+        let save = self.save.as_ref()?;
+        Some((save.waiting_since + CONVERT_TIMEOUT).saturating_duration_since(Instant::now()))
+    }
+
+    /// Ends a save whose owner exited or stopped answering, so later
+    /// `SAVE_TARGETS` requests aren't refused for good.
+    ///
+    /// # Safety
+    /// The display pointer stored in self must still be valid and point to an active X display connection.
+    pub unsafe fn expire(&mut self) {
+        if self
+            .save
+            .as_ref()
+            .is_some_and(|save| save.waiting_since.elapsed() >= CONVERT_TIMEOUT)
+        {
+            debug!("Clipboard owner stopped answering, ending the save");
+            self.finish_save();
+        }
+    }
+
+    /// Drops the saved clipboard once another client copies something.
+    pub fn handle_selection_clear(&mut self, window: xlib::Window, selection: xlib::Atom) {
+        if window != self.window {
+            return;
+        }
+        if selection == self.atoms.clipboard {
+            self.targets.clear();
+        } else if selection == self.atoms.clipboard_manager {
+            warn!("Another clipboard manager took over");
+        }
+    }
+
+    unsafe fn begin_save(&mut self, request: SelectionRequest) {
+        let targets = match request.property {
+            0 => None,
+            property => {
+                get_property::<u64>(self.display, request.requestor, property, xlib::XA_ATOM)
+            }
+        };
+        self.save = Some(Save {
+            requestor: request.requestor,
+            property: request.property,
+            time: request.time,
+            // The requestor may name the targets to save, otherwise all
+            // of them are.
+            remaining: targets.filter(|targets| !targets.is_empty()),
+            saved: Vec::new(),
+            waiting_since: Instant::now(),
+        });
+
+        if self
+            .save
+            .as_ref()
+            .is_some_and(|save| save.remaining.is_some())
+        {
+            self.convert_next();
+        } else {
+            self.convert(self.atoms.targets, request.time);
+        }
+    }
+
+    /// Asks for the next target, or finishes once all are stored.
+    unsafe fn convert_next(&mut self) {
+        let Some(save) = &mut self.save else {
+            return;
+        };
+        let next = save.remaining.as_mut().and_then(|targets| targets.pop());
+        let time = save.time;
+        save.waiting_since = Instant::now();
+        match next {
+            Some(target) => self.convert(target, time),
+            None => self.finish_save(),
+        }
+    }
+
+    unsafe fn convert(&self, target: xlib::Atom, time: xlib::Time) {
+        xlib::XConvertSelection(
+            self.display,
+            self.atoms.clipboard,
+            target,
+            self.atoms.velowm_selection,
+            self.window,
+            time,
+        );
+    }
+
+    /// Takes over the clipboard with what could be saved and tells the
+    /// requestor whether that worked.
+    unsafe fn finish_save(&mut self) {
+        let Some(save) = self.save.take() else {
+            return;
+        };
+        let saved = !save.saved.is_empty();
+        if saved {
+            debug!("Saved {} clipboard targets", save.saved.len());
+            self.targets = save.saved;
+            xlib::XSetSelectionOwner(self.display, self.atoms.clipboard, self.window, save.time);
+        }
+        self.notify(
+            save.requestor,
+            self.atoms.clipboard_manager,
+            self.atoms.save_targets,
+            if saved { save.property } else { 0 },
+            save.time,
+        );
+    }
+
+    /// Reads a converted target. Data sent incrementally (`INCR`) isn't
+    /// supported and is skipped.
+    unsafe fn read_target(&self, target: xlib::Atom, property: xlib::Atom) -> Option<Target> {
+        let mut kind: xlib::Atom = 0;
+        let mut format = 0;
+        let mut items = 0;
+        let mut bytes_after = 0;
+        let mut data: *mut u8 = std::ptr::null_mut();
+        if xlib::XGetWindowProperty(
+            self.display,
+            self.window,
+            property,
+            0,
+            i64::MAX / 4,
+            0,
+            xlib::AnyPropertyType as u64,
+            &mut kind,
+            &mut format,
+            &mut items,
+            &mut bytes_after,
+            &mut data,
+        ) != 0
+            || data.is_null()
+        {
+            return None;
+        }
+
+        let size = match format {
+            32 => std::mem::size_of::<std::ffi::c_long>(),
+            16 => std::mem::size_of::<std::ffi::c_short>(),
+            _ => 1,
+        };
+        let target = (kind != self.atoms.incr).then(|| Target {
+            target,
+            kind,
+            format,
+            items: items as usize,
+            data: std::slice::from_raw_parts(data, items as usize * size).to_vec(),
+        });
+        xlib::XFree(data as *mut _);
+        target
+    }
+
+    /// Stores `targets` and `TARGETS` itself as the answer to `TARGETS`.
+    unsafe fn reply_targets(
+        &self,
+        requestor: xlib::Window,
+        property: xlib::Atom,
+        targets: &[xlib::Atom],
+    ) {
+        let mut targets = targets.to_vec();
+        targets.push(self.atoms.targets);
+        set_property(self.display, requestor, property, xlib::XA_ATOM, &targets);
+    }
+
+    /// Sends the `SelectionNotify` that ends a request, refused when
+    /// `property` is `0`.
+    unsafe fn notify(
+        &self,
+        requestor: xlib::Window,
+        selection: xlib::Atom,
+        target: xlib::Atom,
+        property: xlib::Atom,
+        time: xlib::Time,
+    ) {
+        let mut event = xlib::XEvent {
+            selection: xlib::XSelectionEvent {
+                type_: xlib::SelectionNotify,
+                serial: 0,
+                send_event: 1,
+                display: self.display,
+                requestor,
+                selection,
+                target,
+                property,
+                time,
+            },
+        };
+        xlib::XSendEvent(self.display, requestor, 0, 0, &mut event);
+    }
+}
+
+impl Drop for ClipboardManager {
+    fn drop(&mut self) {
+        // Destroying the window gives up both selections.
+        unsafe { xlib::XDestroyWindow(self.display, self.window) };
+    }
+}
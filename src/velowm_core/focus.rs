@@ -0,0 +1,90 @@
+use x11::xlib;
+
+use crate::utils::x11::{set_focus, set_property, Atoms};
+
+/// Single source of truth for the focused client.
+///
+/// Every focus change goes through here so the X input focus and
+/// `_NET_ACTIVE_WINDOW` never disagree with each other. Border colors are
+/// up to the window manager, which knows the rest of the window's state.
+pub struct FocusManager {
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    atoms: Atoms,
+    focused: Option<xlib::Window>,
+}
+
+impl FocusManager {
+    /// Creates a new focus manager.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection.
+    /// - The root window must be a valid window ID for the given display.
+    /// - The caller must ensure the display connection remains valid for the lifetime of the manager.
+    pub unsafe fn new(display: *mut xlib::Display, root: xlib::Window, atoms: Atoms) -> Self {
+        Self {
+            display,
+            root,
+            atoms,
+            focused: None,
+        }
+    }
+
+    pub fn focused(&self) -> Option<xlib::Window> {
+        self.focused
+    }
+
+    pub fn is_focused(&self, window: xlib::Window) -> bool {
+        self.focused == Some(window)
+    }
+
+    /// Focuses the given client.
+    pub fn focus(&mut self, window: xlib::Window) {
+        if window == 0 || window == self.root {
+            return;
+        }
+
+        unsafe { set_focus(self.display, &self.atoms, window) };
+
+        self.focused = Some(window);
+        self.set_active_window(window);
+    }
+
+    /// Drops focus entirely and gives it back to the root window.
+    pub fn unfocus(&mut self) {
+        self.focused = None;
+        unsafe {
+            xlib::XSetInputFocus(
+                self.display,
+                self.root,
+                xlib::RevertToPointerRoot,
+                xlib::CurrentTime,
+            );
+        }
+
+        self.set_active_window(0);
+    }
+
+    /// Forgets a client that is going away. Returns whether it held the focus.
+    pub fn forget(&mut self, window: xlib::Window) -> bool {
+        if self.focused == Some(window) {
+            self.focused = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn set_active_window(&self, window: xlib::Window) {
+        unsafe {
+            set_property(
+                self.display,
+                self.root,
+                self.atoms.net_active_window,
+                xlib::XA_WINDOW,
+                &[window],
+            );
+            xlib::XSync(self.display, 0);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::process::{Command, Stdio};
+use x11::xlib;
+
+use crate::config::hooks::{Hook, HookKind};
+
+use super::process::ProcessSupervisor;
+
+/// Something that happened in the window manager. Workspaces are 0-based.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HookEvent {
+    WindowMapped {
+        window: xlib::Window,
+        workspace: usize,
+    },
+    WindowClosed {
+        window: xlib::Window,
+    },
+    WorkspaceChanged {
+        workspace: usize,
+    },
+    FocusChanged {
+        window: Option<xlib::Window>,
+    },
+    MonitorChanged {
+        monitor: usize,
+    },
+}
+
+impl HookEvent {
+    pub fn kind(&self) -> HookKind {
+        match self {
+            Self::WindowMapped { .. } => HookKind::WindowMapped,
+            Self::WindowClosed { .. } => HookKind::WindowClosed,
+            Self::WorkspaceChanged { .. } => HookKind::WorkspaceChanged,
+            Self::FocusChanged { .. } => HookKind::FocusChanged,
+            Self::MonitorChanged { .. } => HookKind::MonitorChanged,
+        }
+    }
+
+    /// The environment a hook command gets. Window ids are hex like in
+    /// `xprop`/`xdotool`, workspaces are 1-based like in the config.
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![("VELOWM_EVENT", self.kind().name().to_string())];
+        match *self {
+            Self::WindowMapped { window, workspace } => {
+                env.push(("VELOWM_WINDOW", format!("{:#x}", window)));
+                env.push(("VELOWM_WORKSPACE", (workspace + 1).to_string()));
+            }
+            Self::WindowClosed { window } => {
+                env.push(("VELOWM_WINDOW", format!("{:#x}", window)));
+            }
+            Self::WorkspaceChanged { workspace } => {
+                env.push(("VELOWM_WORKSPACE", (workspace + 1).to_string()));
+            }
+            Self::FocusChanged { window } => {
+                env.push((
+                    "VELOWM_WINDOW",
+                    window.map_or(String::new(), |w| format!("{:#x}", w)),
+                ));
+            }
+            Self::MonitorChanged { monitor } => {
+                env.push(("VELOWM_MONITOR", monitor.to_string()));
+            }
+        }
+        env
+    }
+
+    /// One line of the IPC event stream, e.g.
+    /// `{"event":"workspace_changed","workspace":2}`. Window ids are plain
+    /// numbers and `null` when nothing is focused, workspaces are 1-based.
+    pub fn to_json(&self) -> String {
+        let fields = match *self {
+            Self::WindowMapped { window, workspace } => {
+                format!(r#""window":{},"workspace":{}"#, window, workspace + 1)
+            }
+            Self::WindowClosed { window } => format!(r#""window":{}"#, window),
+            Self::WorkspaceChanged { workspace } => {
+                format!(r#""workspace":{}"#, workspace + 1)
+            }
+            Self::FocusChanged { window } => format!(
+                r#""window":{}"#,
+                window.map_or(String::from("null"), |w| w.to_string())
+            ),
+            Self::MonitorChanged { monitor } => format!(r#""monitor":{}"#, monitor),
+        };
+        format!(r#"{{"event":"{}",{}}}"#, self.kind().name(), fields)
+    }
+}
+
+pub type HookCallback = Box<dyn FnMut(&HookEvent)>;
+
+/// Config hooks plus the callbacks registered by library users.
+#[derive(Default)]
+pub struct HookRegistry {
+    commands: Vec<Hook>,
+    callbacks: Vec<(HookKind, HookCallback)>,
+}
+
+impl HookRegistry {
+    pub fn new(commands: Vec<Hook>) -> Self {
+        Self {
+            commands,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Replaces the config hooks, keeping registered callbacks.
+    pub fn set_commands(&mut self, commands: Vec<Hook>) {
+        self.commands = commands;
+    }
+
+    pub fn register(&mut self, kind: HookKind, callback: HookCallback) {
+        self.callbacks.push((kind, callback));
+    }
+
+    /// Runs the hook commands and callbacks for the event.
+    pub fn emit(&mut self, event: &HookEvent, processes: &mut ProcessSupervisor) {
+        let kind = event.kind();
+
+        for hook in self.commands.iter().filter(|hook| hook.event == kind) {
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(&hook.command)
+                .envs(event.env())
+                .stdout(Stdio::null());
+            if let Err(e) = processes.spawn(&mut command, &hook.command) {
+                log::error!("Failed to run {} hook: {}", kind.name(), e);
+            }
+        }
+
+        for (_, callback) in self.callbacks.iter_mut().filter(|(k, _)| *k == kind) {
+            callback(event);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use log::{debug, info};
+use std::os::raw::c_int;
+use x11::{dpms, xlib};
+
+/// Screen saver settings to put back once idling is allowed again.
+struct Saved {
+    timeout: c_int,
+    interval: c_int,
+    prefer_blanking: c_int,
+    allow_exposures: c_int,
+    /// Whether DPMS was on and has been turned off.
+    dpms: bool,
+}
+
+/// Keeps the screen from blanking, by turning off the X screen saver and
+/// DPMS while inhibited and restoring the previous settings afterwards.
+pub struct IdleInhibitor {
+    display: *mut xlib::Display,
+    saved: Option<Saved>,
+}
+
+impl IdleInhibitor {
+    /// Creates an inhibitor that doesn't inhibit yet.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection.
+    /// - The caller must ensure the display connection remains valid for the lifetime of the inhibitor.
+    pub unsafe fn new(display: *mut xlib::Display) -> Self {
+        Self {
+            display,
+            saved: None,
+        }
+    }
+
+    pub fn is_inhibited(&self) -> bool {
+        self.saved.is_some()
+    }
+
+    /// Turns inhibiting on or off. Does nothing if it already is.
+    pub fn set_inhibited(&mut self, inhibited: bool) {
+        if inhibited == self.is_inhibited() {
+            return;
+        }
+        unsafe {
+            if inhibited {
+                self.inhibit();
+            } else {
+                self.restore();
+            }
+        }
+    }
+
+    unsafe fn inhibit(&mut self) {
+        let mut saved = Saved {
+            timeout: 0,
+            interval: 0,
+            prefer_blanking: 0,
+            allow_exposures: 0,
+            dpms: false,
+        };
+        xlib::XGetScreenSaver(
+            self.display,
+            &mut saved.timeout,
+            &mut saved.interval,
+            &mut saved.prefer_blanking,
+            &mut saved.allow_exposures,
+        );
+        xlib::XSetScreenSaver(
+            self.display,
+            0,
+            saved.interval,
+            saved.prefer_blanking,
+            saved.allow_exposures,
+        );
+
+        let (mut event_base, mut error_base) = (0, 0);
+        if dpms::DPMSQueryExtension(self.display, &mut event_base, &mut error_base) != 0
+            && dpms::DPMSCapable(self.display) != 0
+        {
+            let (mut level, mut enabled) = (0, 0);
+            dpms::DPMSInfo(self.display, &mut level, &mut enabled);
+            if enabled != 0 {
+                dpms::DPMSDisable(self.display);
+                saved.dpms = true;
+            }
+        }
+        xlib::XFlush(self.display);
+
+        info!("Inhibiting idle");
+        debug!(
+            "Screen saver timeout was {}s, DPMS {}",
+            saved.timeout,
+            if saved.dpms { "on" } else { "off" }
+        );
+        self.saved = Some(saved);
+    }
+
+    unsafe fn restore(&mut self) {
+        let Some(saved) = self.saved.take() else {
+            return;
+        };
+        xlib::XSetScreenSaver(
+            self.display,
+            saved.timeout,
+            saved.interval,
+            saved.prefer_blanking,
+            saved.allow_exposures,
+        );
+        if saved.dpms {
+            dpms::DPMSEnable(self.display);
+        }
+        xlib::XFlush(self.display);
+        info!("No longer inhibiting idle");
+    }
+}
+
+impl Drop for IdleInhibitor {
+    fn drop(&mut self) {
+        unsafe { self.restore() };
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::{anyhow, Context, Result};
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    os::{
+        fd::{AsRawFd, RawFd},
+        unix::net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    time::Duration,
+};
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Line based control socket. A client sends one command per connection and
+/// gets back a single line: `ok`, `ok <text>` or `error <text>`.
+///
+/// A client that sends `subscribe` instead gets `ok` and then keeps the
+/// connection open, receiving one JSON object per line for every event.
+pub struct IpcServer {
+    listener: UnixListener,
+    path: PathBuf,
+    subscribers: Vec<UnixStream>,
+}
+
+pub struct IpcRequest {
+    stream: UnixStream,
+    pub message: String,
+}
+
+impl IpcRequest {
+    pub fn reply(mut self, response: Result<String>) {
+        let line = match response {
+            Ok(text) if text.is_empty() => String::from("ok\n"),
+            Ok(text) => format!("ok {}\n", text),
+            Err(e) => format!("error {}\n", e),
+        };
+        if let Err(e) = self.stream.write_all(line.as_bytes()) {
+            log::debug!("Failed to answer IPC client: {}", e);
+        }
+    }
+}
+
+impl IpcServer {
+    /// `$VELOWM_SOCKET`, or a per-display socket in `$XDG_RUNTIME_DIR` (falling
+    /// back to `/tmp`).
+    pub fn socket_path() -> PathBuf {
+        if let Ok(path) = env::var("VELOWM_SOCKET") {
+            return PathBuf::from(path);
+        }
+
+        let dir = env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| String::from("/tmp"));
+        let display = env::var("DISPLAY")
+            .unwrap_or_default()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+        PathBuf::from(dir).join(format!("velowm{}.sock", display))
+    }
+
+    pub fn bind() -> Result<Self> {
+        let path = Self::socket_path();
+        if UnixStream::connect(&path).is_ok() {
+            return Err(anyhow!(
+                "Another velowm instance is listening on {}",
+                path.display()
+            ));
+        }
+        let _ = fs::remove_file(&path);
+
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Failed to bind IPC socket {}", path.display()))?;
+        listener
+            .set_nonblocking(true)
+            .context("Failed to make IPC socket non-blocking")?;
+
+        // Children find the socket through the environment.
+        env::set_var("VELOWM_SOCKET", &path);
+        log::info!("Listening for IPC commands on {}", path.display());
+
+        Ok(Self {
+            listener,
+            path,
+            subscribers: Vec::new(),
+        })
+    }
+
+    pub fn fd(&self) -> RawFd {
+        self.listener.as_raw_fd()
+    }
+
+    /// Accepts every pending connection and reads its request line.
+    /// Subscriptions are set up here and not handed back.
+    pub fn accept(&mut self) -> Vec<IpcRequest> {
+        let mut requests = Vec::new();
+        while let Ok((stream, _)) = self.listener.accept() {
+            let _ = stream.set_nonblocking(false);
+            let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+
+            let mut message = String::new();
+            match BufReader::new(&stream).read_line(&mut message) {
+                Ok(n) if n > 0 && message.trim() == "subscribe" => self.subscribe(stream),
+                Ok(n) if n > 0 => requests.push(IpcRequest {
+                    stream,
+                    message: message.trim().to_string(),
+                }),
+                _ => log::debug!("Dropping IPC client without a request"),
+            }
+        }
+        requests
+    }
+
+    fn subscribe(&mut self, mut stream: UnixStream) {
+        if stream.write_all(b"ok\n").is_err() || stream.set_nonblocking(true).is_err() {
+            return;
+        }
+        log::debug!("IPC client subscribed to events");
+        self.subscribers.push(stream);
+    }
+
+    /// Sends a line to every subscriber. Clients that went away or stopped
+    /// reading are dropped rather than blocking the window manager.
+    pub fn broadcast(&mut self, line: &str) {
+        let line = format!("{}\n", line);
+        self.subscribers
+            .retain_mut(|stream| match stream.write_all(line.as_bytes()) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::debug!("Dropping IPC subscriber: {}", e);
+                    false
+                }
+            });
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Sends one command to the running window manager and returns its answer.
+pub fn send(message: &str) -> Result<String> {
+    let path = IpcServer::socket_path();
+    let mut stream = UnixStream::connect(&path)
+        .with_context(|| format!("Failed to connect to {}", path.display()))?;
+    stream
+        .write_all(format!("{}\n", message).as_bytes())
+        .context("Failed to send command")?;
+
+    let mut response = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut response)
+        .context("Failed to read response")?;
+
+    Ok(response.trim_end().to_string())
+}
+
+/// Subscribes to the event stream and calls `on_event` with every line until
+/// the window manager closes the connection.
+pub fn subscribe(mut on_event: impl FnMut(&str)) -> Result<()> {
+    let path = IpcServer::socket_path();
+    let mut stream = UnixStream::connect(&path)
+        .with_context(|| format!("Failed to connect to {}", path.display()))?;
+    stream
+        .write_all(b"subscribe\n")
+        .context("Failed to send subscribe request")?;
+
+    let mut lines = BufReader::new(&stream).lines();
+    match lines.next() {
+        Some(Ok(response)) if response == "ok" => {}
+        Some(Ok(response)) => return Err(anyhow!("Subscription refused: {}", response)),
+        _ => return Err(anyhow!("No response to subscribe request")),
+    }
+
+    for line in lines {
+        on_event(&line.context("Failed to read event")?);
+    }
+    Ok(())
+}
+
+/// Quotes and escapes a string for the JSON event stream.
+pub fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
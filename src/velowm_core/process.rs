@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, File},
+    os::unix::{fs::FileExt, process::ExitStatusExt},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::atomic::{AtomicI32, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Children exiting within this time are reported as failed launches.
+const EARLY_EXIT: Duration = Duration::from_secs(5);
+
+/// Only the tail of a child's stderr is kept for the report.
+const MAX_OUTPUT: usize = 4096;
+
+static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn handle_sigchld(_: libc::c_int) {
+    let fd = SIGNAL_PIPE.load(Ordering::Relaxed);
+    if fd >= 0 {
+        unsafe {
+            libc::write(fd, [0u8].as_ptr() as *const _, 1);
+        }
+    }
+}
+
+fn set_nonblocking(fd: libc::c_int) {
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+    }
+}
+
+struct Supervised {
+    child: Child,
+    command: String,
+    started: Instant,
+    // Unlinked file holding the child's stderr. A file instead of a pipe so
+    // the child never blocks or gets SIGPIPE once nobody reads it anymore.
+    stderr: Option<File>,
+}
+
+impl Supervised {
+    fn read_stderr(&self) -> String {
+        let Some(file) = &self.stderr else {
+            return String::new();
+        };
+
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let start = len.saturating_sub(MAX_OUTPUT as u64);
+        let mut buf = vec![0u8; (len - start) as usize];
+        let read = file.read_at(&mut buf, start).unwrap_or(0);
+        String::from_utf8_lossy(&buf[..read]).trim().to_string()
+    }
+}
+
+/// A child that has exited, with what it printed to stderr during launch.
+pub struct ChildExit {
+    pub pid: u32,
+    pub command: String,
+    pub status: ExitStatus,
+    pub runtime: Duration,
+    pub stderr: String,
+}
+
+impl ChildExit {
+    /// Whether the child failed right after being launched.
+    pub fn failed_early(&self) -> bool {
+        self.runtime < EARLY_EXIT && !self.status.success()
+    }
+
+    pub fn describe_status(&self) -> String {
+        match (self.status.code(), self.status.signal()) {
+            (Some(code), _) => format!("exit code {}", code),
+            (None, Some(signal)) => format!("signal {}", signal),
+            _ => String::from("unknown status"),
+        }
+    }
+}
+
+/// Supervises spawned commands: children are reaped as soon as `SIGCHLD`
+/// arrives and their stderr is captured while they start up.
+pub struct ProcessSupervisor {
+    signal_read: libc::c_int,
+    signal_write: libc::c_int,
+    children: Vec<Supervised>,
+    sequence: u32,
+}
+
+impl ProcessSupervisor {
+    pub fn new() -> Result<Self> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error()).context("Failed to create signal pipe");
+        }
+        for fd in fds {
+            set_nonblocking(fd);
+            unsafe {
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+        }
+        SIGNAL_PIPE.store(fds[1], Ordering::Relaxed);
+
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle_sigchld as extern "C" fn(libc::c_int) as usize;
+            action.sa_flags = libc::SA_RESTART | libc::SA_NOCLDSTOP;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(libc::SIGCHLD, &action, std::ptr::null_mut()) != 0 {
+                return Err(std::io::Error::last_os_error())
+                    .context("Failed to install SIGCHLD handler");
+            }
+        }
+
+        Ok(Self {
+            signal_read: fds[0],
+            signal_write: fds[1],
+            children: Vec::new(),
+            sequence: 0,
+        })
+    }
+
+    fn capture_file(&mut self) -> std::io::Result<File> {
+        self.sequence += 1;
+        let path = std::env::temp_dir().join(format!(
+            "velowm-{}-{}.stderr",
+            std::process::id(),
+            self.sequence
+        ));
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let _ = fs::remove_file(&path);
+        Ok(file)
+    }
+
+    /// Spawns the command with its stderr captured. Returns the child pid.
+    pub fn spawn(&mut self, command: &mut Command, label: &str) -> std::io::Result<u32> {
+        let stderr = match self.capture_file() {
+            Ok(file) => {
+                command.stderr(file.try_clone()?);
+                Some(file)
+            }
+            Err(e) => {
+                log::warn!("Failed to capture stderr of {}: {}", label, e);
+                command.stderr(Stdio::null());
+                None
+            }
+        };
+
+        let child = command.spawn()?;
+        let pid = child.id();
+        self.children.push(Supervised {
+            child,
+            command: label.to_string(),
+            started: Instant::now(),
+            stderr,
+        });
+        Ok(pid)
+    }
+
+    /// The descriptor the event loop has to wait on besides the X connection.
+    pub fn signal_fd(&self) -> libc::c_int {
+        self.signal_read
+    }
+
+    /// Reaps every child that has exited, supervised or not. Children that
+    /// weren't spawned through the supervisor (for example the ones inherited
+    /// across a restart) are reported without a command or runtime.
+    pub fn reap(&mut self) -> Vec<ChildExit> {
+        let mut buf = [0u8; 64];
+        while unsafe { libc::read(self.signal_read, buf.as_mut_ptr() as *mut _, buf.len()) } > 0 {}
+
+        let mut exits = Vec::new();
+        loop {
+            let mut raw_status = 0;
+            let pid = unsafe { libc::waitpid(-1, &mut raw_status, libc::WNOHANG) };
+            if pid <= 0 {
+                break;
+            }
+
+            let pid = pid as u32;
+            let status = ExitStatus::from_raw(raw_status);
+            let exit = match self.children.iter().position(|c| c.child.id() == pid) {
+                Some(idx) => {
+                    let supervised = self.children.remove(idx);
+                    ChildExit {
+                        pid,
+                        stderr: supervised.read_stderr(),
+                        command: supervised.command,
+                        status,
+                        runtime: supervised.started.elapsed(),
+                    }
+                }
+                None => ChildExit {
+                    pid,
+                    command: String::from("unknown process"),
+                    status,
+                    runtime: Duration::MAX,
+                    stderr: String::new(),
+                },
+            };
+            exits.push(exit);
+        }
+
+        for supervised in &mut self.children {
+            if supervised.started.elapsed() >= EARLY_EXIT {
+                supervised.stderr = None;
+            }
+        }
+        exits
+    }
+}
+
+impl Drop for ProcessSupervisor {
+    fn drop(&mut self) {
+        SIGNAL_PIPE.store(-1, Ordering::Relaxed);
+        unsafe {
+            libc::close(self.signal_read);
+            libc::close(self.signal_write);
+        }
+    }
+}
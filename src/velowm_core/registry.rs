@@ -0,0 +1,126 @@
+use std::{collections::HashMap, ops::Deref};
+use x11::xlib;
+
+/// Something stored in a [`WindowRegistry`], known by its X window.
+pub trait WindowId {
+    fn window_id(&self) -> xlib::Window;
+}
+
+/// Windows in the order they were added, with an index by id so finding
+/// one doesn't scan them all.
+///
+/// Reads go through the slice it derefs to. Entries handed out mutably
+/// must keep their id, the index isn't updated for it.
+pub struct WindowRegistry<T> {
+    entries: Vec<T>,
+    index: HashMap<xlib::Window, usize>,
+}
+
+impl<T: WindowId> WindowRegistry<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Adds `entry` at the end, or replaces the entry with its id in place.
+    pub fn push(&mut self, entry: T) {
+        let id = entry.window_id();
+        match self.index.get(&id) {
+            Some(&position) => self.entries[position] = entry,
+            None => {
+                self.index.insert(id, self.entries.len());
+                self.entries.push(entry);
+            }
+        }
+    }
+
+    /// Takes the entry out, keeping the order of the others.
+    pub fn remove(&mut self, id: xlib::Window) -> Option<T> {
+        let position = self.index.remove(&id)?;
+        let entry = self.entries.remove(position);
+        for later in &self.entries[position..] {
+            *self.index.get_mut(&later.window_id()).unwrap() -= 1;
+        }
+        Some(entry)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    pub fn position(&self, id: xlib::Window) -> Option<usize> {
+        self.index.get(&id).copied()
+    }
+
+    pub fn contains_id(&self, id: xlib::Window) -> bool {
+        self.index.contains_key(&id)
+    }
+
+    pub fn get(&self, id: xlib::Window) -> Option<&T> {
+        self.position(id).map(|position| &self.entries[position])
+    }
+
+    pub fn get_mut(&mut self, id: xlib::Window) -> Option<&mut T> {
+        self.position(id)
+            .map(|position| &mut self.entries[position])
+    }
+
+    /// Swaps the places of two entries. Does nothing unless both are here.
+    pub fn swap(&mut self, first: xlib::Window, second: xlib::Window) -> bool {
+        let (Some(a), Some(b)) = (self.position(first), self.position(second)) else {
+            return false;
+        };
+        self.entries.swap(a, b);
+        self.index.insert(first, b);
+        self.index.insert(second, a);
+        true
+    }
+
+    /// Rearranges the entries with `reorder`, which may only move them
+    /// around, and updates the index to match.
+    pub fn reorder(&mut self, reorder: impl FnOnce(&mut [T])) {
+        reorder(&mut self.entries);
+        for (position, entry) in self.entries.iter().enumerate() {
+            self.index.insert(entry.window_id(), position);
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.entries.iter_mut()
+    }
+}
+
+impl<T: WindowId> Default for WindowRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for WindowRegistry<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.entries
+    }
+}
+
+impl<'a, T> IntoIterator for &'a WindowRegistry<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut WindowRegistry<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter_mut()
+    }
+}
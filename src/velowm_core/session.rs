@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use x11::xlib;
+
+use crate::{
+    config::rules::{Length, Rule, WindowProperties},
+    ui::layout::{Geometry, LayoutSettings},
+};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct SessionState {
+    pub current_workspace: usize,
+    #[serde(default)]
+    pub workspaces: Vec<SavedWorkspace>,
+    #[serde(default)]
+    pub view: u32,
+    /// Bar visibility set with `toggle_bar`, `None` when it follows the config.
+    #[serde(default)]
+    pub bar_visible: Option<bool>,
+    /// Theme chosen with `set_theme`, `None` when it follows the config.
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Wallpaper set over IPC, `None` when it follows the config.
+    #[serde(default)]
+    pub wallpaper: Option<String>,
+    /// The running kiosk application, started again when it exits.
+    #[serde(default)]
+    pub kiosk_pid: Option<u32>,
+    #[serde(default)]
+    pub tags: Vec<SavedTags>,
+    #[serde(default)]
+    pub floating: Vec<SavedFloating>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedWorkspace {
+    pub index: usize,
+    #[serde(default)]
+    pub layout: Option<LayoutSettings>,
+    #[serde(default)]
+    pub windows: Vec<xlib::Window>,
+    #[serde(default)]
+    pub monitor: Option<Geometry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedTags {
+    pub window: xlib::Window,
+    pub tags: u32,
+}
+
+/// A floating window and where it was.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedFloating {
+    pub window: xlib::Window,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub global: bool,
+}
+
+impl SavedFloating {
+    /// A rule floating the window where it was.
+    pub fn rule(&self) -> Rule {
+        Rule {
+            floating: Some(true),
+            x: Some(Length::Pixels(self.x)),
+            y: Some(Length::Pixels(self.y)),
+            width: Some(Length::Pixels(self.width as i32)),
+            height: Some(Length::Pixels(self.height as i32)),
+            global: Some(self.global),
+            ..Rule::default()
+        }
+    }
+}
+
+impl SessionState {
+    pub fn get_state_path() -> Result<PathBuf> {
+        let home = std::env::var("HOME").context("Failed to get HOME directory")?;
+
+        Ok(PathBuf::from(home).join(".cache/velowm/state.toml"))
+    }
+
+    /// Loads and removes the state left behind by a restart, so a fresh
+    /// session never picks up stale window ids.
+    pub fn take() -> Option<Self> {
+        let path = Self::get_state_path().ok()?;
+        let content = fs::read_to_string(&path).ok()?;
+        let _ = fs::remove_file(&path);
+
+        toml::from_str(&content)
+            .map_err(|e| log::warn!("Failed to parse session state: {}", e))
+            .ok()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::get_state_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+
+        let content = toml::to_string(self).context("Failed to serialize session state")?;
+        fs::write(path, content).context("Failed to write session state")
+    }
+
+    pub fn contains_window(&self, window: xlib::Window) -> bool {
+        self.workspaces
+            .iter()
+            .any(|ws| ws.windows.contains(&window))
+    }
+}
+
+/// Window placement remembered across reboots. Written on exit and handed
+/// out as one-shot rules to the matching windows on the next start.
+#[derive(Serialize, Deserialize, Default)]
+pub struct WindowSession {
+    #[serde(default)]
+    pub windows: Vec<Rule>,
+}
+
+impl WindowSession {
+    pub fn get_path() -> Result<PathBuf> {
+        let home = std::env::var("HOME").context("Failed to get HOME directory")?;
+
+        Ok(PathBuf::from(home).join(".cache/velowm/session.toml"))
+    }
+
+    pub fn load() -> Self {
+        let Ok(path) = Self::get_path() else {
+            return Self::default();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        toml::from_str(&content)
+            .map_err(|e| log::warn!("Failed to parse saved session: {}", e))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::get_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create session directory")?;
+        }
+
+        let content = toml::to_string(self).context("Failed to serialize session")?;
+        fs::write(path, content).context("Failed to write session")
+    }
+
+    /// Removes and returns the first saved entry for the window, so several
+    /// windows of the same class are placed one entry each.
+    pub fn take_match(&mut self, props: &WindowProperties) -> Option<Rule> {
+        let idx = self.windows.iter().position(|rule| rule.matches(props))?;
+        Some(self.windows.remove(idx))
+    }
+
+    /// The distinct commands of the saved windows, in saved order.
+    pub fn commands(&self) -> Vec<String> {
+        let mut commands: Vec<String> = Vec::new();
+        for command in self.windows.iter().filter_map(|rule| rule.command.as_ref()) {
+            if !commands.contains(command) {
+                commands.push(command.clone());
+            }
+        }
+        commands
+    }
+}
+
+/// Reads the command line of a process as a shell command.
+pub fn read_command(pid: u32) -> Option<String> {
+    let cmdline = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let args = cmdline
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| {
+            let arg = String::from_utf8_lossy(arg);
+            if arg
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+            {
+                arg.into_owned()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    (!args.is_empty()).then(|| args.join(" "))
+}
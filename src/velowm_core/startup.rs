@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use x11::xlib;
+
+use crate::{
+    input::event::ClientMessage,
+    utils::x11::{get_string_property, get_window_pid, Atoms},
+};
+
+/// How long a launch may take before it is given up on.
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+struct Launch {
+    id: String,
+    pid: u32,
+    workspace: usize,
+    started: Instant,
+}
+
+/// Startup notification (`_NET_STARTUP_ID`) bookkeeping for spawned commands.
+///
+/// Every spawn gets a startup id that is passed down in `DESKTOP_STARTUP_ID`.
+/// When a window shows up with that id (or, for clients that don't support
+/// the protocol, with the pid of the spawned process) it is placed on the
+/// workspace it was launched from.
+pub struct StartupTracker {
+    display: *mut xlib::Display,
+    atoms: Atoms,
+    launches: Vec<Launch>,
+    messages: HashMap<xlib::Window, Vec<u8>>,
+    sequence: u32,
+}
+
+impl StartupTracker {
+    /// Creates a new startup tracker.
+    ///
+    /// # Safety
+    /// - The display pointer must be valid and point to an active X display connection.
+    /// - The caller must ensure the display connection remains valid for the lifetime of the tracker.
+    pub unsafe fn new(display: *mut xlib::Display, atoms: Atoms) -> Self {
+        Self {
+            display,
+            atoms,
+            launches: Vec::new(),
+            messages: HashMap::new(),
+            sequence: 0,
+        }
+    }
+
+    /// Returns a fresh startup id for the given program.
+    pub fn next_id(&mut self, program: &str) -> String {
+        self.sequence += 1;
+        let program = program.rsplit('/').next().unwrap_or(program);
+        format!(
+            "velowm-{}-{}-{}",
+            std::process::id(),
+            program.replace(char::is_whitespace, "_"),
+            self.sequence
+        )
+    }
+
+    pub fn begin(&mut self, id: String, pid: u32, workspace: usize) {
+        log::debug!("Startup {} (pid {}) on workspace {}", id, pid, workspace);
+        self.launches.push(Launch {
+            id,
+            pid,
+            workspace,
+            started: Instant::now(),
+        });
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.launches.is_empty()
+    }
+
+    /// Completes the launch a new window belongs to and returns the
+    /// workspace it was started from.
+    ///
+    /// # Safety
+    /// The display connection must still be valid and the window must belong to it.
+    pub unsafe fn complete(&mut self, window: xlib::Window) -> Option<usize> {
+        if self.launches.is_empty() {
+            return None;
+        }
+
+        let id = get_string_property(self.display, window, self.atoms.net_startup_id);
+        let pid = get_window_pid(self.display, &self.atoms, window);
+
+        let idx = self
+            .launches
+            .iter()
+            .position(|l| id.as_deref() == Some(l.id.as_str()))
+            .or_else(|| self.launches.iter().position(|l| pid == Some(l.pid)))?;
+        Some(self.launches.remove(idx).workspace)
+    }
+
+    /// Ends the launch of a process that exited before mapping a window.
+    /// Returns whether there was one.
+    pub fn cancel(&mut self, pid: u32) -> bool {
+        let before = self.launches.len();
+        self.launches.retain(|l| l.pid != pid);
+        before != self.launches.len()
+    }
+
+    /// Handles a `_NET_STARTUP_INFO(_BEGIN)` client message. Returns whether
+    /// a launch ended because of it.
+    pub fn handle_message(&mut self, event: &ClientMessage) -> bool {
+        let data = event.bytes;
+
+        let buffer = if event.message_type == self.atoms.net_startup_info_begin {
+            self.messages.insert(event.window, Vec::new());
+            self.messages.entry(event.window).or_default()
+        } else if event.message_type == self.atoms.net_startup_info {
+            match self.messages.get_mut(&event.window) {
+                Some(buffer) => buffer,
+                None => return false,
+            }
+        } else {
+            return false;
+        };
+
+        let Some(end) = data.iter().position(|&b| b == 0) else {
+            buffer.extend_from_slice(&data);
+            return false;
+        };
+        buffer.extend_from_slice(&data[..end]);
+
+        let message = String::from_utf8_lossy(buffer).into_owned();
+        self.messages.remove(&event.window);
+
+        let Some(id) = message.strip_prefix("remove:").and_then(parse_id) else {
+            return false;
+        };
+
+        let before = self.launches.len();
+        self.launches.retain(|l| l.id != id);
+        before != self.launches.len()
+    }
+
+    pub fn is_startup_message(&self, message_type: xlib::Atom) -> bool {
+        message_type == self.atoms.net_startup_info_begin
+            || message_type == self.atoms.net_startup_info
+    }
+
+    /// Time left until the oldest launch times out, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.launches
+            .iter()
+            .map(|l| (l.started + LAUNCH_TIMEOUT).saturating_duration_since(Instant::now()))
+            .min()
+    }
+
+    /// Drops launches that never showed a window. Returns whether any expired.
+    pub fn expire(&mut self) -> bool {
+        let before = self.launches.len();
+        self.launches
+            .retain(|l| l.started.elapsed() < LAUNCH_TIMEOUT);
+        before != self.launches.len()
+    }
+}
+
+/// Extracts the `ID` key from the body of a startup notification message.
+fn parse_id(message: &str) -> Option<String> {
+    let mut rest = message.trim_start();
+    while !rest.is_empty() {
+        let (key, value) = rest.split_once('=')?;
+        let (value, tail) = parse_value(value);
+        if key.trim() == "ID" {
+            return Some(value);
+        }
+        rest = tail.trim_start();
+    }
+    None
+}
+
+/// Parses a possibly quoted, backslash-escaped value and returns it with the
+/// remaining input.
+fn parse_value(input: &str) -> (String, &str) {
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut chars = input.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => {
+                if let Some((_, escaped)) = chars.next() {
+                    value.push(escaped);
+                }
+            }
+            ' ' if !quoted => return (value, &input[i..]),
+            c => value.push(c),
+        }
+    }
+
+    (value, "")
+}
@@ -1,13 +1,61 @@
 use x11::xlib;
 
+use super::registry::WindowId;
+use crate::utils::x11::Atoms;
+
+/// How a window is handled by its `_NET_WM_WINDOW_TYPE`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WindowType {
+    Normal,
+    /// Reserves space on every workspace.
+    Dock,
+    /// Dialogs and utility windows, floating.
+    Dialog,
+    /// Floating without a border and never focused.
+    Splash,
+    /// Tooltips, menus and notifications: mapped as asked but never
+    /// managed.
+    Popup,
+}
+
+impl WindowType {
+    /// The first type in `types` that is known, clients list them from the
+    /// most to the least specific.
+    pub fn from_atoms(types: &[xlib::Atom], atoms: &Atoms) -> Self {
+        types
+            .iter()
+            .find_map(|&atom| match atom {
+                _ if atom == atoms.net_wm_window_type_dock => Some(Self::Dock),
+                _ if atom == atoms.net_wm_window_type_dialog
+                    || atom == atoms.net_wm_window_type_utility =>
+                {
+                    Some(Self::Dialog)
+                }
+                _ if atom == atoms.net_wm_window_type_splash => Some(Self::Splash),
+                _ if atom == atoms.net_wm_window_type_tooltip
+                    || atom == atoms.net_wm_window_type_popup_menu
+                    || atom == atoms.net_wm_window_type_dropdown_menu
+                    || atom == atoms.net_wm_window_type_notification =>
+                {
+                    Some(Self::Popup)
+                }
+                _ => None,
+            })
+            .unwrap_or(Self::Normal)
+    }
+}
+
 #[derive(Clone)]
 pub struct Window {
     pub id: xlib::Window,
+    /// Current geometry. For floating windows this is where they float,
+    /// reapplied whenever the window is shown again.
     pub x: i32,
     pub y: i32,
     pub width: u32,
     pub height: u32,
     pub is_floating: bool,
+    /// Geometry the window had in the layout before it was floated.
     pub pre_float_x: i32,
     pub pre_float_y: i32,
     pub pre_float_width: u32,
@@ -17,11 +65,35 @@ pub struct Window {
     pub pre_fullscreen_y: i32,
     pub pre_fullscreen_width: u32,
     pub pre_fullscreen_height: u32,
-    pub pre_fullscreen_border_width: u32,
+    pub fullscreen_monitors: Option<[usize; 4]>,
     pub is_dock: bool,
+    pub is_urgent: bool,
+    pub tags: u32,
+    /// Set with `mark <char>` to come back with `goto_mark <char>`.
+    pub mark: Option<char>,
+    /// From the `inhibit_idle` of a rule. Unset inhibits idle only while
+    /// the window is fullscreen.
+    pub inhibit_idle: Option<bool>,
+    /// Set with `toggle_global`, the window comes along to every workspace.
+    pub is_global: bool,
+    /// Cleared by a `focus = false` rule and for clients that take no
+    /// keyboard input, such windows are never focused.
+    pub accepts_focus: bool,
+    /// Cleared by an `allow_fullscreen = false` rule, the client's
+    /// fullscreen requests are ignored then.
+    pub allows_fullscreen: bool,
+    /// `_NET_WM_PID` when the window was mapped.
+    pub pid: Option<u32>,
+    /// Splash screens are drawn without a border.
+    pub borderless: bool,
 }
 
 impl Window {
+    /// Whether the window is placed by the layout.
+    pub fn is_tiled(&self) -> bool {
+        !self.is_floating && !self.is_fullscreen && !self.is_dock
+    }
+
     pub fn new(id: xlib::Window, x: i32, y: i32, width: u32, height: u32) -> Self {
         Self {
             id,
@@ -39,8 +111,38 @@ impl Window {
             pre_fullscreen_y: 0,
             pre_fullscreen_width: 0,
             pre_fullscreen_height: 0,
-            pre_fullscreen_border_width: 0,
+            fullscreen_monitors: None,
             is_dock: false,
+            is_urgent: false,
+            tags: 0,
+            mark: None,
+            inhibit_idle: None,
+            is_global: false,
+            accepts_focus: true,
+            allows_fullscreen: true,
+            pid: None,
+            borderless: false,
         }
     }
+
+    /// Width of the border, none for docks, fullscreen and borderless
+    /// windows.
+    pub fn border_width(&self, configured: u32) -> u32 {
+        match self.is_dock || self.is_fullscreen || self.borderless {
+            true => 0,
+            false => configured,
+        }
+    }
+
+    /// Whether the window is shown in the tag `view`. Global windows are
+    /// shown in all of them.
+    pub fn is_viewed(&self, view: u32) -> bool {
+        self.is_global || self.tags & view != 0
+    }
+}
+
+impl WindowId for Window {
+    fn window_id(&self) -> xlib::Window {
+        self.id
+    }
 }
@@ -1,1495 +1,4953 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use log::{debug, error, info, warn};
-use std::process::Command as ProcessCommand;
-use x11::{xinerama, xlib};
+use std::{
+    env,
+    os::unix::io::RawFd,
+    process::Command as ProcessCommand,
+    time::{Duration, Instant},
+};
+use x11::{xlib, xrandr};
 
 use crate::{
-    config::loader::Config,
-    ui::{cursor::Cursor, layout::MasterStackLayout, notification::NotificationManager},
-    utils::{command::Command, x11::Display},
+    config::{
+        hooks::HookKind,
+        loader::Config,
+        rules::{Length, Rule, RuleMonitor, WindowProperties},
+        watcher::ConfigWatcher,
+        workspace::ReturnWhenEmpty,
+    },
+    input::{
+        barrier::BarrierManager,
+        event::{ButtonEvent, ClientMessage, ConfigureRequest, Event, KeyEvent, MotionEvent},
+        hot_corner::HotCornerTracker,
+        keyboard::is_auto_repeat,
+        motion::{skip_to_latest_motion, MotionThrottle},
+        mouse::{MoveResize, ResizeEdges, ResizeSide},
+        split_handle::SplitHandle,
+    },
+    ui::{
+        appearance::BarMonitors,
+        bar::{BarState, StatusBar, WorkspaceState},
+        bind_help::show_binds,
+        confirm::confirm,
+        cursor::{Cursor, Shape},
+        drop_indicator::DropIndicator,
+        layout::{Dock, Geometry, MasterStackLayout, Struts},
+        notification::{NotificationAction, NotificationManager},
+        screenshot::{self, ScreenshotTarget},
+        size_popup::SizePopup,
+        slide::{SlideWindow, WorkspaceSlide},
+        wallpaper::Wallpaper,
+        workspace_preview::{PreviewWindow, WorkspacePreview},
+    },
+    utils::{
+        command::{expand_home, CloseTarget, Command, MonitorTarget, Spawn},
+        keybind::MODIFIER_MASK,
+        logging,
+        x11::{
+            get_class_hint, get_size_hints, get_transient_for, get_window_pid, get_window_title,
+            send_configure_notify, send_protocol, supports_protocol, takes_input, Display, Monitor,
+            Pointer, SizeHints,
+        },
+    },
+};
+
+use super::{
+    builder::WindowManagerBuilder,
+    clipboard::ClipboardManager,
+    focus::FocusManager,
+    hooks::{HookEvent, HookRegistry},
+    idle::IdleInhibitor,
+    ipc::{self, IpcServer},
+    process::ProcessSupervisor,
+    session::{self, SavedFloating, SavedTags, SavedWorkspace, SessionState, WindowSession},
+    startup::StartupTracker,
+    window::{Window, WindowType},
+    workspace::Workspace,
 };
 
-use super::{window::Window, workspace::Workspace};
+const ALL_DESKTOPS: u64 = 0xFFFFFFFF;
+
+/// Source indication of EWMH requests: old clients send 0, applications 1
+/// and pagers or other tools acting for the user 2.
+const SOURCE_APPLICATION: i64 = 1;
+const SOURCE_PAGER: i64 = 2;
+
+/// Cap for `workspace new`, tag views are bitmasks of this width.
+pub(crate) const MAX_WORKSPACES: usize = 32;
+
+/// Smallest size a floating window can be resized to when the client sets
+/// no minimum of its own.
+const MIN_FLOATING_SIZE: u32 = 100;
+
+/// Wait before starting a kiosk application again that failed right after
+/// it was started, so a broken one doesn't restart in a tight loop.
+const KIOSK_RESTART_DELAY: Duration = Duration::from_secs(2);
 
 pub struct WindowManager {
-    display: Display,
     running: bool,
+    restart: bool,
+    restored_session: bool,
+    window_session: WindowSession,
+    /// Floating windows saved by a restart, handed out as they're adopted.
+    restored_floating: Vec<SavedFloating>,
+    startup: StartupTracker,
+    processes: ProcessSupervisor,
+    ipc: Option<IpcServer>,
+    config_watcher: Option<ConfigWatcher>,
     #[allow(dead_code)]
     cursor: Cursor,
     config: Config,
     layout: MasterStackLayout,
+    focus: FocusManager,
     notification_manager: NotificationManager,
+    bars: Vec<StatusBar>,
+    wallpaper: Option<Wallpaper>,
+    /// Set by `set_wallpaper`, overrides `wallpaper` for the rest of the
+    /// session.
+    wallpaper_path: Option<String>,
+    barriers: Option<BarrierManager>,
+    clipboard: Option<ClipboardManager>,
+    hot_corners: Option<HotCornerTracker>,
     workspaces: Vec<Workspace>,
     current_workspace: usize,
+    view: u32,
     dragging: bool,
     drag_start_x: i32,
     drag_start_y: i32,
+    /// Where the dragged floating window was when the drag started.
+    drag_window_x: i32,
+    drag_window_y: i32,
     dragged_window: Option<xlib::Window>,
     resizing: bool,
+    resize_start_x: i32,
+    resize_start_y: i32,
     resize_start_width: u32,
     resize_start_height: u32,
+    /// The edges that follow the pointer, the opposite ones stay put.
+    resize_edges: ResizeEdges,
     resized_window: Option<xlib::Window>,
-    net_active_window: xlib::Atom,
-    net_current_desktop: xlib::Atom,
+    resize_hints: SizeHints,
+    size_popup: Option<SizePopup>,
+    /// Outlines `drop_target` while a tiled window is dragged.
+    drop_indicator: Option<DropIndicator>,
+    /// The tiled window a dragged one swaps places with when dropped.
+    drop_target: Option<xlib::Window>,
+    /// Created the first time a workspace button is hovered.
+    workspace_preview: Option<WorkspacePreview>,
+    /// The workspace switch being animated.
+    slide: Option<WorkspaceSlide>,
+    /// Over the gap between master and stack, dragged to move the split.
+    split_handle: SplitHandle,
+    splitting: bool,
+    /// The workspace shown before the current one.
+    previous_workspace: Option<usize>,
+    /// The key whose auto-repeat release was just skipped.
+    repeating_key: Option<u32>,
+    /// Rate limit for drags, set by `limit_drag_rate`.
+    drag_throttle: Option<MotionThrottle>,
+    game_mode: Option<xlib::Window>,
+    game_mode_dismissed: Option<xlib::Window>,
+    idle: IdleInhibitor,
+    hooks: HookRegistry,
+    focused_monitor: Option<usize>,
+    /// Set by `toggle_bar`, overrides `appearance.bar.enabled` for the rest
+    /// of the session.
+    bar_visible: Option<bool>,
+    /// Set by `set_theme`, overrides `theme.name` for the rest of the session.
+    theme: Option<String>,
+    /// The running `kiosk.application`.
+    kiosk_pid: Option<u32>,
+    /// When to start the kiosk application again after it exited.
+    kiosk_restart_at: Option<Instant>,
+    /// Where RandR events start, `None` without RandR.
+    randr_event_base: Option<i32>,
+    // Declared last so everything above can still free its X resources on drop.
+    display: Display,
 }
 
 impl WindowManager {
-    pub fn new() -> Result<Self> {
+    pub fn new(display_name: Option<&str>) -> Result<Self> {
+        let mut builder = Self::builder();
+        if let Some(name) = display_name {
+            builder = builder.display(name);
+        }
+        builder.build()
+    }
+
+    /// Manages the given display with the given configuration instead of
+    /// `DISPLAY` and the user's config file.
+    pub fn with_config(display_name: &str, config: Config) -> Result<Self> {
+        Self::builder().display(display_name).config(config).build()
+    }
+
+    pub fn builder() -> WindowManagerBuilder {
+        WindowManagerBuilder::default()
+    }
+
+    /// Sets up the window manager. `load_error` is shown to the user when
+    /// `config` is the fallback for a config file that failed to load.
+    pub(super) fn init(
+        display: Display,
+        mut config: Config,
+        load_error: Option<anyhow::Error>,
+        config_watcher: Option<ConfigWatcher>,
+    ) -> Result<Self> {
         info!("Initializing window manager");
 
-        let display = Display::new()?;
         let root = unsafe { xlib::XDefaultRootWindow(display.raw()) };
         let cursor = unsafe { Cursor::new(display.raw())? };
 
-        let config = Config::load().unwrap_or_else(|_| {
-            warn!("Failed to load config, using default configuration");
-            Config::default()
-        });
+        let color_error = unsafe { config.resolve_colors(display.raw()) }.err();
+
+        let mut layout = unsafe { MasterStackLayout::new(display.raw(), root, config.clone()) };
+        let mut notification_manager = unsafe {
+            NotificationManager::new(display.raw(), root, *display.atoms(), &config.appearance)
+        };
+
+        if let Some(e) = load_error {
+            error!("Failed to load config: {:#}", e);
+            if config.notifications_enabled {
+                unsafe {
+                    notification_manager.show_error_with_actions(
+                        &format!("Failed to load config: {:#}", e),
+                        &[NotificationAction::OpenConfig],
+                    );
+                }
+            }
+        }
 
-        let layout = unsafe { MasterStackLayout::new(display.raw(), root, config.clone()) };
-        let mut notification_manager = unsafe { NotificationManager::new(display.raw(), root) };
+        Self::apply_config_migration(&mut config, &mut notification_manager);
+        Self::report_config_errors(&config, &mut notification_manager);
 
-        if let Err(e) = Config::load() {
-            error!("Failed to load config: {}", e);
+        if let Some(e) = color_error {
+            error!("Invalid colors in config: {}", e);
             if config.notifications_enabled {
                 unsafe {
-                    notification_manager.show_error(&format!("Failed to load config: {}", e));
+                    notification_manager.show_error_with_actions(
+                        &format!("Invalid colors in config: {}", e),
+                        &[NotificationAction::OpenConfig],
+                    );
                 }
             }
         }
 
         if config.auto_generated && config.notifications_enabled {
+            // Names the keys the config binds, which the first start setup
+            // may have changed.
+            let find = |wanted: fn(&Command) -> bool| {
+                let bind = config.binds.iter().find(|bind| wanted(&bind.command))?;
+                Some((bind.label(&config.modifier), bind.command.clone()))
+            };
+            let mut message = String::from("You are using an auto generated config\n\n");
+            if let Some((keys, Command::Spawn(spawn))) =
+                find(|command| matches!(command, Command::Spawn(_)))
+            {
+                message += &format!("Press {} to open {}\n", keys, spawn.program());
+            }
+            if let Some((keys, _)) = find(|command| *command == Command::Exit) {
+                message += &format!("Press {} to exit\n", keys);
+            }
+            if let Some((keys, _)) = find(|command| *command == Command::ShowBinds) {
+                message += &format!("Press {} to list all binds\n", keys);
+            }
+            message += "\nPress on this to dismiss this message";
             unsafe {
-                notification_manager.show_error(
-                    "You are using an auto generated config\n\
-                    \n\
-                    Press Alt+Q to open alacritty\n\
-                    Press Alt+W to exit\n\
-                    \n\
-                    Press on this to dismiss this message",
-                );
+                notification_manager.show_error(&message);
             }
         }
 
-        let (net_active_window, net_current_desktop) = unsafe {
-            let net_active_window =
-                xlib::XInternAtom(display.raw(), c"_NET_ACTIVE_WINDOW".as_ptr(), 0);
-            let net_current_desktop =
-                xlib::XInternAtom(display.raw(), c"_NET_CURRENT_DESKTOP".as_ptr(), 0);
-            let net_number_of_desktops =
-                xlib::XInternAtom(display.raw(), c"_NET_NUMBER_OF_DESKTOPS".as_ptr(), 0);
-            let net_desktop_names =
-                xlib::XInternAtom(display.raw(), c"_NET_DESKTOP_NAMES".as_ptr(), 0);
-            let net_supported = xlib::XInternAtom(display.raw(), c"_NET_SUPPORTED".as_ptr(), 0);
-
-            let supported_atoms = [
-                net_active_window,
-                net_current_desktop,
-                net_number_of_desktops,
-                net_desktop_names,
-            ];
-
-            xlib::XChangeProperty(
-                display.raw(),
-                root,
-                net_supported,
-                xlib::XA_ATOM,
-                32,
-                xlib::PropModeReplace,
-                supported_atoms.as_ptr() as *const u8,
-                supported_atoms.len() as i32,
-            );
-
-            let num_desktops: u32 = 10;
-            xlib::XChangeProperty(
-                display.raw(),
-                root,
-                net_number_of_desktops,
-                xlib::XA_CARDINAL,
-                32,
-                xlib::PropModeReplace,
-                &num_desktops as *const u32 as *const u8,
-                1,
-            );
-
-            let current_desktop: u32 = 0;
-            xlib::XChangeProperty(
-                display.raw(),
-                root,
-                net_current_desktop,
-                xlib::XA_CARDINAL,
-                32,
-                xlib::PropModeReplace,
-                &current_desktop as *const u32 as *const u8,
-                1,
-            );
-
-            let names = (0..10)
-                .map(|i| format!("Workspace {}", i + 1))
-                .collect::<Vec<_>>();
-            let names_str = names.join("\0") + "\0";
-            xlib::XChangeProperty(
-                display.raw(),
-                root,
-                net_desktop_names,
-                xlib::XInternAtom(display.raw(), c"UTF8_STRING".as_ptr(), 0),
-                8,
-                xlib::PropModeReplace,
-                names_str.as_bytes().as_ptr(),
-                names_str.len() as i32,
+        let atoms = *display.atoms();
+        display.set_property(
+            root,
+            atoms.net_supported,
+            xlib::XA_ATOM,
+            &[
+                atoms.net_supporting_wm_check,
+                atoms.net_wm_name,
+                atoms.net_active_window,
+                atoms.net_close_window,
+                atoms.net_current_desktop,
+                atoms.net_number_of_desktops,
+                atoms.net_desktop_names,
+                atoms.net_wm_fullscreen_monitors,
+                atoms.net_wm_moveresize,
+                atoms.net_wm_desktop,
+                atoms.net_wm_user_time,
+                atoms.net_wm_state,
+                atoms.net_wm_state_fullscreen,
+            ],
+        );
+        display.set_property(root, atoms.net_current_desktop, xlib::XA_CARDINAL, &[0u64]);
+
+        // Tools such as `wmctrl -m` find the WM's name on a child window
+        // that points back to itself, which lives as long as the connection.
+        let check =
+            unsafe { xlib::XCreateSimpleWindow(display.raw(), root, -1, -1, 1, 1, 0, 0, 0) };
+        for window in [root, check] {
+            display.set_property(
+                window,
+                atoms.net_supporting_wm_check,
+                xlib::XA_WINDOW,
+                &[check],
             );
-
-            (net_active_window, net_current_desktop)
-        };
+        }
+        display.set_property(
+            check,
+            atoms.net_wm_name,
+            atoms.utf8_string,
+            b"velowm".as_slice(),
+        );
 
         unsafe {
             xlib::XDefineCursor(display.raw(), root, cursor.normal());
 
-            Self::setup_key_bindings(display.raw(), root, &config);
+            Self::setup_key_bindings(display.raw(), root, &config, false);
 
             xlib::XSelectInput(
                 display.raw(),
                 root,
                 xlib::SubstructureRedirectMask
                     | xlib::SubstructureNotifyMask
-                    | xlib::PointerMotionMask,
+                    | xlib::PointerMotionMask
+                    | xlib::PropertyChangeMask,
             );
 
             xlib::XSync(display.raw(), 0);
         }
 
-        let mut workspaces = Vec::with_capacity(10);
-        for i in 0..10 {
-            workspaces.push(Workspace::new(i));
-        }
+        let count = if config.dynamic_workspaces && !config.tag_mode {
+            1
+        } else {
+            10
+        };
+        let mut workspaces: Vec<Workspace> = (0..count).map(Workspace::new).collect();
+
+        let initial_settings = config.get_layout_settings(0);
+        workspaces[0].layout_settings = Some(initial_settings.clone());
+        layout.set_settings(initial_settings);
+
+        let bars = Self::create_bars(
+            &display,
+            root,
+            &config,
+            config.appearance.bar.enabled,
+            &mut layout,
+        );
+        let barriers = Self::create_barriers(&display, root, &config);
+        let clipboard = config
+            .clipboard_manager
+            .then(|| unsafe { ClipboardManager::new(display.raw(), root, atoms) })
+            .flatten();
+
+        let hot_corners = (!config.hot_corners.is_empty())
+            .then(|| HotCornerTracker::new(config.hot_corners.clone(), display.monitors()));
+
+        let startup = unsafe { StartupTracker::new(display.raw(), atoms) };
+
+        let ipc = IpcServer::bind()
+            .map_err(|e| warn!("IPC disabled: {:#}", e))
+            .ok();
+
+        let focus = unsafe { FocusManager::new(display.raw(), root, atoms) };
+
+        let randr_event_base = unsafe {
+            let (mut event_base, mut error_base) = (0, 0);
+            (xrandr::XRRQueryExtension(display.raw(), &mut event_base, &mut error_base) != 0).then(
+                || {
+                    xrandr::XRRSelectInput(display.raw(), root, xrandr::RRScreenChangeNotifyMask);
+                    event_base
+                },
+            )
+        };
 
-        Ok(Self {
+        let drag_throttle = Self::create_drag_throttle(&display, &config);
+        let split_handle =
+            unsafe { SplitHandle::new(display.raw(), root, cursor.get(Shape::SizeHorizontal)) };
+        let idle = unsafe { IdleInhibitor::new(display.raw()) };
+        let hooks = HookRegistry::new(config.hooks.clone());
+        let mut wm = Self {
             display,
             running: true,
+            restart: false,
+            restored_session: false,
+            window_session: WindowSession::default(),
+            restored_floating: Vec::new(),
+            startup,
+            processes: ProcessSupervisor::new()?,
+            ipc,
+            config_watcher,
             cursor,
             config,
             layout,
+            focus,
             notification_manager,
+            bars,
+            wallpaper: None,
+            wallpaper_path: None,
+            barriers,
+            clipboard,
+            hot_corners,
             workspaces,
             current_workspace: 0,
+            view: 1,
             dragging: false,
             drag_start_x: 0,
             drag_start_y: 0,
+            drag_window_x: 0,
+            drag_window_y: 0,
             dragged_window: None,
             resizing: false,
+            resize_start_x: 0,
+            resize_start_y: 0,
             resize_start_width: 0,
             resize_start_height: 0,
+            resize_edges: ResizeEdges {
+                horizontal: ResizeSide::End,
+                vertical: ResizeSide::End,
+            },
             resized_window: None,
-            net_active_window,
-            net_current_desktop,
-        })
-    }
-
-    unsafe fn setup_key_bindings(display: *mut xlib::Display, root: xlib::Window, config: &Config) {
-        for bind in &config.binds {
-            let keycode = xlib::XKeysymToKeycode(display, config.get_keysym_for_key(&bind.key));
-            xlib::XGrabKey(
-                display,
-                keycode as i32,
-                config.get_modifier(),
-                root,
-                1,
-                xlib::GrabModeAsync,
-                xlib::GrabModeAsync,
-            );
+            resize_hints: SizeHints::default(),
+            size_popup: None,
+            drop_indicator: None,
+            drop_target: None,
+            workspace_preview: None,
+            slide: None,
+            split_handle,
+            splitting: false,
+            previous_workspace: None,
+            repeating_key: None,
+            drag_throttle,
+            game_mode: None,
+            game_mode_dismissed: None,
+            idle,
+            hooks,
+            focused_monitor: None,
+            bar_visible: None,
+            theme: None,
+            kiosk_pid: None,
+            kiosk_restart_at: None,
+            randr_event_base,
+        };
+        let state = SessionState::take();
+        wm.restored_session = state.is_some();
+        if let Some(state) = state.as_ref().filter(|_| wm.dynamic_workspaces()) {
+            let count = state
+                .workspaces
+                .iter()
+                .map(|ws| ws.index)
+                .chain([state.current_workspace])
+                .max()
+                .unwrap_or(0)
+                + 1;
+            while wm.workspaces.len() < count.min(MAX_WORKSPACES) {
+                wm.push_workspace();
+            }
+        }
+        wm.update_desktops();
+        if wm.config.session.save && !wm.restored_session {
+            wm.window_session = WindowSession::load();
         }
+        wm.restore_session(state);
+        wm.show_wallpaper();
+        wm.update_bar();
 
-        xlib::XSync(display, 0);
+        wm.remove_empty_workspaces();
+        wm.flush_layout();
+        Ok(wm)
     }
 
-    pub fn run(&mut self) -> Result<()> {
-        while self.running {
-            let mut event: xlib::XEvent = unsafe { std::mem::zeroed() };
-            unsafe {
-                xlib::XNextEvent(self.display.raw(), &mut event);
-            }
-
-            match event.get_type() {
-                xlib::KeyPress => self.handle_keypress(event),
-                xlib::MapRequest => self.handle_map_request(event),
-                xlib::UnmapNotify => self.handle_unmap_notify(event),
-                xlib::DestroyNotify => self.handle_destroy_notify(event),
-                xlib::MotionNotify => self.handle_motion_notify(event),
-                xlib::ButtonPress => {
-                    let button_event: xlib::XButtonEvent = From::from(event);
-                    self.handle_button_press(button_event);
-                }
-                xlib::ButtonRelease => {
-                    if self.dragging {
-                        self.end_window_drag();
-                    } else if self.resizing {
-                        self.end_window_resize();
+    /// Writes an upgraded config file back, keeping a backup of the old
+    /// one, and tells the user what changed.
+    fn apply_config_migration(config: &mut Config, notifications: &mut NotificationManager) {
+        let Some(migration) = config.migration.take() else {
+            return;
+        };
+
+        match Config::get_config_path().and_then(|path| migration.apply(&path)) {
+            Ok(backup) => {
+                let summary = migration.summary(&backup);
+                info!("{}", summary.replace('\n', " "));
+                if config.notifications_enabled {
+                    unsafe {
+                        notifications
+                            .show_error_with_actions(&summary, &[NotificationAction::OpenConfig]);
                     }
                 }
-                xlib::EnterNotify => self.handle_enter_notify(event),
-                xlib::LeaveNotify => self.handle_leave_notify(event),
-                xlib::Expose => {
-                    let expose_event: xlib::XExposeEvent = From::from(event);
-                    self.handle_expose(expose_event);
-                }
-                xlib::ClientMessage => self.handle_client_message(event),
-                _ => (),
             }
+            Err(e) => error!("Failed to upgrade config file: {:#}", e),
         }
+    }
 
-        Ok(())
+    /// Tells the user about config entries that were dropped while parsing
+    /// and about keys bound more than once.
+    fn report_config_errors(config: &Config, notifications: &mut NotificationManager) {
+        let conflicts = config.keybind_conflicts();
+        for conflict in &conflicts {
+            warn!("Keybind conflict: {}", conflict);
+        }
+        if !config.notifications_enabled {
+            return;
+        }
+
+        // Config::parse already logged each one.
+        if !config.errors.is_empty() {
+            let message = format!(
+                "Ignored invalid config entries, using defaults for them:\n\n{}",
+                config.errors.join("\n")
+            );
+            unsafe {
+                notifications.show_error_with_actions(&message, &[NotificationAction::OpenConfig]);
+            }
+        }
+        if !conflicts.is_empty() {
+            let message = format!(
+                "Some keys are bound more than once, all of their commands run:\n\n{}",
+                conflicts.join("\n")
+            );
+            unsafe {
+                notifications.show_error_with_actions(&message, &[NotificationAction::OpenConfig]);
+            }
+        }
     }
 
-    fn raise_floating_windows(&mut self) {
-        if let Some(workspace) = self.workspaces.get(self.current_workspace) {
-            for window in &workspace.windows {
-                if window.is_floating && !window.is_dock && Some(window.id) != self.dragged_window {
+    fn create_bars(
+        display: &Display,
+        root: xlib::Window,
+        config: &Config,
+        enabled: bool,
+        layout: &mut MasterStackLayout,
+    ) -> Vec<StatusBar> {
+        if !enabled {
+            return Vec::new();
+        }
+
+        let monitors = match config.appearance.bar.monitors {
+            BarMonitors::Primary => vec![display.primary_monitor()],
+            BarMonitors::All => display.monitors(),
+        };
+        monitors
+            .into_iter()
+            .map(|monitor| {
+                let bar = unsafe {
+                    StatusBar::new(
+                        display.raw(),
+                        root,
+                        monitor.x,
+                        monitor.y,
+                        monitor.width,
+                        config.clone(),
+                    )
+                };
+                let area = Geometry {
+                    height: bar.height(),
+                    ..monitor.into()
+                };
+                layout.set_dock(bar.window, Dock::Top(bar.height()).into(), area);
+                bar
+            })
+            .collect()
+    }
+
+    /// Takes down the bars and gives back the space they reserved.
+    fn remove_bars(&mut self) {
+        for bar in self.bars.drain(..) {
+            self.layout.remove_dock(bar.window);
+        }
+    }
+
+    fn create_barriers(
+        display: &Display,
+        root: xlib::Window,
+        config: &Config,
+    ) -> Option<BarrierManager> {
+        if !config.pointer_barriers.enabled {
+            return None;
+        }
+
+        unsafe {
+            BarrierManager::new(
+                display.raw(),
+                root,
+                &display.monitors(),
+                &config.pointer_barriers,
+            )
+        }
+    }
+
+    fn create_drag_throttle(display: &Display, config: &Config) -> Option<MotionThrottle> {
+        config
+            .limit_drag_rate
+            .then(|| MotionThrottle::per_frame(display.refresh_rate().unwrap_or(60)))
+    }
+
+    /// Whether the window manager stopped because a restart was requested.
+    pub fn restart_requested(&self) -> bool {
+        self.restart
+    }
+
+    /// Re-reads the config file and applies it to the running session.
+    /// A broken file is reported and the current config stays in place.
+    fn reload_config(&mut self) {
+        let mut config = match Config::load() {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Failed to reload config: {:#}", e);
+                if self.config.notifications_enabled {
                     unsafe {
-                        xlib::XRaiseWindow(self.display.raw(), window.id);
+                        self.notification_manager.show_error_with_actions(
+                            &format!("Failed to reload config: {:#}", e),
+                            &[
+                                NotificationAction::OpenConfig,
+                                NotificationAction::DismissAll,
+                            ],
+                        );
                     }
                 }
+                return;
             }
+        };
 
-            if let Some(dragged) = self.dragged_window {
-                if let Some(window) = workspace.windows.iter().find(|w| w.id == dragged) {
-                    if window.is_floating {
-                        unsafe {
-                            xlib::XRaiseWindow(self.display.raw(), dragged);
-                        }
-                    }
+        info!("Reloading config");
+        Self::apply_config_migration(&mut config, &mut self.notification_manager);
+        Self::report_config_errors(&config, &mut self.notification_manager);
+        self.hooks.set_commands(config.hooks.clone());
+        if self.theme.is_some() {
+            config.theme.name = self.theme.clone();
+        }
+        if let Err(e) = unsafe { config.resolve_colors(self.display.raw()) } {
+            error!("Invalid colors in config: {}", e);
+            if config.notifications_enabled {
+                unsafe {
+                    self.notification_manager.show_error_with_actions(
+                        &format!("Invalid colors in config: {}", e),
+                        &[
+                            NotificationAction::OpenConfig,
+                            NotificationAction::DismissAll,
+                        ],
+                    );
                 }
             }
+        }
+        unsafe { self.notification_manager.set_appearance(&config.appearance) };
+        if env::var("RUST_LOG").is_err() {
+            let _ = logging::set_filter(&config.logging.filter_spec());
+        }
 
-            for window in &workspace.windows {
-                if window.is_dock {
-                    unsafe {
-                        xlib::XRaiseWindow(self.display.raw(), window.id);
-                    }
+        let root = self.layout.get_root();
+        unsafe {
+            Self::setup_key_bindings(self.display.raw(), root, &config, self.game_mode.is_some());
+
+            for window in self.workspaces.iter().flat_map(|ws| &ws.windows) {
+                if !window.is_dock {
+                    Self::grab_buttons(self.display.raw(), &config, window.id);
+                }
+                if !window.is_dock && !window.is_fullscreen {
+                    xlib::XSetWindowBorderWidth(
+                        self.display.raw(),
+                        window.id,
+                        window.border_width(config.appearance.border_width),
+                    );
                 }
             }
         }
+        // Keep layout changes made at runtime unless the config changes the
+        // same setting.
+        let current = self.current_workspace;
+        self.workspaces[current].layout_settings = Some(self.layout.settings().clone());
+        for workspace in &mut self.workspaces {
+            if let Some(settings) = &mut workspace.layout_settings {
+                settings.rebase(
+                    &self.config.get_layout_settings(workspace.index),
+                    &config.get_layout_settings(workspace.index),
+                );
+            }
+        }
+        let settings = self.workspaces[current]
+            .layout_settings
+            .get_or_insert_with(|| config.get_layout_settings(current))
+            .clone();
+
+        self.remove_bars();
+        self.barriers = None;
+        self.bars = Self::create_bars(
+            &self.display,
+            root,
+            &config,
+            self.bar_visible.unwrap_or(config.appearance.bar.enabled),
+            &mut self.layout,
+        );
+        self.barriers = Self::create_barriers(&self.display, root, &config);
+        if !config.clipboard_manager {
+            self.clipboard = None;
+        } else if self.clipboard.is_none() {
+            self.clipboard =
+                unsafe { ClipboardManager::new(self.display.raw(), root, *self.display.atoms()) };
+        }
+        self.hot_corners = (!config.hot_corners.is_empty())
+            .then(|| HotCornerTracker::new(config.hot_corners.clone(), self.display.monitors()));
+        self.drag_throttle = Self::create_drag_throttle(&self.display, &config);
+
+        self.layout.update_config(config.clone());
+        self.layout.set_settings(settings);
+        let wallpaper_changed = config.wallpaper != self.config.wallpaper;
+        self.config = config;
+        self.paint_borders();
+        if self.kiosk_pid.is_none() {
+            self.start_kiosk_application();
+        }
+        if wallpaper_changed && self.wallpaper_path.is_none() {
+            self.show_wallpaper();
+        }
+        self.update_bar();
+    }
+
+    fn restart(&mut self) {
+        info!("Restarting window manager");
+        for workspace in &mut self.workspaces {
+            if workspace.index == self.current_workspace {
+                workspace.layout_settings = Some(self.layout.settings().clone());
+            }
+        }
 
-        unsafe {
-            self.notification_manager.raise_all();
+        let state = SessionState {
+            current_workspace: self.current_workspace,
+            workspaces: self
+                .workspaces
+                .iter()
+                .map(|ws| SavedWorkspace {
+                    index: ws.index,
+                    layout: ws.layout_settings.clone(),
+                    monitor: ws.monitor,
+                    windows: ws
+                        .windows
+                        .iter()
+                        .filter(|w| !w.is_dock)
+                        .map(|w| w.id)
+                        .collect(),
+                })
+                .collect(),
+            view: self.view,
+            bar_visible: self.bar_visible,
+            theme: self.theme.clone(),
+            wallpaper: self.wallpaper_path.clone(),
+            kiosk_pid: self.kiosk_pid,
+            floating: self
+                .workspaces
+                .iter()
+                .flat_map(|ws| &ws.windows)
+                .filter(|w| w.is_floating && !w.is_dock && !w.is_fullscreen)
+                .map(|w| SavedFloating {
+                    window: w.id,
+                    x: w.x,
+                    y: w.y,
+                    width: w.width,
+                    height: w.height,
+                    global: w.is_global,
+                })
+                .collect(),
+            tags: self
+                .workspaces
+                .iter()
+                .flat_map(|ws| &ws.windows)
+                .filter(|w| !w.is_dock && w.tags != 0)
+                .map(|w| SavedTags {
+                    window: w.id,
+                    tags: w.tags,
+                })
+                .collect(),
+        };
+
+        if let Err(e) = state.save() {
+            error!("Failed to save session state: {}", e);
         }
+
+        self.restart = true;
+        self.running = false;
     }
 
-    fn handle_motion_notify(&mut self, _event: xlib::XEvent) {
-        unsafe {
+    fn restore_session(&mut self, state: Option<SessionState>) {
+        if let Some(state) = &state {
+            self.restored_floating = state.floating.clone();
+
+            if let Some(visible) = state.bar_visible {
+                self.set_bar_visible(visible);
+            }
+            if let Some(theme) = &state.theme {
+                self.set_theme(theme);
+            }
+            self.wallpaper_path = state.wallpaper.clone();
+            self.kiosk_pid = state.kiosk_pid.filter(|_| self.config.kiosk.enabled);
+
+            for saved in &state.workspaces {
+                if let Some(workspace) = self.workspaces.get_mut(saved.index) {
+                    workspace.layout_settings = saved.layout.clone();
+                }
+            }
+
+            if state.current_workspace < self.workspaces.len() && !self.config.tag_mode {
+                self.current_workspace = state.current_workspace;
+                self.update_current_desktop();
+            }
+
+            let config = &self.config;
+            let index = self.current_workspace;
+            let settings = self.workspaces[index]
+                .layout_settings
+                .get_or_insert_with(|| config.get_layout_settings(index))
+                .clone();
+            self.layout.set_settings(settings);
+        }
+
+        let children = unsafe {
             let mut root_return: xlib::Window = 0;
-            let mut child_return: xlib::Window = 0;
-            let mut root_x: i32 = 0;
-            let mut root_y: i32 = 0;
-            let mut win_x: i32 = 0;
-            let mut win_y: i32 = 0;
-            let mut mask_return: u32 = 0;
-
-            xlib::XQueryPointer(
+            let mut parent_return: xlib::Window = 0;
+            let mut children: *mut xlib::Window = std::ptr::null_mut();
+            let mut num_children: u32 = 0;
+
+            if xlib::XQueryTree(
                 self.display.raw(),
                 self.layout.get_root(),
                 &mut root_return,
-                &mut child_return,
-                &mut root_x,
-                &mut root_y,
-                &mut win_x,
-                &mut win_y,
-                &mut mask_return,
-            );
+                &mut parent_return,
+                &mut children,
+                &mut num_children,
+            ) == 0
+                || children.is_null()
+            {
+                return;
+            }
 
-            if self.dragging {
-                if let Some(dragged) = self.dragged_window {
-                    let dx = root_x - self.drag_start_x;
-                    let dy = root_y - self.drag_start_y;
+            let list = std::slice::from_raw_parts(children, num_children as usize).to_vec();
+            xlib::XFree(children as *mut _);
+            list
+        };
 
-                    if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
-                        if let Some(window) = workspace.windows.iter_mut().find(|w| w.id == dragged)
-                        {
-                            if window.is_floating {
-                                let new_x = window.pre_float_x + dx;
-                                let new_y = window.pre_float_y + dy;
-                                window.x = new_x;
-                                window.y = new_y;
-                                xlib::XMoveWindow(self.display.raw(), window.id, new_x, new_y);
-                                self.raise_floating_windows();
-                                return;
-                            }
-                        }
-                    }
+        for window in children {
+            let Some(attrs) = self
+                .display
+                .window_attributes(window)
+                .filter(|attrs| attrs.override_redirect == 0)
+            else {
+                continue;
+            };
 
-                    if let Some(target) = child_return.checked_sub(0).filter(|_| {
-                        child_return != dragged
-                            && child_return != 0
-                            && child_return != self.layout.get_root()
-                    }) {
-                        debug!("Swapping windows {} and {}", dragged, target);
-                        self.layout.swap_windows(dragged, target);
-                        self.layout.relayout();
-                        xlib::XSync(self.display.raw(), 0);
-                        self.raise_floating_windows();
-                    }
-                }
-            } else if self.resizing {
-                if let Some(resized) = self.resized_window {
-                    let dx = root_x - self.drag_start_x;
-                    let dy = root_y - self.drag_start_y;
+            let known = state.as_ref().is_some_and(|s| s.contains_window(window));
+            if attrs.map_state == xlib::IsViewable || known {
+                debug!("Adopting existing window {}", window);
+                self.manage_window(window);
+            }
+        }
 
-                    if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
-                        if let Some(window) = workspace.windows.iter_mut().find(|w| w.id == resized)
-                        {
-                            if window.is_floating {
-                                let new_width =
-                                    ((self.resize_start_width as i32 + dx) as u32).max(100);
-                                let new_height =
-                                    ((self.resize_start_height as i32 + dy) as u32).max(100);
-                                window.width = new_width;
-                                window.height = new_height;
-                                xlib::XResizeWindow(
-                                    self.display.raw(),
-                                    window.id,
-                                    new_width,
-                                    new_height,
-                                );
-                                self.raise_floating_windows();
-                            }
-                        }
-                    }
+        // Adopting picked monitors as if the windows were new.
+        if let Some(state) = &state {
+            for saved in &state.workspaces {
+                if let Some(workspace) = self.workspaces.get_mut(saved.index) {
+                    workspace.monitor = saved.monitor;
                 }
-            } else if child_return != 0 && child_return != self.layout.get_root() {
-                self.layout.focus_window(child_return);
             }
+            self.layout
+                .set_monitor(self.workspace_monitor(self.current_workspace));
         }
-    }
-
-    fn handle_keypress(&mut self, event: xlib::XEvent) {
-        let key_event: xlib::XKeyEvent = From::from(event);
-
-        let binds = self.config.binds.clone();
-        for bind in &binds {
-            let keycode = unsafe {
-                xlib::XKeysymToKeycode(
-                    self.display.raw(),
-                    self.config.get_keysym_for_key(&bind.key),
-                )
-            };
 
-            if key_event.state & self.config.get_modifier() != 0
-                && key_event.keycode as u8 == keycode
-            {
-                match &bind.command {
-                    Command::Exit => self.running = false,
-                    Command::Close => self.close_focused_window(),
-                    Command::Spawn(cmd) => {
-                        if let Err(e) = ProcessCommand::new(cmd)
-                            .stdout(std::process::Stdio::null())
-                            .stderr(std::process::Stdio::null())
-                            .spawn()
-                        {
-                            if self.config.notifications_enabled {
-                                unsafe {
-                                    self.notification_manager
-                                        .show_error(&format!("Failed to spawn {}: {}", cmd, e));
-                                }
-                            }
-                        }
+        if self.config.tag_mode {
+            if let Some(state) = &state {
+                for saved in &state.tags {
+                    if let Some(window) = self.workspaces[0].find_mut(saved.window) {
+                        window.tags = saved.tags;
                     }
-                    Command::Workspace(idx) => self.switch_to_workspace(*idx),
-                    Command::ToggleFloat => self.toggle_float(),
-                    Command::ToggleFullscreen => self.toggle_fullscreen(),
+                }
+                if state.view != 0 {
+                    self.view = state.view;
+                    self.update_current_desktop();
                 }
             }
+            self.apply_view();
         }
     }
 
-    fn toggle_float(&mut self) {
-        unsafe {
-            let mut focused_win: xlib::Window = 0;
-            let mut revert_to: i32 = 0;
-            xlib::XGetInputFocus(self.display.raw(), &mut focused_win, &mut revert_to);
-
-            let mut actual_type: xlib::Atom = 0;
-            let mut actual_format: i32 = 0;
-            let mut nitems: u64 = 0;
-            let mut bytes_after: u64 = 0;
-            let mut data: *mut xlib::Window = std::ptr::null_mut();
-
-            let root = xlib::XDefaultRootWindow(self.display.raw());
-            xlib::XGetWindowProperty(
-                self.display.raw(),
-                root,
-                self.net_active_window,
-                0,
+    /// Grabs the modifier clicks that move and resize `window`, and every
+    /// click when focus doesn't follow the mouse, to focus it. Done when a
+    /// window is managed and again when the config changes.
+    unsafe fn grab_buttons(display: *mut xlib::Display, config: &Config, window: xlib::Window) {
+        xlib::XUngrabButton(display, xlib::AnyButton as u32, xlib::AnyModifier, window);
+        for button in [1, 3] {
+            xlib::XGrabButton(
+                display,
+                button,
+                config.get_modifier(),
+                window,
                 1,
+                (xlib::ButtonPressMask | xlib::ButtonReleaseMask | xlib::PointerMotionMask) as u32,
+                xlib::GrabModeAsync,
+                xlib::GrabModeAsync,
+                0,
                 0,
-                xlib::XA_WINDOW,
-                &mut actual_type,
-                &mut actual_format,
-                &mut nitems,
-                &mut bytes_after,
-                &mut data as *mut *mut xlib::Window as *mut *mut u8,
             );
+        }
 
-            let net_active_win = if !data.is_null() && nitems > 0 {
-                let win = *data;
-                xlib::XFree(data as *mut _);
-                win
-            } else {
-                0
+        if !config.appearance.focus_follows_mouse {
+            xlib::XGrabButton(
+                display,
+                xlib::AnyButton as u32,
+                0,
+                window,
+                1,
+                (xlib::ButtonPressMask | xlib::ButtonReleaseMask) as u32,
+                xlib::GrabModeSync,
+                xlib::GrabModeAsync,
+                0,
+                0,
+            );
+        }
+    }
+
+    unsafe fn setup_key_bindings(
+        display: *mut xlib::Display,
+        root: xlib::Window,
+        config: &Config,
+        game_mode: bool,
+    ) {
+        xlib::XUngrabKey(display, xlib::AnyKey, xlib::AnyModifier, root);
+
+        for bind in &config.binds {
+            if game_mode && !matches!(bind.command, Command::ToggleGameMode) {
+                continue;
+            }
+            // Leave the keys of disallowed binds to the kiosk application.
+            if !config.kiosk.allows(&bind.command) {
+                continue;
+            }
+
+            let keycode = xlib::XKeysymToKeycode(display, config.get_keysym_for_key(&bind.key));
+            // 0 is AnyKey, a key missing from the keymap must not grab them all.
+            if keycode == 0 {
+                warn!("Key '{}' is not on the keyboard, not binding it", bind.key);
+                continue;
+            }
+            xlib::XGrabKey(
+                display,
+                keycode as i32,
+                config.get_bind_modifier(bind),
+                root,
+                1,
+                xlib::GrabModeAsync,
+                xlib::GrabModeAsync,
+            );
+        }
+
+        xlib::XSync(display, 0);
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        // Children that exited while a restart was in progress.
+        self.reap_children();
+        self.signal_ready();
+
+        while self.step(None) {}
+
+        if self.config.session.save && !self.restart {
+            self.save_window_session();
+        }
+
+        if let Ok(path) = Config::get_ready_file_path() {
+            let _ = std::fs::remove_file(path);
+        }
+
+        Ok(())
+    }
+
+    fn next_event(&mut self) -> xlib::XEvent {
+        let mut event: xlib::XEvent = unsafe { std::mem::zeroed() };
+        unsafe {
+            xlib::XNextEvent(self.display.raw(), &mut event);
+            if event.get_type() == xlib::MotionNotify {
+                skip_to_latest_motion(self.display.raw(), &mut event);
+            }
+        }
+        event
+    }
+
+    fn handle_event(&mut self, event: xlib::XEvent) {
+        if self
+            .randr_event_base
+            .is_some_and(|base| event.get_type() == base + xrandr::RRScreenChangeNotify)
+        {
+            self.handle_screen_change(event);
+            return;
+        }
+        if event.get_type() == xlib::KeyRelease
+            && unsafe { is_auto_repeat(self.display.raw(), &event.key) }
+        {
+            self.repeating_key = Some(unsafe { event.key.keycode });
+            return;
+        }
+        let Some(event) = Event::from_xevent(&event) else {
+            return;
+        };
+
+        match event {
+            Event::KeyPress(key) => {
+                // The press that follows an auto-repeat release.
+                let repeat = self.repeating_key.take() == Some(key.keycode);
+                self.handle_key(key, false, repeat);
+            }
+            Event::KeyRelease(key) => self.handle_key(key, true, false),
+            Event::MapRequest { window } => self.handle_map_request(window),
+            Event::UnmapNotify { window, synthetic } => self.handle_unmap_notify(window, synthetic),
+            Event::DestroyNotify { window } => self.handle_destroy_notify(window),
+            Event::ConfigureRequest(request) => self.handle_configure_request(request),
+            Event::ConfigureNotify {
+                window,
+                border_width,
+            } => {
+                if self.border_width(window).is_some_and(|w| w != border_width) {
+                    debug!("Window {} changed its border width, restoring it", window);
+                    self.restore_border(window);
+                }
+            }
+            Event::MotionNotify(motion) => self.handle_motion_notify(motion),
+            Event::ButtonPress(button) => self.handle_button_press(button),
+            Event::ButtonRelease(_) => {
+                // The window ends up where the button was let go.
+                if let Some(motion) = self.drag_throttle.as_mut().and_then(MotionThrottle::flush) {
+                    self.apply_motion(motion);
+                }
+                if self.dragging {
+                    self.end_window_drag();
+                } else if self.resizing {
+                    self.end_window_resize();
+                } else if self.splitting {
+                    self.splitting = false;
+                }
+            }
+            Event::EnterNotify { window } => self.handle_enter_notify(window),
+            Event::Expose { window, .. } => self.handle_expose(window),
+            Event::ClientMessage(message) => self.handle_client_message(message),
+            Event::PropertyNotify { window, atom } => self.handle_property_notify(window, atom),
+            Event::Generic(mut cookie) => {
+                if let Some(barriers) = &mut self.barriers {
+                    unsafe {
+                        barriers.handle_event(&mut cookie);
+                    }
+                }
+            }
+            Event::SelectionRequest(request) => {
+                if let Some(clipboard) = &mut self.clipboard {
+                    unsafe { clipboard.handle_selection_request(request) };
+                }
+            }
+            Event::SelectionNotify(notify) => {
+                if let Some(clipboard) = &mut self.clipboard {
+                    unsafe { clipboard.handle_selection_notify(notify) };
+                }
+            }
+            Event::SelectionClear { window, selection } => {
+                if let Some(clipboard) = &mut self.clipboard {
+                    clipboard.handle_selection_clear(window, selection);
+                }
+            }
+            Event::LeaveNotify { window } => {
+                if self.bars.iter().any(|bar| bar.window == window) {
+                    self.hide_workspace_preview();
+                }
+            }
+        }
+    }
+
+    /// Runs one iteration of the event loop, for hosts that drive the loop
+    /// themselves. Handles the next X event if there is one; otherwise
+    /// waits up to `timeout` (or until a timer is due when `None`) and
+    /// takes care of children, IPC, config changes and timers.
+    ///
+    /// Returns whether the window manager is still running. Unlike `run`,
+    /// this neither writes the ready file nor saves the session on exit.
+    pub fn step(&mut self, timeout: Option<Duration>) -> bool {
+        if !self.running {
+            return false;
+        }
+
+        if unsafe { xlib::XPending(self.display.raw()) } == 0 {
+            self.wait_for_event(timeout);
+            self.reap_children();
+            self.handle_ipc();
+            if self.config_watcher.as_mut().is_some_and(|w| w.changed()) {
+                info!("Config file changed");
+                self.reload_config();
+            }
+            self.run_timers();
+        } else {
+            let event = self.next_event();
+            self.handle_event(event);
+        }
+        self.remove_empty_workspaces();
+        self.flush_layout();
+
+        self.running
+    }
+
+    /// The X connection, for hosts that poll it along with their own
+    /// descriptors before calling `step`.
+    pub fn connection_fd(&self) -> RawFd {
+        unsafe { xlib::XConnectionNumber(self.display.raw()) }
+    }
+
+    /// Handles every event the server has sent so far without blocking.
+    pub fn dispatch_pending(&mut self) {
+        unsafe {
+            xlib::XSync(self.display.raw(), 0);
+        }
+        while unsafe { xlib::XPending(self.display.raw()) } > 0 {
+            let event = self.next_event();
+            self.handle_event(event);
+        }
+        self.reap_children();
+        self.run_timers();
+        self.remove_empty_workspaces();
+        self.flush_layout();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// The workspace shown on screen (always 0 in tag mode).
+    pub fn current_workspace(&self) -> usize {
+        self.current_workspace
+    }
+
+    pub fn focused_window(&self) -> Option<xlib::Window> {
+        self.focus.focused()
+    }
+
+    /// The windows of a workspace, in stacking order of the layout.
+    pub fn workspace_windows(&self, idx: usize) -> Vec<xlib::Window> {
+        self.workspaces
+            .get(idx)
+            .map(|ws| ws.windows.iter().map(|w| w.id).collect())
+            .unwrap_or_default()
+    }
+
+    /// Blocks until the X connection is readable, the next timer is due or
+    /// `timeout` has passed.
+    fn wait_for_event(&self, timeout: Option<Duration>) {
+        let timeout = [
+            timeout,
+            self.hot_corners.as_ref().and_then(|hc| hc.timeout()),
+            self.startup.timeout(),
+            self.kiosk_restart_at
+                .map(|at| at.saturating_duration_since(Instant::now())),
+            self.slide.as_ref().map(WorkspaceSlide::timeout),
+            self.clipboard.as_ref().and_then(ClipboardManager::timeout),
+            self.drag_throttle
+                .as_ref()
+                .and_then(MotionThrottle::timeout),
+            self.bars.iter().filter_map(StatusBar::timeout).min(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .map_or(-1, |t| t.as_millis().min(i32::MAX as u128) as i32);
+
+        let mut fds = [
+            unsafe { xlib::XConnectionNumber(self.display.raw()) },
+            self.processes.signal_fd(),
+            self.ipc.as_ref().map_or(-1, IpcServer::fd),
+            self.config_watcher.as_ref().map_or(-1, ConfigWatcher::fd),
+        ]
+        .map(|fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        });
+        unsafe {
+            libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout);
+        }
+    }
+
+    fn handle_ipc(&mut self) {
+        let Some(ipc) = &mut self.ipc else {
+            return;
+        };
+
+        for request in ipc.accept() {
+            debug!("IPC request: {}", request.message);
+            let response = self.handle_ipc_message(&request.message);
+            request.reply(response);
+        }
+    }
+
+    fn handle_ipc_message(&mut self, message: &str) -> Result<String> {
+        let (name, arg) = message.split_once(' ').unwrap_or((message, ""));
+        match name {
+            "log_level" => {
+                let spec = arg.trim().trim_matches('"');
+                logging::set_filter(spec)?;
+                info!("Log level set to {}", spec);
+                Ok(String::new())
+            }
+            "windows" => Ok(self.describe_windows()),
+            "set_wallpaper" => {
+                let path = arg.trim().trim_matches('"');
+                self.load_wallpaper(path)?;
+                self.wallpaper_path = Some(path.to_string());
+                Ok(String::new())
+            }
+            _ => {
+                let command = message.parse::<Command>().map_err(|e| anyhow!(e))?;
+                if !self.config.kiosk.allows(&command) {
+                    bail!("not allowed in kiosk mode");
+                }
+                self.execute_command(&command);
+                Ok(String::new())
+            }
+        }
+    }
+
+    /// The managed windows as a JSON array for the `windows` IPC query, e.g.
+    /// `[{"window":41943047,"workspace":1,"pid":1234,"title":"vim","focused":true}]`.
+    /// Workspaces are 1-based like in the event stream.
+    fn describe_windows(&self) -> String {
+        let focused = self.focus.focused();
+        let windows = self
+            .workspaces
+            .iter()
+            .flat_map(|ws| &ws.windows)
+            .filter(|window| !window.is_dock)
+            .map(|window| {
+                // The same workspace (or tag) hooks and _NET_WM_DESKTOP
+                // name, none for global windows.
+                let workspace = self
+                    .window_desktop(window.id)
+                    .filter(|_| !window.is_global)
+                    .map_or(String::from("null"), |index| (index + 1).to_string());
+                format!(
+                    r#"{{"window":{},"workspace":{},"pid":{},"title":{},"focused":{}}}"#,
+                    window.id,
+                    workspace,
+                    window
+                        .pid
+                        .map_or(String::from("null"), |pid| pid.to_string()),
+                    ipc::json_string(&self.display.window_title(window.id).unwrap_or_default()),
+                    focused == Some(window.id)
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", windows.join(","))
+    }
+
+    /// Opens the config file in `$VISUAL` or `$EDITOR`, or with `xdg-open`
+    /// when neither is set.
+    fn open_config(&mut self) {
+        let path = match Config::get_config_path() {
+            Ok(path) => path,
+            Err(e) => {
+                error!("Failed to open config: {:#}", e);
+                return;
+            }
+        };
+
+        let mut command = std::process::Command::new("sh");
+        command
+            .arg("-c")
+            .arg(r#"exec ${VISUAL:-${EDITOR:-xdg-open}} "$1""#)
+            .arg("sh")
+            .arg(&path)
+            .stdout(std::process::Stdio::null());
+        if let Err(e) = self.processes.spawn(&mut command, "editor") {
+            error!("Failed to open config: {}", e);
+            if self.config.notifications_enabled {
+                unsafe {
+                    self.notification_manager
+                        .show_error(&format!("Failed to open config: {}", e));
+                }
+            }
+        }
+    }
+
+    fn reap_children(&mut self) {
+        for exit in self.processes.reap() {
+            if self.kiosk_pid == Some(exit.pid) {
+                warn!("Kiosk application exited with {}", exit.describe_status());
+                self.kiosk_pid = None;
+                let delay = match exit.failed_early() {
+                    true => KIOSK_RESTART_DELAY,
+                    false => Duration::ZERO,
+                };
+                self.kiosk_restart_at = Some(Instant::now() + delay);
+            }
+            if self.startup.cancel(exit.pid) {
+                self.update_launch_cursor();
+            }
+
+            if !exit.failed_early() {
+                info!(
+                    "{} (pid {}) exited with {}",
+                    exit.command,
+                    exit.pid,
+                    exit.describe_status()
+                );
+                continue;
+            }
+
+            warn!(
+                "{} failed with {}: {}",
+                exit.command,
+                exit.describe_status(),
+                exit.stderr
+            );
+            if self.config.notifications_enabled {
+                let lines = exit.stderr.lines().collect::<Vec<_>>();
+                let mut message =
+                    format!("{} failed with {}", exit.command, exit.describe_status());
+                for line in &lines[lines.len().saturating_sub(5)..] {
+                    message.push('\n');
+                    message.push_str(line);
+                }
+                unsafe {
+                    self.notification_manager
+                        .show_error_with_actions(&message, &[NotificationAction::DismissAll]);
+                }
+            }
+        }
+    }
+
+    fn run_timers(&mut self) {
+        if let Some(slide) = &mut self.slide {
+            if !unsafe { slide.poll() } {
+                self.slide = None;
+            }
+        }
+
+        if self.kiosk_restart_at.is_some_and(|at| at <= Instant::now()) {
+            self.start_kiosk_application();
+        }
+
+        if self.startup.expire() {
+            self.update_launch_cursor();
+        }
+
+        if let Some(clipboard) = &mut self.clipboard {
+            unsafe { clipboard.expire() };
+        }
+
+        if let Some(motion) = self.drag_throttle.as_mut().and_then(MotionThrottle::poll) {
+            self.apply_motion(motion);
+        }
+
+        if let Some(command) = self.hot_corners.as_mut().and_then(|hc| hc.poll()) {
+            debug!("Hot corner triggered {:?}", command);
+            self.execute_command(&command);
+        }
+
+        let mut changed = false;
+        for bar in &mut self.bars {
+            changed |= bar.poll();
+        }
+        if changed {
+            self.update_bar();
+        }
+    }
+
+    /// Starts `kiosk.application` when kiosk mode has one.
+    fn start_kiosk_application(&mut self) {
+        self.kiosk_restart_at = None;
+        let kiosk = &self.config.kiosk;
+        let Some(application) = kiosk.application.as_ref().filter(|_| kiosk.enabled) else {
+            return;
+        };
+
+        info!("Starting kiosk application: {}", application);
+        let spawn = Spawn::new(application);
+        match self.processes.spawn(
+            spawn.to_process().stdout(std::process::Stdio::null()),
+            application,
+        ) {
+            Ok(pid) => self.kiosk_pid = Some(pid),
+            Err(e) => {
+                error!("Failed to start kiosk application {}: {}", application, e);
+                self.kiosk_restart_at = Some(Instant::now() + KIOSK_RESTART_DELAY);
+            }
+        }
+    }
+
+    /// Whether `window` belongs to the running kiosk application.
+    fn is_kiosk_window(&self, window: xlib::Window) -> bool {
+        self.kiosk_pid.is_some()
+            && unsafe { get_window_pid(self.display.raw(), self.display.atoms(), window) }
+                == self.kiosk_pid
+    }
+
+    fn signal_ready(&mut self) {
+        unsafe {
+            xlib::XSync(self.display.raw(), 0);
+        }
+
+        let ready_file = match Config::get_ready_file_path() {
+            Ok(path) => path,
+            Err(e) => {
+                error!("Failed to get ready file path: {}", e);
+                return;
+            }
+        };
+
+        if let Some(parent) = ready_file.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Err(e) = std::fs::write(&ready_file, std::process::id().to_string()) {
+            error!("Failed to write ready file: {}", e);
+        }
+        info!("Window manager ready");
+
+        if self.kiosk_pid.is_none() {
+            self.start_kiosk_application();
+        }
+        if self.restored_session {
+            return;
+        }
+
+        if self.config.session.relaunch {
+            for command in self.window_session.commands() {
+                info!("Relaunching saved application: {}", command);
+                if let Err(e) = self.processes.spawn(
+                    ProcessCommand::new("sh")
+                        .arg("-c")
+                        .arg(&command)
+                        .stdout(std::process::Stdio::null()),
+                    &command,
+                ) {
+                    error!("Failed to relaunch {}: {}", command, e);
+                }
+            }
+        }
+
+        if let Some(script) = self.config.startup_script.clone() {
+            info!("Running startup script: {}", script);
+            if let Err(e) = self.processes.spawn(
+                ProcessCommand::new("sh")
+                    .arg("-c")
+                    .arg(&script)
+                    .env("VELOWM_READY_FILE", &ready_file)
+                    .stdout(std::process::Stdio::null()),
+                "startup script",
+            ) {
+                error!("Failed to run startup script: {}", e);
+                if self.config.notifications_enabled {
+                    unsafe {
+                        self.notification_manager
+                            .show_error(&format!("Failed to run startup script: {}", e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Shows the busy cursor on the root window while launches are pending.
+    fn update_launch_cursor(&self) {
+        let cursor = if self.startup.is_busy() {
+            self.cursor.busy()
+        } else {
+            self.cursor.normal()
+        };
+        unsafe {
+            xlib::XDefineCursor(self.display.raw(), self.layout.get_root(), cursor);
+        }
+    }
+
+    fn save_window_session(&self) {
+        let mut session = WindowSession::default();
+        for workspace in &self.workspaces {
+            for window in workspace.windows.iter().filter(|w| !w.is_dock) {
+                let Some((instance, class)) =
+                    (unsafe { get_class_hint(self.display.raw(), window.id) })
+                else {
+                    continue;
+                };
+
+                let desktop = if self.config.tag_mode {
+                    window.tags.trailing_zeros() as usize
+                } else {
+                    workspace.index
+                };
+                let command =
+                    unsafe { get_window_pid(self.display.raw(), self.display.atoms(), window.id) }
+                        .and_then(session::read_command);
+                let geometry = window.is_floating.then_some(window);
+
+                session.windows.push(Rule {
+                    class: Some(class),
+                    instance: Some(instance),
+                    command,
+                    workspace: Some(desktop + 1),
+                    floating: Some(window.is_floating),
+                    x: geometry.map(|w| Length::Pixels(w.x)),
+                    y: geometry.map(|w| Length::Pixels(w.y)),
+                    width: geometry.map(|w| Length::Pixels(w.width as i32)),
+                    height: geometry.map(|w| Length::Pixels(w.height as i32)),
+                    ..Default::default()
+                });
+            }
+        }
+
+        info!("Saving placement of {} windows", session.windows.len());
+        if let Err(e) = session.save() {
+            error!("Failed to save window session: {}", e);
+        }
+    }
+
+    /// Finds the rule for a new window. Configured rules come first, then the
+    /// placement saved from the previous session.
+    fn match_rule(&mut self, window_id: xlib::Window) -> Option<Rule> {
+        // Floating windows adopted after a restart keep their geometry.
+        if let Some(i) = self
+            .restored_floating
+            .iter()
+            .position(|saved| saved.window == window_id)
+        {
+            return Some(self.restored_floating.swap_remove(i).rule());
+        }
+
+        let (instance, class) = unsafe { get_class_hint(self.display.raw(), window_id) }?;
+        let props = WindowProperties {
+            class,
+            instance,
+            title: unsafe { get_window_title(self.display.raw(), window_id) }.unwrap_or_default(),
+        };
+
+        let rule = self
+            .config
+            .rules
+            .iter()
+            .find(|rule| rule.matches(&props))
+            .cloned()
+            .or_else(|| self.window_session.take_match(&props));
+        if let Some(rule) = &rule {
+            debug!("Window {} matched rule {:?}", window_id, rule);
+        }
+        rule
+    }
+
+    /// Restacks the current workspace: floating windows above tiled ones,
+    /// global ones above those, then docks and the bar, and fullscreen
+    /// windows on top of everything. Docks and the bar stay below a
+    /// fullscreen window they overlap.
+    fn raise_floating_windows(&mut self) {
+        if let Some(game_window) = self.game_mode {
+            unsafe {
+                xlib::XRaiseWindow(self.display.raw(), game_window);
+            }
+            return;
+        }
+
+        let fullscreen = self.fullscreen_areas();
+        let covered = |area: Monitor| fullscreen.iter().any(|(_, f)| f.intersects(&area));
+
+        if let Some(workspace) = self.workspaces.get(self.current_workspace) {
+            // Global windows stay above the floating windows of each workspace.
+            let (global, local): (Vec<_>, Vec<_>) =
+                workspace.windows.iter().partition(|w| w.is_global);
+            for window in local.into_iter().chain(global) {
+                if window.is_floating
+                    && !window.is_dock
+                    && !window.is_fullscreen
+                    && Some(window.id) != self.dragged_window
+                {
+                    unsafe {
+                        xlib::XRaiseWindow(self.display.raw(), window.id);
+                    }
+                }
+            }
+
+            if let Some(dragged) = self.dragged_window {
+                if let Some(window) = workspace.find(dragged) {
+                    if window.is_floating {
+                        unsafe {
+                            xlib::XRaiseWindow(self.display.raw(), dragged);
+                        }
+                    }
+                }
+            }
+
+            for window in &workspace.windows {
+                if window.is_dock && !covered(Self::window_area(window)) {
+                    unsafe {
+                        xlib::XRaiseWindow(self.display.raw(), window.id);
+                    }
+                }
+            }
+        }
+
+        for bar in &self.bars {
+            let area = self
+                .display
+                .window_attributes(bar.window)
+                .map(|attrs| Monitor {
+                    x: attrs.x,
+                    y: attrs.y,
+                    width: attrs.width as u32,
+                    height: attrs.height as u32,
+                });
+            if !area.is_some_and(covered) {
+                unsafe {
+                    xlib::XRaiseWindow(self.display.raw(), bar.window);
+                }
+            }
+        }
+
+        for (window, _) in fullscreen {
+            unsafe {
+                xlib::XRaiseWindow(self.display.raw(), window);
+            }
+        }
+
+        self.raise_notifications();
+    }
+
+    /// Fullscreen windows on the current workspace and the area they cover.
+    fn fullscreen_areas(&self) -> Vec<(xlib::Window, Monitor)> {
+        self.workspaces
+            .get(self.current_workspace)
+            .map(|ws| {
+                ws.windows
+                    .iter()
+                    .filter(|w| w.is_fullscreen)
+                    .map(|w| (w.id, Self::window_area(w)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn window_area(window: &Window) -> Monitor {
+        Monitor {
+            x: window.x,
+            y: window.y,
+            width: window.width,
+            height: window.height,
+        }
+    }
+
+    fn raise_notifications(&self) {
+        if self.game_mode.is_none() {
+            unsafe {
+                self.notification_manager.raise_all();
+            }
+        }
+    }
+
+    /// Lays out the windows if needed and puts the split handle over the
+    /// gap between master and stack. The handle is left out where a
+    /// fullscreen window or game mode covers the screen.
+    fn flush_layout(&mut self) {
+        self.layout.flush();
+
+        let fullscreen = self.fullscreen_areas();
+        let gap = self
+            .layout
+            .split_gap()
+            .filter(|_| self.game_mode.is_none())
+            .filter(|gap| {
+                let gap = Monitor::from(*gap);
+                !fullscreen.iter().any(|(_, area)| area.intersects(&gap))
+            });
+        unsafe {
+            self.split_handle.place(gap);
+        }
+    }
+
+    fn handle_motion_notify(&mut self, event: MotionEvent) {
+        let event = match &mut self.drag_throttle {
+            Some(throttle) if self.dragging || self.resizing || self.splitting => {
+                throttle.offer(event)
+            }
+            _ => Some(event),
+        };
+        if let Some(event) = event {
+            self.apply_motion(event);
+        }
+    }
+
+    /// Moves or resizes the dragged window to follow the pointer, or
+    /// handles hot corners and focus follows mouse outside of drags.
+    fn apply_motion(&mut self, event: MotionEvent) {
+        // Hot corners still see the motion over the bar.
+        if self.bars.iter().any(|bar| bar.window == event.window) {
+            self.preview_workspace(event.window, event.x_root);
+        }
+
+        unsafe {
+            let (root_x, root_y) = (event.x_root, event.y_root);
+
+            if !self.dragging && !self.resizing && !self.splitting && self.game_mode.is_none() {
+                if let Some(hot_corners) = &mut self.hot_corners {
+                    hot_corners.update(root_x, root_y);
+                }
+            }
+
+            if self.dragging {
+                if let Some(dragged) = self.dragged_window {
+                    let dx = root_x - self.drag_start_x;
+                    let dy = root_y - self.drag_start_y;
+
+                    if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
+                        if let Some(window) = workspace.find_mut(dragged) {
+                            if window.is_floating {
+                                let new_x = self.drag_window_x + dx;
+                                let new_y = self.drag_window_y + dy;
+                                window.x = new_x;
+                                window.y = new_y;
+                                xlib::XMoveWindow(self.display.raw(), window.id, new_x, new_y);
+                                self.raise_floating_windows();
+                                return;
+                            }
+                        }
+                    }
+
+                    // Tiled windows only swap on release, until then the
+                    // window they would swap with is outlined.
+                    let target = self.display.query_pointer().child;
+                    if self
+                        .drop_indicator
+                        .as_ref()
+                        .is_some_and(|indicator| indicator.contains(target))
+                    {
+                        return;
+                    }
+                    let area = self
+                        .layout
+                        .window_geometry(target)
+                        .filter(|_| target != dragged);
+                    self.drop_target = area.map(|_| target);
+
+                    let border = self.config.appearance.border_width;
+                    let (display, root) = (self.display.raw(), self.display.root());
+                    let color = self.config.get_focused_border_color();
+                    self.drop_indicator
+                        .get_or_insert_with(|| DropIndicator::new(display, root, color))
+                        .show(area.map(|area| Geometry {
+                            width: area.width + 2 * border,
+                            height: area.height + 2 * border,
+                            ..area
+                        }));
+                }
+            } else if self.resizing {
+                if let Some(resized) = self.resized_window {
+                    let dx = root_x - self.drag_start_x;
+                    let dy = root_y - self.drag_start_y;
+
+                    if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
+                        if let Some(window) = workspace.find_mut(resized) {
+                            if window.is_floating {
+                                let resize = |start: u32, delta: i32, side: ResizeSide| {
+                                    let size = match side {
+                                        ResizeSide::Start => start as i32 - delta,
+                                        ResizeSide::End => start as i32 + delta,
+                                        ResizeSide::Fixed => start as i32,
+                                    };
+                                    size.max(1) as u32
+                                };
+                                let ResizeEdges {
+                                    horizontal,
+                                    vertical,
+                                } = self.resize_edges;
+
+                                let (new_width, new_height) = self.resize_hints.constrain(
+                                    resize(self.resize_start_width, dx, horizontal),
+                                    resize(self.resize_start_height, dy, vertical),
+                                );
+                                // Left and top resizes keep the right and bottom edges in place.
+                                if horizontal == ResizeSide::Start {
+                                    window.x = self.resize_start_x + self.resize_start_width as i32
+                                        - new_width as i32;
+                                }
+                                if vertical == ResizeSide::Start {
+                                    window.y = self.resize_start_y
+                                        + self.resize_start_height as i32
+                                        - new_height as i32;
+                                }
+                                window.width = new_width;
+                                window.height = new_height;
+                                xlib::XMoveResizeWindow(
+                                    self.display.raw(),
+                                    window.id,
+                                    window.x,
+                                    window.y,
+                                    new_width,
+                                    new_height,
+                                );
+
+                                let (x, y) = (window.x, window.y);
+                                self.raise_floating_windows();
+
+                                if let Some(popup) = &self.size_popup {
+                                    let (columns, rows) = self
+                                        .resize_hints
+                                        .cells(new_width, new_height)
+                                        .unwrap_or((new_width, new_height));
+                                    popup.show(
+                                        &format!("{}x{}", columns, rows),
+                                        x,
+                                        y,
+                                        new_width,
+                                        new_height,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            } else if self.splitting {
+                self.layout.set_split(root_x);
+            } else if self.config.appearance.focus_follows_mouse
+                && self.game_mode.is_none()
+                && self.slide.is_none()
+            {
+                let child = self.display.query_pointer().child;
+                if child != 0
+                    && child != self.layout.get_root()
+                    && child != self.split_handle.window
+                    && !self.focus.is_focused(child)
+                {
+                    self.focus_window(child);
+                }
+            }
+        }
+    }
+
+    /// Runs the binds of a key press, or of a release when `release` is
+    /// set. `repeat` is set for presses sent by auto-repeat.
+    fn handle_key(&mut self, key_event: KeyEvent, release: bool, repeat: bool) {
+        let binds = self.config.binds.clone();
+        for bind in &binds {
+            if self.game_mode.is_some() && !matches!(bind.command, Command::ToggleGameMode) {
+                continue;
+            }
+            if bind.on_release != release || (repeat && !bind.repeat) {
+                continue;
+            }
+
+            let keycode = unsafe {
+                xlib::XKeysymToKeycode(
+                    self.display.raw(),
+                    self.config.get_keysym_for_key(&bind.key),
+                )
+            };
+
+            if key_event.state & MODIFIER_MASK == self.config.get_bind_modifier(bind)
+                && key_event.keycode as u8 == keycode
+            {
+                if bind.confirm && !self.confirm(&bind.command) {
+                    continue;
+                }
+                self.execute_command(&bind.command);
+            }
+        }
+    }
+
+    /// Asks whether to run `command` in a dialog on the pointer's monitor.
+    fn confirm(&self, command: &Command) -> bool {
+        let question = match command {
+            Command::Exit => "Exit velowm?",
+            Command::Restart => "Restart velowm?",
+            Command::Close => "Close the window?",
+            Command::Kill => "Kill the window's client?",
+            _ => "Run this bind?",
+        };
+        let confirmed = unsafe {
+            confirm(
+                self.display.raw(),
+                self.display.root(),
+                self.display.pointer_monitor(),
+                question,
+                self.config.appearance.get_notification_background_color(),
+                self.config.appearance.get_notification_border_color(),
+            )
+        };
+        debug!("{} {}", question, if confirmed { "Yes" } else { "No" });
+        confirmed
+    }
+
+    /// Lists the binds by category until a key is pressed. Binds kiosk mode
+    /// turns off are left out.
+    fn show_binds(&self) {
+        let mut groups: Vec<(&str, Vec<(String, String)>)> = Vec::new();
+        for bind in &self.config.binds {
+            if !self.config.kiosk.allows(&bind.command) {
+                continue;
+            }
+            let modifier = bind.modifier(&self.config.modifier);
+            let keys = match modifier.is_empty() {
+                true => bind.key.clone(),
+                false => format!("{}+{}", modifier, bind.key),
+            };
+            let entry = (keys, bind.command.to_string());
+            let category = bind.command.category();
+            match groups.iter_mut().find(|(name, _)| *name == category) {
+                Some((_, binds)) => binds.push(entry),
+                None => groups.push((category, vec![entry])),
+            }
+        }
+
+        unsafe {
+            show_binds(
+                self.display.raw(),
+                self.display.root(),
+                self.display.pointer_monitor(),
+                &groups,
+                self.config.appearance.get_notification_background_color(),
+                self.config.appearance.get_notification_border_color(),
+            );
+        }
+    }
+
+    pub fn execute_command(&mut self, command: &Command) {
+        if !self.config.kiosk.allows(command) {
+            debug!("Kiosk mode doesn't allow {:?}", command);
+            return;
+        }
+        match command {
+            Command::Sequence(commands) => {
+                for command in commands {
+                    self.execute_command(command);
+                }
+            }
+            Command::Exit => self.running = false,
+            Command::ShowBinds => self.show_binds(),
+            Command::Restart => self.restart(),
+            Command::ReloadConfig => self.reload_config(),
+            Command::SetTheme(name) => self.set_theme(name),
+            Command::Mark(mark) => self.mark_focused_window(*mark),
+            Command::GotoMark(mark) => {
+                let window = self
+                    .workspaces
+                    .iter()
+                    .flat_map(|ws| &ws.windows)
+                    .find(|w| w.mark == Some(*mark))
+                    .map(|w| w.id);
+                if let Some(window) = window {
+                    self.show_window(window);
+                }
+            }
+            Command::Screenshot(target) => self.take_screenshot(*target),
+            Command::Close => self.close_window(false),
+            Command::Kill => self.close_window(true),
+            Command::CenterWindow => self.place_focused_floating(|area, w, h, _| area.center(w, h)),
+            Command::MoveToCorner(corner) => {
+                let margin = self.layout.settings().gaps;
+                self.place_focused_floating(|area, w, h, _| area.corner(*corner, w, h, margin));
+            }
+            Command::MoveToMonitor(index) => {
+                let Some(target) = self.display.monitors().get(*index).copied() else {
+                    return;
+                };
+                let target = self.work_area(target);
+                self.place_focused_floating(|area, w, h, (x, y)| {
+                    // Same spot relative to the monitor, kept inside it.
+                    let max_x = (target.width as i32 - w as i32).max(0);
+                    let max_y = (target.height as i32 - h as i32).max(0);
+                    (
+                        target.x + (x - area.x).clamp(0, max_x),
+                        target.y + (y - area.y).clamp(0, max_y),
+                    )
+                });
+            }
+            Command::MoveWorkspaceToMonitor(target) => self.move_workspace_to_monitor(target),
+            Command::MoveWindowToMonitor(target) => self.move_window_to_monitor(target),
+            Command::FocusPrimaryMonitor => self.focus_primary_monitor(),
+            Command::GrowWidth => self.resize_focused_floating(1, 0),
+            Command::ShrinkWidth => self.resize_focused_floating(-1, 0),
+            Command::GrowHeight => self.resize_focused_floating(0, 1),
+            Command::ShrinkHeight => self.resize_focused_floating(0, -1),
+            Command::FloatAll => self.set_all_floating(true),
+            Command::TileAll => self.set_all_floating(false),
+            Command::Spawn(spawn) => {
+                let startup_id = self.startup.next_id(spawn.program());
+                match self.processes.spawn(
+                    spawn
+                        .to_process()
+                        .env("DESKTOP_STARTUP_ID", &startup_id)
+                        .stdout(std::process::Stdio::null()),
+                    &spawn.command,
+                ) {
+                    Ok(pid) => {
+                        self.startup.begin(startup_id, pid, self.current_desktop());
+                        self.update_launch_cursor();
+                    }
+                    Err(e) => {
+                        if self.config.notifications_enabled {
+                            unsafe {
+                                self.notification_manager.show_error_with_actions(
+                                    &format!("Failed to spawn {}: {}", spawn.command, e),
+                                    &[NotificationAction::DismissAll],
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            Command::Workspace(idx) => self.switch_to_workspace(*idx),
+            Command::NewWorkspace => self.new_workspace(),
+            Command::NextWorkspace | Command::PreviousWorkspace => {
+                let count = self.workspaces.len();
+                let step = if matches!(command, Command::NextWorkspace) {
+                    1
+                } else {
+                    count - 1
+                };
+                self.switch_to_workspace((self.current_desktop() + step) % count);
+            }
+            Command::MoveToWorkspace(idx) => {
+                if let Some(window) = self.focus.focused() {
+                    self.move_window_to_workspace(window, *idx);
+                }
+            }
+            Command::ToggleFloat => self.toggle_float(),
+            Command::ToggleGlobal => self.toggle_global(),
+            Command::ToggleFullscreen => self.toggle_fullscreen(),
+            Command::FullscreenAllMonitors => self.toggle_fullscreen_span(true),
+            Command::IncreaseMasters => self.layout.adjust_nmaster(1),
+            Command::DecreaseMasters => self.layout.adjust_nmaster(-1),
+            Command::ToggleGameMode => self.toggle_game_mode(),
+            Command::IncreaseMasterRatio => self.layout.adjust_master_ratio(0.05),
+            Command::DecreaseMasterRatio => self.layout.adjust_master_ratio(-0.05),
+            Command::IncreaseGaps => self.layout.adjust_gaps(2),
+            Command::DecreaseGaps => self.layout.adjust_gaps(-2),
+            Command::ResetLayout => {
+                let settings = self.config.get_layout_settings(self.current_workspace);
+                self.layout.set_settings(settings);
+                self.update_bar();
+            }
+            Command::RotateStackClockwise => self.layout.rotate_windows(true),
+            Command::RotateStackCounterClockwise => self.layout.rotate_windows(false),
+            Command::ReverseStack => self.layout.reverse_windows(),
+            Command::CycleLayout => {
+                self.layout.cycle_kind();
+                self.update_bar();
+            }
+            Command::ToggleBar => self.set_bar_visible(self.bars.is_empty()),
+            Command::ViewTag(idx) => self.switch_to_workspace(*idx),
+            Command::ToggleViewTag(idx) => {
+                if self.config.tag_mode && *idx < self.workspaces.len() {
+                    self.set_view(self.view ^ (1 << idx));
+                }
+            }
+            Command::TagWindow(idx) => {
+                if let Some(window) = self.focus.focused() {
+                    self.move_window_to_workspace(window, *idx);
+                }
+            }
+            Command::ToggleWindowTag(idx) => {
+                let tagged = self.config.tag_mode && *idx < self.workspaces.len();
+                if let Some(window) = self.focus.focused().filter(|_| tagged) {
+                    let tags = self.workspaces[0].find(window).map_or(0, |w| w.tags);
+                    self.set_window_tags(window, tags ^ (1 << idx));
+                }
+            }
+        }
+    }
+
+    fn toggle_float(&mut self) {
+        let Some(window_id) = self.focus.focused() else {
+            return;
+        };
+        let Some(is_floating) = self
+            .workspaces
+            .get(self.current_workspace)
+            .and_then(|ws| ws.find(window_id))
+            .map(|w| w.is_floating)
+        else {
+            return;
+        };
+
+        if is_floating {
+            self.set_global(window_id, false);
+        }
+        let center = self.config.appearance.floating.center_on_float;
+        unsafe {
+            self.set_floating(window_id, !is_floating, center);
+            self.layout.relayout();
+            self.focus_window(window_id);
+            if !is_floating {
+                xlib::XRaiseWindow(self.display.raw(), window_id);
+            }
+            self.raise_floating_windows();
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
+
+    /// Makes the focused window global: it floats above the tiled windows
+    /// and comes along to whichever workspace is switched to.
+    fn toggle_global(&mut self) {
+        let Some(window_id) = self.focus.focused() else {
+            return;
+        };
+        let Some((is_global, is_floating)) = self
+            .workspaces
+            .get(self.current_workspace)
+            .and_then(|ws| ws.find(window_id))
+            .filter(|w| !w.is_dock)
+            .map(|w| (w.is_global, w.is_floating))
+        else {
+            return;
+        };
+
+        if !is_global && !is_floating {
+            let center = self.config.appearance.floating.center_on_float;
+            unsafe {
+                self.set_floating(window_id, true, center);
+            }
+            self.layout.relayout();
+        }
+        self.set_global(window_id, !is_global);
+        self.raise_floating_windows();
+        self.display.sync();
+    }
+
+    fn set_global(&mut self, window_id: xlib::Window, global: bool) {
+        let Some(window) = self
+            .workspaces
+            .get_mut(self.current_workspace)
+            .and_then(|ws| ws.find_mut(window_id))
+        else {
+            return;
+        };
+        if window.is_global == global {
+            return;
+        }
+
+        info!("Setting window {} global: {}", window_id, global);
+        window.is_global = global;
+        let desktop = match global {
+            true => ALL_DESKTOPS,
+            false if self.config.tag_mode => window.tags.trailing_zeros() as u64,
+            false => self.current_workspace as u64,
+        };
+        self.set_window_desktop(window_id, desktop);
+        self.paint_border(window_id);
+    }
+
+    /// Moves the focused floating window to the position `place` picks. It
+    /// gets the usable area of the window's monitor, the window's outer
+    /// size including borders and its current position.
+    fn place_focused_floating(
+        &mut self,
+        place: impl FnOnce(Monitor, u32, u32, (i32, i32)) -> (i32, i32),
+    ) {
+        let Some(window) = self.focused_floating_window() else {
+            return;
+        };
+
+        let border = self.config.appearance.border_width * 2;
+        let (x, y) = place(
+            self.work_area(self.window_monitor(&window)),
+            window.width + border,
+            window.height + border,
+            (window.x, window.y),
+        );
+
+        if let Some(window) = self
+            .workspaces
+            .get_mut(self.current_workspace)
+            .and_then(|ws| ws.find_mut(window.id))
+        {
+            window.x = x;
+            window.y = y;
+        }
+        unsafe {
+            xlib::XMoveWindow(self.display.raw(), window.id, x, y);
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
+
+    /// Resizes the focused floating window by `resize_step` in the given
+    /// directions, keeping its center. The client's size hints and the
+    /// usable area of its monitor limit the new size.
+    fn resize_focused_floating(&mut self, grow_width: i32, grow_height: i32) {
+        let Some(window) = self.focused_floating_window() else {
+            return;
+        };
+
+        let step = self.config.appearance.floating.resize_step as i32;
+        let border = self.config.appearance.border_width * 2;
+        let area = self.work_area(self.window_monitor(&window));
+
+        let mut hints = unsafe { get_size_hints(self.display.raw(), window.id) };
+        if hints.min.is_none() {
+            hints.min = Some((MIN_FLOATING_SIZE, MIN_FLOATING_SIZE));
+        }
+        let (width, height) = hints.constrain(
+            (window.width as i32 + grow_width * step).max(1) as u32,
+            (window.height as i32 + grow_height * step).max(1) as u32,
+        );
+        let width = width.min(area.width.saturating_sub(border).max(1));
+        let height = height.min(area.height.saturating_sub(border).max(1));
+        if (width, height) == (window.width, window.height) {
+            return;
+        }
+
+        let fit = |pos: i32, start: i32, space: u32, size: u32| {
+            pos.min(start + space as i32 - (size + border) as i32)
+                .max(start)
+        };
+        let x = fit(
+            window.x + (window.width as i32 - width as i32) / 2,
+            area.x,
+            area.width,
+            width,
+        );
+        let y = fit(
+            window.y + (window.height as i32 - height as i32) / 2,
+            area.y,
+            area.height,
+            height,
+        );
+
+        if let Some(window) = self
+            .workspaces
+            .get_mut(self.current_workspace)
+            .and_then(|ws| ws.find_mut(window.id))
+        {
+            window.x = x;
+            window.y = y;
+            window.width = width;
+            window.height = height;
+        }
+        unsafe {
+            xlib::XMoveResizeWindow(self.display.raw(), window.id, x, y, width, height);
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
+
+    /// The focused window, if it floats on the current workspace.
+    fn focused_floating_window(&self) -> Option<Window> {
+        let id = self.focus.focused()?;
+        self.workspaces
+            .get(self.current_workspace)?
+            .find(id)
+            .filter(|w| w.is_floating && !w.is_fullscreen && !w.is_dock)
+            .cloned()
+    }
+
+    /// The monitor holding the center of a window, or the pointer's.
+    fn window_monitor(&self, window: &Window) -> Monitor {
+        let center_x = window.x + window.width as i32 / 2;
+        let center_y = window.y + window.height as i32 / 2;
+        self.display
+            .monitors()
+            .into_iter()
+            .find(|m| m.contains(center_x, center_y))
+            .unwrap_or_else(|| self.display.pointer_monitor())
+    }
+
+    /// The monitor a rule names, `None` when it isn't connected.
+    fn rule_monitor(&self, monitor: &RuleMonitor) -> Option<Geometry> {
+        let found = match monitor {
+            RuleMonitor::Index(index) => index
+                .checked_sub(1)
+                .and_then(|i| self.display.monitors().get(i).copied()),
+            RuleMonitor::Output(name) if name == "primary" => Some(self.display.primary_monitor()),
+            RuleMonitor::Output(name) => self
+                .display
+                .outputs()
+                .into_iter()
+                .find(|(output, _)| output == name)
+                .map(|(_, monitor)| monitor),
+        };
+        if found.is_none() {
+            warn!("Rule names monitor {:?}, which isn't connected", monitor);
+        }
+        found.map(Geometry::from)
+    }
+
+    /// Makes a hidden workspace tile on `monitor` for a window a rule sends
+    /// there, unless it already tiles windows elsewhere.
+    fn move_workspace_for_rule(&mut self, index: usize, monitor: Geometry) {
+        let workspace = &mut self.workspaces[index];
+        if workspace.windows.iter().any(|w| w.is_tiled()) {
+            if workspace.monitor != Some(monitor) {
+                debug!(
+                    "Workspace {} already tiles on another monitor, ignoring the rule's",
+                    index
+                );
+            }
+            return;
+        }
+        workspace.monitor = Some(monitor);
+        workspace.moved = true;
+    }
+
+    /// The monitor a workspace tiles on.
+    fn workspace_monitor(&self, index: usize) -> Geometry {
+        let workspace = self.workspaces.get(index);
+        workspace
+            .filter(|ws| ws.moved)
+            .and_then(|ws| ws.monitor)
+            .or_else(|| self.pinned_monitor(index))
+            .or_else(|| workspace.and_then(|ws| ws.monitor))
+            .unwrap_or_else(|| self.display.primary_monitor().into())
+    }
+
+    /// The monitor `target` names, seen from `from`. `None` when that is
+    /// `from` itself or no such output is connected.
+    fn target_monitor(&self, from: Geometry, target: &MonitorTarget) -> Option<Monitor> {
+        let monitors = self.display.monitors();
+        let position = monitors
+            .iter()
+            .position(|&m| Geometry::from(m) == from)
+            .unwrap_or(0);
+        let to = match target {
+            MonitorTarget::Next => monitors[(position + 1) % monitors.len()],
+            MonitorTarget::Previous => monitors[(position + monitors.len() - 1) % monitors.len()],
+            MonitorTarget::Primary => self.display.primary_monitor(),
+            MonitorTarget::Output(name) => {
+                let output = self
+                    .display
+                    .outputs()
+                    .into_iter()
+                    .find(|(output, _)| output == name);
+                if output.is_none() {
+                    warn!("No monitor named {}", name);
+                }
+                output?.1
+            }
+        };
+        (Geometry::from(to) != from).then_some(to)
+    }
+
+    /// The workspace shown on `monitor` besides the current one: the first
+    /// occupied workspace that tiles there, else any that does, else the
+    /// first empty one, which is moved there.
+    fn monitor_workspace(&mut self, monitor: Geometry) -> Option<usize> {
+        let others: Vec<usize> = (0..self.workspaces.len())
+            .filter(|&i| i != self.current_workspace)
+            .collect();
+        let on_monitor = |i: &&usize| self.workspace_monitor(**i) == monitor;
+        if let Some(&index) = others
+            .iter()
+            .filter(on_monitor)
+            .find(|&&i| self.workspaces[i].is_occupied())
+            .or_else(|| others.iter().find(on_monitor))
+        {
+            return Some(index);
+        }
+
+        let index = *others
+            .iter()
+            .find(|&&i| !self.workspaces[i].is_occupied())?;
+        self.workspaces[index].monitor = Some(monitor);
+        self.workspaces[index].moved = true;
+        Some(index)
+    }
+
+    /// Sends the focused window to the workspace of another monitor. A
+    /// floating window keeps its position relative to the work area,
+    /// scaled to the new monitor.
+    fn move_window_to_monitor(&mut self, target: &MonitorTarget) {
+        let Some(window_id) = self.focus.focused() else {
+            return;
+        };
+        let Some(window) = self
+            .workspaces
+            .get(self.current_workspace)
+            .and_then(|ws| ws.find(window_id))
+            .filter(|w| !w.is_dock)
+            .cloned()
+        else {
+            return;
+        };
+
+        let from = if window.is_tiled() {
+            self.workspace_monitor(self.current_workspace)
+        } else {
+            self.window_monitor(&window).into()
+        };
+        let Some(to) = self.target_monitor(from, target) else {
+            return;
+        };
+        let Some(index) = self.monitor_workspace(to.into()) else {
+            warn!("No workspace left for the monitor at {},{}", to.x, to.y);
+            return;
+        };
+
+        let (from_area, to_area) = (self.work_area(from.into()), self.work_area(to));
+        if let Some(window) = self.workspaces[self.current_workspace].find_mut(window_id) {
+            if window.is_fullscreen {
+                window.x = to.x;
+                window.y = to.y;
+                window.width = to.width;
+                window.height = to.height;
+            } else if window.is_floating {
+                let scale = |offset: i32, from: u32, to: u32| {
+                    (offset as f64 * to as f64 / from.max(1) as f64).round() as i32
+                };
+                let max_x = (to_area.width as i32 - window.width as i32).max(0);
+                let max_y = (to_area.height as i32 - window.height as i32).max(0);
+                window.x = to_area.x
+                    + scale(window.x - from_area.x, from_area.width, to_area.width).clamp(0, max_x);
+                window.y = to_area.y
+                    + scale(window.y - from_area.y, from_area.height, to_area.height)
+                        .clamp(0, max_y);
+            }
+            unsafe {
+                xlib::XMoveResizeWindow(
+                    self.display.raw(),
+                    window.id,
+                    window.x,
+                    window.y,
+                    window.width,
+                    window.height,
+                );
+            }
+        }
+
+        self.move_window_to_workspace(window_id, index);
+        self.focus_next();
+    }
+
+    /// Switches to the workspace shown on the primary monitor, unless the
+    /// current one already is, and moves the pointer to its middle.
+    fn focus_primary_monitor(&mut self) {
+        let primary = self.display.primary_monitor();
+        if self.workspace_monitor(self.current_workspace) != primary.into() {
+            match self.monitor_workspace(primary.into()) {
+                Some(index) => self.switch_to_workspace(index),
+                None => warn!("No workspace left for the primary monitor"),
+            }
+        }
+
+        let pointer = self.display.query_pointer();
+        if !primary.contains(pointer.x, pointer.y) {
+            unsafe {
+                xlib::XWarpPointer(
+                    self.display.raw(),
+                    0,
+                    self.layout.get_root(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    primary.x + primary.width as i32 / 2,
+                    primary.y + primary.height as i32 / 2,
+                );
+            }
+        }
+    }
+
+    /// Moves the current workspace to another monitor: its tiled windows
+    /// are laid out there, between the docks on that monitor, and its
+    /// floating and fullscreen windows follow along.
+    fn move_workspace_to_monitor(&mut self, target: &MonitorTarget) {
+        let from = self.workspace_monitor(self.current_workspace);
+        let Some(to) = self.target_monitor(from, target) else {
+            return;
+        };
+
+        let from = Monitor::from(from);
+        let (from_area, to_area) = (self.work_area(from), self.work_area(to));
+        let Some(workspace) = self.workspaces.get_mut(self.current_workspace) else {
+            return;
+        };
+        workspace.monitor = Some(to.into());
+        workspace.moved = true;
+
+        for window in workspace.windows.iter_mut().filter(|w| !w.is_dock) {
+            if window.is_fullscreen {
+                if Self::window_area(window) != from {
+                    continue;
+                }
+                window.x = to.x;
+                window.y = to.y;
+                window.width = to.width;
+                window.height = to.height;
+            } else if window.is_floating
+                && from.contains(
+                    window.x + window.width as i32 / 2,
+                    window.y + window.height as i32 / 2,
+                )
+            {
+                // Same spot relative to the work area, kept inside it.
+                let max_x = (to_area.width as i32 - window.width as i32).max(0);
+                let max_y = (to_area.height as i32 - window.height as i32).max(0);
+                window.x = to_area.x + (window.x - from_area.x).clamp(0, max_x);
+                window.y = to_area.y + (window.y - from_area.y).clamp(0, max_y);
+            } else {
+                continue;
+            }
+            unsafe {
+                xlib::XMoveResizeWindow(
+                    self.display.raw(),
+                    window.id,
+                    window.x,
+                    window.y,
+                    window.width,
+                    window.height,
+                );
+            }
+        }
+
+        info!(
+            "Moved workspace {} to the monitor at {},{}",
+            self.current_workspace + 1,
+            to.x,
+            to.y
+        );
+        self.layout.set_monitor(to.into());
+        self.raise_floating_windows();
+        self.update_bar();
+    }
+
+    /// The monitor `[monitors]` pins a workspace to, if it is connected.
+    fn pinned_monitor(&self, index: usize) -> Option<Geometry> {
+        let output = self.config.pinned_output(index)?;
+        if output == "primary" {
+            return Some(self.display.primary_monitor().into());
+        }
+        self.display
+            .outputs()
+            .into_iter()
+            .find(|(name, _)| name == output)
+            .map(|(_, monitor)| monitor.into())
+    }
+
+    /// Follows monitors being added, removed or rearranged. Workspaces
+    /// whose monitor is gone fall back to the primary one, pinned
+    /// workspaces return to theirs once it is back, and the bars move to
+    /// where they now belong.
+    fn handle_screen_change(&mut self, mut event: xlib::XEvent) {
+        unsafe { xrandr::XRRUpdateConfiguration(&mut event) };
+        let monitors = self.display.monitors();
+        info!("Screen changed, {} monitors", monitors.len());
+        for workspace in &mut self.workspaces {
+            if workspace
+                .monitor
+                .is_some_and(|m| !monitors.iter().any(|&monitor| Geometry::from(monitor) == m))
+            {
+                workspace.monitor = None;
+                workspace.moved = false;
+            }
+        }
+        if !self.bars.is_empty() {
+            self.remove_bars();
+            self.bars = Self::create_bars(
+                &self.display,
+                self.layout.get_root(),
+                &self.config,
+                true,
+                &mut self.layout,
+            );
+            self.update_bar();
+        }
+        self.layout
+            .set_monitor(self.workspace_monitor(self.current_workspace));
+    }
+
+    /// The part of a monitor floating windows are placed in, without the
+    /// space docks and the bar reserve on it.
+    fn work_area(&self, monitor: Monitor) -> Monitor {
+        let struts = self.layout.struts_on(monitor.into());
+        Monitor {
+            x: monitor.x + struts.left as i32,
+            y: monitor.y + struts.top as i32,
+            width: monitor.width.saturating_sub(struts.left + struts.right),
+            height: monitor.height.saturating_sub(struts.top + struts.bottom),
+        }
+    }
+
+    /// Floats every tiled window of the current workspace where it is, or
+    /// tiles every floating one.
+    fn set_all_floating(&mut self, floating: bool) {
+        let windows: Vec<xlib::Window> = self
+            .workspaces
+            .get(self.current_workspace)
+            .map(|ws| {
+                ws.windows
+                    .iter()
+                    .filter(|w| w.is_floating != floating && !w.is_fullscreen)
+                    .map(|w| w.id)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if windows.is_empty() {
+            return;
+        }
+
+        unsafe {
+            for &window_id in &windows {
+                self.set_floating(window_id, floating, false);
+            }
+            self.layout.relayout();
+            self.raise_floating_windows();
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
+
+    /// Moves a window of the current workspace between the layout and
+    /// floating. The caller relayouts. A window floated without `center`
+    /// stays where it was tiled.
+    unsafe fn set_floating(&mut self, window_id: xlib::Window, floating: bool, center: bool) {
+        let monitor = self.display.pointer_monitor();
+        let root = self.layout.get_root();
+        let Some(window) = self
+            .workspaces
+            .get_mut(self.current_workspace)
+            .and_then(|ws| ws.find_mut(window_id))
+        else {
+            return;
+        };
+        if window.is_floating == floating {
+            return;
+        }
+
+        if !floating {
+            window.is_floating = false;
+            window.x = window.pre_float_x;
+            window.y = window.pre_float_y;
+            window.width = window.pre_float_width;
+            window.height = window.pre_float_height;
+            self.layout.add_window(window_id);
+            return;
+        }
+
+        let mut child_x: i32 = 0;
+        let mut child_y: i32 = 0;
+        let mut child: xlib::Window = 0;
+        xlib::XTranslateCoordinates(
+            self.display.raw(),
+            window.id,
+            root,
+            0,
+            0,
+            &mut child_x,
+            &mut child_y,
+            &mut child,
+        );
+
+        window.is_floating = true;
+        window.pre_float_x = child_x;
+        window.pre_float_y = child_y;
+        window.pre_float_width = window.width;
+        window.pre_float_height = window.height;
+
+        if center {
+            let float_width = self.config.appearance.floating.width;
+            let float_height = self.config.appearance.floating.height;
+            let new_x = monitor.x + (monitor.width.saturating_sub(float_width) / 2) as i32;
+            let new_y = monitor.y + (monitor.height.saturating_sub(float_height) / 2) as i32;
+
+            window.width = float_width;
+            window.height = float_height;
+            window.x = new_x;
+            window.y = new_y;
+
+            window.pre_float_x = new_x;
+            window.pre_float_y = new_y;
+        } else {
+            window.x = child_x;
+            window.y = child_y;
+        }
+
+        xlib::XMoveResizeWindow(
+            self.display.raw(),
+            window.id,
+            window.x,
+            window.y,
+            window.width,
+            window.height,
+        );
+        self.layout.remove_window(window_id);
+    }
+
+    fn toggle_fullscreen(&mut self) {
+        self.toggle_fullscreen_span(false);
+    }
+
+    fn toggle_fullscreen_span(&mut self, all_monitors: bool) {
+        let Pointer {
+            x: root_x,
+            y: root_y,
+            child: child_return,
+            ..
+        } = self.display.query_pointer();
+
+        if child_return == 0 || child_return == self.layout.get_root() {
+            return;
+        }
+
+        let monitors = self.display.monitors();
+        let Some(window) = self
+            .workspaces
+            .get(self.current_workspace)
+            .and_then(|ws| ws.find(child_return))
+        else {
+            return;
+        };
+
+        if window.is_fullscreen {
+            self.exit_fullscreen(child_return);
+        } else {
+            let target = if all_monitors {
+                monitors
+                    .iter()
+                    .skip(1)
+                    .fold(monitors[0], |acc, m| acc.union(m))
+            } else {
+                Self::fullscreen_area(window, &monitors, root_x, root_y)
+            };
+            self.enter_fullscreen(child_return, target);
+        }
+
+        self.raise_floating_windows();
+        self.update_game_mode(self.focus.focused());
+        self.update_idle_inhibit(self.focus.focused());
+    }
+
+    /// Handles a client asking through `_NET_WM_STATE` to leave (0), enter
+    /// (1) or toggle (2) fullscreen, unless a rule forbids it.
+    fn request_fullscreen(&mut self, window_id: xlib::Window, action: i64) {
+        let Some(window) = self
+            .workspaces
+            .get(self.current_workspace)
+            .and_then(|ws| ws.find(window_id))
+            .filter(|w| !w.is_dock)
+        else {
+            return;
+        };
+        let fullscreen = match action {
+            0 => false,
+            1 => true,
+            2 => !window.is_fullscreen,
+            _ => return,
+        };
+        if fullscreen == window.is_fullscreen {
+            return;
+        }
+        if fullscreen && !window.allows_fullscreen {
+            debug!("Ignoring fullscreen request of window {}", window_id);
+            return;
+        }
+
+        if fullscreen {
+            let (x, y) = (
+                window.x + window.width as i32 / 2,
+                window.y + window.height as i32 / 2,
+            );
+            let target = Self::fullscreen_area(window, &self.display.monitors(), x, y);
+            self.enter_fullscreen(window_id, target);
+        } else {
+            self.exit_fullscreen(window_id);
+        }
+
+        self.raise_floating_windows();
+        self.update_game_mode(self.focus.focused());
+        self.update_idle_inhibit(self.focus.focused());
+    }
+
+    /// Makes a window on the current workspace cover `target`. Each monitor
+    /// shows one fullscreen window at a time, so other fullscreen windows
+    /// overlapping `target` leave fullscreen.
+    fn enter_fullscreen(&mut self, window_id: xlib::Window, target: Monitor) {
+        let Some(attrs) = self.display.window_attributes(window_id) else {
+            return;
+        };
+
+        for (other, area) in self.fullscreen_areas() {
+            if other != window_id && area.intersects(&target) {
+                self.exit_fullscreen(other);
+            }
+        }
+
+        let Some(window) = self
+            .workspaces
+            .get_mut(self.current_workspace)
+            .and_then(|ws| ws.find_mut(window_id))
+        else {
+            return;
+        };
+
+        window.is_fullscreen = true;
+        window.pre_fullscreen_x = attrs.x;
+        window.pre_fullscreen_y = attrs.y;
+        window.pre_fullscreen_width = attrs.width as u32;
+        window.pre_fullscreen_height = attrs.height as u32;
+
+        window.x = target.x;
+        window.y = target.y;
+        window.width = target.width;
+        window.height = target.height;
+
+        let atoms = self.display.atoms();
+        self.display.set_property(
+            window_id,
+            atoms.net_wm_state,
+            xlib::XA_ATOM,
+            &[atoms.net_wm_state_fullscreen],
+        );
+        unsafe {
+            xlib::XSetWindowBorderWidth(self.display.raw(), window.id, 0);
+            xlib::XMoveResizeWindow(
+                self.display.raw(),
+                window.id,
+                window.x,
+                window.y,
+                window.width,
+                window.height,
+            );
+        }
+
+        // Tiled windows leave the layout so relayouts don't shrink them.
+        if !window.is_floating {
+            self.layout.remove_window(window_id);
+            self.layout.relayout();
+        }
+    }
+
+    fn exit_fullscreen(&mut self, window_id: xlib::Window) {
+        let Some(window) = self
+            .workspaces
+            .get_mut(self.current_workspace)
+            .and_then(|ws| ws.find_mut(window_id))
+        else {
+            return;
+        };
+
+        window.is_fullscreen = false;
+        self.display.set_property::<u64>(
+            window_id,
+            self.display.atoms().net_wm_state,
+            xlib::XA_ATOM,
+            &[],
+        );
+        window.x = window.pre_fullscreen_x;
+        window.y = window.pre_fullscreen_y;
+        window.width = window.pre_fullscreen_width;
+        window.height = window.pre_fullscreen_height;
+
+        unsafe {
+            xlib::XSetWindowBorderWidth(
+                self.display.raw(),
+                window.id,
+                window.border_width(self.config.appearance.border_width),
+            );
+            if window.is_floating {
+                xlib::XMoveResizeWindow(
+                    self.display.raw(),
+                    window.id,
+                    window.x,
+                    window.y,
+                    window.width,
+                    window.height,
+                );
+            }
+        }
+
+        if !window.is_floating {
+            self.layout.add_window(window_id);
+            self.layout.relayout();
+        }
+        self.paint_border(window_id);
+    }
+
+    /// Returns the area a fullscreen window should cover: the bounding box of the
+    /// monitors requested via `_NET_WM_FULLSCREEN_MONITORS`, or the monitor under the pointer.
+    fn fullscreen_area(window: &Window, monitors: &[Monitor], root_x: i32, root_y: i32) -> Monitor {
+        if let Some(indices) = window.fullscreen_monitors {
+            if indices.iter().all(|&i| i < monitors.len()) {
+                return indices
+                    .iter()
+                    .skip(1)
+                    .fold(monitors[indices[0]], |acc, &i| acc.union(&monitors[i]));
+            }
+        }
+
+        monitors
+            .iter()
+            .find(|m| m.contains(root_x, root_y))
+            .copied()
+            .unwrap_or(monitors[0])
+    }
+
+    fn set_fullscreen_monitors(&mut self, window_id: xlib::Window, indices: [usize; 4]) {
+        let monitors = self.display.monitors();
+        if indices.iter().any(|&i| i >= monitors.len()) {
+            debug!("Ignoring invalid fullscreen monitors {:?}", indices);
+            return;
+        }
+
+        self.display.set_property(
+            window_id,
+            self.display.atoms().net_wm_fullscreen_monitors,
+            xlib::XA_CARDINAL,
+            &indices.map(|i| i as u64),
+        );
+
+        for workspace in &mut self.workspaces {
+            if let Some(window) = workspace.find_mut(window_id) {
+                window.fullscreen_monitors = Some(indices);
+
+                if window.is_fullscreen {
+                    let target = Self::fullscreen_area(window, &monitors, 0, 0);
+                    window.x = target.x;
+                    window.y = target.y;
+                    window.width = target.width;
+                    window.height = target.height;
+                    unsafe {
+                        xlib::XMoveResizeWindow(
+                            self.display.raw(),
+                            window.id,
+                            window.x,
+                            window.y,
+                            window.width,
+                            window.height,
+                        );
+                    }
+                }
+            }
+        }
+
+        unsafe {
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
+
+    /// The window `close` and `kill` act on, as `close_target` picks it.
+    fn close_target(&self) -> Option<xlib::Window> {
+        let window = match self.config.close_target {
+            CloseTarget::Focused => self.focus.focused()?,
+            CloseTarget::Pointer => self.display.query_pointer().child,
+        };
+        self.closable(window)
+    }
+
+    /// `window` if it is managed and no dock.
+    fn closable(&self, window: xlib::Window) -> Option<xlib::Window> {
+        let managed = self.workspaces.iter().find_map(|ws| ws.find(window))?;
+        if managed.is_dock {
+            debug!("Ignoring close request for dock window");
+            return None;
+        }
+        Some(window)
+    }
+
+    /// Asks the target window to close, or with `kill` disconnects its
+    /// client, which also takes down the other windows of that client.
+    fn close_window(&mut self, kill: bool) {
+        if let Some(window) = self.close_target() {
+            self.close(window, kill);
+        }
+    }
+
+    fn close(&mut self, window: xlib::Window, kill: bool) {
+        debug!("Closing window {} (kill: {})", window, kill);
+
+        let atoms = *self.display.atoms();
+        unsafe {
+            if kill {
+                xlib::XKillClient(self.display.raw(), window);
+            } else if supports_protocol(self.display.raw(), window, atoms.wm_delete_window) {
+                send_protocol(self.display.raw(), &atoms, window, atoms.wm_delete_window);
+            } else {
+                xlib::XDestroyWindow(self.display.raw(), window);
+            }
+
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
+
+    fn handle_map_request(&mut self, window: xlib::Window) {
+        debug!("Handling map request for window {}", window);
+        self.manage_window(window);
+    }
+
+    /// Tiled and fullscreen windows keep the geometry the layout gave them
+    /// and are told so; everything else gets what it asked for.
+    fn handle_configure_request(&mut self, request: ConfigureRequest) {
+        let border_width = self.border_width(request.window);
+        let managed = self
+            .workspaces
+            .iter_mut()
+            .find_map(|ws| ws.find_mut(request.window));
+
+        if let Some(window) = managed {
+            if !window.is_floating || window.is_fullscreen {
+                unsafe {
+                    send_configure_notify(
+                        self.display.raw(),
+                        window.id,
+                        window.x,
+                        window.y,
+                        window.width,
+                        window.height,
+                        border_width.unwrap_or_default(),
+                    );
+                }
+                return;
+            }
+
+            if request.value_mask & xlib::CWX as u64 != 0 {
+                window.x = request.x;
+            }
+            if request.value_mask & xlib::CWY as u64 != 0 {
+                window.y = request.y;
+            }
+            if request.value_mask & xlib::CWWidth as u64 != 0 {
+                window.width = request.width;
+            }
+            if request.value_mask & xlib::CWHeight as u64 != 0 {
+                window.height = request.height;
+            }
+        }
+
+        // Docks may not restack themselves above a fullscreen window.
+        let mut value_mask = request.value_mask as u32;
+        if value_mask & xlib::CWStackMode as u32 != 0 && self.is_covered_dock(request.window) {
+            value_mask &= !(xlib::CWStackMode | xlib::CWSibling) as u32;
+        }
+
+        let mut changes = xlib::XWindowChanges {
+            x: request.x,
+            y: request.y,
+            width: request.width as i32,
+            height: request.height as i32,
+            // Clients don't get to drop the border of a managed window.
+            border_width: border_width.unwrap_or(request.border_width) as i32,
+            sibling: request.sibling,
+            stack_mode: request.stack_mode,
+        };
+        unsafe {
+            xlib::XConfigureWindow(self.display.raw(), request.window, value_mask, &mut changes);
+        }
+    }
+
+    fn is_covered_dock(&self, window_id: xlib::Window) -> bool {
+        let fullscreen = self.fullscreen_areas();
+        self.workspaces
+            .get(self.current_workspace)
+            .and_then(|ws| ws.find(window_id))
+            .filter(|w| w.is_dock)
+            .is_some_and(|w| {
+                let area = Self::window_area(w);
+                fullscreen.iter().any(|(_, f)| f.intersects(&area))
+            })
+    }
+
+    fn manage_window(&mut self, window_id: xlib::Window) {
+        if self.workspaces.iter().any(|ws| ws.contains(window_id)) {
+            return;
+        }
+
+        let Some(attrs) = self.display.window_attributes(window_id) else {
+            debug!("Window {} is gone before it could be managed", window_id);
+            return;
+        };
+        // Override-redirect windows place themselves, whatever path led here.
+        if attrs.override_redirect != 0 {
+            debug!("Not managing override-redirect window {}", window_id);
+            return;
+        }
+        let atoms = *self.display.atoms();
+        let types = self
+            .display
+            .get_property::<u64>(window_id, atoms.net_wm_window_type, xlib::XA_ATOM)
+            .unwrap_or_default();
+        let window_type = WindowType::from_atoms(&types, &atoms);
+        if window_type == WindowType::Popup {
+            debug!("Mapping popup window {} without managing it", window_id);
+            unsafe {
+                xlib::XMapRaised(self.display.raw(), window_id);
+            }
+            return;
+        }
+        let is_dock = window_type == WindowType::Dock;
+        let is_splash = window_type == WindowType::Splash;
+        let is_dialog = window_type == WindowType::Dialog
+            || (window_type == WindowType::Normal
+                && unsafe { get_transient_for(self.display.raw(), window_id) }.is_some());
+
+        let rule = if is_dock {
+            None
+        } else {
+            self.match_rule(window_id)
+        };
+        if rule.as_ref().and_then(|r| r.unmanaged) == Some(true) {
+            debug!("Mapping window {} without managing it by rule", window_id);
+            unsafe {
+                xlib::XMapWindow(self.display.raw(), window_id);
+            }
+            return;
+        }
+
+        if !is_dock {
+            debug!("Grabbing buttons for window {}", window_id);
+            unsafe { Self::grab_buttons(self.display.raw(), &self.config, window_id) };
+        }
+
+        let mut window = Window::new(
+            window_id,
+            attrs.x,
+            attrs.y,
+            attrs.width as u32,
+            attrs.height as u32,
+        );
+        window.pid = unsafe { get_window_pid(self.display.raw(), self.display.atoms(), window_id) };
+        window.borderless = is_splash;
+
+        let force_tiling = rule.as_ref().and_then(|r| r.force_tiling) == Some(true);
+        let rule_global = rule.as_ref().and_then(|r| r.global) == Some(true) && !force_tiling;
+        let rule_floating = if force_tiling {
+            Some(false)
+        } else {
+            rule.as_ref()
+                .and_then(|r| r.floating)
+                .or(rule_global.then_some(true))
+        };
+        let rule_monitor = rule
+            .as_ref()
+            .and_then(|r| r.monitor.as_ref())
+            .and_then(|monitor| self.rule_monitor(monitor));
+        if rule_floating == Some(true) || ((is_dialog || is_splash) && rule_floating.is_none()) {
+            let rule = rule.clone().unwrap_or_default();
+            // Percentages in the rule are of the monitor the rule names,
+            // or the one the pointer is on.
+            let monitor =
+                rule_monitor.map_or_else(|| self.display.pointer_monitor(), Monitor::from);
+            window.is_floating = true;
+            window.width = rule.width.map_or(window.width, |w| w.size(monitor.width));
+            window.height = rule
+                .height
+                .map_or(window.height, |h| h.size(monitor.height));
+            // Without a position from a rule, new floating windows and
+            // dialogs open in the middle of that monitor.
+            let border = window.border_width(self.config.appearance.border_width) * 2;
+            let (x, y) = monitor.center(window.width + border, window.height + border);
+            window.x = rule.x.map_or(x, |x| x.position(monitor.x, monitor.width));
+            window.y = rule.y.map_or(y, |y| y.position(monitor.y, monitor.height));
+            window.pre_float_x = window.x;
+            window.pre_float_y = window.y;
+            window.pre_float_width = window.width;
+            window.pre_float_height = window.height;
+            unsafe {
+                xlib::XSelectInput(
+                    self.display.raw(),
+                    window_id,
+                    xlib::EnterWindowMask
+                        | xlib::LeaveWindowMask
+                        | xlib::FocusChangeMask
+                        | xlib::PropertyChangeMask,
+                );
+                xlib::XMoveResizeWindow(
+                    self.display.raw(),
+                    window_id,
+                    window.x,
+                    window.y,
+                    window.width,
+                    window.height,
+                );
+            }
+        }
+        window.inhibit_idle = rule.as_ref().and_then(|r| r.inhibit_idle);
+        window.is_global = rule_global && window.is_floating;
+        window.accepts_focus = rule.as_ref().and_then(|r| r.focus) != Some(false)
+            && !is_splash
+            && unsafe { takes_input(self.display.raw(), self.display.atoms(), window_id) };
+        let takes_focus = window.accepts_focus && !self.maps_without_focus(window_id);
+        window.allows_fullscreen = rule.as_ref().and_then(|r| r.allow_fullscreen) != Some(false);
+        let rule_desktop = rule.as_ref().and_then(|r| r.workspace_index());
+        let launch_desktop = if is_dock {
+            None
+        } else {
+            unsafe { self.startup.complete(window_id) }
+        };
+        if launch_desktop.is_some() {
+            self.update_launch_cursor();
+        }
+        let pinned = self.pinned_monitor(self.current_workspace);
+
+        unsafe {
+            if is_dock {
+                window.is_floating = true;
+                window.is_dock = true;
+
+                xlib::XSetWindowBorderWidth(self.display.raw(), window_id, 0);
+
+                for workspace in &mut self.workspaces {
+                    workspace.add_window(window.clone());
+                }
+
+                xlib::XMapWindow(self.display.raw(), window_id);
+                xlib::XRaiseWindow(self.display.raw(), window_id);
+
+                self.set_window_desktop(window_id, ALL_DESKTOPS);
+                // Follow changes to the struts.
+                xlib::XSelectInput(self.display.raw(), window_id, xlib::PropertyChangeMask);
+                let struts = self.dock_struts(&window);
+                self.layout
+                    .set_dock(window_id, struts, Self::window_area(&window).into());
+            } else if let Some(desktop) = self
+                .get_window_desktop(window_id)
+                .or(rule_desktop)
+                .or(launch_desktop)
+                .filter(|&d| d < self.workspaces.len() && !self.is_desktop_visible(d))
+                .filter(|_| !window.is_global)
+            {
+                debug!(
+                    "Placing window {} on requested workspace {}",
+                    window_id, desktop
+                );
+                xlib::XSetWindowBorderWidth(
+                    self.display.raw(),
+                    window_id,
+                    window.border_width(self.config.appearance.border_width),
+                );
+                window.tags = 1 << desktop;
+                let home = if self.config.tag_mode { 0 } else { desktop };
+                if let Some(monitor) = rule_monitor.filter(|_| window.is_tiled()) {
+                    self.move_workspace_for_rule(home, monitor);
+                }
+                self.workspaces[home].add_window(window);
+                self.paint_border(window_id);
+                self.set_window_desktop(window_id, desktop as u64);
+            } else if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
+                xlib::XMapWindow(self.display.raw(), window_id);
+                xlib::XSetWindowBorderWidth(
+                    self.display.raw(),
+                    window_id,
+                    window.border_width(self.config.appearance.border_width),
+                );
+
+                window.tags = self.view;
+                let is_floating = window.is_floating;
+                let is_global = window.is_global;
+                // The first tiled window decides which monitor the
+                // workspace tiles on: the one the pointer is on, unless
+                // the workspace is pinned to a monitor. A rule naming a
+                // monitor wins over both.
+                if !is_floating && !workspace.windows.iter().any(|w| w.is_tiled()) {
+                    let monitor = rule_monitor
+                        .or(pinned)
+                        .unwrap_or_else(|| self.display.pointer_monitor().into());
+                    workspace.monitor = Some(monitor);
+                    workspace.moved |= rule_monitor.is_some();
+                    self.layout.set_monitor(monitor);
+                }
+                workspace.add_window(window);
+                if !is_floating {
+                    self.layout.add_window(window_id);
+                }
+
+                let desktop = if is_global {
+                    ALL_DESKTOPS
+                } else {
+                    self.current_desktop() as u64
+                };
+                self.set_window_desktop(window_id, desktop);
+                self.paint_border(window_id);
+                if takes_focus {
+                    self.focus_window(window_id);
+                } else if let Some(focused) = self.focus.focused() {
+                    // Keep what has the focus first in line for it.
+                    self.workspaces[self.current_workspace].focus(focused);
+                }
+                xlib::XSync(self.display.raw(), 0);
+            }
+        }
+
+        // Clients can ask to start out fullscreen.
+        let starts_fullscreen = self
+            .display
+            .get_property::<u64>(window_id, atoms.net_wm_state, xlib::XA_ATOM)
+            .is_some_and(|state| state.contains(&atoms.net_wm_state_fullscreen));
+        if (starts_fullscreen || self.is_kiosk_window(window_id)) && !is_dock {
+            self.request_fullscreen(window_id, 1);
+        }
+
+        self.raise_floating_windows();
+        self.update_bar();
+        unsafe {
+            self.raise_notifications();
+            xlib::XSync(self.display.raw(), 0);
+        }
+
+        if let Some(workspace) = self.window_desktop(window_id) {
+            self.emit(HookEvent::WindowMapped {
+                window: window_id,
+                workspace,
+            });
+        }
+    }
+
+    fn handle_unmap_notify(&mut self, window: xlib::Window, synthetic: bool) {
+        // Windows hidden by a view change stay managed in tag mode, and so
+        // do those unmapped when their workspace was left. A hidden window
+        // is only withdrawn by the synthetic unmap its client sends.
+        let hidden = if self.config.tag_mode {
+            self.workspaces[0]
+                .find(window)
+                .is_some_and(|w| !w.is_dock && !w.is_viewed(self.view))
+        } else {
+            !self.workspaces[self.current_workspace].contains(window)
+                && self.workspaces.iter().any(|ws| ws.contains(window))
+        };
+        if !hidden || synthetic {
+            self.unmanage_window(window);
+        }
+    }
+
+    fn handle_destroy_notify(&mut self, window: xlib::Window) {
+        self.unmanage_window(window);
+    }
+
+    /// The space a dock reserves: `_NET_WM_STRUT_PARTIAL` or `_NET_WM_STRUT`
+    /// when it sets them, otherwise the edge of the monitor it is closest to.
+    fn dock_struts(&self, window: &Window) -> Struts {
+        let atoms = self.display.atoms();
+        let reserved = self
+            .display
+            .get_property::<u64>(window.id, atoms.net_wm_strut_partial, xlib::XA_CARDINAL)
+            .or_else(|| {
+                self.display
+                    .get_property::<u64>(window.id, atoms.net_wm_strut, xlib::XA_CARDINAL)
+            })
+            .filter(|values| values.len() >= 4);
+        if let Some(values) = reserved {
+            return Struts {
+                left: values[0] as u32,
+                right: values[1] as u32,
+                top: values[2] as u32,
+                bottom: values[3] as u32,
             };
+        }
+
+        let monitor = self.window_monitor(window);
+        let dock = if window.width >= window.height {
+            if window.y < monitor.y + monitor.height as i32 / 2 {
+                Dock::Top(window.height)
+            } else {
+                Dock::Bottom(window.height)
+            }
+        } else if window.x < monitor.x + monitor.width as i32 / 2 {
+            Dock::Left(window.width)
+        } else {
+            Dock::Right(window.width)
+        };
+        dock.into()
+    }
+
+    fn unmanage_window(&mut self, window_id: xlib::Window) {
+        let source = self
+            .workspaces
+            .iter()
+            .position(|workspace| workspace.contains(window_id));
+        let removed = source.and_then(|index| self.workspaces[index].remove_window(window_id));
+        if removed.is_some() {
+            self.emit(HookEvent::WindowClosed { window: window_id });
+        }
+
+        // Docks are on every workspace and give their space back.
+        if self.layout.remove_dock(window_id) {
+            for workspace in &mut self.workspaces {
+                workspace.remove_window(window_id);
+            }
+        }
+        self.layout.remove_window(window_id);
+
+        if self.focus.forget(window_id) {
+            self.focus_next();
+        }
+        self.update_game_mode(self.focus.focused());
+        self.update_idle_inhibit(self.focus.focused());
+        self.raise_floating_windows();
+        self.update_bar();
+        unsafe {
+            self.raise_notifications();
+            xlib::XSync(self.display.raw(), 0);
+        }
+
+        if source == Some(self.current_workspace) && removed.is_some_and(|w| !w.is_dock) {
+            self.return_from_empty_workspace();
+        }
+    }
+
+    /// Leaves the current workspace for the one `return_when_empty` names
+    /// once nothing is left on it.
+    fn return_from_empty_workspace(&mut self) {
+        if self.config.tag_mode || self.workspaces[self.current_workspace].is_occupied() {
+            return;
+        }
+        let target = match self.config.return_when_empty {
+            ReturnWhenEmpty::Off => None,
+            ReturnWhenEmpty::Previous => self.previous_workspace,
+            ReturnWhenEmpty::Home(index) => Some(index),
+        };
+        if let Some(index) = target.filter(|&i| i != self.current_workspace) {
+            debug!(
+                "Workspace {} is empty, returning to {}",
+                self.current_workspace, index
+            );
+            self.switch_to_workspace(index);
+            // The workspace left is empty, nothing to return to there.
+            self.previous_workspace = None;
+        }
+    }
+
+    fn handle_enter_notify(&mut self, window: xlib::Window) {
+        if !self.dragging
+            && !self.resizing
+            && self.slide.is_none()
+            && window != 0
+            && window != self.layout.get_root()
+            && !self.notification_manager.contains_window(window)
+            && self.config.appearance.focus_follows_mouse
+            && self.game_mode.is_none()
+        {
+            let window_id = window;
+            let is_floating = self
+                .workspaces
+                .get(self.current_workspace)
+                .and_then(|ws| ws.find(window_id))
+                .is_some_and(|w| w.is_floating);
+
+            self.focus_window(window_id);
+
+            if is_floating {
+                unsafe {
+                    xlib::XRaiseWindow(self.display.raw(), window_id);
+                }
+                self.raise_notifications();
+            } else {
+                self.raise_floating_windows();
+                self.raise_notifications();
+            }
+        }
+    }
+
+    fn switch_to_workspace(&mut self, index: usize) {
+        if self.config.tag_mode && index < self.workspaces.len() {
+            self.set_view(1 << index);
+            return;
+        }
+
+        if index >= self.workspaces.len() || index == self.current_workspace {
+            debug!("Invalid workspace switch request to {}", index);
+            return;
+        }
+
+        info!("Switching to workspace {}", index);
+        if let Some(mut slide) = self.slide.take() {
+            unsafe { slide.finish() };
+        }
+        let previous = self.current_workspace;
+        self.previous_workspace = Some(previous);
+        self.carry_windows(index);
+        self.workspaces[previous].layout_settings = Some(self.layout.settings().clone());
+
+        let animate = self.config.appearance.slide.enabled
+            && self.game_mode.is_none()
+            && ![previous, index]
+                .iter()
+                .any(|&ws| self.workspaces[ws].windows.iter().any(|w| w.is_fullscreen));
+        let mut outgoing = Vec::new();
+        for window in &self.workspaces[previous].windows {
+            if window.is_dock {
+                continue;
+            }
+            if animate {
+                let (x, y) = self
+                    .layout
+                    .window_geometry(window.id)
+                    .map_or((window.x, window.y), |g| (g.x, g.y));
+                outgoing.push(SlideWindow {
+                    id: window.id,
+                    x,
+                    y,
+                });
+            } else {
+                unsafe {
+                    xlib::XUnmapWindow(self.display.raw(), window.id);
+                }
+            }
+        }
+
+        self.current_workspace = index;
+        self.update_current_desktop();
+
+        // The layout takes over the tiled windows of the workspace as they
+        // were left, so windows that stay put aren't touched.
+        let config = &self.config;
+        let settings = self.workspaces[index]
+            .layout_settings
+            .get_or_insert_with(|| config.get_layout_settings(index))
+            .clone();
+        self.layout.set_settings(settings);
+        self.layout.set_monitor(self.workspace_monitor(index));
+        let tiled = self.workspaces[index]
+            .windows
+            .iter()
+            .filter(|w| w.is_tiled())
+            .map(|w| w.id)
+            .collect::<Vec<_>>();
+        let stashed = std::mem::take(&mut self.workspaces[index].layout_windows);
+        self.workspaces[previous].layout_windows = self.layout.replace_windows(stashed, &tiled);
+        self.layout.flush();
+
+        let windows = self.workspaces[index]
+            .windows
+            .iter()
+            .filter(|w| !w.is_dock)
+            .collect::<Vec<_>>();
+        for window in &windows {
+            if window.is_floating || window.is_fullscreen {
+                unsafe {
+                    xlib::XMoveResizeWindow(
+                        self.display.raw(),
+                        window.id,
+                        window.x,
+                        window.y,
+                        window.width,
+                        window.height,
+                    );
+                }
+            }
+        }
+        if animate {
+            // Moving on to a later workspace slides the windows to the left.
+            let width = self.workspace_monitor(index).width as i32;
+            let distance = if index > previous { width } else { -width };
+            // Windows on every workspace stay where they are.
+            let incoming = windows
+                .iter()
+                .filter(|w| !w.is_global)
+                .map(|window| {
+                    let (x, y) = self
+                        .layout
+                        .window_geometry(window.id)
+                        .map_or((window.x, window.y), |g| (g.x, g.y));
+                    SlideWindow {
+                        id: window.id,
+                        x,
+                        y,
+                    }
+                })
+                .collect();
+            self.slide = Some(unsafe {
+                WorkspaceSlide::new(
+                    self.display.raw(),
+                    Duration::from_millis(self.config.appearance.slide.duration),
+                    self.display.refresh_rate().unwrap_or(60),
+                    distance,
+                    outgoing,
+                    incoming,
+                )
+            });
+        }
+        for window in windows {
+            unsafe {
+                xlib::XMapWindow(self.display.raw(), window.id);
+            }
+        }
+        self.raise_floating_windows();
+        self.focus_next();
+
+        self.update_bar();
+        unsafe {
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
 
-            let window_id = if focused_win != 0 && focused_win != self.layout.get_root() {
-                focused_win
-            } else if net_active_win != 0 && net_active_win != self.layout.get_root() {
-                net_active_win
-            } else if let Some(workspace) = self.workspaces.get(self.current_workspace) {
-                workspace.get_focused_window().map(|w| w.id).unwrap_or(0)
+    /// Switching workspaces only changes the monitor under the pointer:
+    /// fullscreen windows on other monitors move along to workspace `index`,
+    /// unless it has its own fullscreen window there. Global windows always
+    /// move along.
+    fn carry_windows(&mut self, index: usize) {
+        let switched = self.display.pointer_monitor();
+        let target = &self.workspaces[index];
+        let kept = target
+            .windows
+            .iter()
+            .filter(|w| w.is_fullscreen)
+            .map(Self::window_area)
+            .collect::<Vec<_>>();
+        let previous_focus = target.get_focused_window().map(|w| w.id);
+
+        let carried = self
+            .fullscreen_areas()
+            .into_iter()
+            .filter(|(_, area)| {
+                !area.intersects(&switched) && !kept.iter().any(|k| k.intersects(area))
+            })
+            .map(|(window, _)| window)
+            .collect::<Vec<_>>();
+        let global = self.workspaces[self.current_workspace]
+            .windows
+            .iter()
+            .filter(|w| w.is_global)
+            .map(|w| w.id)
+            .collect::<Vec<_>>();
+
+        for window in carried.into_iter().chain(global) {
+            debug!("Keeping window {} on workspace {}", window, index);
+            let (source, target) = if self.current_workspace < index {
+                let (left, right) = self.workspaces.split_at_mut(index);
+                (&mut left[self.current_workspace], &mut right[0])
             } else {
-                0
+                let (left, right) = self.workspaces.split_at_mut(self.current_workspace);
+                (&mut right[0], &mut left[index])
             };
+            let desktop = match source.find(window) {
+                Some(w) if w.is_global => ALL_DESKTOPS,
+                _ => index as u64,
+            };
+            if source.move_window(window, target) {
+                self.set_window_desktop(window, desktop);
+            }
+        }
 
-            if window_id != 0 {
-                let (is_floating, should_update) = if let Some(workspace) =
-                    self.workspaces.get_mut(self.current_workspace)
-                {
-                    let is_floating = workspace
-                        .windows
-                        .iter()
-                        .find(|w| w.id == window_id)
-                        .map(|w| w.is_floating)
-                        .unwrap_or(false);
-
-                    if is_floating {
-                        if let Some(window) =
-                            workspace.windows.iter_mut().find(|w| w.id == window_id)
-                        {
-                            window.is_floating = false;
-                            window.x = window.pre_float_x;
-                            window.y = window.pre_float_y;
-                            window.width = window.pre_float_width;
-                            window.height = window.pre_float_height;
-                        }
-                        (false, true)
+        // The carried windows shouldn't take the focus on their new workspace.
+        if let Some(previous) = previous_focus {
+            self.workspaces[index].focus(previous);
+        }
+    }
+
+    /// The desktop reported through EWMH. In tag mode this is the lowest viewed tag.
+    fn current_desktop(&self) -> usize {
+        if self.config.tag_mode {
+            self.view.trailing_zeros() as usize
+        } else {
+            self.current_workspace
+        }
+    }
+
+    fn is_desktop_visible(&self, desktop: usize) -> bool {
+        if self.config.tag_mode {
+            self.view & (1 << desktop) != 0
+        } else {
+            desktop == self.current_workspace
+        }
+    }
+
+    /// Shows the windows carrying any of the given tags. Tag mode only.
+    fn set_view(&mut self, view: u32) {
+        if view == 0 || view == self.view {
+            return;
+        }
+
+        info!("Viewing tags {:#b}", view);
+        self.view = view;
+        self.update_current_desktop();
+        self.apply_view();
+    }
+
+    /// Replaces the tags of a window. Tag mode only, a window always keeps at least one tag.
+    fn set_window_tags(&mut self, window_id: xlib::Window, tags: u32) {
+        if tags == 0 {
+            return;
+        }
+
+        let Some(window) = self.workspaces[0]
+            .find_mut(window_id)
+            .filter(|w| !w.is_dock)
+        else {
+            return;
+        };
+
+        info!("Tagging window {} with {:#b}", window_id, tags);
+        window.tags = tags;
+        self.set_window_desktop(window_id, tags.trailing_zeros() as u64);
+        self.apply_view();
+    }
+
+    /// Maps the windows that belong to the current view, unmaps the rest and
+    /// rebuilds the layout from the visible tiled windows.
+    fn apply_view(&mut self) {
+        self.layout.clear_windows();
+
+        for window in &self.workspaces[0].windows {
+            if window.is_dock {
+                continue;
+            }
+
+            unsafe {
+                if window.is_viewed(self.view) {
+                    xlib::XMapWindow(self.display.raw(), window.id);
+                    if window.is_floating || window.is_fullscreen {
+                        xlib::XMoveResizeWindow(
+                            self.display.raw(),
+                            window.id,
+                            window.x,
+                            window.y,
+                            window.width,
+                            window.height,
+                        );
                     } else {
-                        if let Some(window) =
-                            workspace.windows.iter_mut().find(|w| w.id == window_id)
-                        {
-                            let mut win_attrs: xlib::XWindowAttributes = std::mem::zeroed();
-                            xlib::XGetWindowAttributes(
-                                self.display.raw(),
-                                window.id,
-                                &mut win_attrs,
-                            );
-
-                            let mut child_x: i32 = 0;
-                            let mut child_y: i32 = 0;
-                            let mut child: xlib::Window = 0;
-                            xlib::XTranslateCoordinates(
-                                self.display.raw(),
-                                window.id,
-                                self.layout.get_root(),
-                                0,
-                                0,
-                                &mut child_x,
-                                &mut child_y,
-                                &mut child,
-                            );
-
-                            window.is_floating = true;
-                            window.pre_float_x = child_x;
-                            window.pre_float_y = child_y;
-                            window.pre_float_width = window.width;
-                            window.pre_float_height = window.height;
-
-                            if self.config.appearance.floating.center_on_float {
-                                let float_width = self.config.appearance.floating.width;
-                                let float_height = self.config.appearance.floating.height;
-                                let mut num_monitors = 0;
-                                let monitors = xinerama::XineramaQueryScreens(
-                                    self.display.raw(),
-                                    &mut num_monitors,
-                                );
+                        self.layout.add_window(window.id);
+                    }
+                } else {
+                    xlib::XUnmapWindow(self.display.raw(), window.id);
+                }
+            }
+        }
 
-                                if !monitors.is_null() && num_monitors > 0 {
-                                    let monitors_slice =
-                                        std::slice::from_raw_parts(monitors, num_monitors as usize);
-
-                                    let mut root_return: xlib::Window = 0;
-                                    let mut child_return: xlib::Window = 0;
-                                    let mut root_x: i32 = 0;
-                                    let mut root_y: i32 = 0;
-                                    let mut win_x: i32 = 0;
-                                    let mut win_y: i32 = 0;
-                                    let mut mask_return: u32 = 0;
-
-                                    xlib::XQueryPointer(
-                                        self.display.raw(),
-                                        self.layout.get_root(),
-                                        &mut root_return,
-                                        &mut child_return,
-                                        &mut root_x,
-                                        &mut root_y,
-                                        &mut win_x,
-                                        &mut win_y,
-                                        &mut mask_return,
-                                    );
+        self.layout.relayout();
+
+        let view = self.view;
+        let focused_visible = self.focus.focused().is_some_and(|id| {
+            self.workspaces[0]
+                .find(id)
+                .is_some_and(|w| w.is_viewed(view))
+        });
+        if !focused_visible {
+            self.focus_next();
+        }
+
+        self.raise_floating_windows();
+        self.update_bar();
+        unsafe {
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
+
+    fn move_window_to_workspace(&mut self, window_id: xlib::Window, index: usize) {
+        if index >= self.workspaces.len() {
+            return;
+        }
+
+        if self.config.tag_mode {
+            self.set_window_tags(window_id, 1 << index);
+            return;
+        }
+
+        let Some(source) = self
+            .workspaces
+            .iter()
+            .position(|ws| ws.find(window_id).is_some_and(|w| !w.is_dock))
+        else {
+            return;
+        };
+
+        if source == index {
+            return;
+        }
+
+        info!("Moving window {} to workspace {}", window_id, index);
+        let Some(mut window) = self.workspaces[source].remove_window(window_id) else {
+            return;
+        };
+        window.is_global = false;
+
+        if source == self.current_workspace {
+            self.layout.remove_window(window_id);
+            unsafe {
+                xlib::XUnmapWindow(self.display.raw(), window_id);
+            }
+        }
+
+        let is_floating = window.is_floating;
+        self.workspaces[index].add_window(window);
+
+        if index == self.current_workspace {
+            unsafe {
+                xlib::XMapWindow(self.display.raw(), window_id);
+            }
+            if !is_floating {
+                self.layout.add_window(window_id);
+            }
+        }
+
+        self.set_window_desktop(window_id, index as u64);
+        self.paint_border(window_id);
+        self.raise_floating_windows();
+        self.update_bar();
+        unsafe {
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
+
+    fn activate_window(&mut self, window_id: xlib::Window) {
+        let Some(index) = self
+            .workspaces
+            .iter()
+            .position(|ws| ws.find(window_id).is_some_and(|w| !w.is_dock))
+        else {
+            return;
+        };
+
+        if !self.config.focus_on_activate {
+            debug!("Marking window {} urgent on activation request", window_id);
+            if let Some(window) = self.workspaces[index].find_mut(window_id) {
+                window.is_urgent = true;
+            }
+            self.paint_border(window_id);
+            self.update_bar();
+            return;
+        }
+
+        debug!("Activating window {} on workspace {}", window_id, index);
+        self.show_window(window_id);
+    }
+
+    /// Gives the focused window `mark`, which no other window keeps.
+    fn mark_focused_window(&mut self, mark: char) {
+        let Some(focused) = self.focus.focused() else {
+            return;
+        };
+        for window in self.workspaces.iter_mut().flat_map(|ws| &mut ws.windows) {
+            if window.id == focused {
+                window.mark = Some(mark);
+            } else if window.mark == Some(mark) {
+                window.mark = None;
+            }
+        }
+        self.update_bar();
+    }
+
+    /// Switches to the workspace (or tags) of a window, then focuses and
+    /// raises it.
+    fn show_window(&mut self, window_id: xlib::Window) {
+        let Some(index) = self
+            .workspaces
+            .iter()
+            .position(|ws| ws.find(window_id).is_some_and(|w| !w.is_dock))
+        else {
+            return;
+        };
+
+        if self.config.tag_mode {
+            if let Some(tags) = self.workspaces[0]
+                .find(window_id)
+                .filter(|w| !w.is_viewed(self.view))
+                .map(|w| w.tags)
+            {
+                self.set_view(tags);
+            }
+        } else if index != self.current_workspace {
+            self.switch_to_workspace(index);
+        }
+
+        let is_floating = self.workspaces[index]
+            .find(window_id)
+            .is_some_and(|w| w.is_floating);
+
+        self.focus_window(window_id);
+
+        if is_floating {
+            unsafe {
+                xlib::XRaiseWindow(self.display.raw(), window_id);
+            }
+        }
+        self.raise_floating_windows();
+    }
+
+    /// Whether the client asked not to be focused when it is mapped, with
+    /// a `_NET_WM_USER_TIME` of 0. The time may be kept on the window
+    /// `_NET_WM_USER_TIME_WINDOW` names.
+    fn maps_without_focus(&self, window: xlib::Window) -> bool {
+        let atoms = self.display.atoms();
+        let time_window = self
+            .display
+            .get_property::<u64>(window, atoms.net_wm_user_time_window, xlib::XA_WINDOW)
+            .and_then(|windows| windows.first().copied())
+            .unwrap_or(window);
+        self.display
+            .get_property::<u64>(time_window, atoms.net_wm_user_time, xlib::XA_CARDINAL)
+            .is_some_and(|time| time.first() == Some(&0))
+    }
+
+    fn get_window_desktop(&self, window: xlib::Window) -> Option<usize> {
+        self.display
+            .get_property::<u64>(
+                window,
+                self.display.atoms().net_wm_desktop,
+                xlib::XA_CARDINAL,
+            )?
+            .first()
+            .map(|&desktop| desktop as usize)
+    }
+
+    fn set_window_desktop(&self, window: xlib::Window, desktop: u64) {
+        self.display.set_property(
+            window,
+            self.display.atoms().net_wm_desktop,
+            xlib::XA_CARDINAL,
+            &[desktop],
+        );
+    }
+
+    fn start_window_drag(&mut self, window_id: xlib::Window) {
+        debug!("Starting window drag for window {}", window_id);
+        self.dragging = true;
+        unsafe {
+            let Pointer {
+                x: root_x,
+                y: root_y,
+                ..
+            } = self.display.query_pointer();
+
+            self.drag_start_x = root_x;
+            self.drag_start_y = root_y;
+            self.dragged_window = Some(window_id);
+
+            // Floating windows move with the pointer, tiled ones swap places
+            // with the window they are dropped on.
+            let floating = match self
+                .workspaces
+                .get(self.current_workspace)
+                .and_then(|ws| ws.find(window_id))
+            {
+                Some(window) if window.is_floating => {
+                    self.drag_window_x = window.x;
+                    self.drag_window_y = window.y;
+                    true
+                }
+                _ => false,
+            };
+            let cursor = if floating {
+                self.cursor.moving()
+            } else {
+                self.cursor.grabbing()
+            };
+            debug!("Setting drag cursor for window {}", window_id);
+            xlib::XDefineCursor(self.display.raw(), window_id, cursor);
+            self.focus_window(window_id);
+            xlib::XSync(self.display.raw(), 0);
+        }
+    }
+
+    fn end_window_drag(&mut self) {
+        if let Some(window) = self.dragged_window {
+            debug!("Ending window drag for window {}", window);
+            unsafe {
+                debug!("Resetting cursor for window {}", window);
+                xlib::XDefineCursor(self.display.raw(), window, self.cursor.normal());
+                xlib::XUngrabPointer(self.display.raw(), xlib::CurrentTime);
+                xlib::XSync(self.display.raw(), 0);
+            }
+        }
+        if let (Some(window), Some(target)) = (self.dragged_window, self.drop_target.take()) {
+            debug!("Swapping windows {} and {}", window, target);
+            self.layout.swap_windows(window, target);
+            self.raise_floating_windows();
+        }
+        self.drop_indicator = None;
+        self.dragging = false;
+        self.dragged_window = None;
+    }
+
+    /// Resizes by `edges`, or by the ones nearest to the pointer.
+    fn start_window_resize(&mut self, window_id: xlib::Window, edges: Option<ResizeEdges>) {
+        debug!("Starting window resize for window {}", window_id);
+        self.resizing = true;
+        unsafe {
+            let Pointer {
+                x: root_x,
+                y: root_y,
+                ..
+            } = self.display.query_pointer();
+
+            if let Some(workspace) = self.workspaces.get(self.current_workspace) {
+                if let Some(window) = workspace.find(window_id) {
+                    self.resize_start_x = window.x;
+                    self.resize_start_y = window.y;
+                    self.resize_start_width = window.width;
+                    self.resize_start_height = window.height;
 
-                                    let current_monitor = monitors_slice
-                                        .iter()
-                                        .find(|monitor| {
-                                            root_x >= monitor.x_org as i32
-                                                && root_x
-                                                    < monitor.x_org as i32 + monitor.width as i32
-                                                && root_y >= monitor.y_org as i32
-                                                && root_y
-                                                    < monitor.y_org as i32 + monitor.height as i32
-                                        })
-                                        .unwrap_or(&monitors_slice[0]);
-
-                                    let new_x = current_monitor.x_org as i32
-                                        + ((current_monitor.width as u32 - float_width) / 2) as i32;
-                                    let new_y = current_monitor.y_org as i32
-                                        + ((current_monitor.height as u32 - float_height) / 2)
-                                            as i32;
-
-                                    window.width = float_width;
-                                    window.height = float_height;
-                                    window.x = new_x;
-                                    window.y = new_y;
-
-                                    window.pre_float_x = new_x;
-                                    window.pre_float_y = new_y;
-
-                                    xlib::XFree(monitors as *mut _);
-                                } else {
-                                    let screen_width = xlib::XDisplayWidth(
-                                        self.display.raw(),
-                                        xlib::XDefaultScreen(self.display.raw()),
-                                    ) as u32;
-                                    let screen_height = xlib::XDisplayHeight(
-                                        self.display.raw(),
-                                        xlib::XDefaultScreen(self.display.raw()),
-                                    )
-                                        as u32;
-
-                                    let new_x = ((screen_width - float_width) / 2) as i32;
-                                    let new_y = ((screen_height - float_height) / 2) as i32;
-
-                                    window.width = float_width;
-                                    window.height = float_height;
-                                    window.x = new_x;
-                                    window.y = new_y;
-
-                                    window.pre_float_x = new_x;
-                                    window.pre_float_y = new_y;
-                                }
+                    self.resize_edges = edges.unwrap_or_else(|| {
+                        ResizeEdges::at(
+                            root_x - window.x,
+                            root_y - window.y,
+                            window.width,
+                            window.height,
+                        )
+                    });
+                    self.drag_start_x = root_x;
+                    self.drag_start_y = root_y;
+                    self.resized_window = Some(window_id);
 
-                                xlib::XMoveResizeWindow(
-                                    self.display.raw(),
-                                    window.id,
-                                    window.x,
-                                    window.y,
-                                    window.width,
-                                    window.height,
-                                );
-                            }
-                        }
-                        (true, true)
+                    self.resize_hints = get_size_hints(self.display.raw(), window_id);
+                    if self.resize_hints.min.is_none() {
+                        self.resize_hints.min = Some((MIN_FLOATING_SIZE, MIN_FLOATING_SIZE));
                     }
-                } else {
-                    (false, false)
-                };
-
-                if should_update {
-                    if !is_floating {
-                        self.layout.add_window(window_id);
-                        self.layout.relayout();
-                    } else {
-                        self.layout.remove_window(window_id);
-                        self.layout.relayout();
+                    if window.is_floating && self.config.appearance.floating.show_size {
+                        self.size_popup = Some(SizePopup::new(
+                            self.display.raw(),
+                            self.display.root(),
+                            self.config.appearance.get_notification_background_color(),
+                            self.config.appearance.get_notification_border_color(),
+                        ));
                     }
 
-                    xlib::XSetInputFocus(
+                    debug!("Setting resize cursor for window {}", window_id);
+                    xlib::XDefineCursor(
                         self.display.raw(),
                         window_id,
-                        xlib::RevertToPointerRoot,
-                        xlib::CurrentTime,
+                        self.cursor.get(self.resize_edges.shape()),
                     );
-                    self.set_active_window(window_id);
-
-                    if let Some(workspace) = self.workspaces.get(self.current_workspace) {
-                        for window in &workspace.windows {
-                            let border_color = if window.id == window_id {
-                                self.config.get_focused_border_color()
-                            } else {
-                                self.config.get_border_color()
-                            };
-                            xlib::XSetWindowBorder(self.display.raw(), window.id, border_color);
-                        }
-                    }
-
-                    if is_floating {
-                        xlib::XRaiseWindow(self.display.raw(), window_id);
-                    }
-
-                    self.raise_floating_windows();
+                    self.focus_window(window_id);
                     xlib::XSync(self.display.raw(), 0);
                 }
             }
         }
     }
 
-    fn toggle_fullscreen(&mut self) {
+    fn end_window_resize(&mut self) {
+        if let Some(window) = self.resized_window {
+            debug!("Ending window resize for window {}", window);
+            unsafe {
+                debug!("Resetting cursor for window {}", window);
+                xlib::XDefineCursor(self.display.raw(), window, self.cursor.normal());
+                xlib::XUngrabPointer(self.display.raw(), xlib::CurrentTime);
+                xlib::XSync(self.display.raw(), 0);
+            }
+        }
+        self.resizing = false;
+        self.resized_window = None;
+        self.size_popup = None;
+    }
+
+    /// Starts a move or resize a client asked for, usually from a click
+    /// on its own title bar. The client has let go of the pointer, so it
+    /// is grabbed on the root window until the button is released.
+    fn move_resize(&mut self, window_id: xlib::Window, request: MoveResize) {
+        if request == MoveResize::Cancel {
+            if self.dragging {
+                self.end_window_drag();
+            } else if self.resizing {
+                self.end_window_resize();
+            }
+            return;
+        }
+        let Some(window) = self
+            .workspaces
+            .get(self.current_workspace)
+            .and_then(|ws| ws.find(window_id))
+            .filter(|w| !w.is_dock && !w.is_fullscreen)
+        else {
+            return;
+        };
+        if self.dragging || self.resizing || self.splitting || self.game_mode.is_some() {
+            return;
+        }
+        // Only floating windows change size, tiled ones follow the layout.
+        if matches!(request, MoveResize::Resize(_)) && !window.is_floating {
+            return;
+        }
+
         unsafe {
-            let mut root_return: xlib::Window = 0;
-            let mut child_return: xlib::Window = 0;
-            let mut root_x: i32 = 0;
-            let mut root_y: i32 = 0;
-            let mut win_x: i32 = 0;
-            let mut win_y: i32 = 0;
-            let mut mask_return: u32 = 0;
-
-            xlib::XQueryPointer(
+            let grabbed = xlib::XGrabPointer(
                 self.display.raw(),
-                self.layout.get_root(),
-                &mut root_return,
-                &mut child_return,
-                &mut root_x,
-                &mut root_y,
-                &mut win_x,
-                &mut win_y,
-                &mut mask_return,
+                self.display.root(),
+                0,
+                (xlib::ButtonReleaseMask | xlib::PointerMotionMask) as u32,
+                xlib::GrabModeAsync,
+                xlib::GrabModeAsync,
+                0,
+                0,
+                xlib::CurrentTime,
             );
+            if grabbed != xlib::GrabSuccess {
+                debug!("Couldn't grab the pointer to move window {}", window_id);
+                return;
+            }
+        }
+        match request {
+            MoveResize::Move => self.start_window_drag(window_id),
+            MoveResize::Resize(edges) => self.start_window_resize(window_id, edges),
+            MoveResize::Cancel => {}
+        }
+    }
 
-            if child_return != 0 && child_return != self.layout.get_root() {
-                if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
-                    if let Some(window) =
-                        workspace.windows.iter_mut().find(|w| w.id == child_return)
-                    {
-                        let mut num_monitors = 0;
-                        let monitors =
-                            xinerama::XineramaQueryScreens(self.display.raw(), &mut num_monitors);
-
-                        if !monitors.is_null() && num_monitors > 0 {
-                            let monitors_slice =
-                                std::slice::from_raw_parts(monitors, num_monitors as usize);
-                            let current_monitor = monitors_slice
-                                .iter()
-                                .find(|monitor| {
-                                    root_x >= monitor.x_org as i32
-                                        && root_x < monitor.x_org as i32 + monitor.width as i32
-                                        && root_y >= monitor.y_org as i32
-                                        && root_y < monitor.y_org as i32 + monitor.height as i32
-                                })
-                                .unwrap_or(&monitors_slice[0]);
-
-                            if window.is_fullscreen {
-                                window.is_fullscreen = false;
-                                window.x = window.pre_fullscreen_x;
-                                window.y = window.pre_fullscreen_y;
-                                window.width = window.pre_fullscreen_width;
-                                window.height = window.pre_fullscreen_height;
-                                xlib::XSetWindowBorderWidth(
-                                    self.display.raw(),
-                                    window.id,
-                                    window.pre_fullscreen_border_width,
-                                );
-                                if window.is_floating {
-                                    xlib::XMoveResizeWindow(
-                                        self.display.raw(),
-                                        window.id,
-                                        window.x,
-                                        window.y,
-                                        window.width,
-                                        window.height,
-                                    );
-                                } else {
-                                    self.layout.relayout();
-                                }
-                            } else {
-                                let mut attrs: xlib::XWindowAttributes = std::mem::zeroed();
-                                xlib::XGetWindowAttributes(
-                                    self.display.raw(),
-                                    window.id,
-                                    &mut attrs,
-                                );
+    fn toggle_game_mode(&mut self) {
+        if let Some(game_window) = self.game_mode {
+            self.game_mode_dismissed = Some(game_window);
+            self.set_game_mode(None);
+        } else {
+            self.game_mode_dismissed = None;
+            let focused = self.focus.focused();
+            if self.is_fullscreen(focused) {
+                self.set_game_mode(focused);
+            }
+        }
+    }
 
-                                window.is_fullscreen = true;
-                                window.pre_fullscreen_x = attrs.x;
-                                window.pre_fullscreen_y = attrs.y;
-                                window.pre_fullscreen_width = attrs.width as u32;
-                                window.pre_fullscreen_height = attrs.height as u32;
-                                window.pre_fullscreen_border_width = attrs.border_width as u32;
+    fn is_fullscreen(&self, window: Option<xlib::Window>) -> bool {
+        window.is_some_and(|id| {
+            self.workspaces
+                .get(self.current_workspace)
+                .and_then(|ws| ws.find(id))
+                .is_some_and(|w| w.is_fullscreen)
+        })
+    }
 
-                                window.x = current_monitor.x_org as i32;
-                                window.y = current_monitor.y_org as i32;
-                                window.width = current_monitor.width as u32;
-                                window.height = current_monitor.height as u32;
+    /// Keeps the screen on while the focused window is fullscreen or has
+    /// a rule asking for it.
+    fn update_idle_inhibit(&mut self, focused: Option<xlib::Window>) {
+        let inhibit = focused
+            .and_then(|id| self.workspaces.get(self.current_workspace)?.find(id))
+            .is_some_and(|w| w.inhibit_idle.unwrap_or(w.is_fullscreen));
+        self.idle.set_inhibited(inhibit);
+    }
 
-                                xlib::XSetWindowBorderWidth(self.display.raw(), window.id, 0);
-                                xlib::XMoveResizeWindow(
-                                    self.display.raw(),
-                                    window.id,
-                                    window.x,
-                                    window.y,
-                                    window.width,
-                                    window.height,
-                                );
-                                xlib::XRaiseWindow(self.display.raw(), window.id);
-                            }
+    fn update_game_mode(&mut self, focused: Option<xlib::Window>) {
+        if self.game_mode_dismissed != focused {
+            self.game_mode_dismissed = None;
+        }
 
-                            xlib::XFree(monitors as *mut _);
-                        }
-                    }
-                }
+        let focused_fullscreen = focused.filter(|_| self.is_fullscreen(focused));
+        match self.game_mode {
+            Some(game_window) if focused_fullscreen != Some(game_window) => {
+                self.set_game_mode(None)
+            }
+            None if self.config.game_mode.auto
+                && focused_fullscreen.is_some()
+                && self.game_mode_dismissed.is_none() =>
+            {
+                self.set_game_mode(focused_fullscreen)
             }
+            _ => (),
         }
     }
 
-    fn close_focused_window(&mut self) {
-        debug!("Attempting to close focused window");
+    fn set_game_mode(&mut self, window: Option<xlib::Window>) {
+        match window {
+            Some(id) => info!("Entering game mode for window {}", id),
+            None => info!("Leaving game mode"),
+        }
+
+        self.game_mode = window;
         unsafe {
-            let (focused_window, _was_floating, next_window) = {
-                let workspace = self.workspaces.get(self.current_workspace);
+            Self::setup_key_bindings(
+                self.display.raw(),
+                self.layout.get_root(),
+                &self.config,
+                window.is_some(),
+            );
+        }
+        self.raise_floating_windows();
+    }
 
-                let mut focused_win: xlib::Window = 0;
-                let mut revert_to: i32 = 0;
-                xlib::XGetInputFocus(self.display.raw(), &mut focused_win, &mut revert_to);
+    /// Paints the border of a window in the color for its state. Every
+    /// border color change goes through here.
+    fn paint_border(&self, window_id: xlib::Window) {
+        let Some(window) = self
+            .workspaces
+            .iter()
+            .find_map(|ws| ws.find(window_id))
+            .filter(|w| !w.is_dock)
+        else {
+            return;
+        };
+        let color = self.config.appearance.get_window_border_color(
+            self.focus.is_focused(window_id),
+            window.is_urgent,
+            window.is_global,
+        );
+        unsafe {
+            xlib::XSetWindowBorder(self.display.raw(), window_id, color);
+        }
+    }
 
-                let mut actual_type: xlib::Atom = 0;
-                let mut actual_format: i32 = 0;
-                let mut nitems: u64 = 0;
-                let mut bytes_after: u64 = 0;
-                let mut data: *mut xlib::Window = std::ptr::null_mut();
+    /// The border width a managed window should have: none for docks and
+    /// fullscreen windows, the configured one for everything else.
+    fn border_width(&self, window_id: xlib::Window) -> Option<u32> {
+        let window = self.workspaces.iter().find_map(|ws| ws.find(window_id))?;
+        Some(window.border_width(self.config.appearance.border_width))
+    }
 
-                let root = xlib::XDefaultRootWindow(self.display.raw());
-                xlib::XGetWindowProperty(
-                    self.display.raw(),
-                    root,
-                    self.net_active_window,
-                    0,
-                    1,
-                    0,
-                    xlib::XA_WINDOW,
-                    &mut actual_type,
-                    &mut actual_format,
-                    &mut nitems,
-                    &mut bytes_after,
-                    &mut data as *mut *mut xlib::Window as *mut *mut u8,
-                );
+    /// Puts back the border width and color of a window whose client
+    /// changed them.
+    fn restore_border(&self, window_id: xlib::Window) {
+        let Some(width) = self.border_width(window_id) else {
+            return;
+        };
+        unsafe {
+            xlib::XSetWindowBorderWidth(self.display.raw(), window_id, width);
+        }
+        self.paint_border(window_id);
+    }
 
-                let net_active_win = if !data.is_null() && nitems > 0 {
-                    let win = *data;
-                    xlib::XFree(data as *mut _);
-                    win
-                } else {
-                    0
-                };
+    /// Repaints every border, for when the colors change.
+    fn paint_borders(&self) {
+        for window in self.workspaces.iter().flat_map(|ws| &ws.windows) {
+            self.paint_border(window.id);
+        }
+    }
 
-                let (focused_id, is_floating) =
-                    if focused_win != 0 && focused_win != self.layout.get_root() {
-                        workspace.and_then(|ws| {
-                            ws.windows
-                                .iter()
-                                .find(|w| w.id == focused_win)
-                                .map(|w| (w.id, w.is_floating))
-                        })
-                    } else if net_active_win != 0 && net_active_win != self.layout.get_root() {
-                        workspace.and_then(|ws| {
-                            ws.windows
-                                .iter()
-                                .find(|w| w.id == net_active_win)
-                                .map(|w| (w.id, w.is_floating))
-                        })
-                    } else {
-                        None
-                    }
-                    .unwrap_or_else(|| {
-                        workspace
-                            .and_then(|ws| ws.get_focused_window().map(|w| (w.id, w.is_floating)))
-                            .or_else(|| self.layout.get_focused_window().map(|id| (id, false)))
-                            .unwrap_or((0, false))
-                    });
+    /// Focuses a client and keeps the workspace, urgency and game mode
+    /// state in line with the focus manager.
+    fn focus_window(&mut self, window_id: xlib::Window) {
+        if self
+            .workspaces
+            .iter()
+            .filter_map(|ws| ws.find(window_id))
+            .any(|w| !w.accepts_focus)
+        {
+            return;
+        }
+        let previous = self.focus.focused();
+        let changed = previous != Some(window_id);
+        self.focus.focus(window_id);
 
-                let next = if focused_id != 0 {
-                    workspace.and_then(|ws| {
-                        if is_floating {
-                            let next_floating = ws
-                                .windows
-                                .iter()
-                                .filter(|w| w.is_floating && !w.is_dock && w.id != focused_id)
-                                .last();
-
-                            next_floating
-                                .or_else(|| {
-                                    ws.windows
-                                        .iter()
-                                        .filter(|w| !w.is_floating && !w.is_dock)
-                                        .last()
-                                })
-                                .map(|w| (w.id, w.is_floating))
-                        } else {
-                            ws.windows
-                                .iter()
-                                .filter(|w| !w.is_dock)
-                                .last()
-                                .map(|w| (w.id, w.is_floating))
-                        }
-                    })
-                } else {
-                    None
-                };
+        if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
+            workspace.focus(window_id);
+            if let Some(window) = workspace.find_mut(window_id) {
+                window.is_urgent = false;
+            }
+        }
+        if let Some(previous) = previous.filter(|_| changed) {
+            self.paint_border(previous);
+        }
+        self.paint_border(window_id);
+        self.update_bar();
+
+        self.update_game_mode(Some(window_id));
+        self.update_idle_inhibit(Some(window_id));
+
+        if changed {
+            self.emit(HookEvent::FocusChanged {
+                window: Some(window_id),
+            });
+            self.broadcast_title();
+            self.update_focused_monitor(window_id);
+        }
+    }
 
-                (focused_id, is_floating, next)
-            };
+    /// Emits `MonitorChanged` when the newly focused window is on another
+    /// monitor than the previously focused one.
+    fn update_focused_monitor(&mut self, window_id: xlib::Window) {
+        let Some(attrs) = self.display.window_attributes(window_id) else {
+            return;
+        };
+        let (x, y) = (attrs.x + attrs.width / 2, attrs.y + attrs.height / 2);
+        let Some(monitor) = self
+            .display
+            .monitors()
+            .iter()
+            .position(|m| m.contains(x, y))
+        else {
+            return;
+        };
 
-            if focused_window == 0 {
-                return;
-            }
+        if self
+            .focused_monitor
+            .replace(monitor)
+            .is_some_and(|m| m != monitor)
+        {
+            self.emit(HookEvent::MonitorChanged { monitor });
+        }
+    }
 
-            if let Some(workspace) = self.workspaces.get(self.current_workspace) {
-                if let Some(window) = workspace.windows.iter().find(|w| w.id == focused_window) {
-                    if window.is_dock {
-                        debug!("Ignoring close request for dock window");
-                        return;
-                    }
-                }
-            }
+    /// Runs the hooks for the event and sends it to IPC subscribers.
+    fn emit(&mut self, event: HookEvent) {
+        if let Some(ipc) = &mut self.ipc {
+            ipc.broadcast(&event.to_json());
+        }
+        self.hooks.emit(&event, &mut self.processes);
+    }
 
-            let wm_protocols = xlib::XInternAtom(self.display.raw(), c"WM_PROTOCOLS".as_ptr(), 0);
-            let wm_delete_window =
-                xlib::XInternAtom(self.display.raw(), c"WM_DELETE_WINDOW".as_ptr(), 0);
+    /// Tells IPC subscribers the title of the focused window, after focus
+    /// moved or the focused window renamed itself.
+    fn broadcast_title(&mut self) {
+        let Some(ipc) = &mut self.ipc else {
+            return;
+        };
+        let window = self.focus.focused();
+        let title = window.and_then(|window| self.display.window_title(window));
+        ipc.broadcast(&format!(
+            r#"{{"event":"title_changed","window":{},"title":{}}}"#,
+            window.map_or(String::from("null"), |w| w.to_string()),
+            title.map_or(String::from("null"), |t| ipc::json_string(&t))
+        ));
+    }
 
-            let mut protocols: *mut xlib::Atom = std::ptr::null_mut();
-            let mut num_protocols: i32 = 0;
+    /// Calls `callback` on every event of the given kind, after the hook
+    /// commands from the config.
+    pub fn add_hook(&mut self, kind: HookKind, callback: impl FnMut(&HookEvent) + 'static) {
+        self.hooks.register(kind, Box::new(callback));
+    }
 
-            if xlib::XGetWMProtocols(
-                self.display.raw(),
-                focused_window,
-                &mut protocols,
-                &mut num_protocols,
-            ) != 0
-            {
-                let protocols_slice = std::slice::from_raw_parts(protocols, num_protocols as usize);
-                if protocols_slice.contains(&wm_delete_window) {
-                    let mut data: xlib::ClientMessageData = std::mem::zeroed();
-                    data.set_long(0, wm_delete_window as i64);
-
-                    let mut event = xlib::XEvent {
-                        client_message: xlib::XClientMessageEvent {
-                            type_: xlib::ClientMessage,
-                            serial: 0,
-                            send_event: 1,
-                            display: self.display.raw(),
-                            window: focused_window,
-                            message_type: wm_protocols,
-                            format: 32,
-                            data,
-                        },
-                    };
-                    xlib::XSendEvent(self.display.raw(), focused_window, 0, 0, &mut event);
-                } else {
-                    xlib::XDestroyWindow(self.display.raw(), focused_window);
-                }
-                xlib::XFree(protocols as *mut _);
-            } else {
-                xlib::XDestroyWindow(self.display.raw(), focused_window);
-            }
+    /// The desktop a managed window is on: its workspace, or its first tag
+    /// in tag mode.
+    fn window_desktop(&self, window_id: xlib::Window) -> Option<usize> {
+        let index = self
+            .workspaces
+            .iter()
+            .position(|ws| ws.contains(window_id))?;
+        if self.config.tag_mode {
+            let tags = self.workspaces[index].find(window_id)?.tags;
+            Some(tags.trailing_zeros() as usize)
+        } else {
+            Some(index)
+        }
+    }
 
-            xlib::XSync(self.display.raw(), 0);
+    /// Moves focus to the best remaining client on the current workspace,
+    /// or drops it when there is none.
+    fn focus_next(&mut self) {
+        let tag_mode = self.config.tag_mode;
+        let view = self.view;
+        let focusable =
+            |w: &&Window| !w.is_dock && w.accepts_focus && (!tag_mode || w.is_viewed(view));
+        let next = self.workspaces.get(self.current_workspace).and_then(|ws| {
+            ws.focus_history()
+                .find(focusable)
+                .or_else(|| ws.windows.iter().rfind(focusable))
+                .map(|w| w.id)
+        });
 
-            if let Some((next_id, is_floating)) = next_window {
-                if is_floating {
-                    xlib::XRaiseWindow(self.display.raw(), next_id);
-                }
-                self.layout.focus_window(next_id);
-                self.set_active_window(next_id);
-
-                if let Some(workspace) = self.workspaces.get(self.current_workspace) {
-                    for w in &workspace.windows {
-                        let border_color = if w.id == next_id {
-                            self.config.get_focused_border_color()
-                        } else {
-                            self.config.get_border_color()
-                        };
-                        xlib::XSetWindowBorder(self.display.raw(), w.id, border_color);
-                    }
+        match next {
+            Some(id) => self.focus_window(id),
+            None => {
+                let previous = self.focus.focused();
+                let changed = previous.is_some();
+                self.focus.unfocus();
+                if let Some(previous) = previous {
+                    self.paint_border(previous);
+                }
+                self.update_game_mode(None);
+                self.update_idle_inhibit(None);
+                self.update_bar();
+                if changed {
+                    self.emit(HookEvent::FocusChanged { window: None });
+                    self.broadcast_title();
                 }
             }
         }
     }
 
-    fn handle_map_request(&mut self, event: xlib::XEvent) {
-        let map_event: xlib::XMapRequestEvent = From::from(event);
-        let window_id = map_event.window;
-        debug!("Handling map request for window {}", window_id);
-
-        let mut attrs: xlib::XWindowAttributes = unsafe { std::mem::zeroed() };
-        let is_dock = unsafe {
-            xlib::XGetWindowAttributes(self.display.raw(), window_id, &mut attrs);
+    fn dynamic_workspaces(&self) -> bool {
+        self.config.dynamic_workspaces && !self.config.tag_mode
+    }
 
-            let net_wm_window_type =
-                xlib::XInternAtom(self.display.raw(), c"_NET_WM_WINDOW_TYPE".as_ptr(), 0);
-            let net_wm_window_type_dock =
-                xlib::XInternAtom(self.display.raw(), c"_NET_WM_WINDOW_TYPE_DOCK".as_ptr(), 0);
+    /// Adds a workspace at the end and switches to it.
+    fn new_workspace(&mut self) {
+        if !self.dynamic_workspaces() {
+            warn!("workspace new needs dynamic_workspaces");
+            return;
+        }
+        let index = self.workspaces.len();
+        if index >= MAX_WORKSPACES {
+            warn!("Not adding more than {} workspaces", MAX_WORKSPACES);
+            return;
+        }
 
-            let mut actual_type: xlib::Atom = 0;
-            let mut actual_format: i32 = 0;
-            let mut nitems: u64 = 0;
-            let mut bytes_after: u64 = 0;
-            let mut prop: *mut u8 = std::ptr::null_mut();
+        info!("Adding workspace {}", index);
+        self.push_workspace();
+        self.update_desktops();
+        self.switch_to_workspace(index);
+    }
 
-            let is_dock = if xlib::XGetWindowProperty(
-                self.display.raw(),
-                window_id,
-                net_wm_window_type,
-                0,
-                1,
-                0,
-                xlib::XA_ATOM,
-                &mut actual_type,
-                &mut actual_format,
-                &mut nitems,
-                &mut bytes_after,
-                &mut prop,
-            ) == 0
-                && !prop.is_null()
-                && nitems > 0
-            {
-                let atom = *(prop as *const xlib::Atom);
-                xlib::XFree(prop as *mut _);
-                atom == net_wm_window_type_dock
-            } else {
-                false
-            };
+    /// Adds an empty workspace at the end. Docks are on every workspace,
+    /// so the new one starts out with them.
+    fn push_workspace(&mut self) {
+        let mut workspace = Workspace::new(self.workspaces.len());
+        let docks = self
+            .workspaces
+            .first()
+            .into_iter()
+            .flat_map(|ws| &ws.windows)
+            .filter(|window| window.is_dock)
+            .cloned()
+            .collect::<Vec<_>>();
+        for dock in docks {
+            workspace.add_window(dock);
+        }
+        self.workspaces.push(workspace);
+    }
 
-            debug!("Grabbing buttons for window {}", window_id);
-            if !is_dock {
-                xlib::XGrabButton(
-                    self.display.raw(),
-                    1,
-                    self.config.get_modifier(),
-                    window_id,
-                    1,
-                    (xlib::ButtonPressMask | xlib::ButtonReleaseMask | xlib::PointerMotionMask)
-                        as u32,
-                    xlib::GrabModeAsync,
-                    xlib::GrabModeAsync,
-                    0,
-                    0,
-                );
-                xlib::XGrabButton(
-                    self.display.raw(),
-                    3,
-                    self.config.get_modifier(),
-                    window_id,
-                    1,
-                    (xlib::ButtonPressMask | xlib::ButtonReleaseMask | xlib::PointerMotionMask)
-                        as u32,
-                    xlib::GrabModeAsync,
-                    xlib::GrabModeAsync,
-                    0,
-                    0,
-                );
+    /// Drops the empty workspaces that aren't shown when workspaces are
+    /// dynamic. The ones after them move up, windows are told their new
+    /// desktop.
+    fn remove_empty_workspaces(&mut self) {
+        if !self.dynamic_workspaces() {
+            return;
+        }
+        let current = self.current_workspace;
+        let removable = |(i, ws): (usize, &Workspace)| i != current && !ws.is_occupied();
+        if !self.workspaces.iter().enumerate().any(removable) {
+            return;
+        }
 
-                if !self.config.appearance.focus_follows_mouse {
-                    xlib::XGrabButton(
-                        self.display.raw(),
-                        xlib::AnyButton as u32,
-                        0,
-                        window_id,
-                        1,
-                        (xlib::ButtonPressMask | xlib::ButtonReleaseMask) as u32,
-                        xlib::GrabModeSync,
-                        xlib::GrabModeAsync,
-                        0,
-                        0,
-                    );
+        let old_previous = self.previous_workspace;
+        let before = self.workspaces.len();
+        let mut kept = Vec::with_capacity(self.workspaces.len());
+        for (old, mut ws) in std::mem::take(&mut self.workspaces).into_iter().enumerate() {
+            if old != current && !ws.is_occupied() {
+                if old_previous == Some(old) {
+                    self.previous_workspace = None;
                 }
+                continue;
             }
-            is_dock
-        };
-
-        let mut window = Window::new(
-            window_id,
-            attrs.x,
-            attrs.y,
-            attrs.width as u32,
-            attrs.height as u32,
-        );
-
-        unsafe {
-            if is_dock {
-                window.is_floating = true;
-                window.is_dock = true;
-
-                xlib::XSetWindowBorderWidth(self.display.raw(), window_id, 0);
-
-                for workspace in &mut self.workspaces {
-                    workspace.add_window(window.clone());
+            let index = kept.len();
+            if index != old {
+                ws.index = index;
+                ws.name = format!("Workspace {}", index + 1);
+                for window in &ws.windows {
+                    self.set_window_desktop(window.id, index as u64);
+                }
+                if old == current {
+                    self.current_workspace = index;
+                }
+                if old_previous == Some(old) {
+                    self.previous_workspace = Some(index);
                 }
+            }
+            kept.push(ws);
+        }
+        debug!("Removed {} empty workspaces", before - kept.len());
+        self.workspaces = kept;
 
-                xlib::XMapWindow(self.display.raw(), window_id);
-                xlib::XRaiseWindow(self.display.raw(), window_id);
+        self.update_desktops();
+        if self.current_workspace != current {
+            self.update_current_desktop();
+        }
+        self.update_bar();
+    }
 
-                self.layout.update_dock_space(window.y, window.height);
-            } else if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
-                xlib::XMapWindow(self.display.raw(), window_id);
-                xlib::XSetWindowBorderWidth(
-                    self.display.raw(),
-                    window_id,
-                    self.config.appearance.border_width,
-                );
+    /// Publishes the number and names of the workspaces.
+    fn update_desktops(&self) {
+        let atoms = self.display.atoms();
+        self.display.set_property(
+            self.display.root(),
+            atoms.net_number_of_desktops,
+            xlib::XA_CARDINAL,
+            &[self.workspaces.len() as u64],
+        );
+        let names = self
+            .workspaces
+            .iter()
+            .map(|ws| ws.name.clone() + "\0")
+            .collect::<String>();
+        self.display.set_property(
+            self.display.root(),
+            atoms.net_desktop_names,
+            atoms.utf8_string,
+            names.as_bytes(),
+        );
+    }
 
-                workspace.add_window(window);
-                self.layout.add_window(window_id);
+    fn update_current_desktop(&mut self) {
+        self.emit(HookEvent::WorkspaceChanged {
+            workspace: self.current_desktop(),
+        });
+        self.display.set_property(
+            self.display.root(),
+            self.display.atoms().net_current_desktop,
+            xlib::XA_CARDINAL,
+            &[self.current_desktop() as u64],
+        );
+        self.display.sync();
+    }
 
-                for window in &workspace.windows {
-                    let border_color = if window.id == window_id {
-                        self.config.get_focused_border_color()
-                    } else {
-                        self.config.get_border_color()
-                    };
-                    xlib::XSetWindowBorder(self.display.raw(), window.id, border_color);
-                }
+    fn handle_button_press(&mut self, button_event: ButtonEvent) {
+        debug!(
+            "Button press: window={}, button={}, state={}",
+            button_event.window, button_event.button, button_event.state
+        );
+        self.hide_workspace_preview();
 
-                self.set_active_window(window_id);
-                xlib::XSync(self.display.raw(), 0);
-            }
-        }
+        if let Some(bar) = self
+            .bars
+            .iter()
+            .find(|bar| bar.window == button_event.window)
+        {
+            let on_layout = bar.is_layout_at(button_event.x);
+            let scroll = match button_event.button {
+                4 => Some(&self.config.appearance.bar.scroll_up),
+                5 => Some(&self.config.appearance.bar.scroll_down),
+                _ => None,
+            };
 
-        self.raise_floating_windows();
-        unsafe {
-            self.notification_manager.raise_all();
-            xlib::XSync(self.display.raw(), 0);
+            if let Some(command) = scroll {
+                if on_layout {
+                    self.execute_command(&Command::CycleLayout);
+                } else if let Some(command) = command.clone() {
+                    self.execute_command(&command);
+                }
+            } else if let Some(index) = bar.workspace_at(button_event.x) {
+                self.switch_to_workspace(index);
+            } else if on_layout {
+                self.execute_command(&Command::CycleLayout);
+            }
+            return;
         }
-    }
 
-    fn handle_unmap_notify(&mut self, event: xlib::XEvent) {
-        let unmap_event: xlib::XUnmapEvent = From::from(event);
-        if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
-            workspace.remove_window(unmap_event.window);
-        }
-        self.layout.remove_window(unmap_event.window);
-        self.raise_floating_windows();
-        unsafe {
-            self.notification_manager.raise_all();
-            xlib::XSync(self.display.raw(), 0);
+        if button_event.window == self.split_handle.window {
+            self.splitting = button_event.button == 1;
+            return;
         }
-    }
 
-    fn handle_destroy_notify(&mut self, event: xlib::XEvent) {
-        let destroy_event: xlib::XDestroyWindowEvent = From::from(event);
-        if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
-            workspace.remove_window(destroy_event.window);
-        }
-        self.layout.remove_window(destroy_event.window);
-        self.raise_floating_windows();
-        unsafe {
-            self.notification_manager.raise_all();
-            xlib::XSync(self.display.raw(), 0);
+        let action = unsafe {
+            self.notification_manager.handle_button_press(
+                button_event.window,
+                button_event.x,
+                button_event.y,
+            )
+        };
+        if action == Some(NotificationAction::OpenConfig) {
+            self.open_config();
         }
-    }
 
-    fn handle_enter_notify(&mut self, event: xlib::XEvent) {
-        let enter_event: xlib::XCrossingEvent = From::from(event);
-        if !self.dragging
-            && !self.resizing
-            && enter_event.window != 0
-            && enter_event.window != self.layout.get_root()
+        if button_event.state & self.config.get_modifier() != 0 {
+            match button_event.button {
+                1 => self.start_window_drag(button_event.window),
+                3 => self.start_window_resize(button_event.window, None),
+                _ => (),
+            }
+        } else if !self.config.appearance.focus_follows_mouse
+            && button_event.window != 0
+            && button_event.window != self.layout.get_root()
             && !self
                 .notification_manager
-                .contains_window(enter_event.window)
-            && self.config.appearance.focus_follows_mouse
+                .contains_window(button_event.window)
         {
-            let window_id = enter_event.window;
-            let is_floating = if let Some(workspace) = self.workspaces.get(self.current_workspace) {
-                for window in &workspace.windows {
-                    unsafe {
-                        let border_color = if window.id == window_id {
-                            self.config.get_focused_border_color()
-                        } else {
-                            self.config.get_border_color()
-                        };
-                        xlib::XSetWindowBorder(self.display.raw(), window.id, border_color);
-                    }
-                }
-
-                workspace
-                    .windows
-                    .iter()
-                    .find(|w| w.id == window_id)
-                    .map(|w| w.is_floating)
-                    .unwrap_or(false)
-            } else {
-                false
-            };
+            let window_id = button_event.window;
+            let is_floating = self
+                .workspaces
+                .get(self.current_workspace)
+                .and_then(|ws| ws.find(window_id))
+                .is_some_and(|w| w.is_floating);
 
-            self.layout.focus_window(window_id);
-            self.set_active_window(window_id);
+            self.focus_window(window_id);
 
             if is_floating {
                 unsafe {
                     xlib::XRaiseWindow(self.display.raw(), window_id);
-                    self.notification_manager.raise_all();
                 }
+                self.raise_notifications();
             } else {
                 self.raise_floating_windows();
-                unsafe {
-                    self.notification_manager.raise_all();
-                }
+                self.raise_notifications();
             }
-        }
-    }
-
-    fn handle_leave_notify(&mut self, _event: xlib::XEvent) {
-        // no-op
-    }
-
-    fn switch_to_workspace(&mut self, index: usize) {
-        if index >= self.workspaces.len() || index == self.current_workspace {
-            debug!("Invalid workspace switch request to {}", index);
-            return;
-        }
 
-        info!("Switching to workspace {}", index);
-        if let Some(current) = self.workspaces.get(self.current_workspace) {
-            for window in &current.windows {
-                if !window.is_dock {
-                    unsafe {
-                        xlib::XUnmapWindow(self.display.raw(), window.id);
-                    }
-                }
+            unsafe {
+                xlib::XAllowEvents(self.display.raw(), xlib::ReplayPointer, 0);
+                xlib::XSync(self.display.raw(), 0);
             }
         }
+    }
 
-        self.current_workspace = index;
-        self.update_current_desktop();
-        self.layout.clear_windows();
-
-        if let Some(new) = self.workspaces.get(self.current_workspace) {
-            for window in &new.windows {
-                unsafe {
-                    if !window.is_dock {
-                        xlib::XMapWindow(self.display.raw(), window.id);
-                        xlib::XSetWindowBorderWidth(
-                            self.display.raw(),
-                            window.id,
-                            self.config.appearance.border_width,
-                        );
-                        xlib::XGrabButton(
-                            self.display.raw(),
-                            1,
-                            self.config.get_modifier(),
-                            window.id,
-                            1,
-                            (xlib::ButtonPressMask
-                                | xlib::ButtonReleaseMask
-                                | xlib::PointerMotionMask) as u32,
-                            xlib::GrabModeAsync,
-                            xlib::GrabModeAsync,
-                            0,
-                            0,
-                        );
-                        xlib::XGrabButton(
-                            self.display.raw(),
-                            3,
-                            self.config.get_modifier(),
-                            window.id,
-                            1,
-                            (xlib::ButtonPressMask
-                                | xlib::ButtonReleaseMask
-                                | xlib::PointerMotionMask) as u32,
-                            xlib::GrabModeAsync,
-                            xlib::GrabModeAsync,
-                            0,
-                            0,
-                        );
-
-                        if !self.config.appearance.focus_follows_mouse {
-                            xlib::XGrabButton(
-                                self.display.raw(),
-                                xlib::AnyButton as u32,
-                                0,
-                                window.id,
-                                1,
-                                (xlib::ButtonPressMask | xlib::ButtonReleaseMask) as u32,
-                                xlib::GrabModeSync,
-                                xlib::GrabModeAsync,
-                                0,
-                                0,
-                            );
-                        }
-
-                        if window.is_floating {
-                            xlib::XMoveResizeWindow(
-                                self.display.raw(),
-                                window.id,
-                                window.x,
-                                window.y,
-                                window.width,
-                                window.height,
-                            );
-                        }
-                    }
-                }
-                if !window.is_dock && !window.is_floating {
-                    self.layout.add_window(window.id);
-                }
-            }
-            if let Some(focused) = new.get_focused_window() {
-                if !focused.is_dock {
-                    self.layout.focus_window(focused.id);
-                    self.set_active_window(focused.id);
-                }
-            }
-            self.raise_floating_windows();
+    fn handle_expose(&mut self, window: xlib::Window) {
+        if self.bars.iter().any(|bar| bar.window == window) {
+            self.update_bar();
+            return;
         }
 
-        self.layout.relayout();
         unsafe {
-            xlib::XSync(self.display.raw(), 0);
+            self.notification_manager.handle_expose(window);
         }
     }
 
-    fn start_window_drag(&mut self, event: xlib::XButtonEvent) {
-        debug!("Starting window drag for window {}", event.window);
-        self.dragging = true;
-        unsafe {
-            let mut root_return: xlib::Window = 0;
-            let mut child_return: xlib::Window = 0;
-            let mut root_x: i32 = 0;
-            let mut root_y: i32 = 0;
-            let mut win_x: i32 = 0;
-            let mut win_y: i32 = 0;
-            let mut mask_return: u32 = 0;
-
-            xlib::XQueryPointer(
-                self.display.raw(),
+    /// Shows or hides the bar and its reserved space until the session ends.
+    fn set_bar_visible(&mut self, visible: bool) {
+        self.bar_visible = Some(visible);
+        if visible != self.bars.is_empty() {
+            return;
+        }
+
+        if !visible {
+            info!("Hiding the bar");
+            self.remove_bars();
+        } else {
+            info!("Showing the bar");
+            self.bars = Self::create_bars(
+                &self.display,
                 self.layout.get_root(),
-                &mut root_return,
-                &mut child_return,
-                &mut root_x,
-                &mut root_y,
-                &mut win_x,
-                &mut win_y,
-                &mut mask_return,
+                &self.config,
+                true,
+                &mut self.layout,
             );
-
-            self.drag_start_x = root_x;
-            self.drag_start_y = root_y;
-            self.dragged_window = Some(event.window);
-
-            debug!("Setting grabbing cursor for window {}", event.window);
-            xlib::XDefineCursor(self.display.raw(), event.window, self.cursor.grabbing());
-            self.layout.focus_window(event.window);
-            self.set_active_window(event.window);
-            xlib::XSync(self.display.raw(), 0);
+            self.update_bar();
+            self.raise_floating_windows();
         }
     }
 
-    fn end_window_drag(&mut self) {
-        if let Some(window) = self.dragged_window {
-            debug!("Ending window drag for window {}", window);
-            unsafe {
-                debug!("Resetting cursor for window {}", window);
-                xlib::XDefineCursor(self.display.raw(), window, self.cursor.normal());
-                if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
-                    if let Some(win) = workspace.windows.iter_mut().find(|w| w.id == window) {
-                        if win.is_floating {
-                            self.drag_start_x = 0;
-                            self.drag_start_y = 0;
-                            win.pre_float_x = win.x;
-                            win.pre_float_y = win.y;
-                        }
-                    }
+    /// Shows the wallpaper set over IPC or in the config, if any.
+    fn show_wallpaper(&mut self) {
+        let Some(path) = self
+            .wallpaper_path
+            .clone()
+            .or_else(|| self.config.wallpaper.clone())
+        else {
+            return;
+        };
+
+        if let Err(e) = self.load_wallpaper(&path) {
+            error!("Failed to set wallpaper: {:#}", e);
+            if self.config.notifications_enabled {
+                unsafe {
+                    self.notification_manager.show_error_with_actions(
+                        &format!("Failed to set wallpaper: {:#}", e),
+                        &[NotificationAction::OpenConfig],
+                    );
                 }
-                xlib::XSync(self.display.raw(), 0);
             }
         }
-        self.dragging = false;
-        self.dragged_window = None;
     }
 
-    fn start_window_resize(&mut self, event: xlib::XButtonEvent) {
-        debug!("Starting window resize for window {}", event.window);
-        self.resizing = true;
-        unsafe {
-            let mut root_return: xlib::Window = 0;
-            let mut child_return: xlib::Window = 0;
-            let mut root_x: i32 = 0;
-            let mut root_y: i32 = 0;
-            let mut win_x: i32 = 0;
-            let mut win_y: i32 = 0;
-            let mut mask_return: u32 = 0;
-
-            xlib::XQueryPointer(
+    fn load_wallpaper(&mut self, path: &str) -> Result<()> {
+        let path = expand_home(path);
+        let wallpaper = unsafe {
+            Wallpaper::set(
                 self.display.raw(),
                 self.layout.get_root(),
-                &mut root_return,
-                &mut child_return,
-                &mut root_x,
-                &mut root_y,
-                &mut win_x,
-                &mut win_y,
-                &mut mask_return,
-            );
+                self.display.atoms(),
+                &path,
+                &self.display.monitors(),
+            )?
+        };
+        info!("Wallpaper set to {}", path.display());
+        // Replacing the old one frees its pixmap, now that the root no
+        // longer points to it.
+        self.wallpaper = Some(wallpaper);
+        Ok(())
+    }
 
-            if let Some(workspace) = self.workspaces.get(self.current_workspace) {
-                if let Some(window) = workspace.windows.iter().find(|w| w.id == event.window) {
-                    self.resize_start_width = window.width;
-                    self.resize_start_height = window.height;
-                    self.drag_start_x = root_x;
-                    self.drag_start_y = root_y;
-                    self.resized_window = Some(event.window);
+    /// Saves a screenshot to the configured directory and tells the user
+    /// where, or why it failed.
+    fn take_screenshot(&mut self, target: ScreenshotTarget) {
+        let root = self.layout.get_root();
+        let area = match target {
+            ScreenshotTarget::Full => self.display.window_attributes(root).map(|attrs| Monitor {
+                x: 0,
+                y: 0,
+                width: attrs.width as u32,
+                height: attrs.height as u32,
+            }),
+            ScreenshotTarget::Window => self
+                .focus
+                .focused()
+                .and_then(|window| self.display.window_attributes(window))
+                .map(|attrs| Monitor {
+                    x: attrs.x,
+                    y: attrs.y,
+                    width: (attrs.width + 2 * attrs.border_width) as u32,
+                    height: (attrs.height + 2 * attrs.border_width) as u32,
+                }),
+            ScreenshotTarget::Region => unsafe {
+                screenshot::select_region(
+                    self.display.raw(),
+                    root,
+                    self.cursor.get(Shape::Crosshair),
+                )
+            },
+        };
+        // Nothing focused or the selection was cancelled.
+        let Some(area) = area else {
+            return;
+        };
 
-                    debug!("Setting grabbing cursor for window {}", event.window);
-                    xlib::XDefineCursor(self.display.raw(), event.window, self.cursor.grabbing());
-                    self.layout.focus_window(event.window);
-                    self.set_active_window(event.window);
-                    xlib::XSync(self.display.raw(), 0);
+        let directory = expand_home(&self.config.screenshot.directory);
+        let result = unsafe { screenshot::capture(self.display.raw(), root, area) }
+            .and_then(|image| screenshot::save(&image, &directory));
+        match result {
+            Ok(path) => {
+                info!("Saved screenshot to {}", path.display());
+                if self.config.notifications_enabled {
+                    unsafe {
+                        self.notification_manager
+                            .show_message(&format!("Saved screenshot to {}", path.display()));
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to take screenshot: {:#}", e);
+                if self.config.notifications_enabled {
+                    unsafe {
+                        self.notification_manager.show_error_with_actions(
+                            &format!("Failed to take screenshot: {:#}", e),
+                            &[NotificationAction::OpenConfig],
+                        );
+                    }
                 }
             }
         }
     }
 
-    fn end_window_resize(&mut self) {
-        if let Some(window) = self.resized_window {
-            debug!("Ending window resize for window {}", window);
-            unsafe {
-                debug!("Resetting cursor for window {}", window);
-                xlib::XDefineCursor(self.display.raw(), window, self.cursor.normal());
-                if let Some(workspace) = self.workspaces.get_mut(self.current_workspace) {
-                    if let Some(win) = workspace.windows.iter_mut().find(|w| w.id == window) {
-                        if win.is_floating {
-                            win.pre_float_width = win.width;
-                            win.pre_float_height = win.height;
-                        }
-                    }
+    /// Switches to another theme until the session ends.
+    fn set_theme(&mut self, name: &str) {
+        if let Err(e) = self.config.theme.get(name) {
+            error!("Failed to set theme: {}", e);
+            if self.config.notifications_enabled {
+                unsafe {
+                    self.notification_manager
+                        .show_error_with_actions(&e.to_string(), &[NotificationAction::OpenConfig]);
                 }
-                xlib::XSync(self.display.raw(), 0);
             }
+            return;
         }
-        self.resizing = false;
-        self.resized_window = None;
-    }
 
-    fn set_active_window(&mut self, window: xlib::Window) {
-        unsafe {
-            let root = xlib::XDefaultRootWindow(self.display.raw());
-            xlib::XChangeProperty(
-                self.display.raw(),
-                root,
-                self.net_active_window,
-                xlib::XA_WINDOW,
-                32,
-                xlib::PropModeReplace,
-                &window as *const xlib::Window as *const u8,
-                1,
+        info!("Switching to theme '{}'", name);
+        self.theme = Some(name.to_string());
+        let mut config = self.config.clone();
+        config.theme.name = self.theme.clone();
+        // Invalid colors were reported when the config was loaded.
+        let _ = unsafe { config.resolve_colors(self.display.raw()) };
+
+        unsafe { self.notification_manager.set_appearance(&config.appearance) };
+        if !self.bars.is_empty() {
+            self.remove_bars();
+            self.bars = Self::create_bars(
+                &self.display,
+                self.layout.get_root(),
+                &config,
+                true,
+                &mut self.layout,
             );
-            xlib::XSync(self.display.raw(), 0);
         }
+        self.layout.update_config(config.clone());
+        self.config = config;
+        self.paint_borders();
+        self.update_bar();
+        self.raise_floating_windows();
     }
 
-    fn update_current_desktop(&mut self) {
-        unsafe {
-            let root = xlib::XDefaultRootWindow(self.display.raw());
-            let current_desktop = self.current_workspace as u32;
-            xlib::XChangeProperty(
-                self.display.raw(),
-                root,
-                self.net_current_desktop,
-                xlib::XA_CARDINAL,
-                32,
-                xlib::PropModeReplace,
-                &current_desktop as *const u32 as *const u8,
-                1,
-            );
-            xlib::XSync(self.display.raw(), 0);
+    /// Shows the windows of the workspace whose button on `bar` is at
+    /// `x_root`, or hides the preview when there is none.
+    fn preview_workspace(&mut self, bar: xlib::Window, x_root: i32) {
+        let Some(bar) = self
+            .bars
+            .iter()
+            .find(|b| b.window == bar)
+            .filter(|_| self.config.appearance.bar.preview)
+        else {
+            return;
+        };
+        let Some((index, button)) = bar
+            .workspace_at_root(x_root)
+            .and_then(|index| Some((index, bar.workspace_button(index)?)))
+        else {
+            self.hide_workspace_preview();
+            return;
+        };
+        let shown = Some((bar.window, index));
+        if self.workspace_preview.as_ref().and_then(|p| p.shown) == shown {
+            return;
         }
-    }
 
-    fn handle_button_press(&mut self, event: xlib::XButtonEvent) {
-        let button_event: xlib::XButtonEvent = event;
-        debug!(
-            "Button press: window={}, button={}, state={}",
-            button_event.window, button_event.button, button_event.state
+        let windows = if self.config.tag_mode {
+            self.workspaces[0]
+                .windows
+                .iter()
+                .filter(|w| w.tags & (1 << index) != 0)
+                .collect::<Vec<_>>()
+        } else {
+            self.workspaces[index].windows.iter().collect()
+        };
+        let windows = windows
+            .into_iter()
+            .filter(|w| !w.is_dock)
+            .map(|w| PreviewWindow {
+                title: self.display.window_title(w.id).unwrap_or_default(),
+                area: Self::window_area(w).into(),
+            })
+            .collect::<Vec<_>>();
+        let bounds = self
+            .display
+            .monitors()
+            .into_iter()
+            .find(|m| m.contains(button.x, button.y))
+            .map_or(button, Geometry::from);
+        let monitor = self.workspace_monitor(index);
+
+        let (display, root) = (self.display.raw(), self.display.root());
+        let (background, border) = (
+            self.config.appearance.get_notification_background_color(),
+            self.config.appearance.get_notification_border_color(),
         );
-
+        let preview = self.workspace_preview.get_or_insert_with(|| unsafe {
+            WorkspacePreview::new(display, root, background, border)
+        });
         unsafe {
-            self.notification_manager
-                .handle_button_press(button_event.window);
+            preview.show(button, bounds, monitor, &windows);
         }
+        preview.shown = shown;
+    }
 
-        if button_event.state & self.config.get_modifier() != 0 {
-            match button_event.button {
-                1 => self.start_window_drag(button_event),
-                3 => self.start_window_resize(button_event),
-                _ => (),
+    fn hide_workspace_preview(&mut self) {
+        if let Some(preview) = &mut self.workspace_preview {
+            unsafe {
+                preview.hide();
             }
-        } else if !self.config.appearance.focus_follows_mouse
-            && button_event.window != 0
-            && button_event.window != self.layout.get_root()
-            && !self
-                .notification_manager
-                .contains_window(button_event.window)
-        {
-            let window_id = button_event.window;
-            let is_floating = if let Some(workspace) = self.workspaces.get(self.current_workspace) {
-                for window in &workspace.windows {
-                    unsafe {
-                        let border_color = if window.id == window_id {
-                            self.config.get_focused_border_color()
-                        } else {
-                            self.config.get_border_color()
-                        };
-                        xlib::XSetWindowBorder(self.display.raw(), window.id, border_color);
-                    }
-                }
+        }
+    }
 
-                workspace
-                    .windows
+    fn update_bar(&mut self) {
+        if !self.bars.is_empty() {
+            let (workspaces, window_count) = if self.config.tag_mode {
+                let windows = &self.workspaces[0].windows;
+                let workspaces = (0..self.workspaces.len())
+                    .map(|tag| {
+                        let mut tagged = windows.iter().filter(|w| w.tags & (1 << tag) != 0);
+                        WorkspaceState {
+                            focused: self.view & (1 << tag) != 0,
+                            occupied: tagged.clone().any(|w| !w.is_dock),
+                            urgent: tagged.any(|w| w.is_urgent),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let visible = windows
                     .iter()
-                    .find(|w| w.id == window_id)
-                    .map(|w| w.is_floating)
-                    .unwrap_or(false)
+                    .filter(|w| !w.is_dock && w.is_viewed(self.view))
+                    .count();
+                (workspaces, visible)
             } else {
-                false
+                let workspaces = self
+                    .workspaces
+                    .iter()
+                    .map(|ws| WorkspaceState {
+                        focused: ws.index == self.current_workspace,
+                        occupied: ws.is_occupied(),
+                        urgent: ws.windows.iter().any(|w| w.is_urgent),
+                    })
+                    .collect::<Vec<_>>();
+                let current = &self.workspaces[self.current_workspace];
+                (
+                    workspaces,
+                    current.tiled().count() + current.floating().count(),
+                )
             };
 
-            self.layout.focus_window(window_id);
-            self.set_active_window(window_id);
+            let focused = self.focus.focused();
+            let mut title = focused.and_then(|window| self.display.window_title(window));
+            let mark = focused
+                .filter(|_| self.config.appearance.bar.show_marks)
+                .and_then(|window| self.workspaces.iter().find_map(|ws| ws.find(window)))
+                .and_then(|window| window.mark);
+            if let Some(mark) = mark {
+                title = Some(match title {
+                    Some(title) => format!("[{}] {}", mark, title),
+                    None => format!("[{}]", mark),
+                });
+            }
 
-            if is_floating {
+            let state = BarState {
+                workspaces,
+                window_count,
+                layout: self.layout.settings().kind,
+                title,
+                focused_pid: focused
+                    .and_then(|window| self.workspaces.iter().find_map(|ws| ws.find(window)))
+                    .and_then(|window| window.pid),
+            };
+            for bar in &mut self.bars {
                 unsafe {
-                    xlib::XRaiseWindow(self.display.raw(), window_id);
-                    self.notification_manager.raise_all();
+                    bar.draw(&state);
                 }
+            }
+        }
+    }
+
+    fn handle_property_notify(&mut self, window: xlib::Window, atom: xlib::Atom) {
+        if atom == xlib::XA_WM_NAME || atom == self.display.atoms().net_wm_name {
+            if self.focus.focused() == Some(window) {
+                self.update_bar();
+                self.broadcast_title();
+            }
+            return;
+        }
+
+        let atoms = self.display.atoms();
+        if atom == atoms.net_wm_strut || atom == atoms.net_wm_strut_partial {
+            let dock = self
+                .workspaces
+                .get(self.current_workspace)
+                .and_then(|ws| ws.find(window))
+                .filter(|w| w.is_dock)
+                .cloned();
+            if let Some(dock) = dock {
+                let struts = self.dock_struts(&dock);
+                self.layout
+                    .set_dock(window, struts, Self::window_area(&dock).into());
+            }
+            return;
+        }
+
+        if atom != xlib::XA_WM_HINTS {
+            return;
+        }
+
+        let is_urgent = unsafe {
+            let hints = xlib::XGetWMHints(self.display.raw(), window);
+            if hints.is_null() {
+                false
             } else {
-                self.raise_floating_windows();
-                unsafe {
-                    self.notification_manager.raise_all();
-                }
+                let urgent = (*hints).flags & xlib::XUrgencyHint != 0;
+                xlib::XFree(hints as *mut _);
+                urgent
             }
+        };
 
-            unsafe {
-                xlib::XAllowEvents(self.display.raw(), xlib::ReplayPointer, 0);
-                xlib::XSync(self.display.raw(), 0);
+        let mut changed = false;
+        for workspace in &mut self.workspaces {
+            if let Some(window) = workspace.find_mut(window) {
+                changed |= window.is_urgent != is_urgent;
+                window.is_urgent = is_urgent;
             }
         }
-    }
 
-    fn handle_expose(&mut self, event: xlib::XExposeEvent) {
-        unsafe {
-            self.notification_manager.handle_expose(event.window);
+        if changed {
+            self.paint_border(window);
+            self.update_bar();
         }
     }
 
-    fn handle_client_message(&mut self, event: xlib::XEvent) {
-        let client_event: xlib::XClientMessageEvent = From::from(event);
-        if client_event.message_type == self.net_current_desktop {
-            let workspace_index = client_event.data.get_long(0) as usize;
+    fn handle_client_message(&mut self, client_event: ClientMessage) {
+        if self.config.kiosk.enabled
+            && !self.startup.is_startup_message(client_event.message_type)
+            && !self.is_kiosk_window(client_event.window)
+        {
+            debug!(
+                "Kiosk mode ignores client message for window {}",
+                client_event.window
+            );
+            return;
+        }
+        if self.startup.is_startup_message(client_event.message_type) {
+            if self.startup.handle_message(&client_event) {
+                self.update_launch_cursor();
+            }
+        } else if client_event.message_type == self.display.atoms().net_current_desktop {
+            let workspace_index = client_event.data[0] as usize;
             if workspace_index < self.workspaces.len() {
                 self.switch_to_workspace(workspace_index);
             }
+        } else if client_event.message_type == self.display.atoms().net_active_window {
+            self.activate_window(client_event.window);
+        } else if client_event.message_type == self.display.atoms().net_close_window {
+            if !(0..=SOURCE_PAGER).contains(&client_event.data[1]) {
+                debug!("Ignoring close request with unknown source");
+            } else if let Some(window) = self.closable(client_event.window) {
+                self.close(window, false);
+            }
+        } else if client_event.message_type == self.display.atoms().net_wm_desktop {
+            // Applications don't get to move themselves away from the user,
+            // only pagers and old clients are followed.
+            let source = client_event.data[1];
+            if source == SOURCE_APPLICATION || !(0..=SOURCE_PAGER).contains(&source) {
+                debug!(
+                    "Ignoring desktop change of window {} from source {}",
+                    client_event.window, source
+                );
+                return;
+            }
+            let desktop = client_event.card32(0) as u64;
+            if desktop == ALL_DESKTOPS {
+                self.set_global(client_event.window, true);
+            } else {
+                self.set_global(client_event.window, false);
+                self.move_window_to_workspace(client_event.window, desktop as usize);
+            }
+        } else if client_event.message_type == self.display.atoms().net_wm_state {
+            let fullscreen = self.display.atoms().net_wm_state_fullscreen;
+            if client_event.data[1] as xlib::Atom == fullscreen
+                || client_event.data[2] as xlib::Atom == fullscreen
+            {
+                self.request_fullscreen(client_event.window, client_event.data[0]);
+            }
+        } else if client_event.message_type == self.display.atoms().net_wm_moveresize {
+            if let Some(request) = MoveResize::from_direction(client_event.data[2]) {
+                self.move_resize(client_event.window, request);
+            }
+        } else if client_event.message_type == self.display.atoms().net_wm_fullscreen_monitors {
+            let indices = [0, 1, 2, 3].map(|i| client_event.data[i] as usize);
+            self.set_fullscreen_monitors(client_event.window, indices);
         }
     }
 }
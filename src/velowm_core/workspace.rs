@@ -1,41 +1,112 @@
-use super::window::Window;
+use x11::xlib;
+
+use super::{registry::WindowRegistry, window::Window};
+use crate::ui::layout::{self, Geometry, LayoutSettings};
 
 pub struct Workspace {
-    pub windows: Vec<Window>,
-    pub focused: Option<usize>,
+    pub windows: WindowRegistry<Window>,
+    /// Window ids in the order they were focused, most recent last.
+    focus_history: Vec<xlib::Window>,
     pub index: usize,
     pub name: String,
+    pub layout_settings: Option<LayoutSettings>,
+    /// The tiled windows as the layout last placed them, kept while the
+    /// workspace is hidden.
+    pub layout_windows: WindowRegistry<layout::Window>,
+    /// The monitor the tiled windows are on, the first one when unset.
+    pub monitor: Option<Geometry>,
+    /// Set by `move_workspace_to_monitor`, `monitor` then wins over
+    /// `[monitors]` until that monitor goes away.
+    pub moved: bool,
 }
 
 impl Workspace {
     pub fn new(index: usize) -> Self {
         Self {
-            windows: Vec::new(),
-            focused: None,
+            windows: WindowRegistry::new(),
+            focus_history: Vec::new(),
             index,
             name: format!("Workspace {}", index + 1),
+            layout_settings: None,
+            layout_windows: WindowRegistry::new(),
+            monitor: None,
+            moved: false,
         }
     }
 
+    /// Adds a window and makes it the focused one.
     pub fn add_window(&mut self, window: Window) {
+        let id = window.id;
         self.windows.push(window);
-        self.focused = Some(self.windows.len() - 1);
-    }
-
-    pub fn remove_window(&mut self, window_id: u64) {
-        if let Some(idx) = self.windows.iter().position(|w| w.id == window_id) {
-            self.windows.remove(idx);
-            if self.focused == Some(idx) {
-                self.focused = if !self.windows.is_empty() {
-                    Some(idx.saturating_sub(1))
-                } else {
-                    None
-                };
+        self.focus(id);
+    }
+
+    /// Removes a window, handing it back so it can be added elsewhere.
+    pub fn remove_window(&mut self, window_id: xlib::Window) -> Option<Window> {
+        let window = self.windows.remove(window_id)?;
+        self.focus_history.retain(|&id| id != window_id);
+        self.layout_windows.remove(window_id);
+        Some(window)
+    }
+
+    /// Moves a window out of this workspace into `target`, where it becomes
+    /// the focused window. Returns whether the window was here.
+    pub fn move_window(&mut self, window_id: xlib::Window, target: &mut Workspace) -> bool {
+        match self.remove_window(window_id) {
+            Some(window) => {
+                target.add_window(window);
+                true
             }
+            None => false,
         }
     }
 
+    /// Records the window as the most recently focused one. Returns false
+    /// when it isn't on this workspace.
+    pub fn focus(&mut self, window_id: xlib::Window) -> bool {
+        if !self.contains(window_id) {
+            return false;
+        }
+        self.focus_history.retain(|&id| id != window_id);
+        self.focus_history.push(window_id);
+        true
+    }
+
     pub fn get_focused_window(&self) -> Option<&Window> {
-        self.focused.and_then(|idx| self.windows.get(idx))
+        self.focus_history.last().and_then(|&id| self.find(id))
+    }
+
+    /// Windows from the most to the least recently focused. Windows that
+    /// were never focused are not included.
+    pub fn focus_history(&self) -> impl Iterator<Item = &Window> {
+        self.focus_history
+            .iter()
+            .rev()
+            .filter_map(|&id| self.find(id))
+    }
+
+    pub fn contains(&self, window_id: xlib::Window) -> bool {
+        self.windows.contains_id(window_id)
+    }
+
+    pub fn find(&self, window_id: xlib::Window) -> Option<&Window> {
+        self.windows.get(window_id)
+    }
+
+    pub fn find_mut(&mut self, window_id: xlib::Window) -> Option<&mut Window> {
+        self.windows.get_mut(window_id)
+    }
+
+    pub fn floating(&self) -> impl Iterator<Item = &Window> {
+        self.windows.iter().filter(|w| w.is_floating && !w.is_dock)
+    }
+
+    pub fn tiled(&self) -> impl Iterator<Item = &Window> {
+        self.windows.iter().filter(|w| !w.is_floating && !w.is_dock)
+    }
+
+    /// Whether the workspace holds any window besides docks.
+    pub fn is_occupied(&self) -> bool {
+        self.windows.iter().any(|w| !w.is_dock)
     }
 }
@@ -0,0 +1,264 @@
+//! Runs the window manager against a private Xvfb server.
+//!
+//! Tests call [`Harness::start`] and return early when it yields `None`,
+//! which happens when `Xvfb` isn't installed.
+
+#![allow(dead_code)]
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{Mutex, MutexGuard},
+    thread,
+    time::{Duration, Instant},
+};
+use velowm::{velowm_core::wm::WindowManager, Config};
+use x11::xlib;
+
+pub const SCREEN_WIDTH: u32 = 1280;
+pub const SCREEN_HEIGHT: u32 = 720;
+
+// The window manager changes process-wide state (error handler, SIGCHLD,
+// environment), so only one harness runs at a time.
+static LOCK: Mutex<()> = Mutex::new(());
+
+pub struct XServer {
+    child: Child,
+    pub display: String,
+}
+
+impl XServer {
+    pub fn start() -> Option<Self> {
+        let number = (90..200).find(|n| {
+            !Path::new(&format!("/tmp/.X11-unix/X{}", n)).exists()
+                && !Path::new(&format!("/tmp/.X{}-lock", n)).exists()
+        })?;
+        let display = format!(":{}", number);
+
+        let child = match Command::new("Xvfb")
+            .arg(&display)
+            .args(["-screen", "0"])
+            .arg(format!("{}x{}x24", SCREEN_WIDTH, SCREEN_HEIGHT))
+            .args(["-nolisten", "tcp"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("skipping: failed to start Xvfb: {}", e);
+                return None;
+            }
+        };
+        let mut server = Self { child, display };
+
+        let socket = PathBuf::from(format!("/tmp/.X11-unix/X{}", number));
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !socket.exists() {
+            if Instant::now() > deadline || server.child.try_wait().ok().flatten().is_some() {
+                eprintln!("skipping: Xvfb did not come up on {}", server.display);
+                return None;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        Some(server)
+    }
+}
+
+impl Drop for XServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A plain X client on its own connection.
+pub struct Client {
+    display: *mut xlib::Display,
+}
+
+impl Client {
+    pub fn connect(display_name: &str) -> Self {
+        let name = std::ffi::CString::new(display_name).unwrap();
+        let display = unsafe { xlib::XOpenDisplay(name.as_ptr()) };
+        assert!(!display.is_null(), "failed to connect to {}", display_name);
+        Self { display }
+    }
+
+    /// Creates and maps a 100x100 top-level window.
+    pub fn create_window(&self) -> xlib::Window {
+        unsafe {
+            let root = xlib::XDefaultRootWindow(self.display);
+            let window = xlib::XCreateSimpleWindow(self.display, root, 0, 0, 100, 100, 0, 0, 0);
+            xlib::XMapWindow(self.display, window);
+            xlib::XSync(self.display, 0);
+            window
+        }
+    }
+
+    /// Creates and maps a dock along the top edge that reserves `strut`
+    /// pixels with `_NET_WM_STRUT`.
+    pub fn create_dock(&self, height: u32, strut: u64) -> xlib::Window {
+        unsafe {
+            let root = xlib::XDefaultRootWindow(self.display);
+            let window =
+                xlib::XCreateSimpleWindow(self.display, root, 0, 0, SCREEN_WIDTH, height, 0, 0, 0);
+            let kind = [self.atom("_NET_WM_WINDOW_TYPE_DOCK")];
+            self.set_property(window, "_NET_WM_WINDOW_TYPE", xlib::XA_ATOM, &kind);
+            self.set_strut(window, strut);
+            xlib::XMapWindow(self.display, window);
+            xlib::XSync(self.display, 0);
+            window
+        }
+    }
+
+    /// Reserves `top` pixels at the top edge for a dock.
+    pub fn set_strut(&self, window: xlib::Window, top: u64) {
+        self.set_property(window, "_NET_WM_STRUT", xlib::XA_CARDINAL, &[0, 0, top, 0]);
+    }
+
+    /// Whether `window` is stacked above `other`.
+    pub fn is_above(&self, window: xlib::Window, other: xlib::Window) -> bool {
+        unsafe {
+            let root = xlib::XDefaultRootWindow(self.display);
+            let (mut root_return, mut parent) = (0, 0);
+            let mut children: *mut xlib::Window = std::ptr::null_mut();
+            let mut count = 0;
+            xlib::XSync(self.display, 0);
+            xlib::XQueryTree(
+                self.display,
+                root,
+                &mut root_return,
+                &mut parent,
+                &mut children,
+                &mut count,
+            );
+            // Children come bottom to top.
+            let stack = std::slice::from_raw_parts(children, count as usize).to_vec();
+            xlib::XFree(children as *mut _);
+            let position = |w| stack.iter().position(|&c| c == w);
+            matches!((position(window), position(other)), (Some(a), Some(b)) if a > b)
+        }
+    }
+
+    fn atom(&self, name: &str) -> xlib::Atom {
+        let name = std::ffi::CString::new(name).unwrap();
+        unsafe { xlib::XInternAtom(self.display, name.as_ptr(), 0) }
+    }
+
+    fn set_property(&self, window: xlib::Window, name: &str, kind: xlib::Atom, values: &[u64]) {
+        unsafe {
+            xlib::XChangeProperty(
+                self.display,
+                window,
+                self.atom(name),
+                kind,
+                32,
+                xlib::PropModeReplace,
+                values.as_ptr() as *const u8,
+                values.len() as i32,
+            );
+            xlib::XSync(self.display, 0);
+        }
+    }
+
+    pub fn destroy_window(&self, window: xlib::Window) {
+        unsafe {
+            xlib::XDestroyWindow(self.display, window);
+            xlib::XSync(self.display, 0);
+        }
+    }
+
+    /// Position and size of the window, without its border.
+    pub fn geometry(&self, window: xlib::Window) -> (i32, i32, u32, u32) {
+        let attrs = self.attributes(window);
+        (attrs.x, attrs.y, attrs.width as u32, attrs.height as u32)
+    }
+
+    pub fn is_viewable(&self, window: xlib::Window) -> bool {
+        self.attributes(window).map_state == xlib::IsViewable
+    }
+
+    fn attributes(&self, window: xlib::Window) -> xlib::XWindowAttributes {
+        unsafe {
+            let mut attrs: xlib::XWindowAttributes = std::mem::zeroed();
+            xlib::XSync(self.display, 0);
+            xlib::XGetWindowAttributes(self.display, window, &mut attrs);
+            attrs
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XCloseDisplay(self.display);
+        }
+    }
+}
+
+/// The default configuration without anything that would put extra windows
+/// on screen or react to the pointer.
+pub fn test_config() -> Config {
+    let mut config = Config {
+        auto_generated: false,
+        notifications_enabled: false,
+        ..Default::default()
+    };
+    config.appearance.bar.enabled = false;
+    config.appearance.focus_follows_mouse = false;
+    config
+}
+
+pub struct Harness {
+    // Field order matters: the window manager and the client disconnect
+    // before the server goes away.
+    pub wm: WindowManager,
+    pub client: Client,
+    _server: XServer,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Harness {
+    pub fn start() -> Option<Self> {
+        Self::with_config(test_config())
+    }
+
+    pub fn with_config(config: Config) -> Option<Self> {
+        let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Keep session files, the ready file and the IPC socket out of the
+        // real home directory.
+        let home = env::temp_dir().join(format!("velowm-test-{}", std::process::id()));
+        std::fs::create_dir_all(&home).unwrap();
+        env::set_var("HOME", &home);
+        env::set_var("XDG_RUNTIME_DIR", &home);
+        env::remove_var("VELOWM_SOCKET");
+
+        let server = XServer::start()?;
+        let wm = WindowManager::with_config(&server.display, config)
+            .expect("failed to start the window manager");
+        let client = Client::connect(&server.display);
+
+        Some(Self {
+            wm,
+            client,
+            _server: server,
+            _lock: lock,
+        })
+    }
+
+    /// Maps a new client window and lets the window manager manage it.
+    pub fn map_window(&mut self) -> xlib::Window {
+        let window = self.client.create_window();
+        self.wm.dispatch_pending();
+        window
+    }
+
+    pub fn destroy_window(&mut self, window: xlib::Window) {
+        self.client.destroy_window(window);
+        self.wm.dispatch_pending();
+    }
+}
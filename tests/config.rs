@@ -0,0 +1,558 @@
+use velowm::{
+    config::{
+        rules::{Length, RuleMonitor},
+        workspace::ReturnWhenEmpty,
+    },
+    ui::appearance::BarMonitors,
+    utils::{
+        command::{CloseTarget, Command, MonitorTarget},
+        keybind::Bind,
+    },
+    Config,
+};
+
+const CONFIG: &str = r#"modifier = "super"
+
+[appearance.bar]
+enabled = true
+height = "tall"
+
+[[binds]]
+key = "q"
+command = "spawn alacritty"
+
+[[binds]]
+key = "3"
+command = "workspsace3"
+
+[[binds]]
+key = "w"
+command = "exit"
+"#;
+
+#[test]
+fn invalid_entries_fall_back_to_defaults() {
+    let config = Config::parse(CONFIG).unwrap();
+
+    assert_eq!(config.modifier, "super");
+    assert!(config.appearance.bar.enabled);
+    assert_eq!(
+        config.appearance.bar.height,
+        Config::default().appearance.bar.height
+    );
+
+    let keys: Vec<&str> = config.binds.iter().map(|b| b.key.as_str()).collect();
+    assert_eq!(keys, ["q", "w"]);
+    assert!(matches!(config.binds[1].command, Command::Exit));
+}
+
+#[test]
+fn errors_name_the_line_and_key() {
+    let config = Config::parse(CONFIG).unwrap();
+
+    assert_eq!(config.errors.len(), 2);
+    assert!(config.errors[0].starts_with("line 5, appearance.bar.height: "));
+    assert_eq!(
+        config.errors[1],
+        "line 13, binds.command: Unknown command 'workspsace3'"
+    );
+}
+
+#[test]
+fn valid_config_has_no_errors() {
+    let config = Config::parse("modifier = \"alt\"\nbinds = []\n").unwrap();
+    assert!(config.errors.is_empty());
+}
+
+#[test]
+fn broken_syntax_is_an_error() {
+    let Err(e) = Config::parse("modifier = \"alt\"\nbinds = [\n") else {
+        panic!("expected a syntax error");
+    };
+    assert!(format!("{:#}", e).contains("line 2"));
+}
+
+#[test]
+fn keys_bound_twice_are_conflicts() {
+    let config = Config::parse(
+        r#"modifier = "alt"
+
+[[binds]]
+key = "Q"
+command = "close"
+
+[[binds]]
+key = "escape"
+command = "exit"
+
+[[binds]]
+key = "q"
+command = "spawn alacritty"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.keybind_conflicts(),
+        [r#"alt+q is bound 2 times, by keys "Q", "q""#]
+    );
+}
+
+#[test]
+fn unknown_keys_are_rejected_with_suggestions() {
+    let config = Config::parse(
+        r#"modifier = "alt"
+
+[[binds]]
+key = "Retrun"
+command = "exit"
+
+[[binds]]
+key = "F13"
+command = "close"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.binds.len(), 1);
+    assert_eq!(config.binds[0].key, "F13");
+    assert_eq!(config.errors.len(), 1);
+    assert!(config.errors[0].starts_with("line 4, binds.key: Unknown key 'Retrun', did you mean"));
+    assert!(config.errors[0].contains("'Return'"));
+}
+
+#[test]
+fn bind_commands_can_be_sequences() {
+    let config = Config::parse(
+        r#"modifier = "alt"
+
+[[binds]]
+key = "f"
+command = ["workspace3", "spawn firefox"]
+
+[[binds]]
+key = "g"
+command = ["workspace3", "spwan firefox"]
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.binds.len(), 1);
+    let Command::Sequence(commands) = &config.binds[0].command else {
+        panic!("expected a sequence");
+    };
+    assert!(matches!(
+        commands.as_slice(),
+        [Command::Workspace(2), Command::Spawn(spawn)] if spawn.command == "firefox"
+    ));
+    assert_eq!(
+        config.errors,
+        ["line 9, binds.command: Unknown command 'spwan firefox'"]
+    );
+}
+
+#[test]
+fn spawn_tables_set_cwd_and_env() {
+    let config = Config::parse(
+        r#"modifier = "alt"
+
+[[binds]]
+key = "Return"
+command = { spawn = "alacritty -e htop", cwd = "/tmp", env = { FOO = "1" } }
+
+[[binds]]
+key = "t"
+command = { spawn = "alacritty", directory = "/tmp" }
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.binds.len(), 1);
+    let Command::Spawn(spawn) = &config.binds[0].command else {
+        panic!("expected a spawn command");
+    };
+    assert_eq!(spawn.program(), "alacritty");
+    assert_eq!(spawn.cwd.as_deref(), Some("/tmp"));
+    assert_eq!(spawn.env["FOO"], "1");
+
+    let process = spawn.to_process();
+    assert_eq!(
+        process.get_current_dir(),
+        Some(std::path::Path::new("/tmp"))
+    );
+    assert_eq!(config.errors.len(), 1);
+    assert!(config.errors[0].contains("directory"));
+}
+
+#[test]
+fn workspaces_are_pinned_to_outputs() {
+    let config = Config::parse(
+        r#"modifier = "alt"
+binds = []
+
+[monitors]
+"DP-1" = [1, 2, 3]
+"HDMI-1" = [6]
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.pinned_output(0), Some("DP-1"));
+    assert_eq!(config.pinned_output(5), Some("HDMI-1"));
+    assert_eq!(config.pinned_output(3), None);
+}
+
+#[test]
+fn workspaces_move_to_named_or_neighbouring_monitors() {
+    let parse = |s: &str| match s.parse::<Command>() {
+        Ok(Command::MoveWorkspaceToMonitor(target)) => target,
+        _ => panic!("expected move_workspace_to_monitor"),
+    };
+    assert_eq!(
+        parse("move_workspace_to_monitor prev"),
+        MonitorTarget::Previous
+    );
+    assert_eq!(
+        parse("move_workspace_to_monitor HDMI-1"),
+        MonitorTarget::Output("HDMI-1".to_string())
+    );
+    assert!("move_workspace_to_monitor ".parse::<Command>().is_err());
+    assert!(matches!(
+        "move_window_to_monitor next".parse(),
+        Ok(Command::MoveWindowToMonitor(MonitorTarget::Next))
+    ));
+    assert_eq!(
+        parse("move_workspace_to_monitor primary"),
+        MonitorTarget::Primary
+    );
+    assert!(matches!(
+        "focus_primary_monitor".parse(),
+        Ok(Command::FocusPrimaryMonitor)
+    ));
+}
+
+#[test]
+fn bars_default_to_the_primary_monitor() {
+    assert_eq!(
+        Config::default().appearance.bar.monitors,
+        BarMonitors::Primary
+    );
+
+    let config = Config::parse(
+        r#"modifier = "alt"
+binds = []
+
+[appearance.bar]
+monitors = "all"
+
+[monitors]
+primary = [1]
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.appearance.bar.monitors, BarMonitors::All);
+    assert_eq!(config.pinned_output(0), Some("primary"));
+}
+
+#[test]
+fn binds_can_use_their_own_modifier() {
+    let config = Config::parse(
+        r#"modifier = "super"
+
+[[binds]]
+key = "F4"
+modifier = "alt"
+command = "close"
+
+[[binds]]
+key = "F4"
+command = "exit"
+
+[[binds]]
+key = "q"
+modifier = "super+shift"
+command = "exit"
+
+[[binds]]
+key = "Q"
+modifier = "shift+super"
+command = "close"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.binds[0].modifier(&config.modifier), "alt");
+    assert_eq!(config.binds[1].modifier(&config.modifier), "super");
+    assert_ne!(
+        config.get_bind_modifier(&config.binds[0]),
+        config.get_bind_modifier(&config.binds[1])
+    );
+    assert_eq!(
+        config.keybind_conflicts(),
+        [r#"super+shift+q is bound 2 times, by keys "q", "Q""#]
+    );
+}
+
+#[test]
+fn binds_can_run_on_release_and_skip_repeats() {
+    let config = Config::parse(
+        r#"modifier = "alt"
+
+[[binds]]
+key = "Tab"
+command = "next_workspace"
+repeat = false
+
+[[binds]]
+key = "Tab"
+on_release = true
+command = "previous_workspace"
+"#,
+    )
+    .unwrap();
+
+    assert!(!config.binds[0].on_release && !config.binds[0].repeat);
+    assert!(config.binds[1].on_release && config.binds[1].repeat);
+    assert!(config.keybind_conflicts().is_empty());
+}
+
+#[test]
+fn return_when_empty_takes_a_mode_or_workspace() {
+    let parse = |value: &str| {
+        Config::parse(&format!(
+            "modifier = \"alt\"\nbinds = []\nreturn_when_empty = {}\n",
+            value
+        ))
+        .unwrap()
+    };
+    assert_eq!(Config::default().return_when_empty, ReturnWhenEmpty::Off);
+    assert_eq!(
+        parse("\"previous\"").return_when_empty,
+        ReturnWhenEmpty::Previous
+    );
+    assert_eq!(parse("3").return_when_empty, ReturnWhenEmpty::Home(2));
+
+    let config = parse("11");
+    assert_eq!(config.return_when_empty, ReturnWhenEmpty::Off);
+    assert_eq!(config.errors.len(), 1);
+}
+
+#[test]
+fn dynamic_workspaces_adds_workspace_new() {
+    let config =
+        Config::parse("modifier = \"alt\"\nbinds = []\ndynamic_workspaces = true\n").unwrap();
+    assert!(config.dynamic_workspaces);
+    assert!(!Config::default().dynamic_workspaces);
+    assert!(matches!(
+        "workspace new".parse::<Command>(),
+        Ok(Command::NewWorkspace)
+    ));
+    assert!(matches!(
+        "workspace 2".parse::<Command>(),
+        Ok(Command::Workspace(1))
+    ));
+    assert!(matches!(
+        "move_to_workspace32".parse::<Command>(),
+        Ok(Command::MoveToWorkspace(31))
+    ));
+    assert!("workspace33".parse::<Command>().is_err());
+}
+
+#[test]
+fn rule_monitor_takes_an_output_or_index() {
+    let config = Config::parse(
+        r#"
+modifier = "alt"
+binds = []
+
+[[rules]]
+class = "Slack"
+monitor = "HDMI-1"
+workspace = 3
+
+[[rules]]
+class = "mpv"
+monitor = 2
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.rules[0].monitor,
+        Some(RuleMonitor::Output("HDMI-1".to_string()))
+    );
+    assert_eq!(config.rules[0].workspace_index(), Some(2));
+    assert_eq!(config.rules[1].monitor, Some(RuleMonitor::Index(2)));
+}
+
+#[test]
+fn rule_geometry_takes_pixels_or_percentages() {
+    let config = Config::parse(
+        r#"
+modifier = "alt"
+binds = []
+
+[[rules]]
+class = "Galculator"
+floating = true
+x = "75%"
+y = 40
+width = "25%"
+height = 300
+"#,
+    )
+    .unwrap();
+    let rule = &config.rules[0];
+    assert_eq!(rule.x, Some(Length::Percent(75.0)));
+    assert_eq!(rule.y, Some(Length::Pixels(40)));
+
+    // On a 1000x800 monitor to the right of another one.
+    assert_eq!(rule.x.unwrap().position(1920, 1000), 2670);
+    assert_eq!(rule.y.unwrap().position(0, 800), 40);
+    assert_eq!(rule.width.unwrap().size(1000), 250);
+    assert_eq!(rule.height.unwrap().size(800), 300);
+}
+
+#[test]
+fn rule_focus_defaults_to_unset() {
+    let config = Config::parse(
+        "modifier = \"alt\"\nbinds = []\n\n[[rules]]\nclass = \"Onboard\"\nfocus = false\n\n[[rules]]\nclass = \"mpv\"\n",
+    )
+    .unwrap();
+    assert_eq!(config.rules[0].focus, Some(false));
+    assert_eq!(config.rules[1].focus, None);
+}
+
+#[test]
+fn rule_parses_fullscreen_and_tiling_overrides() {
+    let config = Config::parse(
+        "modifier = \"alt\"\nbinds = []\n\n[[rules]]\nclass = \"Slack\"\nallow_fullscreen = false\nforce_tiling = true\n",
+    )
+    .unwrap();
+    assert_eq!(config.rules[0].allow_fullscreen, Some(false));
+    assert_eq!(config.rules[0].force_tiling, Some(true));
+}
+
+#[test]
+fn close_target_picks_the_window_close_and_kill_act_on() {
+    let config =
+        Config::parse("modifier = \"alt\"\nbinds = []\nclose_target = \"pointer\"\n").unwrap();
+    assert_eq!(config.close_target, CloseTarget::Pointer);
+    assert_eq!(Config::default().close_target, CloseTarget::Focused);
+    assert!(matches!("kill".parse::<Command>(), Ok(Command::Kill)));
+}
+
+#[test]
+fn binds_can_ask_for_confirmation() {
+    let config = Config::parse(
+        r#"modifier = "alt"
+
+[[binds]]
+key = "w"
+command = "exit"
+confirm = true
+
+[[binds]]
+key = "c"
+command = "close"
+"#,
+    )
+    .unwrap();
+
+    assert!(config.binds[0].confirm);
+    assert!(!config.binds[1].confirm);
+}
+
+#[test]
+fn kiosk_mode_only_allows_listed_commands() {
+    let config = Config::parse(
+        r#"modifier = "alt"
+binds = []
+
+[kiosk]
+enabled = true
+allowed_commands = ["toggle_bar", "workspace1"]
+application = "firefox --kiosk"
+"#,
+    )
+    .unwrap();
+
+    let kiosk = &config.kiosk;
+    assert_eq!(kiosk.application.as_deref(), Some("firefox --kiosk"));
+    assert!(kiosk.allows(&Command::ToggleBar));
+    assert!(kiosk.allows(&Command::Workspace(0)));
+    assert!(!kiosk.allows(&Command::Workspace(1)));
+    assert!(!kiosk.allows(&Command::Exit));
+    assert!(!kiosk.allows(&Command::Sequence(vec![Command::ToggleBar, Command::Close])));
+    assert!(Config::default().kiosk.allows(&Command::Exit));
+}
+
+#[test]
+fn workspace_slide_is_opt_in() {
+    let config = Config::parse(
+        "modifier = \"alt\"\nbinds = []\n\n[appearance.slide]\nenabled = true\nduration = 200\n",
+    )
+    .unwrap();
+    assert!(config.appearance.slide.enabled);
+    assert_eq!(config.appearance.slide.duration, 200);
+    assert!(!Config::default().appearance.slide.enabled);
+}
+
+#[test]
+fn rules_can_leave_windows_unmanaged() {
+    let config = Config::parse(
+        "modifier = \"alt\"\nbinds = []\n\n[[rules]]\nclass = \"Kmag\"\nunmanaged = true\n",
+    )
+    .unwrap();
+    assert_eq!(config.rules[0].unmanaged, Some(true));
+}
+
+#[test]
+fn commands_are_listed_as_they_are_written() {
+    for text in [
+        "show_binds",
+        "workspace3",
+        "move_to_corner br",
+        "move_window_to_monitor HDMI-1",
+        "spawn alacritty -e htop",
+        "screenshot region",
+    ] {
+        let command = text.parse::<Command>().unwrap();
+        assert_eq!(command.to_string(), text);
+    }
+    assert_eq!(Command::Close.category(), "Windows");
+    assert_eq!(
+        Command::Sequence(vec![Command::Workspace(0), Command::Close]).category(),
+        "Workspaces"
+    );
+}
+
+#[test]
+fn first_start_setup_rewrites_the_default_binds() {
+    use velowm::config::setup::Setup;
+
+    let setup = Setup {
+        modifier: String::from("super"),
+        terminal: String::from("kitty"),
+        launcher: Some(String::from("rofi -show drun")),
+    };
+    let content = setup
+        .apply("# Global modifier\nmodifier = \"alt\"\n\n[[binds]]\nkey = \"q\"\ncommand = \"spawn alacritty\"\n")
+        .unwrap();
+    let config = Config::parse(&content).unwrap();
+
+    assert!(content.contains("# Global modifier"));
+    assert_eq!(config.modifier, "super");
+    assert_eq!(config.binds[0].command.to_string(), "spawn kitty");
+    assert_eq!(config.binds[1].key, "d");
+    assert_eq!(config.binds[1].command.to_string(), "spawn rofi -show drun");
+}
+
+#[test]
+fn binds_are_named_by_the_keys_printed_on_them() {
+    assert_eq!(Bind::new("slash", Command::ShowBinds).label("alt"), "Alt+/");
+    assert_eq!(Bind::new("q", Command::Close).label("super"), "Super+Q");
+
+    let mut bind = Bind::new("Return", Command::Exit);
+    bind.modifier = Some(String::from("super+shift"));
+    assert_eq!(bind.label("alt"), "Super+Shift+Return");
+}
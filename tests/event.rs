@@ -0,0 +1,17 @@
+use velowm::input::event::ClientMessage;
+
+#[test]
+fn client_message_data_is_read_as_sent() {
+    // Xlib sign-extends format 32 data, a pager asking for all desktops
+    // sends 0xFFFFFFFF and it arrives as -1.
+    let message = ClientMessage {
+        window: 1,
+        message_type: 2,
+        format: 32,
+        data: [-1, 2, 0, 0, 0],
+        bytes: [0; 20],
+    };
+
+    assert_eq!(message.card32(0), 0xFFFFFFFF);
+    assert_eq!(message.card32(1), 2);
+}
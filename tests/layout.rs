@@ -0,0 +1,213 @@
+use velowm::ui::layout::{
+    compute_layout, ratio_at, split_gap, Dock, Geometry, LayoutKind, LayoutSettings, Struts,
+};
+
+const MONITOR: Geometry = Geometry {
+    x: 0,
+    y: 0,
+    width: 1000,
+    height: 800,
+};
+
+fn settings(master_ratio: f32, gaps: u32, nmaster: usize) -> LayoutSettings {
+    LayoutSettings {
+        kind: LayoutKind::MasterStack,
+        master_ratio,
+        gaps,
+        nmaster,
+    }
+}
+
+fn geometry(x: i32, y: i32, width: u32, height: u32) -> Geometry {
+    Geometry {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+#[test]
+fn no_windows() {
+    assert!(compute_layout(&[], MONITOR, Dock::None, &settings(0.5, 8, 1)).is_empty());
+}
+
+#[test]
+fn single_window_is_inset_by_gaps() {
+    let layout = compute_layout(&[1], MONITOR, Dock::None, &settings(0.5, 10, 1));
+    assert_eq!(layout, vec![(1, geometry(10, 10, 980, 780))]);
+}
+
+#[test]
+fn master_and_stack_are_separated_by_one_gap() {
+    let layout = compute_layout(&[1, 2], MONITOR, Dock::None, &settings(0.5, 10, 1));
+    assert_eq!(
+        layout,
+        vec![
+            (1, geometry(10, 10, 490, 780)),
+            (2, geometry(510, 10, 480, 780)),
+        ]
+    );
+}
+
+#[test]
+fn stacked_windows_share_the_height_minus_gaps() {
+    let layout = compute_layout(&[1, 2, 3, 4], MONITOR, Dock::None, &settings(0.5, 10, 1));
+    assert_eq!(
+        layout[1..],
+        [
+            (2, geometry(510, 10, 480, 253)),
+            (3, geometry(510, 273, 480, 253)),
+            (4, geometry(510, 536, 480, 253)),
+        ]
+    );
+}
+
+#[test]
+fn without_gaps_windows_touch() {
+    let layout = compute_layout(&[1, 2, 3], MONITOR, Dock::None, &settings(0.5, 0, 1));
+    assert_eq!(
+        layout,
+        vec![
+            (1, geometry(0, 0, 500, 800)),
+            (2, geometry(500, 0, 500, 400)),
+            (3, geometry(500, 400, 500, 400)),
+        ]
+    );
+}
+
+#[test]
+fn top_dock_pushes_windows_down() {
+    let layout = compute_layout(&[1], MONITOR, Dock::Top(20), &settings(0.5, 10, 1));
+    assert_eq!(layout, vec![(1, geometry(10, 30, 980, 760))]);
+}
+
+#[test]
+fn bottom_dock_only_shrinks_windows() {
+    let layout = compute_layout(&[1], MONITOR, Dock::Bottom(20), &settings(0.5, 10, 1));
+    assert_eq!(layout, vec![(1, geometry(10, 10, 980, 760))]);
+}
+
+#[test]
+fn docks_on_several_edges_all_reserve_space() {
+    let struts = Struts {
+        top: 20,
+        left: 50,
+        right: 30,
+        ..Struts::default()
+    };
+    let layout = compute_layout(&[1], MONITOR, struts, &settings(0.5, 10, 1));
+    assert_eq!(layout, vec![(1, geometry(60, 30, 900, 760))]);
+}
+
+#[test]
+fn docks_on_the_same_edge_overlap() {
+    let struts = Struts::from(Dock::Top(20)).max(Dock::Top(30).into());
+    assert_eq!(struts.top, 30);
+    assert_eq!(Struts::from(Dock::Left(40)).max(struts).left, 40);
+}
+
+#[test]
+fn layout_follows_the_monitor_origin() {
+    let monitor = Geometry {
+        x: 1920,
+        y: 100,
+        ..MONITOR
+    };
+    let layout = compute_layout(&[1], monitor, Dock::Top(20), &settings(0.5, 10, 1));
+    assert_eq!(layout, vec![(1, geometry(1930, 130, 980, 760))]);
+}
+
+#[test]
+fn master_ratio_sets_the_master_width() {
+    let layout = compute_layout(&[1, 2], MONITOR, Dock::None, &settings(0.7, 0, 1));
+    assert_eq!(layout[0].1.width, 700);
+    assert_eq!(layout[1].1, geometry(700, 0, 300, 800));
+}
+
+#[test]
+fn extreme_master_ratios_never_overflow() {
+    let layout = compute_layout(&[1, 2], MONITOR, Dock::None, &settings(1.0, 10, 1));
+    assert_eq!(layout[0].1.width, 980);
+    assert_eq!(layout[1].1.width, 0);
+
+    let layout = compute_layout(&[1, 2], MONITOR, Dock::None, &settings(0.0, 10, 1));
+    assert_eq!(layout[0].1.width, 0);
+    assert_eq!(layout[1].1, geometry(20, 10, 970, 780));
+}
+
+#[test]
+fn all_windows_in_one_column_without_a_stack() {
+    for nmaster in [0, 2, 5] {
+        let layout = compute_layout(&[1, 2], MONITOR, Dock::None, &settings(0.5, 0, nmaster));
+        assert_eq!(
+            layout,
+            vec![
+                (1, geometry(0, 0, 1000, 400)),
+                (2, geometry(0, 400, 1000, 400)),
+            ]
+        );
+    }
+}
+
+#[test]
+fn several_masters_share_the_master_column() {
+    let layout = compute_layout(&[1, 2, 3], MONITOR, Dock::None, &settings(0.5, 0, 2));
+    assert_eq!(
+        layout,
+        vec![
+            (1, geometry(0, 0, 500, 400)),
+            (2, geometry(0, 400, 500, 400)),
+            (3, geometry(500, 0, 500, 800)),
+        ]
+    );
+}
+
+#[test]
+fn monocle_gives_every_window_the_whole_area() {
+    let settings = LayoutSettings {
+        kind: LayoutKind::Monocle,
+        ..settings(0.5, 10, 1)
+    };
+    let layout = compute_layout(&[1, 2], MONITOR, Dock::Top(20), &settings);
+    assert_eq!(
+        layout,
+        vec![
+            (1, geometry(10, 30, 980, 760)),
+            (2, geometry(10, 30, 980, 760)),
+        ]
+    );
+}
+
+#[test]
+fn gaps_larger_than_the_monitor_saturate() {
+    let layout = compute_layout(&[1, 2], MONITOR, Dock::Top(900), &settings(0.5, 600, 1));
+    assert!(layout.iter().all(|(_, g)| g.width == 0 && g.height == 0));
+}
+
+#[test]
+fn split_gap_lies_between_master_and_stack() {
+    let settings = settings(0.5, 10, 1);
+    assert_eq!(
+        split_gap(2, MONITOR, Dock::None, &settings),
+        Some(geometry(500, 10, 10, 780))
+    );
+    assert_eq!(split_gap(1, MONITOR, Dock::None, &settings), None);
+
+    // Dragging the middle of the gap back onto itself keeps the ratio.
+    assert_eq!(ratio_at(505, MONITOR, Dock::None, &settings), 0.5);
+    assert_eq!(ratio_at(0, MONITOR, Dock::None, &settings), 0.1);
+}
+
+#[test]
+fn rebase_keeps_runtime_changes_the_config_leaves_alone() {
+    // nmaster and the ratio were changed at runtime, the config reload
+    // changes the gaps only.
+    let mut current = settings(0.7, 8, 2);
+    current.rebase(&settings(0.5, 8, 1), &settings(0.5, 12, 1));
+
+    assert_eq!(current.master_ratio, 0.7);
+    assert_eq!(current.nmaster, 2);
+    assert_eq!(current.gaps, 12);
+    assert_eq!(current.kind, LayoutKind::MasterStack);
+}
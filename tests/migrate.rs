@@ -0,0 +1,46 @@
+use velowm::config::migrate::{migrate, CURRENT_VERSION};
+
+const LEGACY: &str = r#"# My config
+modifier = "alt"
+
+[[binds]]
+key = "q"
+command = "alacritty" # terminal
+
+[[binds]]
+key = "w"
+command = "exit"
+"#;
+
+#[test]
+fn legacy_bind_commands_become_spawn() {
+    let migration = migrate(LEGACY).unwrap().unwrap();
+    assert_eq!(migration.from, 1);
+    assert!(migration
+        .content
+        .contains(r#"command = "spawn alacritty" # terminal"#));
+    assert!(migration.content.contains(r#"command = "exit""#));
+    assert_eq!(migration.changes.len(), 2);
+}
+
+#[test]
+fn comments_and_version_are_kept_in_order() {
+    let migration = migrate(LEGACY).unwrap().unwrap();
+    let version = migration.content.find("version = ").unwrap();
+    let comment = migration.content.find("# My config").unwrap();
+    assert!(version < comment);
+
+    let config: toml::Table = toml::from_str(&migration.content).unwrap();
+    assert_eq!(config["version"].as_integer(), Some(CURRENT_VERSION as i64));
+}
+
+#[test]
+fn current_config_is_left_alone() {
+    let content = format!("version = {}\n{}", CURRENT_VERSION, LEGACY);
+    assert!(migrate(&content).unwrap().is_none());
+}
+
+#[test]
+fn invalid_version_is_an_error() {
+    assert!(migrate("version = \"two\"\n").is_err());
+}
@@ -0,0 +1,25 @@
+use std::time::Duration;
+use velowm::input::{event::MotionEvent, motion::MotionThrottle};
+
+fn motion(x_root: i32) -> MotionEvent {
+    MotionEvent {
+        window: 1,
+        state: 0,
+        x_root,
+        y_root: 0,
+    }
+}
+
+#[test]
+fn throttled_motion_is_held_until_flushed() {
+    let mut throttle = MotionThrottle::new(Duration::from_secs(3600));
+    assert_eq!(throttle.offer(motion(1)).map(|m| m.x_root), Some(1));
+    assert!(throttle.offer(motion(2)).is_none());
+    assert!(throttle.offer(motion(3)).is_none());
+    assert!(throttle.timeout().is_some_and(|t| !t.is_zero()));
+    assert!(throttle.poll().is_none());
+
+    assert_eq!(throttle.flush().map(|m| m.x_root), Some(3));
+    assert!(throttle.timeout().is_none());
+    assert_eq!(throttle.offer(motion(4)).map(|m| m.x_root), Some(4));
+}
@@ -0,0 +1,34 @@
+use velowm::{input::hot_corner::Corner, utils::x11::Monitor};
+
+const MONITOR: Monitor = Monitor {
+    x: 1920,
+    y: 30,
+    width: 1000,
+    height: 800,
+};
+
+#[test]
+fn center_is_relative_to_the_monitor() {
+    assert_eq!(MONITOR.center(200, 100), (2320, 380));
+}
+
+#[test]
+fn corners_keep_the_margin() {
+    assert_eq!(MONITOR.corner(Corner::TopLeft, 200, 100, 10), (1930, 40));
+    assert_eq!(MONITOR.corner(Corner::TopRight, 200, 100, 10), (2710, 40));
+    assert_eq!(
+        MONITOR.corner(Corner::BottomLeft, 200, 100, 10),
+        (1930, 720)
+    );
+    assert_eq!(
+        MONITOR.corner(Corner::BottomRight, 200, 100, 10),
+        (2710, 720)
+    );
+}
+
+#[test]
+fn corner_names() {
+    assert_eq!("tl".parse::<Corner>(), Ok(Corner::TopLeft));
+    assert_eq!("bottom_right".parse::<Corner>(), Ok(Corner::BottomRight));
+    assert!("middle".parse::<Corner>().is_err());
+}
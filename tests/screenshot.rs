@@ -0,0 +1,34 @@
+use chrono::{Local, TimeZone};
+use velowm::{
+    ui::screenshot::{file_name, rectangle, ScreenshotTarget},
+    utils::command::Command,
+    utils::x11::Monitor,
+};
+
+#[test]
+fn rectangles_are_normalized() {
+    let area = Monitor {
+        x: 10,
+        y: 20,
+        width: 90,
+        height: 30,
+    };
+    assert_eq!(rectangle((10, 20), (100, 50)), area);
+    assert_eq!(rectangle((100, 50), (10, 20)), area);
+    assert_eq!(rectangle((100, 20), (10, 50)), area);
+}
+
+#[test]
+fn screenshots_are_named_after_the_time() {
+    let time = Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap();
+    assert_eq!(file_name(time), "screenshot-2024-03-09-140507.png");
+}
+
+#[test]
+fn screenshot_commands_are_parsed() {
+    let Ok(Command::Screenshot(target)) = "screenshot region".parse() else {
+        panic!("expected a screenshot command");
+    };
+    assert_eq!(target, ScreenshotTarget::Region);
+    assert!("screenshot desktop".parse::<Command>().is_err());
+}
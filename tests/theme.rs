@@ -0,0 +1,75 @@
+use velowm::{config::theme::presets, Config};
+
+const KEYS: [&str; 11] = [
+    "border_color",
+    "focused_border_color",
+    "urgent_border_color",
+    "sticky_border_color",
+    "notification.background_color",
+    "notification.border_color",
+    "bar.background_color",
+    "bar.focused_color",
+    "bar.occupied_color",
+    "bar.urgent_color",
+    "bar.empty_color",
+];
+
+#[test]
+fn presets_set_every_color() {
+    let presets = presets();
+    assert!(presets.contains_key("gruvbox"));
+    assert!(presets.contains_key("nord"));
+    for (name, theme) in &presets {
+        for key in KEYS {
+            assert!(theme.color(key).is_some(), "{} has no {}", name, key);
+        }
+    }
+}
+
+#[test]
+fn config_colors_override_the_theme() {
+    let mut config = Config::parse(
+        r##"modifier = "alt"
+binds = []
+
+[appearance]
+border_color = "#111111"
+
+[theme]
+name = "mine"
+
+[theme.custom.mine]
+border_color = "#222222"
+bar = { focused_color = "#333333" }
+"##,
+    )
+    .unwrap();
+    assert!(config.errors.is_empty());
+
+    let theme = config.theme.selected().unwrap().unwrap();
+    config.appearance.apply_theme(&theme);
+    let appearance = &config.appearance;
+    assert_eq!(appearance.border_color.to_string(), "#111111");
+    assert_eq!(appearance.bar.focused_color.to_string(), "#333333");
+    assert_eq!(
+        appearance.bar.empty_color,
+        Config::default().appearance.bar.empty_color
+    );
+}
+
+#[test]
+fn unknown_themes_are_reported() {
+    let config = Config::parse(
+        r#"modifier = "alt"
+binds = []
+
+[theme]
+name = "nrod"
+"#,
+    )
+    .unwrap();
+
+    assert!(config.theme.name.is_none());
+    assert_eq!(config.errors.len(), 1);
+    assert!(config.errors[0].starts_with("line 5, theme.name: Unknown theme 'nrod', available: "));
+}
@@ -0,0 +1,23 @@
+use velowm::{ui::wallpaper::cover, utils::x11::Monitor};
+
+const MONITOR: Monitor = Monitor {
+    x: 1920,
+    y: 0,
+    width: 1920,
+    height: 1080,
+};
+
+#[test]
+fn wide_images_are_cropped_at_the_sides() {
+    assert_eq!(cover(3000, 1000, MONITOR), (3240, 1080, 660, 0));
+}
+
+#[test]
+fn tall_images_are_cropped_at_the_top_and_bottom() {
+    assert_eq!(cover(1000, 1000, MONITOR), (1920, 1920, 0, 420));
+}
+
+#[test]
+fn small_images_are_scaled_up() {
+    assert_eq!(cover(960, 540, MONITOR), (1920, 1080, 0, 0));
+}
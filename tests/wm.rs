@@ -0,0 +1,187 @@
+mod common;
+
+use common::{Harness, SCREEN_HEIGHT, SCREEN_WIDTH};
+use std::{cell::RefCell, rc::Rc};
+use velowm::{config::hooks::HookKind, utils::command::Command, velowm_core::hooks::HookEvent};
+
+const GAPS: u32 = 8;
+
+#[test]
+fn single_window_fills_the_screen() {
+    let Some(mut harness) = Harness::start() else {
+        return;
+    };
+
+    let window = harness.map_window();
+
+    assert_eq!(
+        harness.client.geometry(window),
+        (
+            GAPS as i32,
+            GAPS as i32,
+            SCREEN_WIDTH - 2 * GAPS,
+            SCREEN_HEIGHT - 2 * GAPS
+        )
+    );
+}
+
+#[test]
+fn second_window_goes_to_the_stack() {
+    let Some(mut harness) = Harness::start() else {
+        return;
+    };
+
+    let master = harness.map_window();
+    let stack = harness.map_window();
+
+    let usable_width = SCREEN_WIDTH - 2 * GAPS;
+    let master_width = usable_width / 2;
+    let height = SCREEN_HEIGHT - 2 * GAPS;
+
+    assert_eq!(
+        harness.client.geometry(master),
+        (GAPS as i32, GAPS as i32, master_width, height)
+    );
+    assert_eq!(
+        harness.client.geometry(stack),
+        (
+            (GAPS * 2 + master_width) as i32,
+            GAPS as i32,
+            usable_width - master_width - GAPS,
+            height
+        )
+    );
+}
+
+#[test]
+fn switching_workspaces_hides_and_restores_windows() {
+    let Some(mut harness) = Harness::start() else {
+        return;
+    };
+
+    let window = harness.map_window();
+    assert!(harness.client.is_viewable(window));
+
+    harness.wm.execute_command(&Command::Workspace(1));
+    harness.wm.dispatch_pending();
+    assert_eq!(harness.wm.current_workspace(), 1);
+    assert!(!harness.client.is_viewable(window));
+    assert_eq!(harness.wm.workspace_windows(0), vec![window]);
+
+    harness.wm.execute_command(&Command::Workspace(0));
+    harness.wm.dispatch_pending();
+    assert_eq!(harness.wm.current_workspace(), 0);
+    assert!(harness.client.is_viewable(window));
+}
+
+#[test]
+fn moving_a_window_sends_it_to_the_other_workspace() {
+    let Some(mut harness) = Harness::start() else {
+        return;
+    };
+
+    let window = harness.map_window();
+    harness.wm.execute_command(&Command::MoveToWorkspace(2));
+    harness.wm.dispatch_pending();
+
+    assert!(harness.wm.workspace_windows(0).is_empty());
+    assert_eq!(harness.wm.workspace_windows(2), vec![window]);
+    assert!(!harness.client.is_viewable(window));
+}
+
+#[test]
+fn focus_follows_new_and_destroyed_windows() {
+    let Some(mut harness) = Harness::start() else {
+        return;
+    };
+
+    let first = harness.map_window();
+    assert_eq!(harness.wm.focused_window(), Some(first));
+
+    let second = harness.map_window();
+    assert_eq!(harness.wm.focused_window(), Some(second));
+
+    harness.destroy_window(second);
+    assert_eq!(harness.wm.focused_window(), Some(first));
+    assert_eq!(harness.wm.workspace_windows(0), vec![first]);
+}
+
+#[test]
+fn windows_destroyed_on_a_hidden_workspace_are_forgotten() {
+    let Some(mut harness) = Harness::start() else {
+        return;
+    };
+
+    let window = harness.map_window();
+    harness.wm.execute_command(&Command::Workspace(1));
+    harness.wm.dispatch_pending();
+    assert_eq!(harness.wm.workspace_windows(0), vec![window]);
+
+    harness.destroy_window(window);
+    assert!(!harness.wm.workspace_windows(0).contains(&window));
+}
+
+#[test]
+fn closing_a_hidden_window_runs_the_window_closed_hooks() {
+    let Some(mut harness) = Harness::start() else {
+        return;
+    };
+
+    let closed = Rc::new(RefCell::new(Vec::new()));
+    let seen = Rc::clone(&closed);
+    harness.wm.add_hook(HookKind::WindowClosed, move |event| {
+        if let HookEvent::WindowClosed { window } = event {
+            seen.borrow_mut().push(*window);
+        }
+    });
+
+    let window = harness.map_window();
+    harness.wm.execute_command(&Command::Workspace(1));
+    harness.wm.dispatch_pending();
+    assert!(closed.borrow().is_empty());
+
+    harness.destroy_window(window);
+    assert_eq!(*closed.borrow(), vec![window]);
+}
+
+#[test]
+fn docks_stay_on_workspaces_added_later() {
+    let mut config = common::test_config();
+    config.dynamic_workspaces = true;
+    let Some(mut harness) = Harness::with_config(config) else {
+        return;
+    };
+
+    let dock = harness.client.create_dock(30, 30);
+    harness.wm.dispatch_pending();
+    harness.wm.execute_command(&Command::NewWorkspace);
+    harness.wm.dispatch_pending();
+    assert_eq!(harness.wm.current_workspace(), 1);
+
+    let window = harness.map_window();
+    assert!(harness.client.is_above(dock, window));
+    assert_eq!(harness.client.geometry(window).1, (30 + GAPS) as i32);
+
+    harness.client.set_strut(dock, 50);
+    harness.wm.dispatch_pending();
+    assert_eq!(harness.client.geometry(window).1, (50 + GAPS) as i32);
+}
+
+#[test]
+fn goto_mark_returns_to_the_marked_window() {
+    let Some(mut harness) = Harness::start() else {
+        return;
+    };
+
+    let marked = harness.map_window();
+    harness.wm.execute_command(&Command::Mark('a'));
+    harness.wm.execute_command(&Command::Workspace(1));
+    harness.wm.dispatch_pending();
+    let other = harness.map_window();
+    assert_eq!(harness.wm.focused_window(), Some(other));
+
+    harness.wm.execute_command(&Command::GotoMark('a'));
+    harness.wm.dispatch_pending();
+    assert_eq!(harness.wm.current_workspace(), 0);
+    assert_eq!(harness.wm.focused_window(), Some(marked));
+}
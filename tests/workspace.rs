@@ -0,0 +1,167 @@
+use velowm::velowm_core::{registry::WindowRegistry, window::Window, workspace::Workspace};
+
+fn window(id: u64) -> Window {
+    Window::new(id, 0, 0, 100, 100)
+}
+
+fn floating(id: u64) -> Window {
+    Window {
+        is_floating: true,
+        ..window(id)
+    }
+}
+
+fn ids<'a>(windows: impl Iterator<Item = &'a Window>) -> Vec<u64> {
+    windows.map(|w| w.id).collect()
+}
+
+fn workspace(windows: &[u64]) -> Workspace {
+    let mut workspace = Workspace::new(0);
+    for &id in windows {
+        workspace.add_window(window(id));
+    }
+    workspace
+}
+
+#[test]
+fn new_workspace_is_empty() {
+    let workspace = Workspace::new(2);
+    assert_eq!(workspace.name, "Workspace 3");
+    assert!(workspace.get_focused_window().is_none());
+    assert!(!workspace.is_occupied());
+}
+
+#[test]
+fn added_window_gets_focus() {
+    let workspace = workspace(&[1, 2, 3]);
+    assert_eq!(workspace.get_focused_window().map(|w| w.id), Some(3));
+    assert_eq!(ids(workspace.focus_history()), vec![3, 2, 1]);
+}
+
+#[test]
+fn focus_moves_window_to_front_of_history() {
+    let mut workspace = workspace(&[1, 2, 3]);
+    assert!(workspace.focus(1));
+    assert_eq!(ids(workspace.focus_history()), vec![1, 3, 2]);
+}
+
+#[test]
+fn focusing_unknown_window_is_rejected() {
+    let mut workspace = workspace(&[1, 2]);
+    assert!(!workspace.focus(7));
+    assert_eq!(workspace.get_focused_window().map(|w| w.id), Some(2));
+}
+
+#[test]
+fn removing_focused_window_falls_back_to_previous_focus() {
+    let mut workspace = workspace(&[1, 2, 3]);
+    workspace.focus(1);
+    workspace.focus(3);
+
+    let removed = workspace.remove_window(3);
+    assert_eq!(removed.map(|w| w.id), Some(3));
+    assert_eq!(workspace.get_focused_window().map(|w| w.id), Some(1));
+    assert_eq!(ids(workspace.windows.iter()), vec![1, 2]);
+}
+
+#[test]
+fn removing_unknown_window_returns_none() {
+    let mut workspace = workspace(&[1]);
+    assert!(workspace.remove_window(5).is_none());
+    assert_eq!(workspace.windows.len(), 1);
+}
+
+#[test]
+fn find_by_id() {
+    let mut workspace = workspace(&[1, 2]);
+    assert!(workspace.contains(2));
+    assert!(!workspace.contains(3));
+    assert_eq!(workspace.find(1).map(|w| w.id), Some(1));
+    assert!(workspace.find(3).is_none());
+
+    workspace.find_mut(2).unwrap().is_urgent = true;
+    assert!(workspace.find(2).unwrap().is_urgent);
+}
+
+#[test]
+fn floating_and_tiled_iterators() {
+    let mut workspace = workspace(&[1]);
+    workspace.add_window(floating(2));
+    workspace.add_window(window(3));
+    workspace.add_window(Window {
+        is_dock: true,
+        ..window(4)
+    });
+
+    assert_eq!(ids(workspace.tiled()), vec![1, 3]);
+    assert_eq!(ids(workspace.floating()), vec![2]);
+}
+
+#[test]
+fn dock_alone_does_not_occupy_workspace() {
+    let mut workspace = Workspace::new(0);
+    workspace.add_window(Window {
+        is_dock: true,
+        ..window(1)
+    });
+    assert!(!workspace.is_occupied());
+
+    workspace.add_window(window(2));
+    assert!(workspace.is_occupied());
+}
+
+#[test]
+fn move_window_to_other_workspace() {
+    let mut source = workspace(&[1, 2]);
+    let mut target = workspace(&[3]);
+
+    assert!(source.move_window(2, &mut target));
+    assert_eq!(ids(source.windows.iter()), vec![1]);
+    assert_eq!(source.get_focused_window().map(|w| w.id), Some(1));
+    assert_eq!(ids(target.windows.iter()), vec![3, 2]);
+    assert_eq!(target.get_focused_window().map(|w| w.id), Some(2));
+}
+
+#[test]
+fn moving_unknown_window_changes_nothing() {
+    let mut source = workspace(&[1]);
+    let mut target = workspace(&[2]);
+
+    assert!(!source.move_window(9, &mut target));
+    assert_eq!(ids(source.windows.iter()), vec![1]);
+    assert_eq!(ids(target.windows.iter()), vec![2]);
+}
+
+#[test]
+fn registry_keeps_order_and_index_in_step() {
+    let mut windows = WindowRegistry::new();
+    for id in 1..=4 {
+        windows.push(window(id));
+    }
+
+    assert_eq!(windows.remove(2).map(|w| w.id), Some(2));
+    assert!(windows.remove(2).is_none());
+    assert!(windows.swap(1, 4));
+    assert_eq!(ids(windows.iter()), vec![4, 3, 1]);
+    assert_eq!(windows.position(1), Some(2));
+    assert_eq!(windows.get(3).map(|w| w.id), Some(3));
+    assert!(!windows.contains_id(2));
+}
+
+#[test]
+fn registry_reorder_keeps_the_index_in_step() {
+    let mut windows = WindowRegistry::new();
+    for id in 1..=4 {
+        windows.push(window(id));
+    }
+
+    windows.reorder(|entries| entries.rotate_right(1));
+    assert_eq!(ids(windows.iter()), vec![4, 1, 2, 3]);
+    assert_eq!(windows.position(4), Some(0));
+    assert_eq!(windows.position(3), Some(3));
+
+    windows.reorder(<[Window]>::reverse);
+    assert_eq!(ids(windows.iter()), vec![3, 2, 1, 4]);
+    assert_eq!(windows.get(1).map(|w| w.id), Some(1));
+    assert_eq!(windows.position(4), Some(3));
+}
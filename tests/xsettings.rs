@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use velowm::{
+    utils::xsettings::{parse_resources, parse_settings, Palette, Setting},
+    Config,
+};
+
+fn setting(data: &mut Vec<u8>, kind: u8, name: &str, value: &[u8]) {
+    data.extend([kind, 0]);
+    data.extend((name.len() as u16).to_le_bytes());
+    data.extend(name.as_bytes());
+    data.resize(data.len().next_multiple_of(4), 0);
+    data.extend(0u32.to_le_bytes());
+    data.extend(value);
+}
+
+#[test]
+fn settings_are_parsed() {
+    let mut data = vec![0, 0, 0, 0];
+    data.extend(7u32.to_le_bytes());
+    data.extend(2u32.to_le_bytes());
+    setting(&mut data, 0, "Xft/DPI", &(144 * 1024i32).to_le_bytes());
+    let mut theme = 7u32.to_le_bytes().to_vec();
+    theme.extend(b"Adwaita\0");
+    setting(&mut data, 1, "Net/ThemeName", &theme);
+
+    let settings = parse_settings(&data).unwrap();
+    assert_eq!(settings["Xft/DPI"], Setting::Integer(144 * 1024));
+    assert_eq!(settings["Net/ThemeName"], Setting::String("Adwaita".into()));
+}
+
+#[test]
+fn truncated_settings_are_rejected() {
+    let mut data = vec![0, 0, 0, 0];
+    data.extend(7u32.to_le_bytes());
+    data.extend(1u32.to_le_bytes());
+    setting(&mut data, 0, "Xft/DPI", &[0, 0]);
+    assert!(parse_settings(&data).is_none());
+}
+
+#[test]
+fn resources_are_parsed() {
+    let resources = parse_resources("! comment\nXft.dpi:\t192\n*.background:\t#1d2021\n");
+    assert_eq!(resources["Xft.dpi"], "192");
+    assert_eq!(resources["*.background"], "#1d2021");
+    assert_eq!(resources.len(), 2);
+}
+
+#[test]
+fn system_colors_fill_in_unset_colors() {
+    let mut config = Config::parse(
+        r##"modifier = "alt"
+binds = []
+
+[appearance]
+system_colors = true
+border_color = "#111111"
+"##,
+    )
+    .unwrap();
+
+    let mut settings = HashMap::new();
+    settings.insert(
+        "Gtk/ColorScheme".to_string(),
+        Setting::String("bg_color:#000000\nselected_bg_color:#0000ff".to_string()),
+    );
+    let resources = parse_resources(
+        "*.color8: #222222\n*background: #333333\nvelowm.bar.focused_color: #444444\n",
+    );
+    config
+        .appearance
+        .apply_palette(&Palette::new(&settings, resources));
+
+    let appearance = &config.appearance;
+    assert_eq!(appearance.border_color.to_string(), "#111111");
+    assert_eq!(appearance.bar.background_color.to_string(), "#333333");
+    assert_eq!(appearance.bar.focused_color.to_string(), "#444444");
+    assert_eq!(appearance.bar.empty_color.to_string(), "#222222");
+    assert_eq!(appearance.focused_border_color.to_string(), "#0000FF");
+}